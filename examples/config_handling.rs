@@ -45,12 +45,192 @@ fn main() -> Result<()> {
         assert_eq!(file_config.max_capacity, config.max_capacity)
     }
 
+    if command_line_args.chunk_cache_size.is_some() {
+        assert_eq!(command_line_args.chunk_cache_size, config.chunk_cache_size)
+    } else {
+        assert_eq!(file_config.chunk_cache_size, config.chunk_cache_size)
+    }
+
+    if command_line_args.chunk_scrub_rate.is_some() {
+        assert_eq!(command_line_args.chunk_scrub_rate, config.chunk_scrub_rate)
+    } else {
+        assert_eq!(file_config.chunk_scrub_rate, config.chunk_scrub_rate)
+    }
+
+    if command_line_args.chunk_quarantine_capacity.is_some() {
+        assert_eq!(
+            command_line_args.chunk_quarantine_capacity,
+            config.chunk_quarantine_capacity
+        )
+    } else {
+        assert_eq!(
+            file_config.chunk_quarantine_capacity,
+            config.chunk_quarantine_capacity
+        )
+    }
+
+    if command_line_args.chunk_replication_timeout.is_some() {
+        assert_eq!(
+            command_line_args.chunk_replication_timeout,
+            config.chunk_replication_timeout
+        )
+    } else {
+        assert_eq!(
+            file_config.chunk_replication_timeout,
+            config.chunk_replication_timeout
+        )
+    }
+
+    if command_line_args.max_chunk_replications.is_some() {
+        assert_eq!(
+            command_line_args.max_chunk_replications,
+            config.max_chunk_replications
+        )
+    } else {
+        assert_eq!(
+            file_config.max_chunk_replications,
+            config.max_chunk_replications
+        )
+    }
+
+    if command_line_args.chunk_replication_queue_size.is_some() {
+        assert_eq!(
+            command_line_args.chunk_replication_queue_size,
+            config.chunk_replication_queue_size
+        )
+    } else {
+        assert_eq!(
+            file_config.chunk_replication_queue_size,
+            config.chunk_replication_queue_size
+        )
+    }
+
+    if command_line_args.storage_thresholds.is_some() {
+        assert_eq!(
+            command_line_args.storage_thresholds,
+            config.storage_thresholds
+        )
+    } else {
+        assert_eq!(file_config.storage_thresholds, config.storage_thresholds)
+    }
+
+    if command_line_args.recent_msg_timeout.is_some() {
+        assert_eq!(
+            command_line_args.recent_msg_timeout,
+            config.recent_msg_timeout
+        )
+    } else {
+        assert_eq!(file_config.recent_msg_timeout, config.recent_msg_timeout)
+    }
+
+    if command_line_args.max_recent_msgs.is_some() {
+        assert_eq!(command_line_args.max_recent_msgs, config.max_recent_msgs)
+    } else {
+        assert_eq!(file_config.max_recent_msgs, config.max_recent_msgs)
+    }
+
+    if command_line_args.chunk_replication_rate.is_some() {
+        assert_eq!(
+            command_line_args.chunk_replication_rate,
+            config.chunk_replication_rate
+        )
+    } else {
+        assert_eq!(
+            file_config.chunk_replication_rate,
+            config.chunk_replication_rate
+        )
+    }
+
+    if command_line_args.max_chunk_replication_failures.is_some() {
+        assert_eq!(
+            command_line_args.max_chunk_replication_failures,
+            config.max_chunk_replication_failures
+        )
+    } else {
+        assert_eq!(
+            file_config.max_chunk_replication_failures,
+            config.max_chunk_replication_failures
+        )
+    }
+
+    if command_line_args.max_maps_per_owner.is_some() {
+        assert_eq!(
+            command_line_args.max_maps_per_owner,
+            config.max_maps_per_owner
+        )
+    } else {
+        assert_eq!(file_config.max_maps_per_owner, config.max_maps_per_owner)
+    }
+
+    if command_line_args.max_sequence_range_entries.is_some() {
+        assert_eq!(
+            command_line_args.max_sequence_range_entries,
+            config.max_sequence_range_entries
+        )
+    } else {
+        assert_eq!(
+            file_config.max_sequence_range_entries,
+            config.max_sequence_range_entries
+        )
+    }
+
+    if command_line_args.max_sequence_range_size.is_some() {
+        assert_eq!(
+            command_line_args.max_sequence_range_size,
+            config.max_sequence_range_size
+        )
+    } else {
+        assert_eq!(
+            file_config.max_sequence_range_size,
+            config.max_sequence_range_size
+        )
+    }
+
+    if command_line_args.max_client_requests_per_sec.is_some() {
+        assert_eq!(
+            command_line_args.max_client_requests_per_sec,
+            config.max_client_requests_per_sec
+        )
+    } else {
+        assert_eq!(
+            file_config.max_client_requests_per_sec,
+            config.max_client_requests_per_sec
+        )
+    }
+
+    if command_line_args.reward_payout_timeout.is_some() {
+        assert_eq!(
+            command_line_args.reward_payout_timeout,
+            config.reward_payout_timeout
+        )
+    } else {
+        assert_eq!(
+            file_config.reward_payout_timeout,
+            config.reward_payout_timeout
+        )
+    }
+
     if command_line_args.root_dir.is_some() {
         assert_eq!(command_line_args.root_dir, config.root_dir)
     } else {
         assert_eq!(file_config.root_dir, config.root_dir)
     }
 
+    if command_line_args.chunk_root_dir.is_some() {
+        assert_eq!(command_line_args.chunk_root_dir, config.chunk_root_dir)
+    } else {
+        assert_eq!(file_config.chunk_root_dir, config.chunk_root_dir)
+    }
+
+    if command_line_args.metadata_root_dir.is_some() {
+        assert_eq!(
+            command_line_args.metadata_root_dir,
+            config.metadata_root_dir
+        )
+    } else {
+        assert_eq!(file_config.metadata_root_dir, config.metadata_root_dir)
+    }
+
     if command_line_args.verbose > 0 {
         assert_eq!(command_line_args.verbose, config.verbose)
     } else {
@@ -81,6 +261,48 @@ fn main() -> Result<()> {
         config.clear_data,
         file_config.clear_data || command_line_args.clear_data
     );
+    assert_eq!(
+        config.compact_chunk_store,
+        file_config.compact_chunk_store || command_line_args.compact_chunk_store
+    );
+    assert_eq!(
+        config.skip_used_space_check,
+        file_config.skip_used_space_check || command_line_args.skip_used_space_check
+    );
+    assert_eq!(
+        config.fast_used_space_check,
+        file_config.fast_used_space_check || command_line_args.fast_used_space_check
+    );
+    assert_eq!(
+        config.in_memory_chunks,
+        file_config.in_memory_chunks || command_line_args.in_memory_chunks
+    );
+
+    if command_line_args.export_data.is_some() {
+        assert_eq!(command_line_args.export_data, config.export_data)
+    } else {
+        assert_eq!(file_config.export_data, config.export_data)
+    }
+
+    if command_line_args.import_data.is_some() {
+        assert_eq!(command_line_args.import_data, config.import_data)
+    } else {
+        assert_eq!(file_config.import_data, config.import_data)
+    }
+
+    assert_eq!(
+        config.strict_payment_checks,
+        file_config.strict_payment_checks || command_line_args.strict_payment_checks
+    );
+
+    if command_line_args.funds_monitor_key.is_some() {
+        assert_eq!(
+            command_line_args.funds_monitor_key,
+            config.funds_monitor_key
+        )
+    } else {
+        assert_eq!(file_config.funds_monitor_key, config.funds_monitor_key)
+    }
 
     if command_line_args.local_addr.is_some() || command_line_args.first.is_some() {
         assert_eq!(
@@ -29,7 +29,9 @@
 
 use log::{self, error, info};
 use self_update::{cargo_crate_version, Status};
-use sn_node::{self, add_connection_info, set_connection_info, utils, Config, Node};
+use sn_node::{
+    self, add_connection_info, export_data, import_data, set_connection_info, utils, Config, Node,
+};
 use std::{io::Write, process};
 use structopt::{clap, StructOpt};
 
@@ -96,6 +98,41 @@ async fn run_node() {
         }
     }
 
+    if let Some(archive) = config.export_data() {
+        match export_data(&config, archive) {
+            Ok(manifest) => {
+                println!(
+                    "Exported {} chunks, {} bytes in all, to {}",
+                    manifest.chunk_count,
+                    manifest.total_size,
+                    archive.display()
+                );
+                process::exit(0);
+            }
+            Err(e) => {
+                println!("Exporting node data failed: {:?}", e);
+                error!("Exporting node data failed: {:?}", e);
+                process::exit(1);
+            }
+        }
+    }
+
+    if let Some(archive) = config.import_data() {
+        match import_data(&config, archive) {
+            Ok(manifest) => info!(
+                "Imported {} chunks, {} bytes in all, from {}",
+                manifest.chunk_count,
+                manifest.total_size,
+                archive.display()
+            ),
+            Err(e) => {
+                println!("Importing node data failed: {:?}", e);
+                error!("Importing node data failed: {:?}", e);
+                process::exit(1);
+            }
+        }
+    }
+
     let message = format!(
         "Running {} v{}",
         Config::clap().get_name(),
@@ -6,8 +6,9 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::{utils, Result};
+use crate::{utils, Error, Result};
 use futures::lock::Mutex;
+use log::info;
 use pickledb::PickleDb;
 use std::sync::Arc;
 use std::{cell::RefCell, path::Path, rc::Rc};
@@ -15,6 +16,13 @@ use std::{cell::RefCell, path::Path, rc::Rc};
 const BLOB_META_DB_NAME: &str = "immutable_data.db";
 const HOLDER_META_DB_NAME: &str = "holder_data.db";
 const FULL_ADULTS_DB_NAME: &str = "full_adults.db";
+const REPLICATION_FAILURES_DB_NAME: &str = "replication_failures.db";
+const PENDING_REPLICATIONS_DB_NAME: &str = "pending_replications.db";
+const FORMAT_VERSION_DB_NAME: &str = "chunk_dbs_version.db";
+const FORMAT_VERSION_KEY: &str = "format_version";
+/// Version of the layout of the records in these DBs, to be bumped on any change to it,
+/// along with a migration step from the previous version in `migrate`.
+pub(crate) const FORMAT_VERSION: u32 = 1;
 // The number of separate copies of a blob chunk which should be maintained.
 
 #[derive(Clone)]
@@ -22,18 +30,95 @@ pub struct ChunkHolderDbs {
     pub metadata: Arc<Mutex<PickleDb>>,
     pub holders: Arc<Mutex<PickleDb>>,
     pub full_adults: Arc<Mutex<PickleDb>>,
+    pub replication_failures: Arc<Mutex<PickleDb>>,
+    pub pending_replications: Arc<Mutex<PickleDb>>,
 }
 
 impl ChunkHolderDbs {
-    ///
+    /// Opens the DBs, migrating their records to the current format version first.
     pub fn new(path: &Path) -> Result<Self> {
-        let metadata = utils::new_auto_dump_db(path, BLOB_META_DB_NAME)?;
-        let holders = utils::new_auto_dump_db(path, HOLDER_META_DB_NAME)?;
-        let full_adults = utils::new_auto_dump_db(path, FULL_ADULTS_DB_NAME)?;
+        let mut versions = utils::new_auto_dump_db(path, FORMAT_VERSION_DB_NAME)?;
+        // DBs predating the version record have the layout of version 1.
+        let version = versions.get::<u32>(FORMAT_VERSION_KEY).unwrap_or(1);
+        if version > FORMAT_VERSION {
+            return Err(Error::UnsupportedFormatVersion(version, FORMAT_VERSION));
+        }
+
+        let mut metadata = utils::new_auto_dump_db(path, BLOB_META_DB_NAME)?;
+        let mut holders = utils::new_auto_dump_db(path, HOLDER_META_DB_NAME)?;
+        let mut full_adults = utils::new_auto_dump_db(path, FULL_ADULTS_DB_NAME)?;
+        let mut replication_failures = utils::new_auto_dump_db(path, REPLICATION_FAILURES_DB_NAME)?;
+        let mut pending_replications = utils::new_auto_dump_db(path, PENDING_REPLICATIONS_DB_NAME)?;
+        for from in version..FORMAT_VERSION {
+            info!("Migrating chunk metadata from format version {}", from);
+            migrate(
+                from,
+                &mut metadata,
+                &mut holders,
+                &mut full_adults,
+                &mut replication_failures,
+                &mut pending_replications,
+            )?;
+        }
+        versions.set(FORMAT_VERSION_KEY, &FORMAT_VERSION)?;
+
         Ok(Self {
             metadata: Arc::new(Mutex::new(metadata)),
             holders: Arc::new(Mutex::new(holders)),
             full_adults: Arc::new(Mutex::new(full_adults)),
+            replication_failures: Arc::new(Mutex::new(replication_failures)),
+            pending_replications: Arc::new(Mutex::new(pending_replications)),
         })
     }
 }
+
+// Rewrites the records of the DBs from the `from` format version to the layout of the
+// next version. Steps are added here as arms of the match as the layout changes, each
+// reading the records as they were in `from`, the current types only describing the
+// latest layout.
+#[allow(clippy::match_single_binding)]
+fn migrate(
+    from: u32,
+    metadata: &mut PickleDb,
+    holders: &mut PickleDb,
+    full_adults: &mut PickleDb,
+    replication_failures: &mut PickleDb,
+    pending_replications: &mut PickleDb,
+) -> Result<()> {
+    match from {
+        _ => Err(Error::Logic(format!(
+            "No migration step for chunk metadata from format version {} to {}",
+            from,
+            from + 1
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChunkHolderDbs, FORMAT_VERSION, FORMAT_VERSION_DB_NAME, FORMAT_VERSION_KEY};
+    use crate::{utils, Error, Result};
+    use tempdir::TempDir;
+
+    #[test]
+    fn dbs_in_a_newer_format_are_refused() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let _ = ChunkHolderDbs::new(root.path())?;
+        let mut versions = utils::new_auto_dump_db(root.path(), FORMAT_VERSION_DB_NAME)?;
+        assert_eq!(
+            versions.get::<u32>(FORMAT_VERSION_KEY),
+            Some(FORMAT_VERSION)
+        );
+
+        versions.set(FORMAT_VERSION_KEY, &(FORMAT_VERSION + 1))?;
+        match ChunkHolderDbs::new(root.path()) {
+            Err(Error::UnsupportedFormatVersion(version, FORMAT_VERSION))
+                if version == FORMAT_VERSION + 1 =>
+            {
+                Ok(())
+            }
+            Err(error) => Err(Error::Logic(format!("Unexpected: {:?}", error))),
+            Ok(_) => Err(Error::Logic("DBs in a newer format opened".to_string())),
+        }
+    }
+}
@@ -7,6 +7,7 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::{Capacity, MAX_CHUNK_SIZE, MAX_NETWORK_STORAGE_RATIO, MAX_SUPPLY};
+use crate::section_funds::reward_calc::RewardCalc;
 use crate::Network;
 use crate::Result;
 use log::info;
@@ -37,6 +38,14 @@ impl RateLimit {
         RateLimit::rate_limit(bytes, full_nodes, all_nodes, prefix_len)
     }
 
+    /// The calculation of node rewards, at the current usage of the section's storage capacity.
+    pub async fn reward_calc(&self) -> RewardCalc {
+        let prefix_len = self.network.our_prefix().await.bit_count();
+        let full_nodes = self.capacity.full_nodes().await;
+        let all_nodes = self.network.our_adults().await.len() as u8;
+        RewardCalc::new(prefix_len, full_nodes, all_nodes)
+    }
+
     /// Adds this node to the list of full nodes.
     pub async fn increase_full_node_count(&mut self, node_id: PublicKey) -> Result<()> {
         self.capacity.increase_full_node_count(node_id).await
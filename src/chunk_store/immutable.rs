@@ -7,7 +7,7 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::chunk::{Chunk, ChunkId};
-use sn_data_types::{Blob, BlobAddress};
+use sn_data_types::{Blob, BlobAddress, PrivateBlob, PublicBlob};
 
 impl Chunk for Blob {
     type Id = BlobAddress;
@@ -20,3 +20,16 @@ impl Chunk for Blob {
 }
 
 impl ChunkId for BlobAddress {}
+
+/// Returns whether the address of the blob matches the one derived from its content,
+/// i.e. that the blob has not been corrupted or tampered with.
+pub(crate) fn is_intact(blob: &Blob) -> bool {
+    match blob {
+        Blob::Public(ref chunk) => {
+            PublicBlob::new(chunk.value().clone()).address() == chunk.address()
+        }
+        Blob::Private(ref chunk) => {
+            PrivateBlob::new(chunk.value().clone(), *chunk.owner()).address() == chunk.address()
+        }
+    }
+}
@@ -19,19 +19,33 @@ mod used_space;
 use crate::error::{Error, Result};
 use crate::utils;
 use chunk::{Chunk, ChunkId};
-use log::{info, trace};
+pub(crate) use immutable::is_intact;
+use log::{info, trace, warn};
 use sn_data_types::{Blob, Map, Sequence};
 use std::{
     fs::{self, DirEntry, File, Metadata},
-    io::{Read, Write},
+    io::{BufReader, Write},
     marker::PhantomData,
     path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
 };
-use used_space::StoreId;
-pub use used_space::UsedSpace;
+pub use used_space::{StorageStats, StoreStats, UsedSpace, UsedSpaceCheck};
+use used_space::{StoreId, USED_SPACE_FILENAME};
 
 const CHUNK_STORE_DIR: &str = "chunks";
 
+/// Size of the segments chunk files are read in.
+const READ_SEGMENT_SIZE: usize = 64 * 1024;
+
+/// Extension of the temporary files chunks are written to before being moved into place.
+const TEMP_FILE_EXTENSION: &str = "tmp";
+
+/// Subdirectory of a store where corrupted chunk files are moved to.
+const QUARANTINE_DIR: &str = "corrupt";
+
+/// Number of chunk files looked at by a fast check of the space used by a store.
+const USED_SPACE_SAMPLE_SIZE: usize = 1000;
+
 /// The max name length for a chunk file.
 const MAX_CHUNK_FILE_NAME_LENGTH: usize = 104;
 
@@ -39,12 +53,33 @@ pub(crate) type BlobChunkStore = ChunkStore<Blob>;
 pub(crate) type MapChunkStore = ChunkStore<Map>;
 pub(crate) type SequenceChunkStore = ChunkStore<Sequence>;
 
+/// Outcome of compacting a `ChunkStore`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct CompactionSummary {
+    /// Number of files found in the store.
+    pub files_scanned: u64,
+    /// Number of files removed for not being chunks.
+    pub orphans_removed: u64,
+    /// Space freed by removing those files.
+    pub bytes_reclaimed: u64,
+}
+
+/// A chunk written to a temporary file, yet to be moved into place.
+struct TempChunkFile {
+    file: File,
+    temp_path: PathBuf,
+    file_path: PathBuf,
+    consumed_space: u64,
+}
+
 /// `ChunkStore` is a store of data held as serialised files on disk, implementing a maximum disk
 /// usage to restrict storage.
 pub(crate) struct ChunkStore<T: Chunk> {
     dir: PathBuf,
     // Maximum space allowed for all `ChunkStore`s to consume.
     used_space: UsedSpace,
+    // Maximum space the quarantined chunk files may consume.
+    quarantine_capacity: u64,
     id: StoreId,
     _phantom: PhantomData<T>,
 }
@@ -57,80 +92,264 @@ where
     /// Creates a new `ChunkStore` at location `root/CHUNK_STORE_DIR/<chunk type>`.
     ///
     /// If the location specified already exists, the previous ChunkStore there is opened, otherwise
-    /// the required folder structure is created.
+    /// the required folder structure is created. Temporary files left over by interrupted writes
+    /// are removed when opening an existing store.
+    ///
+    /// A store `used_space` knows as opened before is never created anew: it is refused if its
+    /// directory is missing, or lost the record of its used space, e.g. as its volume didn't
+    /// mount, rather than taking chunks in as a fresh store, only to lose them along with those
+    /// missing once the right volume is back.
     ///
     /// The maximum storage space is defined by `max_capacity`.  This specifies the max usable by
     /// _all_ `ChunkStores`, not per `ChunkStore`.
     pub async fn new<P: AsRef<Path>>(root: P, used_space: UsedSpace) -> Result<Self> {
         let dir = root.as_ref().join(CHUNK_STORE_DIR).join(Self::subdir());
 
-        if fs::read(&dir).is_err() {
-            Self::create_new_root(&dir)?
+        let known = used_space.is_known_store(&dir)?;
+        Self::open_root(&dir, !known)?;
+        if known && !dir.join(USED_SPACE_FILENAME).exists() {
+            return Err(Error::ChunkStoreEmptied(dir.display().to_string()));
         }
+        Self::remove_temp_files(&dir)?;
 
         let id = used_space.add_local_store(&dir).await?;
-        Ok(ChunkStore {
+        used_space.add_known_store(&dir)?;
+        let mut store = ChunkStore {
             dir,
             used_space,
+            quarantine_capacity: 0,
             id,
             _phantom: PhantomData,
-        })
+        };
+        store.check_used_space().await?;
+        Ok(store)
     }
 }
 
 impl<T: Chunk> ChunkStore<T> {
-    fn create_new_root(root: &Path) -> Result<()> {
-        fs::create_dir_all(root)?;
+    // Creates the directory if missing and `create` is set, then verifies that chunk files can
+    // be created in it.
+    fn open_root(root: &Path, create: bool) -> Result<()> {
+        let not_writable = |error: std::io::Error| {
+            Error::ChunkStoreNotWritable(root.display().to_string(), error.to_string())
+        };
+        if create {
+            fs::create_dir_all(root).map_err(not_writable)?;
+        } else if !root.is_dir() {
+            return Err(Error::ChunkStoreMissing(root.display().to_string()));
+        }
 
-        // Verify that chunk files can be created.
         let temp_file_path = root.join("0".repeat(MAX_CHUNK_FILE_NAME_LENGTH));
-        let _ = File::create(&temp_file_path)?;
-        fs::remove_file(temp_file_path)?;
+        let _ = File::create(&temp_file_path).map_err(not_writable)?;
+        fs::remove_file(temp_file_path).map_err(not_writable)?;
 
         Ok(())
     }
 
+    fn remove_temp_files(dir: &Path) -> Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path
+                .extension()
+                .map_or(false, |ext| ext == TEMP_FILE_EXTENSION)
+            {
+                warn!("Removing leftover temporary chunk file: {:?}", path);
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+
     /// Stores a new data chunk.
     ///
     /// If there is not enough storage space available, returns `Error::NotEnoughSpace`.  In case of
     /// an IO error, it returns `Error::Io`.
     ///
     /// If a chunk with the same id already exists, it will be overwritten.
+    ///
+    /// The chunk is first written and synced to a temporary file, which is then renamed into
+    /// place, so that an interrupted write never leaves a truncated chunk behind.
     pub async fn put(&mut self, chunk: &T) -> Result<()> {
         info!("Writing chunk");
-        let serialised_chunk = utils::serialise(chunk)?;
-        let consumed_space = serialised_chunk.len() as u64;
+        let pending = self.write_temp_file(chunk).await?;
+        if let Err(e) = pending.file.sync_all() {
+            info!("Writing chunk failed!");
+            let _ = fs::remove_file(&pending.temp_path);
+            return Err(e.into());
+        }
+        self.commit_temp_file(pending).await
+    }
 
-        info!("consumed space: {:?}", consumed_space);
-        info!("max : {:?}", self.used_space.max_capacity().await);
-        info!("use space total : {:?}", self.used_space.total().await);
+    /// Stores a batch of data chunks, syncing all of them to disk before moving any into place,
+    /// which spares the disk a round of seeks per chunk compared to individual `put`s.
+    ///
+    /// Returns the outcome for each chunk, in the order given. A chunk failing to be stored
+    /// does not prevent the others from being stored.
+    pub async fn put_batch(&mut self, chunks: &[T]) -> Vec<Result<()>> {
+        info!("Writing batch of {} chunks", chunks.len());
+        let mut pending = Vec::with_capacity(chunks.len());
+        for chunk in chunks {
+            pending.push(self.write_temp_file(chunk).await);
+        }
+        let mut results = Vec::with_capacity(chunks.len());
+        for written in pending {
+            let result = match written {
+                Ok(written) => match written.file.sync_all() {
+                    Ok(()) => self.commit_temp_file(written).await,
+                    Err(e) => {
+                        let _ = fs::remove_file(&written.temp_path);
+                        Err(e.into())
+                    }
+                },
+                Err(e) => Err(e),
+            };
+            results.push(result);
+        }
+        results
+    }
 
-        let file_path = self.file_path(chunk.id())?;
-        self.do_delete(&file_path).await?;
+    /// Checks whether `chunk` would fit within the configured max capacity, taking into account
+    /// the space that would be released by overwriting an existing chunk with the same id.
+    ///
+    /// Returns `Error::NotEnoughSpace` if storing the chunk would exceed the max capacity.
+    pub async fn can_store(&self, chunk: &T) -> Result<()> {
+        let consumed_space = bincode::serialized_size(chunk)?;
+        self.check_capacity(&self.file_path(chunk.id())?, consumed_space)
+            .await
+    }
 
-        // pre-reserve space
-        self.used_space.increase(self.id, consumed_space).await?;
-        trace!(
-            "use space total after add: {:?}",
-            self.used_space.total().await
+    /// Walks the store, removing any file whose name doesn't parse as a chunk id, and
+    /// rebuilds the used space of this store from the sizes of the chunk files found.
+    ///
+    /// This can take a while for large stores.
+    pub async fn compact(&mut self) -> Result<CompactionSummary> {
+        let mut summary = CompactionSummary::default();
+        let mut used = 0;
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if !metadata.is_file() || entry.file_name() == USED_SPACE_FILENAME {
+                continue;
+            }
+            summary.files_scanned += 1;
+            if to_chunk_id::<T::Id>(&entry).is_some() {
+                used += metadata.len();
+            } else {
+                warn!("Removing orphaned chunk store file: {:?}", entry.path());
+                fs::remove_file(entry.path())?;
+                summary.orphans_removed += 1;
+                summary.bytes_reclaimed += metadata.len();
+            }
+        }
+        self.used_space.set_local(self.id, used).await?;
+        info!(
+            "Compacted chunk store {:?}: {} files scanned, {} orphans removed, {} bytes reclaimed",
+            self.dir, summary.files_scanned, summary.orphans_removed, summary.bytes_reclaimed
         );
+        Ok(summary)
+    }
 
-        let res = File::create(&file_path).and_then(|mut file| {
-            file.write_all(&serialised_chunk)?;
-            file.sync_all()
-        });
+    // Checks the space recorded as used by the store against the sizes of its chunk files,
+    // as set by the `UsedSpace`, adopting their sum if it differs.
+    async fn check_used_space(&mut self) -> Result<()> {
+        let check = self.used_space.check();
+        if check == UsedSpaceCheck::Off {
+            return Ok(());
+        }
+        let recorded = self.local_used_space().await;
+        let mut files = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            if to_chunk_id::<T::Id>(&entry).is_some() {
+                files.push(entry);
+            }
+        }
 
-        match res {
-            Ok(_) => {
-                info!("Writing chunk succeeded!");
-                Ok(())
+        if check == UsedSpaceCheck::Fast {
+            let step = (files.len() / USED_SPACE_SAMPLE_SIZE).max(1);
+            let mut sampled = 0;
+            let mut sampled_size = 0;
+            for entry in files.iter().step_by(step) {
+                sampled += 1;
+                sampled_size += entry.metadata()?.len();
             }
-            Err(e) => {
-                info!("Writing chunk failed!");
-                self.used_space.decrease(self.id, consumed_space).await?;
-                Err(e.into())
+            let estimate = if sampled == 0 {
+                0
+            } else {
+                (sampled_size as u128 * files.len() as u128 / sampled as u128) as u64
+            };
+            let max_capacity = self.used_space.max_capacity().await;
+            if recorded <= max_capacity
+                && recorded <= estimate.saturating_mul(2)
+                && estimate <= recorded.saturating_mul(2)
+            {
+                return Ok(());
             }
         }
+
+        let mut held = 0;
+        for entry in &files {
+            held += entry.metadata()?.len();
+        }
+        if held != recorded {
+            warn!(
+                "Space recorded as used by chunk store {:?} is off by {} bytes ({} recorded, {} held), adopting the space held",
+                self.dir,
+                i128::from(held) - i128::from(recorded),
+                recorded,
+                held
+            );
+            self.used_space.set_local(self.id, held).await?;
+        }
+        Ok(())
+    }
+
+    /// Sets the maximum space the quarantined chunk files may consume (zero by default, i.e.
+    /// quarantined chunks are discarded right away).
+    pub fn set_quarantine_capacity(&mut self, capacity: u64) {
+        self.quarantine_capacity = capacity;
+    }
+
+    /// Moves the file of the chunk stored under `id` to the quarantine directory, e.g. because
+    /// it is corrupted, releasing the space charged for it when written. The oldest quarantined files
+    /// are then removed until the quarantine directory fits within its capacity.
+    ///
+    /// If the data doesn't exist, it does nothing and returns `Ok`.
+    pub async fn quarantine(&mut self, id: &T::Id) -> Result<()> {
+        let file_path = self.file_path(id)?;
+        if !file_path.is_file() {
+            return Ok(());
+        }
+        let charged = self.charged_space(&file_path).await?;
+        let quarantine_dir = self.dir.join(QUARANTINE_DIR);
+        fs::create_dir_all(&quarantine_dir)?;
+        // Prefix the file name with the time of quarantine, so that files sort oldest first.
+        let since_epoch = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let file_name = format!(
+            "{:020}_{}",
+            since_epoch.as_nanos(),
+            hex::encode(utils::serialise(id)?)
+        );
+        fs::rename(&file_path, quarantine_dir.join(file_name))?;
+        self.used_space.decrease(self.id, charged).await?;
+        warn!("Quarantined chunk file {:?}", file_path);
+        self.prune_quarantine(&quarantine_dir)
+    }
+
+    // The space charged for the chunk file at `file_path` when written, i.e. the space recorded
+    // as used by the store beyond its other chunk files. The size of the file itself can't be
+    // relied upon once it is corrupted, e.g. truncated.
+    async fn charged_space(&self, file_path: &Path) -> Result<u64> {
+        let mut others = 0;
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            if entry.path() != file_path && to_chunk_id::<T::Id>(&entry).is_some() {
+                others += entry.metadata()?.len();
+            }
+        }
+        Ok(self.local_used_space().await.saturating_sub(others))
     }
 
     /// Deletes the data chunk stored under `id`.
@@ -141,6 +360,11 @@ impl<T: Chunk> ChunkStore<T> {
         self.do_delete(&self.file_path(id)?).await
     }
 
+    /// Sets the maximum space all `ChunkStore`s may consume.
+    pub async fn set_max_capacity(&self, max_capacity: u64) {
+        self.used_space.set_max_capacity(max_capacity).await
+    }
+
     /// Used space to max space ratio.
     pub async fn used_space_ratio(&self) -> f64 {
         let used = self.total_used_space().await;
@@ -156,10 +380,11 @@ impl<T: Chunk> ChunkStore<T> {
     ///
     /// If the data file can't be accessed, it returns `Error::NoSuchChunk`.
     pub fn get(&self, id: &T::Id) -> Result<T> {
-        let mut file = File::open(self.file_path(id)?).map_err(|_| Error::NoSuchChunk)?;
-        let mut contents = vec![];
-        let _ = file.read_to_end(&mut contents)?;
-        let chunk = bincode::deserialize::<T>(&contents)?;
+        let file = File::open(self.file_path(id)?).map_err(|_| Error::NoSuchChunk)?;
+        // Deserialise straight from the file, read in fixed-size segments, so that large chunks
+        // aren't held in memory twice (as file contents and as the deserialised chunk).
+        let reader = BufReader::with_capacity(READ_SEGMENT_SIZE, file);
+        let chunk = bincode::deserialize_from::<_, T>(reader)?;
         // Check it's the requested chunk variant.
         if chunk.id() == id {
             Ok(chunk)
@@ -168,10 +393,22 @@ impl<T: Chunk> ChunkStore<T> {
         }
     }
 
+    /// Hashes the nonce followed by the file holding the chunk stored under `id`, read in
+    /// fixed-size segments, so that large chunks aren't loaded in memory.
+    pub fn digest(&self, id: &T::Id, nonce: &[u8]) -> Result<[u8; 32]> {
+        let file = File::open(self.file_path(id)?).map_err(|_| Error::NoSuchChunk)?;
+        utils::digest(nonce, BufReader::with_capacity(READ_SEGMENT_SIZE, file))
+    }
+
     pub async fn total_used_space(&self) -> u64 {
         self.used_space.total().await
     }
 
+    /// The space consumed by the chunks of this store alone.
+    pub async fn local_used_space(&self) -> u64 {
+        self.used_space.local(self.id).await
+    }
+
     /// Tests if a data chunk has been previously stored under `id`.
     pub fn has(&self, id: &T::Id) -> bool {
         if let Ok(path) = self.file_path(id) {
@@ -185,7 +422,6 @@ impl<T: Chunk> ChunkStore<T> {
     }
 
     /// Lists all keys of currently stored data.
-    #[cfg_attr(not(test), allow(unused))]
     pub fn keys(&self) -> Vec<T::Id> {
         fs::read_dir(&self.dir)
             .map(|entries| {
@@ -198,18 +434,130 @@ impl<T: Chunk> ChunkStore<T> {
 
     async fn do_delete(&mut self, file_path: &Path) -> Result<()> {
         if let Ok(metadata) = fs::metadata(file_path) {
-            self.used_space.decrease(self.id, metadata.len()).await?;
-            fs::remove_file(file_path).map_err(From::from)
+            // Only release the space once the chunk is actually gone.
+            fs::remove_file(file_path)?;
+            self.used_space.decrease(self.id, metadata.len()).await
         } else {
             Ok(())
         }
     }
 
+    fn prune_quarantine(&self, quarantine_dir: &Path) -> Result<()> {
+        let mut files = vec![];
+        for entry in fs::read_dir(quarantine_dir)? {
+            let entry = entry?;
+            files.push((entry.file_name(), entry.path(), entry.metadata()?.len()));
+        }
+        files.sort();
+        let mut total: u64 = files.iter().map(|(_, _, len)| len).sum();
+        for (_, path, len) in files {
+            if total <= self.quarantine_capacity {
+                break;
+            }
+            fs::remove_file(path)?;
+            total -= len;
+        }
+        Ok(())
+    }
+
+    // Writes the chunk to a temporary file next to its final location, without syncing it.
+    async fn write_temp_file(&self, chunk: &T) -> Result<TempChunkFile> {
+        let serialised_chunk = utils::serialise(chunk)?;
+        let consumed_space = serialised_chunk.len() as u64;
+
+        info!("consumed space: {:?}", consumed_space);
+        info!("max : {:?}", self.used_space.max_capacity().await);
+        info!("use space total : {:?}", self.used_space.total().await);
+
+        let file_path = self.file_path(chunk.id())?;
+        self.check_capacity(&file_path, consumed_space).await?;
+
+        let temp_path = file_path.with_extension(TEMP_FILE_EXTENSION);
+        let res = File::create(&temp_path).and_then(|mut file| {
+            file.write_all(&serialised_chunk)?;
+            Ok(file)
+        });
+        match res {
+            Ok(file) => Ok(TempChunkFile {
+                file,
+                temp_path,
+                file_path,
+                consumed_space,
+            }),
+            Err(e) => {
+                info!("Writing chunk failed!");
+                let _ = fs::remove_file(&temp_path);
+                Err(e.into())
+            }
+        }
+    }
+
+    // Moves a synced temporary file into place, accounting for its space.
+    async fn commit_temp_file(&mut self, written: TempChunkFile) -> Result<()> {
+        let TempChunkFile {
+            temp_path,
+            file_path,
+            consumed_space,
+            ..
+        } = written;
+
+        // Swap the accounting of any overwritten chunk for the new one.
+        let released_space = fs::metadata(&file_path).map_or(0, |metadata| metadata.len());
+        self.used_space.decrease(self.id, released_space).await?;
+        if let Err(e) = self.used_space.increase(self.id, consumed_space).await {
+            self.used_space.increase(self.id, released_space).await?;
+            let _ = fs::remove_file(&temp_path);
+            return Err(e);
+        }
+        trace!(
+            "use space total after add: {:?}",
+            self.used_space.total().await
+        );
+
+        match fs::rename(&temp_path, &file_path) {
+            Ok(()) => {
+                info!("Writing chunk succeeded!");
+                Ok(())
+            }
+            Err(e) => {
+                info!("Writing chunk failed!");
+                let _ = fs::remove_file(&temp_path);
+                self.used_space.decrease(self.id, consumed_space).await?;
+                self.used_space.increase(self.id, released_space).await?;
+                Err(e.into())
+            }
+        }
+    }
+
+    async fn check_capacity(&self, file_path: &Path, consumed_space: u64) -> Result<()> {
+        let released_space = fs::metadata(file_path).map_or(0, |metadata| metadata.len());
+        let total = self.used_space.total().await.saturating_sub(released_space);
+        let new_total = total
+            .checked_add(consumed_space)
+            .ok_or(Error::NotEnoughSpace)?;
+        if new_total > self.used_space.max_capacity().await {
+            return Err(Error::NotEnoughSpace);
+        }
+        Ok(())
+    }
+
     fn file_path(&self, id: &T::Id) -> Result<PathBuf> {
         Ok(self.dir.join(&hex::encode(utils::serialise(id)?)))
     }
 }
 
+/// Whether the file at `path`, relative to the root of the stores, is a chunk file, i.e. one
+/// directly within the directory of a `ChunkStore` other than its record of used space.
+pub(crate) fn is_chunk_file(path: &Path) -> bool {
+    let components: Vec<_> = path.components().map(|c| c.as_os_str()).collect();
+    components.len() == 3
+        && components[0] == CHUNK_STORE_DIR
+        && components[2] != USED_SPACE_FILENAME
+        && path
+            .extension()
+            .map_or(true, |ext| ext != TEMP_FILE_EXTENSION)
+}
+
 pub(crate) trait Subdir {
     fn subdir() -> &'static Path;
 }
@@ -8,12 +8,15 @@
 
 use super::{
     chunk::{Chunk, ChunkId},
-    ChunkStore, Result as ChunkStoreResult, Subdir, UsedSpace,
+    ChunkStore, CompactionSummary, Result as ChunkStoreResult, Subdir, UsedSpace, UsedSpaceCheck,
 };
 use crate::{Error, Result, ToDbKey};
 use rand::{distributions::Standard, rngs::ThreadRng, Rng};
 use serde::{Deserialize, Serialize};
-use std::{path::Path, u64};
+use std::{
+    path::{Path, PathBuf},
+    u64,
+};
 use tempdir::TempDir;
 
 #[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -141,6 +144,345 @@ async fn failed_put_when_not_enough_space() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn failed_put_leaves_store_unchanged_when_full() -> Result<()> {
+    let mut rng = new_rng();
+    let root = temp_dir()?;
+    let first = Data {
+        id: Id(0),
+        value: rng.sample_iter(&Standard).take(64).collect(),
+    };
+    let second = Data {
+        id: Id(1),
+        value: rng.sample_iter(&Standard).take(1).collect(),
+    };
+    // Just enough space for the first chunk, i.e. the store is at the brink after storing it.
+    let capacity = bincode::serialized_size(&first).map_err(Error::Bincode)?;
+    let used_space = UsedSpace::new(capacity);
+    let mut chunk_store = ChunkStore::new(root.path(), used_space.clone()).await?;
+
+    chunk_store.put(&first).await?;
+    assert_eq!(chunk_store.total_used_space().await, capacity);
+
+    match chunk_store.can_store(&second).await {
+        Err(Error::NotEnoughSpace) => (),
+        x => return Err(Error::Logic(format!("Unexpected: {:?}", x))),
+    }
+    match chunk_store.put(&second).await {
+        Err(Error::NotEnoughSpace) => (),
+        x => return Err(Error::Logic(format!("Unexpected: {:?}", x))),
+    }
+
+    assert!(!chunk_store.has(&second.id));
+    assert_eq!(chunk_store.keys(), vec![first.id]);
+    assert_eq!(chunk_store.total_used_space().await, capacity);
+    assert_eq!(chunk_store.get(&first.id)?, first);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn put_batch_stores_each_chunk_it_has_space_for() -> Result<()> {
+    let root = temp_dir()?;
+    let batch: Vec<_> = (0..3)
+        .map(|index| Data {
+            id: Id(index),
+            value: vec![index as u8; 10],
+        })
+        .collect();
+    let chunk_size = bincode::serialized_size(&batch[0]).map_err(Error::Bincode)?;
+    let used_space = UsedSpace::new(2 * chunk_size);
+    let mut chunk_store = ChunkStore::new(root.path(), used_space.clone()).await?;
+
+    let results = chunk_store.put_batch(&batch).await;
+    assert!(results[0].is_ok());
+    assert!(results[1].is_ok());
+    match &results[2] {
+        Err(Error::NotEnoughSpace) => (),
+        x => return Err(Error::Logic(format!("Unexpected: {:?}", x))),
+    }
+
+    assert_eq!(chunk_store.get(&Id(0))?, batch[0]);
+    assert_eq!(chunk_store.get(&Id(1))?, batch[1]);
+    assert!(!chunk_store.has(&Id(2)));
+    assert_eq!(used_space.total().await, 2 * chunk_size);
+
+    // No temporary file is left behind by the chunk that didn't fit.
+    assert!(!chunk_store
+        .file_path(&Id(2))?
+        .with_extension(super::TEMP_FILE_EXTENSION)
+        .exists());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn interrupted_put_is_cleaned_up_on_reopen() -> Result<()> {
+    let mut rng = new_rng();
+    let root = temp_dir()?;
+    let data = Data {
+        id: Id(0),
+        value: rng.sample_iter(&Standard).take(64).collect(),
+    };
+    let size = bincode::serialized_size(&data).map_err(Error::Bincode)?;
+
+    let mut chunk_store = ChunkStore::new(root.path(), UsedSpace::new(u64::MAX)).await?;
+    chunk_store.put(&data).await?;
+
+    // Simulate a crash in the middle of writing a second chunk.
+    let temp_file_path = chunk_store
+        .file_path(&Id(1))?
+        .with_extension(super::TEMP_FILE_EXTENSION);
+    std::fs::write(&temp_file_path, &[1, 2, 3])?;
+    drop(chunk_store);
+
+    let used_space = UsedSpace::new(u64::MAX);
+    let chunk_store: ChunkStore<Data> = ChunkStore::new(root.path(), used_space.clone()).await?;
+
+    assert!(!temp_file_path.exists());
+    assert_eq!(chunk_store.keys(), vec![data.id]);
+    assert_eq!(chunk_store.get(&data.id)?, data);
+    assert_eq!(used_space.local(chunk_store.id).await, size);
+
+    Ok(())
+}
+
+// A store holding a chunk, along with the dir of the node keeping the list of known stores.
+async fn known_store_with_a_chunk() -> Result<(TempDir, TempDir, PathBuf, PathBuf)> {
+    let (root, node_root) = (temp_dir()?, temp_dir()?);
+    let used_space = UsedSpace::new(u64::MAX).with_known_stores(node_root.path());
+    let mut chunk_store = ChunkStore::new(root.path(), used_space).await?;
+    let data = Data {
+        id: Id(0),
+        value: vec![1; 64],
+    };
+    chunk_store.put(&data).await?;
+    let file_path = chunk_store.file_path(&data.id)?;
+    let dir = chunk_store.dir.clone();
+    Ok((root, node_root, dir, file_path))
+}
+
+#[tokio::test]
+async fn known_store_gone_missing_is_refused() -> Result<()> {
+    let (root, node_root, dir, _) = known_store_with_a_chunk().await?;
+
+    // As if the volume holding the store didn't mount.
+    std::fs::remove_dir_all(&dir)?;
+    let used_space = UsedSpace::new(u64::MAX).with_known_stores(node_root.path());
+    let result: Result<ChunkStore<Data>> = ChunkStore::new(root.path(), used_space).await;
+    match result {
+        Err(Error::ChunkStoreMissing(_)) => (),
+        Err(error) => return Err(Error::Logic(format!("Unexpected: {:?}", error))),
+        Ok(_) => return Err(Error::Logic("Missing store opened".to_string())),
+    }
+    assert!(!dir.exists());
+    Ok(())
+}
+
+#[tokio::test]
+async fn known_store_emptied_is_refused() -> Result<()> {
+    let (root, node_root, dir, _) = known_store_with_a_chunk().await?;
+
+    std::fs::remove_dir_all(&dir)?;
+    std::fs::create_dir_all(&dir)?;
+    let used_space = UsedSpace::new(u64::MAX).with_known_stores(node_root.path());
+    let result: Result<ChunkStore<Data>> = ChunkStore::new(root.path(), used_space).await;
+    match result {
+        Err(Error::ChunkStoreEmptied(_)) => Ok(()),
+        Err(error) => Err(Error::Logic(format!("Unexpected: {:?}", error))),
+        Ok(_) => Err(Error::Logic("Emptied store opened".to_string())),
+    }
+}
+
+#[tokio::test]
+async fn known_store_missing_a_chunk_is_opened_and_reconciled() -> Result<()> {
+    let (root, node_root, _, file_path) = known_store_with_a_chunk().await?;
+
+    // As if the node stopped after removing the last chunk, before updating its used space.
+    std::fs::remove_file(file_path)?;
+    let used_space = UsedSpace::new(u64::MAX)
+        .with_check(UsedSpaceCheck::Full)
+        .with_known_stores(node_root.path());
+    let chunk_store: ChunkStore<Data> = ChunkStore::new(root.path(), used_space).await?;
+    assert!(chunk_store.keys().is_empty());
+    assert_eq!(chunk_store.local_used_space().await, 0);
+    Ok(())
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn read_only_store_is_refused_on_open() -> Result<()> {
+    use std::{fs, os::unix::fs::PermissionsExt};
+
+    let root = temp_dir()?;
+    let chunk_store: ChunkStore<Data> =
+        ChunkStore::new(root.path(), UsedSpace::new(u64::MAX)).await?;
+    let dir = chunk_store.dir.clone();
+    drop(chunk_store);
+
+    fs::set_permissions(&dir, fs::Permissions::from_mode(0o555))?;
+    // Permissions aren't enforced for the superuser.
+    let enforced = fs::File::create(dir.join("probe")).is_err();
+    let result: Result<ChunkStore<Data>> =
+        ChunkStore::new(root.path(), UsedSpace::new(u64::MAX)).await;
+    fs::set_permissions(&dir, fs::Permissions::from_mode(0o755))?;
+    if !enforced {
+        return Ok(());
+    }
+    match result {
+        Err(Error::ChunkStoreNotWritable(..)) => Ok(()),
+        Err(error) => Err(Error::Logic(format!("Unexpected: {:?}", error))),
+        Ok(_) => Err(Error::Logic("Read-only store opened".to_string())),
+    }
+}
+
+#[tokio::test]
+async fn compact_removes_orphans_and_rebuilds_used_space() -> Result<()> {
+    let mut rng = new_rng();
+    let root = temp_dir()?;
+    let data = Data {
+        id: Id(0),
+        value: rng.sample_iter(&Standard).take(64).collect(),
+    };
+    let size = bincode::serialized_size(&data).map_err(Error::Bincode)?;
+
+    let used_space = UsedSpace::new(u64::MAX);
+    let mut chunk_store = ChunkStore::new(root.path(), used_space.clone()).await?;
+    chunk_store.put(&data).await?;
+
+    // Dirty the store: a stray file, and a used space record drifting from reality.
+    let orphan_path = chunk_store.dir.join("not_a_chunk");
+    std::fs::write(&orphan_path, &[0; 10])?;
+    used_space.increase(chunk_store.id, 100).await?;
+    drop(chunk_store);
+
+    let used_space = UsedSpace::new(u64::MAX);
+    let mut chunk_store: ChunkStore<Data> =
+        ChunkStore::new(root.path(), used_space.clone()).await?;
+    let summary = chunk_store.compact().await?;
+
+    assert_eq!(
+        summary,
+        CompactionSummary {
+            files_scanned: 2,
+            orphans_removed: 1,
+            bytes_reclaimed: 10,
+        }
+    );
+    assert!(!orphan_path.exists());
+    assert_eq!(used_space.local(chunk_store.id).await, size);
+    assert_eq!(chunk_store.get(&data.id)?, data);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn drifted_used_space_is_reconciled_on_reopen() -> Result<()> {
+    let root = temp_dir()?;
+    let data = Data {
+        id: Id(0),
+        value: vec![1; 64],
+    };
+    let size = bincode::serialized_size(&data).map_err(Error::Bincode)?;
+
+    for recorded in &[size + 100, size - 10] {
+        let used_space = UsedSpace::new(u64::MAX);
+        let mut chunk_store = ChunkStore::new(root.path(), used_space.clone()).await?;
+        chunk_store.put(&data).await?;
+        // As left by an unclean shutdown.
+        used_space.set_local(chunk_store.id, *recorded).await?;
+        drop(chunk_store);
+
+        let used_space = UsedSpace::new(u64::MAX);
+        let chunk_store: ChunkStore<Data> =
+            ChunkStore::new(root.path(), used_space.clone()).await?;
+        assert_eq!(used_space.local(chunk_store.id).await, *recorded);
+        drop(chunk_store);
+
+        let used_space = UsedSpace::new(u64::MAX).with_check(UsedSpaceCheck::Full);
+        let chunk_store: ChunkStore<Data> =
+            ChunkStore::new(root.path(), used_space.clone()).await?;
+        assert_eq!(used_space.local(chunk_store.id).await, size);
+        assert_eq!(used_space.total().await, size);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn fast_check_only_reconciles_impossible_used_space() -> Result<()> {
+    let root = temp_dir()?;
+    let data = Data {
+        id: Id(0),
+        value: vec![1; 64],
+    };
+    let size = bincode::serialized_size(&data).map_err(Error::Bincode)?;
+    let capacity = 10 * size;
+
+    for (recorded, reconciled) in &[(size + 10, false), (4 * size, true), (capacity + 1, true)] {
+        let used_space = UsedSpace::new(u64::MAX);
+        let mut chunk_store = ChunkStore::new(root.path(), used_space.clone()).await?;
+        chunk_store.put(&data).await?;
+        used_space.set_local(chunk_store.id, *recorded).await?;
+        drop(chunk_store);
+
+        let used_space = UsedSpace::new(capacity).with_check(UsedSpaceCheck::Fast);
+        let chunk_store: ChunkStore<Data> =
+            ChunkStore::new(root.path(), used_space.clone()).await?;
+        let expected = if *reconciled { size } else { *recorded };
+        assert_eq!(used_space.local(chunk_store.id).await, expected);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn quarantine_releases_space_and_prunes_oldest() -> Result<()> {
+    let mut rng = new_rng();
+    let root = temp_dir()?;
+    let used_space = UsedSpace::new(u64::MAX);
+    let mut chunk_store = ChunkStore::new(root.path(), used_space.clone()).await?;
+
+    let first = Data {
+        id: Id(0),
+        value: rng.sample_iter(&Standard).take(64).collect(),
+    };
+    let second = Data {
+        id: Id(1),
+        value: rng.sample_iter(&Standard).take(64).collect(),
+    };
+    let size = bincode::serialized_size(&first).map_err(Error::Bincode)?;
+    // Room for a single quarantined chunk.
+    chunk_store.set_quarantine_capacity(size);
+
+    chunk_store.put(&first).await?;
+    chunk_store.put(&second).await?;
+    chunk_store.quarantine(&first.id).await?;
+
+    let quarantine_dir = chunk_store.dir.join(super::QUARANTINE_DIR);
+    assert!(!chunk_store.has(&first.id));
+    assert_eq!(chunk_store.keys(), vec![second.id]);
+    assert_eq!(chunk_store.total_used_space().await, size);
+    assert_eq!(std::fs::read_dir(&quarantine_dir)?.count(), 1);
+
+    chunk_store.quarantine(&second.id).await?;
+
+    assert!(chunk_store.keys().is_empty());
+    assert_eq!(chunk_store.total_used_space().await, 0);
+    let quarantined: Vec<_> = std::fs::read_dir(&quarantine_dir)?
+        .map(|entry| entry.map(|entry| entry.file_name()))
+        .collect::<std::io::Result<_>>()?;
+    assert_eq!(quarantined.len(), 1);
+    let second_file_name = chunk_store
+        .file_path(&second.id)?
+        .file_name()
+        .map(|name| name.to_os_string())
+        .ok_or_else(|| Error::Logic("No file name".to_string()))?;
+    assert!(quarantined[0]
+        .to_string_lossy()
+        .ends_with(&*second_file_name.to_string_lossy()));
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn delete() -> Result<()> {
     let mut rng = new_rng();
@@ -192,6 +534,26 @@ async fn put_and_get_value_should_be_same() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn get_value_larger_than_read_segment() -> Result<()> {
+    let mut rng = new_rng();
+    let root = temp_dir()?;
+    let used_space = UsedSpace::new(u64::MAX);
+    let mut chunk_store = ChunkStore::new(root.path(), used_space.clone()).await?;
+
+    let data = Data {
+        id: Id(0),
+        value: rng
+            .sample_iter(&Standard)
+            .take(3 * super::READ_SEGMENT_SIZE + 1)
+            .collect(),
+    };
+    chunk_store.put(&data).await?;
+    assert_eq!(chunk_store.get(&data.id)?, data);
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn overwrite_value() -> Result<()> {
     let mut rng = new_rng();
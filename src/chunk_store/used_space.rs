@@ -8,22 +8,68 @@
 
 use crate::{Error, Result};
 use log::warn;
-use std::{path::Path, sync::Arc};
+use std::{
+    collections::BTreeSet,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 use tokio::{io::AsyncSeekExt, sync::Mutex};
 
-const USED_SPACE_FILENAME: &str = "used_space";
+pub(super) const USED_SPACE_FILENAME: &str = "used_space";
+
+/// Name of the file listing the stores opened before, kept apart from the stores.
+const KNOWN_STORES_FILENAME: &str = "chunk_stores";
 
 /// This holds a record (in-memory and on-disk) of the space used by a single `ChunkStore`, and also
 /// an in-memory record of the total space used by all `ChunkStore`s.
 #[derive(Debug, Clone)]
 pub struct UsedSpace {
     inner: Arc<Mutex<inner::UsedSpace>>,
+    check: UsedSpaceCheck,
+    // The file listing the stores opened before, if kept.
+    known_stores: Option<PathBuf>,
+}
+
+/// How the space recorded as used by a `ChunkStore` is checked against the chunk files it holds
+/// when opened, e.g. after an unclean shutdown left the record out of date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsedSpaceCheck {
+    /// The record is trusted.
+    Off,
+    /// A sample of the files is looked at, and all of them only if the record is obviously
+    /// wrong, i.e. beyond the max capacity or far off the size estimated from the sample.
+    Fast,
+    /// All the files are looked at.
+    Full,
 }
 
 /// Identifies a `ChunkStore` within the larger
 /// used space tracking
 pub type StoreId = u64;
 
+/// Breakdown of the storage used by the local `ChunkStore`s.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageStats {
+    /// The maximum space all stores may consume.
+    pub max_capacity: u64,
+    /// The space consumed by all stores.
+    pub total_used_space: u64,
+    /// The usage of each store.
+    pub stores: Vec<StoreStats>,
+}
+
+/// Storage used by a single `ChunkStore`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoreStats {
+    /// Directory of the store.
+    pub dir: PathBuf,
+    /// The space consumed by the store.
+    pub used_space: u64,
+    /// The number of chunks held by the store.
+    pub chunk_count: u64,
+}
+
 impl UsedSpace {
     /// construct a new used space instance
     /// NOTE: this constructs a new async-safe instance,
@@ -33,6 +79,61 @@ impl UsedSpace {
     pub fn new(max_capacity: u64) -> Self {
         Self {
             inner: Arc::new(Mutex::new(inner::UsedSpace::new(max_capacity))),
+            check: UsedSpaceCheck::Off,
+            known_stores: None,
+        }
+    }
+
+    /// Sets how the stores tracked check the space recorded as used against their files
+    /// when opened. Not at all by default.
+    pub fn with_check(mut self, check: UsedSpaceCheck) -> Self {
+        self.check = check;
+        self
+    }
+
+    /// How the stores tracked check the space recorded as used against their files.
+    pub fn check(&self) -> UsedSpaceCheck {
+        self.check
+    }
+
+    /// Keeps the list of the stores opened in a file in `dir`, apart from the stores, for a
+    /// store gone missing, e.g. as its volume didn't mount, to be told apart from a new one.
+    /// Every store is taken as new by default.
+    pub fn with_known_stores(mut self, dir: &Path) -> Self {
+        self.known_stores = Some(dir.join(KNOWN_STORES_FILENAME));
+        self
+    }
+
+    /// Whether the store at `dir` was opened before.
+    pub fn is_known_store(&self, dir: &Path) -> Result<bool> {
+        Ok(self.read_known_stores()?.contains(dir))
+    }
+
+    /// Lists the store at `dir` among the stores opened, for it to be expected from then on.
+    pub fn add_known_store(&self, dir: &Path) -> Result<()> {
+        let path = match &self.known_stores {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let mut stores = self.read_known_stores()?;
+        if !stores.insert(dir.to_path_buf()) {
+            return Ok(());
+        }
+        let temp_path = path.with_extension("tmp");
+        fs::write(&temp_path, bincode::serialize(&stores)?)?;
+        fs::rename(temp_path, path)?;
+        Ok(())
+    }
+
+    fn read_known_stores(&self) -> Result<BTreeSet<PathBuf>> {
+        let path = match &self.known_stores {
+            Some(path) => path,
+            None => return Ok(BTreeSet::new()),
+        };
+        match fs::read(path) {
+            Ok(bytes) => Ok(bincode::deserialize(&bytes)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(BTreeSet::new()),
+            Err(e) => Err(e.into()),
         }
     }
 
@@ -48,6 +149,12 @@ impl UsedSpace {
         inner::UsedSpace::max_capacity(self.inner.clone()).await
     }
 
+    /// Sets the maximum capacity, e.g. as changed in the config of a running node.
+    /// The space already used is kept even if beyond it, only further writes being refused.
+    pub async fn set_max_capacity(&self, max_capacity: u64) {
+        inner::UsedSpace::set_max_capacity(self.inner.clone(), max_capacity).await
+    }
+
     /// Returns the total used space as a snapshot
     /// Note, due to the async nature of this, the value
     /// may be stale by the time it is read if there are multiple
@@ -80,6 +187,16 @@ impl UsedSpace {
     pub async fn decrease(&self, id: StoreId, released: u64) -> Result<()> {
         inner::UsedSpace::decrease(self.inner.clone(), id, released).await
     }
+
+    /// Returns the used space of each local store along with the number of chunks they hold
+    pub async fn stats(&self) -> StorageStats {
+        inner::UsedSpace::stats(self.inner.clone()).await
+    }
+
+    /// Set the used amount of a single chunk store, adjusting the global used value
+    pub async fn set_local(&self, id: StoreId, used: u64) -> Result<()> {
+        inner::UsedSpace::set_local(self.inner.clone(), id, used).await
+    }
 }
 
 mod inner {
@@ -108,6 +225,8 @@ mod inner {
     /// An entry used to track the used space of a single `ChunkStore`
     #[derive(Debug)]
     struct LocalUsedSpace {
+        // Directory of this one `ChunkStore`.
+        pub dir: PathBuf,
         // Space consumed by this one `ChunkStore`.
         pub local_value: u64,
         // File used to maintain on-disk record of `local_value`.
@@ -150,6 +269,11 @@ mod inner {
             used_space_lock.max_capacity
         }
 
+        /// Sets the maximum value `total_value` can attain
+        pub async fn set_max_capacity(used_space: Arc<Mutex<UsedSpace>>, max_capacity: u64) {
+            used_space.lock().await.max_capacity = max_capacity;
+        }
+
         /// Returns the total used space as a snapshot
         /// Note, due to the async nature of this, the value
         /// may be stale by the time it is read if there are multiple
@@ -171,13 +295,47 @@ mod inner {
                 .map_or(0, |res| res.local_value)
         }
 
+        /// Returns the used space of each local store, counting the chunks
+        /// held in their directories
+        pub async fn stats(used_space: Arc<Mutex<UsedSpace>>) -> StorageStats {
+            let used_space_lock = used_space.lock().await;
+            let mut stores: Vec<_> = used_space_lock
+                .local_stores
+                .values()
+                .map(|local_store| StoreStats {
+                    dir: local_store.dir.clone(),
+                    used_space: local_store.local_value,
+                    chunk_count: count_chunks(&local_store.dir),
+                })
+                .collect();
+            stores.sort_by(|lhs, rhs| lhs.dir.cmp(&rhs.dir));
+            StorageStats {
+                max_capacity: used_space_lock.max_capacity,
+                total_used_space: used_space_lock.total_value,
+                stores,
+            }
+        }
+
         /// Adds a new record for tracking the actions
         /// of a local chunk store as part of the global
-        /// used amount tracking
+        /// used amount tracking.
+        /// The space already used by the store counts toward the global used amount.
+        /// A store already tracked keeps its existing record.
         pub async fn add_local_store<T: AsRef<Path>>(
             used_space: Arc<Mutex<UsedSpace>>,
             dir: T,
         ) -> Result<StoreId> {
+            if let Some(id) = used_space
+                .lock()
+                .await
+                .local_stores
+                .iter()
+                .find(|(_, local_store)| local_store.dir == dir.as_ref())
+                .map(|(id, _)| *id)
+            {
+                return Ok(id);
+            }
+
             let mut local_record = OpenOptions::new()
                 .read(true)
                 .write(true)
@@ -200,10 +358,12 @@ mod inner {
             };
 
             let local_store = LocalUsedSpace {
+                dir: dir.as_ref().to_path_buf(),
                 local_value,
                 local_record,
             };
             let mut used_space_lock = used_space.lock().await;
+            used_space_lock.total_value = used_space_lock.total_value.saturating_add(local_value);
             let id = used_space_lock.next_id;
             used_space_lock.next_id += 1;
             let _ = used_space_lock.local_stores.insert(id, local_store);
@@ -283,6 +443,28 @@ mod inner {
             Ok(())
         }
 
+        /// Asynchronous implementation to set the used space of a local store,
+        /// adjusting the global used value accordingly
+        pub async fn set_local(
+            used_space: Arc<Mutex<UsedSpace>>,
+            id: StoreId,
+            used: u64,
+        ) -> Result<()> {
+            let mut used_space_lock = used_space.lock().await;
+            let local_store = used_space_lock
+                .local_stores
+                .get_mut(&id)
+                .ok_or(Error::NoStoreId)?;
+            let previous_local = local_store.local_value;
+            Self::write_local_to_file(&mut local_store.local_record, used).await?;
+            local_store.local_value = used;
+            used_space_lock.total_value = used_space_lock
+                .total_value
+                .saturating_sub(previous_local)
+                .saturating_add(used);
+            Ok(())
+        }
+
         /// helper to write the contents of local to file
         /// NOTE: For now, ou should hold the lock on the inner while doing this
         /// It's slow, but maintains behaviour from the previous implementation
@@ -300,6 +482,26 @@ mod inner {
     }
 }
 
+// Counts the chunk files in a store directory, i.e. all files but the used space
+// record and the temporary files of interrupted writes.
+fn count_chunks(dir: &Path) -> u64 {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_type().map_or(false, |kind| kind.is_file()))
+                .filter(|entry| entry.file_name() != USED_SPACE_FILENAME)
+                .filter(|entry| {
+                    entry
+                        .path()
+                        .extension()
+                        .map_or(true, |ext| ext != super::TEMP_FILE_EXTENSION)
+                })
+                .count() as u64
+        })
+        .unwrap_or(0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::{Error, Result, UsedSpace};
@@ -365,4 +567,28 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn used_space_is_restored_on_reopen() -> Result<()> {
+        let root_dir = create_temp_root()?;
+        let store_dir = create_temp_store(&root_dir)?;
+
+        let used_space = UsedSpace::new(TEST_STORE_MAX_SIZE);
+        let id = used_space.add_local_store(&store_dir).await?;
+        used_space.increase(id, 100).await?;
+        used_space.decrease(id, 40).await?;
+        // Registering the same store again doesn't count it twice.
+        assert_eq!(id, used_space.add_local_store(&store_dir).await?);
+        assert_eq!(60, used_space.total().await);
+
+        let used_space = UsedSpace::new(TEST_STORE_MAX_SIZE);
+        let _ = used_space.add_local_store(&store_dir).await?;
+        let stats = used_space.stats().await;
+        assert_eq!(60, stats.total_used_space);
+        assert_eq!(1, stats.stores.len());
+        assert_eq!(60, stats.stores[0].used_space);
+        assert_eq!(0, stats.stores[0].chunk_count);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,243 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{chunk_store::BlobChunkStore, utils, Error, Result};
+use async_trait::async_trait;
+use sn_data_types::{Blob, BlobAddress};
+use std::collections::HashMap;
+
+/// Storage of the data chunks held by an Adult.
+#[async_trait]
+pub(crate) trait ChunkBackend: Send + Sync {
+    /// Stores the chunk, overwriting any chunk stored at the same address.
+    async fn put(&mut self, chunk: &Blob) -> Result<()>;
+
+    /// Stores a batch of chunks, returning the outcome for each of them in the order given.
+    async fn put_batch(&mut self, chunks: &[Blob]) -> Vec<Result<()>> {
+        let mut results = Vec::with_capacity(chunks.len());
+        for chunk in chunks {
+            results.push(self.put(chunk).await);
+        }
+        results
+    }
+
+    /// Checks whether the chunk would fit within the max capacity.
+    async fn can_store(&self, chunk: &Blob) -> Result<()>;
+
+    /// Deletes the chunk, doing nothing if it isn't stored.
+    async fn delete(&mut self, address: &BlobAddress) -> Result<()>;
+
+    /// Removes a corrupted chunk from the store, setting it aside where the backend supports it.
+    async fn quarantine(&mut self, address: &BlobAddress) -> Result<()> {
+        self.delete(address).await
+    }
+
+    /// Returns the chunk stored at `address`, or `Error::NoSuchChunk`.
+    fn get(&self, address: &BlobAddress) -> Result<Blob>;
+
+    /// Tests whether a chunk is stored at `address`.
+    fn has(&self, address: &BlobAddress) -> bool;
+
+    /// Hashes the nonce followed by the serialised chunk stored at `address`.
+    fn digest(&self, address: &BlobAddress, nonce: &[u8]) -> Result<[u8; 32]> {
+        let chunk = self.get(address)?;
+        utils::digest(nonce, &utils::serialise(&chunk)?[..])
+    }
+
+    /// Lists the addresses of all stored chunks.
+    fn keys(&self) -> Vec<BlobAddress>;
+
+    /// The number of stored chunks, read from their addresses only.
+    fn count(&self) -> usize {
+        self.keys().len()
+    }
+
+    /// The space consumed by the stored chunks.
+    async fn used_space(&self) -> u64;
+
+    /// Used space to max space ratio.
+    async fn used_space_ratio(&self) -> f64;
+
+    /// Sets the max capacity, the chunks already stored being kept even if beyond it.
+    async fn set_max_capacity(&mut self, max_capacity: u64);
+}
+
+/// The default backend, storing chunks as files on disk.
+#[async_trait]
+impl ChunkBackend for BlobChunkStore {
+    async fn put(&mut self, chunk: &Blob) -> Result<()> {
+        BlobChunkStore::put(self, chunk).await
+    }
+
+    async fn put_batch(&mut self, chunks: &[Blob]) -> Vec<Result<()>> {
+        BlobChunkStore::put_batch(self, chunks).await
+    }
+
+    async fn can_store(&self, chunk: &Blob) -> Result<()> {
+        BlobChunkStore::can_store(self, chunk).await
+    }
+
+    async fn delete(&mut self, address: &BlobAddress) -> Result<()> {
+        BlobChunkStore::delete(self, address).await
+    }
+
+    async fn quarantine(&mut self, address: &BlobAddress) -> Result<()> {
+        BlobChunkStore::quarantine(self, address).await
+    }
+
+    fn get(&self, address: &BlobAddress) -> Result<Blob> {
+        BlobChunkStore::get(self, address)
+    }
+
+    fn has(&self, address: &BlobAddress) -> bool {
+        BlobChunkStore::has(self, address)
+    }
+
+    fn digest(&self, address: &BlobAddress, nonce: &[u8]) -> Result<[u8; 32]> {
+        BlobChunkStore::digest(self, address, nonce)
+    }
+
+    fn keys(&self) -> Vec<BlobAddress> {
+        BlobChunkStore::keys(self)
+    }
+
+    async fn used_space(&self) -> u64 {
+        BlobChunkStore::local_used_space(self).await
+    }
+
+    async fn used_space_ratio(&self) -> f64 {
+        BlobChunkStore::used_space_ratio(self).await
+    }
+
+    async fn set_max_capacity(&mut self, max_capacity: u64) {
+        BlobChunkStore::set_max_capacity(self, max_capacity).await
+    }
+}
+
+/// A backend holding chunks in memory, for tests and ephemeral nodes.
+/// Its chunks are lost when it is dropped.
+pub(crate) struct MemoryChunkStore {
+    chunks: HashMap<BlobAddress, Blob>,
+    used_space: u64,
+    max_capacity: u64,
+}
+
+impl MemoryChunkStore {
+    pub fn new(max_capacity: u64) -> Self {
+        Self {
+            chunks: HashMap::new(),
+            used_space: 0,
+            max_capacity,
+        }
+    }
+
+    // Space accounted for a chunk, the same as when stored on disk.
+    fn consumed_space(chunk: &Blob) -> Result<u64> {
+        Ok(bincode::serialized_size(chunk)?)
+    }
+}
+
+#[async_trait]
+impl ChunkBackend for MemoryChunkStore {
+    async fn put(&mut self, chunk: &Blob) -> Result<()> {
+        self.can_store(chunk).await?;
+        self.delete(chunk.address()).await?;
+        self.used_space += Self::consumed_space(chunk)?;
+        let _ = self.chunks.insert(*chunk.address(), chunk.clone());
+        Ok(())
+    }
+
+    async fn can_store(&self, chunk: &Blob) -> Result<()> {
+        let released_space = match self.chunks.get(chunk.address()) {
+            Some(existing) => Self::consumed_space(existing)?,
+            None => 0,
+        };
+        let new_total = self
+            .used_space
+            .saturating_sub(released_space)
+            .checked_add(Self::consumed_space(chunk)?)
+            .ok_or(Error::NotEnoughSpace)?;
+        if new_total > self.max_capacity {
+            return Err(Error::NotEnoughSpace);
+        }
+        Ok(())
+    }
+
+    async fn delete(&mut self, address: &BlobAddress) -> Result<()> {
+        if let Some(chunk) = self.chunks.remove(address) {
+            self.used_space = self
+                .used_space
+                .saturating_sub(Self::consumed_space(&chunk)?);
+        }
+        Ok(())
+    }
+
+    fn get(&self, address: &BlobAddress) -> Result<Blob> {
+        self.chunks.get(address).cloned().ok_or(Error::NoSuchChunk)
+    }
+
+    fn has(&self, address: &BlobAddress) -> bool {
+        self.chunks.contains_key(address)
+    }
+
+    fn keys(&self) -> Vec<BlobAddress> {
+        self.chunks.keys().copied().collect()
+    }
+
+    fn count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    async fn used_space(&self) -> u64 {
+        self.used_space
+    }
+
+    async fn used_space_ratio(&self) -> f64 {
+        self.used_space as f64 / self.max_capacity as f64
+    }
+
+    async fn set_max_capacity(&mut self, max_capacity: u64) {
+        self.max_capacity = max_capacity;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChunkBackend, MemoryChunkStore};
+    use crate::{Error, Result};
+    use sn_data_types::{Blob, PublicBlob};
+
+    #[tokio::test]
+    async fn memory_store_accounts_for_used_space() -> Result<()> {
+        let chunk = Blob::Public(PublicBlob::new(vec![1; 100]));
+        let size = bincode::serialized_size(&chunk)?;
+        let mut store = MemoryChunkStore::new(size);
+
+        store.put(&chunk).await?;
+        assert!(store.has(chunk.address()));
+        assert_eq!(store.get(chunk.address())?, chunk);
+        assert_eq!(store.keys(), vec![*chunk.address()]);
+        assert!((store.used_space_ratio().await - 1.0).abs() < f64::EPSILON);
+
+        // Overwriting releases the space of the previous copy.
+        store.put(&chunk).await?;
+
+        let other = Blob::Public(PublicBlob::new(vec![2; 1]));
+        match store.put(&other).await {
+            Err(Error::NotEnoughSpace) => (),
+            x => return Err(Error::Logic(format!("Unexpected: {:?}", x))),
+        }
+
+        store.delete(chunk.address()).await?;
+        assert!(!store.has(chunk.address()));
+        assert!(store.used_space_ratio().await < f64::EPSILON);
+        store.put(&other).await?;
+
+        Ok(())
+    }
+}
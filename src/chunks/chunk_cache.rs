@@ -0,0 +1,165 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use sn_data_types::{Blob, BlobAddress};
+use std::collections::{BTreeMap, HashMap};
+
+/// Hit and miss counts of the chunk cache.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    /// The number of reads served from the cache.
+    pub hits: u64,
+    /// The number of reads of chunks not cached.
+    pub misses: u64,
+}
+
+/// Bounded in-memory LRU cache of chunks, sized by the total bytes of the cached values.
+/// Its memory is not accounted for in the used space of the chunk store.
+pub(crate) struct ChunkCache {
+    max_size: u64,
+    size: u64,
+    tick: u64,
+    entries: HashMap<BlobAddress, (Blob, u64)>,
+    // Order of use, least recently used first.
+    order: BTreeMap<u64, BlobAddress>,
+    stats: CacheStats,
+}
+
+impl ChunkCache {
+    /// Creates a cache holding up to `max_size` bytes of chunks. A size of zero disables it.
+    pub fn new(max_size: u64) -> Self {
+        Self {
+            max_size,
+            size: 0,
+            tick: 0,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            stats: CacheStats::default(),
+        }
+    }
+
+    /// Resizes the cache, evicting the least recently used chunks until they fit.
+    pub fn set_max_size(&mut self, max_size: u64) {
+        self.max_size = max_size;
+        while self.size > self.max_size {
+            let lru = match self.order.iter().next() {
+                Some((_, address)) => *address,
+                None => break,
+            };
+            self.remove(&lru);
+        }
+    }
+
+    /// Returns the cached chunk, if any, marking it as most recently used.
+    pub fn get(&mut self, address: &BlobAddress) -> Option<Blob> {
+        if self.max_size == 0 {
+            return None;
+        }
+        let tick = self.next_tick();
+        match self.entries.get_mut(address) {
+            Some((blob, last_used)) => {
+                let _ = self.order.remove(last_used);
+                let _ = self.order.insert(tick, *address);
+                *last_used = tick;
+                self.stats.hits += 1;
+                Some(blob.clone())
+            }
+            None => {
+                self.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Caches the chunk, evicting the least recently used ones until it fits.
+    /// Chunks larger than the whole cache are not cached.
+    pub fn insert(&mut self, blob: &Blob) {
+        let blob_size = blob.value().len() as u64;
+        if blob_size > self.max_size {
+            return;
+        }
+        self.remove(blob.address());
+        while self.size + blob_size > self.max_size {
+            let lru = match self.order.iter().next() {
+                Some((_, address)) => *address,
+                None => break,
+            };
+            self.remove(&lru);
+        }
+        let tick = self.next_tick();
+        let _ = self.order.insert(tick, *blob.address());
+        let _ = self.entries.insert(*blob.address(), (blob.clone(), tick));
+        self.size += blob_size;
+    }
+
+    /// Removes the chunk from the cache, if present.
+    pub fn remove(&mut self, address: &BlobAddress) {
+        if let Some((blob, last_used)) = self.entries.remove(address) {
+            let _ = self.order.remove(&last_used);
+            self.size -= blob.value().len() as u64;
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CacheStats, ChunkCache};
+    use sn_data_types::{Blob, PublicBlob};
+
+    fn chunk(byte: u8, size: usize) -> Blob {
+        Blob::Public(PublicBlob::new(vec![byte; size]))
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut cache = ChunkCache::new(30);
+        let (a, b, c) = (chunk(1, 10), chunk(2, 10), chunk(3, 10));
+        cache.insert(&a);
+        cache.insert(&b);
+        cache.insert(&c);
+
+        // Touch `a` so that `b` becomes the least recently used.
+        assert_eq!(cache.get(a.address()), Some(a.clone()));
+
+        let d = chunk(4, 10);
+        cache.insert(&d);
+
+        assert_eq!(cache.get(b.address()), None);
+        assert_eq!(cache.get(a.address()), Some(a));
+        assert_eq!(cache.get(c.address()), Some(c));
+        assert_eq!(cache.get(d.address()), Some(d));
+        assert_eq!(cache.stats(), CacheStats { hits: 4, misses: 1 });
+    }
+
+    #[test]
+    fn removed_chunk_is_not_served() {
+        let mut cache = ChunkCache::new(100);
+        let a = chunk(1, 10);
+        cache.insert(&a);
+        cache.remove(a.address());
+        assert_eq!(cache.get(a.address()), None);
+    }
+
+    #[test]
+    fn disabled_cache_holds_nothing() {
+        let mut cache = ChunkCache::new(0);
+        let a = chunk(1, 10);
+        cache.insert(&a);
+        assert_eq!(cache.get(a.address()), None);
+        assert_eq!(cache.stats(), CacheStats::default());
+    }
+}
@@ -0,0 +1,251 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::chunk_storage::ChunkStorage;
+use crate::utils;
+use log::{info, warn};
+use sn_data_types::BlobAddress;
+use std::{
+    collections::VecDeque,
+    fs,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+const SCRUB_CURSOR_FILENAME: &str = "scrub_cursor";
+
+/// Progress of the chunk scrubber.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ScrubStats {
+    /// Number of chunks verified since start.
+    pub scrubbed: u64,
+    /// Number of corrupted chunks found since start.
+    pub corrupted: u64,
+    /// Number of errors encountered while scrubbing since start.
+    pub errors: u64,
+    /// Number of complete passes over the store since start.
+    pub passes: u64,
+}
+
+/// Periodically walks the chunk store, verifying that each chunk's content
+/// still matches its address. The position in the store is persisted, so that
+/// a restart resumes where the scrubbing left off.
+pub(crate) struct ChunkScrubber {
+    cursor_path: PathBuf,
+    cursor: Option<BlobAddress>,
+    // The cursor last written to disk.
+    persisted: Option<BlobAddress>,
+    // The addresses listed after the cursor, yet to be verified.
+    page: VecDeque<BlobAddress>,
+    // Whether the addresses listed reach the end of the store.
+    listed_to_end: bool,
+    bucket: TokenBucket,
+    stats: ScrubStats,
+}
+
+impl ChunkScrubber {
+    /// Creates a scrubber verifying up to `rate` chunks per minute. A rate of zero disables it.
+    pub fn new(root: &Path, rate: u64) -> Self {
+        let cursor_path = root.join(SCRUB_CURSOR_FILENAME);
+        let cursor = fs::read(&cursor_path)
+            .ok()
+            .and_then(|bytes| utils::deserialise(&bytes).ok());
+        Self {
+            cursor_path,
+            cursor,
+            persisted: cursor,
+            page: VecDeque::new(),
+            listed_to_end: false,
+            bucket: TokenBucket::new(rate as f64 / 60.0, Instant::now()),
+            stats: ScrubStats::default(),
+        }
+    }
+
+    /// Verifies as many chunks as the rate allows. A corrupted chunk is removed from the store.
+    pub async fn scrub(&mut self, storage: &mut ChunkStorage) {
+        let count = self.bucket.take(Instant::now());
+        self.verify(count, storage).await
+    }
+
+    async fn verify(&mut self, count: usize, storage: &mut ChunkStorage) {
+        for _ in 0..count {
+            let address = match self.next_address(storage) {
+                Some(address) => address,
+                None => break, // nothing stored
+            };
+            self.cursor = Some(address);
+            self.stats.scrubbed += 1;
+            match storage.scrub_chunk(&address).await {
+                Ok(true) => (),
+                Ok(false) => self.stats.corrupted += 1,
+                Err(e) => {
+                    self.stats.errors += 1;
+                    warn!("Error scrubbing chunk {:?}: {:?}", address, e);
+                }
+            }
+        }
+        if self.cursor != self.persisted {
+            self.persist_cursor();
+        }
+    }
+
+    // Takes the next address to verify, listing the store anew only once the addresses
+    // listed before are all verified, and starting a new pass past the last one.
+    fn next_address(&mut self, storage: &ChunkStorage) -> Option<BlobAddress> {
+        loop {
+            if let Some(address) = self.page.pop_front() {
+                return Some(address);
+            }
+            if self.listed_to_end && self.cursor.is_some() {
+                self.stats.passes += 1;
+                info!("Completed chunk scrubbing pass: {:?}", self.stats);
+                self.cursor = None;
+            }
+            let page = storage.list_chunks(self.cursor);
+            // The pass is completed once the last page of the store is verified.
+            self.listed_to_end = page.next.is_none();
+            if page.addresses.is_empty() && self.cursor.is_none() {
+                return None;
+            }
+            self.page = page.addresses.into();
+        }
+    }
+
+    pub fn stats(&self) -> ScrubStats {
+        self.stats
+    }
+
+    fn persist_cursor(&mut self) {
+        let res = match self.cursor {
+            Some(address) => utils::serialise(&address)
+                .and_then(|bytes| fs::write(&self.cursor_path, bytes).map_err(From::from)),
+            None => fs::remove_file(&self.cursor_path).or_else(|e| {
+                if e.kind() == std::io::ErrorKind::NotFound {
+                    Ok(())
+                } else {
+                    Err(e.into())
+                }
+            }),
+        };
+        match res {
+            Ok(()) => self.persisted = self.cursor,
+            Err(e) => warn!("Could not persist chunk scrub cursor: {:?}", e),
+        }
+    }
+}
+
+/// Token bucket throttling the scrubbing, so that it never starves foreground reads.
+struct TokenBucket {
+    rate_per_sec: f64,
+    capacity: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate_per_sec: f64, now: Instant) -> Self {
+        Self {
+            rate_per_sec,
+            // Allow at least one whole token to accumulate for rates below one per second.
+            capacity: rate_per_sec.max(1.0),
+            tokens: 0.0,
+            last_refill: now,
+        }
+    }
+
+    /// Refills the bucket for the time elapsed, and takes all whole tokens available.
+    fn take(&mut self, now: Instant) -> usize {
+        if self.rate_per_sec <= 0.0 {
+            return 0;
+        }
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.last_refill = now;
+        self.tokens = (self.tokens + elapsed * self.rate_per_sec).min(self.capacity);
+        let taken = self.tokens.floor();
+        self.tokens -= taken;
+        taken as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{chunk_backend::MemoryChunkStore, chunk_storage::ChunkStorage},
+        ChunkScrubber, ScrubStats, TokenBucket, SCRUB_CURSOR_FILENAME,
+    };
+    use crate::{Error, Result};
+    use sn_data_types::{Blob, Keypair, PublicBlob};
+    use sn_messaging::{EndUser, MessageId};
+    use std::time::{Duration, Instant};
+    use tempdir::TempDir;
+    use xor_name::XorName;
+
+    #[tokio::test]
+    async fn scrubbing_resumes_from_the_persisted_cursor() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let mut storage = ChunkStorage::new(
+            XorName::random(),
+            Box::new(MemoryChunkStore::new(u64::MAX)),
+            0,
+            Duration::from_secs(10),
+            10,
+            10,
+        );
+        let origin =
+            EndUser::AllClients(Keypair::new_ed25519(&mut rand::thread_rng()).public_key());
+        for value in 0..3_u8 {
+            let data = Blob::Public(PublicBlob::new(vec![value; 64]));
+            let _ = storage.store(&data, MessageId::new(), origin).await?;
+        }
+
+        let mut scrubber = ChunkScrubber::new(root.path(), 0);
+        scrubber.verify(2, &mut storage).await;
+        let cursor = scrubber.cursor;
+        assert!(cursor.is_some());
+        assert!(root.path().join(SCRUB_CURSOR_FILENAME).exists());
+
+        // Restarted, the last chunk is verified, then the next pass starts over.
+        let mut scrubber = ChunkScrubber::new(root.path(), 0);
+        assert_eq!(scrubber.cursor, cursor);
+        scrubber.verify(2, &mut storage).await;
+        assert_eq!(
+            scrubber.stats(),
+            ScrubStats {
+                scrubbed: 2,
+                passes: 1,
+                ..ScrubStats::default()
+            }
+        );
+        assert_eq!(
+            scrubber.cursor,
+            storage.list_chunks(None).addresses.first().copied()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn token_bucket_limits_rate() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(2.0, start);
+        assert_eq!(bucket.take(start), 0);
+        assert_eq!(bucket.take(start + Duration::from_millis(500)), 1);
+        assert_eq!(bucket.take(start + Duration::from_millis(1500)), 2);
+        // Idle time does not accumulate beyond the bucket capacity.
+        assert_eq!(bucket.take(start + Duration::from_secs(60)), 2);
+    }
+
+    #[test]
+    fn token_bucket_accumulates_slow_rates() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(0.5, start);
+        assert_eq!(bucket.take(start + Duration::from_secs(1)), 0);
+        assert_eq!(bucket.take(start + Duration::from_secs(2)), 1);
+    }
+}
@@ -6,15 +6,22 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use super::{
+    chunk_backend::ChunkBackend,
+    chunk_cache::{CacheStats, ChunkCache},
+    replication::{PendingReplications, QueuedReplication, ReplicationStats, Retry},
+    write_batch::WriteBatch,
+};
 use crate::{
-    chunk_store::{BlobChunkStore, UsedSpace},
+    capacity::MAX_CHUNK_SIZE,
+    chunk_store::is_intact,
     error::convert_to_error_message,
-    node_ops::{NodeDuty, OutgoingMsg},
+    node_ops::{NodeDuties, NodeDuty, OutgoingMsg},
     section_funds::elder_signing,
     Error, NodeInfo, Result,
 };
-use log::{error, info};
-use sn_data_types::{Blob, BlobAddress};
+use log::{debug, error, info, warn};
+use sn_data_types::{Blob, BlobAddress, Error as DtError};
 use sn_messaging::{
     client::{
         CmdError, Error as ErrorMessage, Message, NodeDataQueryResponse, NodeQuery,
@@ -26,24 +33,51 @@ use std::{
     collections::BTreeSet,
     env::current_dir,
     fmt::{self, Display, Formatter},
-    path::Path,
+    time::{Duration, Instant},
 };
 use xor_name::XorName;
 
+/// Max number of chunk addresses returned in a single page when listing held chunks.
+const CHUNK_LIST_PAGE_SIZE: usize = 1000;
+
+/// A page of the addresses of chunks held by this node.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ChunkListPage {
+    /// The addresses in this page, in ascending order.
+    pub addresses: Vec<BlobAddress>,
+    /// The address to continue listing after, if there are more chunks.
+    pub next: Option<BlobAddress>,
+}
+
 /// Storage of data chunks.
 pub(crate) struct ChunkStorage {
     node_name: XorName,
-    chunks: BlobChunkStore,
+    chunks: Box<dyn ChunkBackend>,
+    cache: ChunkCache,
+    batch: WriteBatch,
+    replications: PendingReplications,
 }
 
 impl ChunkStorage {
-    pub(crate) async fn new(
+    pub(crate) fn new(
         node_name: XorName,
-        path: &Path,
-        used_space: UsedSpace,
-    ) -> Result<Self> {
-        let chunks = BlobChunkStore::new(path, used_space).await?;
-        Ok(Self { chunks, node_name })
+        chunks: Box<dyn ChunkBackend>,
+        cache_size: u64,
+        replication_timeout: Duration,
+        max_replications: usize,
+        replication_queue_size: usize,
+    ) -> Self {
+        Self {
+            chunks,
+            cache: ChunkCache::new(cache_size),
+            batch: WriteBatch::default(),
+            replications: PendingReplications::new(
+                replication_timeout,
+                max_replications,
+                replication_queue_size,
+            ),
+            node_name,
+        }
     }
 
     pub(crate) async fn store(
@@ -53,24 +87,17 @@ impl ChunkStorage {
         origin: EndUser,
     ) -> Result<NodeDuty> {
         if let Err(error) = self.try_store(data, origin).await {
-            Ok(NodeDuty::Send(OutgoingMsg {
-                msg: Message::CmdError {
-                    error: CmdError::Data(convert_to_error_message(error)?),
-                    id: MessageId::in_response_to(&msg_id),
-                    correlation_id: msg_id,
-                    target_section_pk: None,
-                },
-                section_source: false, // sent as single node
-                dst: DstLocation::EndUser(origin),
-                aggregation: Aggregation::None, // TODO: to_be_aggregated: Aggregation::AtDestination,
-            }))
+            crate::trace_msg!("store_failed", msg_id, origin.id(), "error={:?}", error);
+            Ok(cmd_error(convert_to_error_message(error)?, msg_id, origin))
         } else {
+            crate::trace_msg!("stored", msg_id, origin.id());
             Ok(NodeDuty::NoOp)
         }
     }
 
     async fn try_store(&mut self, data: &Blob, origin: EndUser) -> Result<()> {
         info!("TRYING TO STORE BLOB");
+        validate_size(data)?;
         if data.is_private() {
             let data_owner = data
                 .owner()
@@ -84,28 +111,63 @@ impl ChunkStorage {
             }
         }
 
+        self.store_chunk(data).await
+    }
+
+    /// Stores the chunk, unless it is already held, in which case nothing is written.
+    /// A corrupted copy held is replaced by the chunk.
+    async fn store_chunk(&mut self, data: &Blob) -> Result<()> {
         if self.chunks.has(data.address()) {
+            match self.get_chunk(data.address()).await {
+                Ok(existing) if existing == *data => {
+                    info!(
+                        "{}: Immutable chunk already exists, not storing: {:?}",
+                        self,
+                        data.address()
+                    );
+                    return Ok(());
+                }
+                Ok(_) => {
+                    error!(
+                        "{}: Immutable chunk already exists with different content, keeping ours: {:?}",
+                        self,
+                        data.address()
+                    );
+                    return Err(Error::DataExists);
+                }
+                Err(Error::CorruptedChunk) => warn!(
+                    "{}: Copy held of the chunk was corrupted, storing the one received: {:?}",
+                    self,
+                    data.address()
+                ),
+                Err(error) => return Err(error),
+            }
+        }
+
+        if let Err(error) = self.chunks.can_store(data).await {
             info!(
-                "{}: Immutable chunk already exists, not storing: {:?}",
+                "{}: Not enough space to store chunk: {:?}",
                 self,
                 data.address()
             );
-            return Err(Error::DataExists);
+            return Err(error);
         }
-        self.chunks.put(&data).await
+
+        self.chunks.put(&data).await?;
+        self.cache.insert(data);
+        Ok(())
     }
 
+    /// Serves the chunk to the client, private ones only to their owner, regardless of
+    /// the checks Elders made before forwarding the read.
     pub(crate) async fn get(
-        &self,
+        &mut self,
         address: &BlobAddress,
         msg_id: MessageId,
         origin: EndUser,
-    ) -> Result<NodeDuty> {
-        let result = self
-            .chunks
-            .get(address)
-            .map_err(|_| ErrorMessage::NoSuchData);
-        Ok(NodeDuty::Send(OutgoingMsg {
+    ) -> Result<NodeDuties> {
+        let result = self.read_for(address, origin).await;
+        Ok(vec![NodeDuty::Send(OutgoingMsg {
             msg: Message::QueryResponse {
                 id: MessageId::in_response_to(&msg_id),
                 response: QueryResponse::GetBlob(result),
@@ -115,15 +177,204 @@ impl ChunkStorage {
             section_source: false, // sent as single node
             dst: DstLocation::EndUser(origin),
             aggregation: Aggregation::None, // TODO: to_be_aggregated: Aggregation::AtDestination,
-        }))
+        })])
     }
 
+    // Reads the chunk for the client, private ones being readable by their owner only.
+    async fn read_for(
+        &mut self,
+        address: &BlobAddress,
+        origin: EndUser,
+    ) -> std::result::Result<Blob, ErrorMessage> {
+        match self.get_chunk(address).await {
+            Ok(data) => match data.owner() {
+                Some(owner) if owner != origin.id() => {
+                    warn!(
+                        "{}: Read of private chunk {:?} by non-owner {:?} denied",
+                        self,
+                        address,
+                        origin.id()
+                    );
+                    Err(ErrorMessage::AccessDenied(*origin.id()))
+                }
+                _ => Ok(data),
+            },
+            Err(_) => Err(ErrorMessage::NoSuchData),
+        }
+    }
+
+    /// Starts the replication of the chunk to us, unless too many are already in flight,
+    /// in which case it is queued. Should the queue be full, the replication is reported
+    /// as failed, for Elders to re-issue it later.
+    ///
+    /// A chunk already held intact is not fetched again, its replication being reported
+    /// as completed right away. A chunk already being replicated isn't fetched twice either,
+    /// `msg_id` being answered along with the others once its replication ends.
     pub async fn replicate_chunk(
-        &self,
+        &mut self,
         address: BlobAddress,
         current_holders: BTreeSet<XorName>,
         msg_id: MessageId,
-    ) -> Result<NodeDuty> {
+    ) -> Result<NodeDuties> {
+        if self.has_chunk(&address) {
+            // A corrupted copy is quarantined by the read, to be replicated anew.
+            if self.get_chunk(&address).await.is_ok() {
+                info!(
+                    "{}: Chunk to replicate already held, not fetching it: {:?}",
+                    self, address
+                );
+                return Ok(vec![NodeDuty::ReportReplicationCompleted {
+                    address,
+                    correlation_id: msg_id,
+                }]);
+            }
+        }
+        if self.replications.join(msg_id, address) {
+            info!(
+                "{}: Chunk already being replicated, coalescing: {:?}",
+                self, address
+            );
+            return Ok(vec![]);
+        }
+        if self.replications.has_capacity() {
+            return Ok(self.start_replication(address, current_holders, msg_id));
+        }
+        let queued = QueuedReplication {
+            msg_id,
+            address,
+            holders: current_holders,
+        };
+        match self.replications.enqueue(queued) {
+            Ok(()) => {
+                info!("{}: Queued replication of chunk: {:?}", self, address);
+                Ok(vec![])
+            }
+            Err(_) => {
+                log::warn!(
+                    "{}: Too many replications pending, refusing chunk: {:?}",
+                    self,
+                    address
+                );
+                Ok(vec![NodeDuty::ReportReplicationFailure {
+                    address,
+                    error: ErrorMessage::TooManyEntries,
+                    correlation_id: msg_id,
+                }])
+            }
+        }
+    }
+
+    fn start_replication(
+        &mut self,
+        address: BlobAddress,
+        current_holders: BTreeSet<XorName>,
+        msg_id: MessageId,
+    ) -> NodeDuties {
+        match self
+            .replications
+            .insert(msg_id, address, current_holders, Instant::now())
+        {
+            Some(holder) => {
+                info!("Sending NodeSystemQuery::GetChunk to existing holder");
+                vec![self.query_holder(address, holder, msg_id)]
+            }
+            None => {
+                log::warn!(
+                    "{}: No holders to replicate chunk from: {:?}",
+                    self,
+                    address
+                );
+                // Requesters that joined it while queued are answered too.
+                let mut requesters = self.replications.remove(&address);
+                let _ = requesters.insert(msg_id);
+                report_failure(address, ErrorMessage::NoSuchData, requesters)
+            }
+        }
+    }
+
+    /// Starts the queued replications, oldest first, as slots have freed up.
+    fn start_queued_replications(&mut self) -> NodeDuties {
+        let mut duties = NodeDuties::new();
+        while let Some(queued) = self.replications.dequeue() {
+            duties.extend(self.start_replication(queued.address, queued.holders, queued.msg_id));
+        }
+        duties
+    }
+
+    /// Queries the next holder for a copy of the chunk, after `holder` failed to provide one.
+    /// Once all holders have failed, the chunk is reported as unrecoverable to Elders.
+    pub async fn handle_copy_failure(
+        &mut self,
+        msg_id: MessageId,
+        holder: XorName,
+    ) -> Result<NodeDuties> {
+        match self.replications.fail(&msg_id, &holder) {
+            Some(Retry::Next { address, holder }) => {
+                info!(
+                    "{}: Retrying replication of chunk {:?} from {}",
+                    self, address, holder
+                );
+                Ok(vec![self.query_holder(address, holder, msg_id)])
+            }
+            Some(Retry::Exhausted {
+                address,
+                requesters,
+            }) => {
+                log::warn!(
+                    "{}: All holders failed to provide a copy of chunk: {:?}",
+                    self,
+                    address
+                );
+                Ok(report_failure(
+                    address,
+                    ErrorMessage::NoSuchData,
+                    requesters,
+                ))
+            }
+            None if self.replications.was_completed(&msg_id, Instant::now()) => {
+                debug!(
+                    "{}: Ignoring late response from {} to completed replication: {:?}",
+                    self, holder, msg_id
+                );
+                Ok(vec![])
+            }
+            None => {
+                info!(
+                    "{}: Ignoring copy failure from {} for no pending replication: {:?}",
+                    self, holder, msg_id
+                );
+                Ok(vec![])
+            }
+        }
+    }
+
+    /// The id of the replication cmd for the chunk sent to us by Elders, to answer
+    /// when no cmd is pending, e.g. as it expired before its copy arrived.
+    fn replication_id(&self, address: &BlobAddress) -> MessageId {
+        MessageId::combine(vec![*address.name(), self.node_name])
+    }
+
+    /// Duties reporting the completion of the replication of the chunk to its requesters.
+    fn report_completion(&mut self, address: BlobAddress) -> NodeDuties {
+        let mut requesters = self.replications.complete(&address, Instant::now());
+        if requesters.is_empty() {
+            info!(
+                "{}: Replicated chunk no longer awaited: {:?}",
+                self, address
+            );
+            // Reported even if no longer awaited, as the chunk is held all the same.
+            let _ = requesters.insert(self.replication_id(&address));
+        }
+        requesters
+            .into_iter()
+            .map(|correlation_id| NodeDuty::ReportReplicationCompleted {
+                address,
+                correlation_id,
+            })
+            .collect()
+    }
+
+    fn query_holder(&self, address: BlobAddress, holder: XorName, msg_id: MessageId) -> NodeDuty {
         let msg = Message::NodeQuery {
             query: NodeQuery::System(NodeSystemQuery::GetChunk {
                 address,
@@ -133,58 +384,301 @@ impl ChunkStorage {
             id: msg_id,
             target_section_pk: None,
         };
-        info!("Sending NodeSystemQuery::GetChunk to existing holders");
-
-        Ok(NodeDuty::SendToNodes {
+        NodeDuty::SendToNodes {
             msg,
-            targets: current_holders,
-        })
+            targets: std::iter::once(holder).collect(),
+        }
     }
 
     ///
     pub async fn get_for_replication(
-        &self,
+        &mut self,
         address: BlobAddress,
         msg_id: MessageId,
         new_holder: XorName,
-    ) -> Result<NodeDuty> {
-        let result = match self.chunks.get(&address) {
-            Ok(res) => Ok(res),
-            Err(error) => Err(convert_to_error_message(error)?),
+    ) -> Result<NodeDuties> {
+        let result = match self.get_chunk(&address).await {
+            Ok(data) => Ok(data),
+            Err(error) => {
+                log::warn!("Could not read chunk for replication: {:?}", error);
+                // Let the new holder know, so that it can try the other holders.
+                Err(convert_to_error_message(error)?)
+            }
         };
-
-        if let Ok(data) = result {
-            Ok(NodeDuty::Send(OutgoingMsg {
-                msg: Message::NodeQueryResponse {
-                    response: NodeQueryResponse::Data(NodeDataQueryResponse::GetChunk(Ok(data))),
-                    id: MessageId::in_response_to(&msg_id),
-                    correlation_id: msg_id,
-                    target_section_pk: None,
-                },
-                section_source: false, // sent as single node
-                dst: DstLocation::Node(new_holder),
-                aggregation: Aggregation::None, // TODO: to_be_aggregated: Aggregation::AtDestination,
-            }))
-        } else {
-            log::warn!("Could not read chunk for replication: {:?}", result);
-            Ok(NodeDuty::NoOp)
-        }
+        Ok(vec![NodeDuty::Send(OutgoingMsg {
+            msg: Message::NodeQueryResponse {
+                response: NodeQueryResponse::Data(NodeDataQueryResponse::GetChunk(result)),
+                id: MessageId::in_response_to(&msg_id),
+                correlation_id: msg_id,
+                target_section_pk: None,
+            },
+            section_source: false, // sent as single node
+            dst: DstLocation::Node(new_holder),
+            aggregation: Aggregation::None, // TODO: to_be_aggregated: Aggregation::AtDestination,
+        })])
     }
 
+    /// Queues a chunk received for replication, to be written to the store along with
+    /// the other chunks received around the same time. The batch is flushed right away
+    /// once full, otherwise by `flush_writes` once its max latency has passed.
     ///
-    pub async fn store_for_replication(&mut self, blob: Blob) -> Result<NodeDuty> {
+    /// A chunk received for no replication pending under `msg_id` is dropped, so that no node
+    /// can fill our store with chunks we weren't asked to hold. A chunk other than the one
+    /// requested under `msg_id`, exceeding the max chunk size or
+    /// not matching its own address, is discarded, and the next holder is queried as if
+    /// `holder` had failed.
+    pub async fn store_for_replication(
+        &mut self,
+        blob: Blob,
+        msg_id: MessageId,
+        holder: XorName,
+    ) -> Result<NodeDuties> {
+        let expected = match self.replications.address(&msg_id) {
+            Some(address) => address,
+            None if self.replications.was_completed(&msg_id, Instant::now()) => {
+                debug!(
+                    "{}: Dropping late copy of chunk {:?} from {} for completed replication",
+                    self,
+                    blob.address(),
+                    holder
+                );
+                return Ok(vec![]);
+            }
+            None => {
+                log::warn!(
+                    "{}: Discarding copy of chunk {:?} from {} for no pending replication",
+                    self,
+                    blob.address(),
+                    holder
+                );
+                return Ok(vec![]);
+            }
+        };
+        if expected != *blob.address() || validate_size(&blob).is_err() || !is_intact(&blob) {
+            log::warn!(
+                "{}: Discarding replicated chunk {:?} from {}, expected {:?}",
+                self,
+                blob.address(),
+                holder,
+                expected
+            );
+            return self.handle_copy_failure(msg_id, holder).await;
+        }
+        if self.batch.get(blob.address()).is_some() {
+            // Its completion is reported once written.
+            debug!(
+                "{}: Copy of chunk from {} already queued for writing: {:?}",
+                self,
+                holder,
+                blob.address()
+            );
+            return Ok(vec![]);
+        }
         if self.chunks.has(blob.address()) {
             info!(
                 "{}: Immutable chunk already exists, not storing: {:?}",
                 self,
                 blob.address()
             );
-            return Ok(NodeDuty::NoOp);
+            return Ok(self.report_completion(*blob.address()));
         }
 
-        self.chunks.put(&blob).await?;
+        self.batch.push(blob, Instant::now());
+        if self.batch.is_full() {
+            return Ok(self.flush_batch().await);
+        }
 
-        Ok(NodeDuty::NoOp)
+        Ok(vec![])
+    }
+
+    /// Writes the queued replicated chunks to the store, if their max latency has passed,
+    /// then starts the queued replications for which slots have freed up.
+    pub(crate) async fn flush_writes(&mut self) -> Result<NodeDuties> {
+        let mut duties = NodeDuties::new();
+        if self.batch.is_due(Instant::now()) {
+            duties.extend(self.flush_batch().await);
+        }
+        duties.extend(self.start_queued_replications());
+        Ok(duties)
+    }
+
+    /// Writes all the queued replicated chunks to the store, due or not, e.g. before
+    /// the storage is dropped on promotion to Elder.
+    pub(crate) async fn flush_all_writes(&mut self) -> NodeDuties {
+        self.flush_batch().await
+    }
+
+    /// Writes all the queued replicated chunks to the store and abandons the replications
+    /// still awaiting a copy, reporting them to Elders for them to be re-issued, e.g. before
+    /// the node shuts down.
+    pub(crate) async fn close(&mut self) -> NodeDuties {
+        let mut duties = self.flush_all_writes().await;
+        for (address, requesters) in self.replications.abandon_all() {
+            log::warn!(
+                "{}: Abandoning replication of chunk on close: {:?}",
+                self,
+                address
+            );
+            duties.extend(report_failure(
+                address,
+                ErrorMessage::NoSuchData,
+                requesters,
+            ));
+        }
+        duties
+    }
+
+    /// Writes the queued replicated chunks to the store, returning a duty to report
+    /// the completion of each replication to Elders.
+    async fn flush_batch(&mut self) -> NodeDuties {
+        let mut duties = NodeDuties::new();
+        let chunks = self.batch.take();
+        let results = self.chunks.put_batch(&chunks).await;
+        for (chunk, result) in chunks.iter().zip(results) {
+            match result {
+                Ok(()) => duties.extend(self.report_completion(*chunk.address())),
+                Err(error) => log::warn!(
+                    "{}: Could not store replicated chunk {:?}: {:?}",
+                    self,
+                    chunk.address(),
+                    error
+                ),
+            }
+        }
+        duties
+    }
+
+    /// Drops the replications whose copy hasn't arrived within the timeout,
+    /// returning a duty to report each of them to Elders, along with the duties
+    /// starting the queued replications in their place.
+    pub(crate) fn check_pending_ops(&mut self, now: Instant) -> NodeDuties {
+        let mut duties = NodeDuties::new();
+        for (address, requesters) in self.replications.expire(now) {
+            log::warn!(
+                "{}: Timed out waiting for a copy of chunk to replicate: {:?}",
+                self,
+                address
+            );
+            duties.extend(report_failure(
+                address,
+                ErrorMessage::NoSuchData,
+                requesters,
+            ));
+        }
+        duties.extend(self.start_queued_replications());
+        duties
+    }
+
+    /// Numbers of replications in flight and queued, for diagnostics.
+    pub(crate) fn replication_stats(&self) -> ReplicationStats {
+        self.replications.stats()
+    }
+
+    /// Hashes the nonce followed by the stored chunk, streaming it from the store.
+    pub(crate) fn digest(&self, address: &BlobAddress, nonce: &[u8]) -> Result<[u8; 32]> {
+        self.chunks.digest(address, nonce)
+    }
+
+    /// Whether the chunk is held, be it written to the store or queued for writing.
+    pub(crate) fn has_chunk(&self, address: &BlobAddress) -> bool {
+        self.chunks.has(address) || self.batch.get(address).is_some()
+    }
+
+    /// The number of chunks written to the store, not counting those queued for writing.
+    pub(crate) fn count(&self) -> usize {
+        self.chunks.count()
+    }
+
+    /// The space consumed by the chunks written to the store.
+    pub(crate) async fn used_space(&self) -> u64 {
+        self.chunks.used_space().await
+    }
+
+    /// Reads a chunk from the store and verifies that its content matches its address.
+    /// A chunk failing to deserialise or to match its address is quarantined, and
+    /// `Error::CorruptedChunk` is returned.
+    async fn get_chunk(&mut self, address: &BlobAddress) -> Result<Blob> {
+        if let Some(data) = self.cache.get(address) {
+            return Ok(data);
+        }
+        if let Some(data) = self.batch.get(address) {
+            return Ok(data.clone());
+        }
+        if !self.chunks.has(address) {
+            return Err(Error::NoSuchChunk);
+        }
+        match self.chunks.get(address) {
+            Ok(data) if is_intact(&data) => {
+                self.cache.insert(&data);
+                return Ok(data);
+            }
+            Ok(_) => error!(
+                "{}: Chunk content does not match its address, quarantining it: {:?}",
+                self, address
+            ),
+            Err(error) => error!(
+                "{}: Chunk could not be read, quarantining it: {:?}: {:?}",
+                self, address, error
+            ),
+        }
+        self.chunks.quarantine(address).await?;
+        Err(Error::CorruptedChunk)
+    }
+
+    /// Verifies that the stored content of the chunk matches its address, bypassing the cache.
+    /// A corrupted chunk is removed from the store. Returns whether the chunk is intact
+    /// (a chunk no longer held is not considered corrupted).
+    pub(crate) async fn scrub_chunk(&mut self, address: &BlobAddress) -> Result<bool> {
+        if !self.chunks.has(address) {
+            return Ok(true);
+        }
+        let intact = self
+            .chunks
+            .get(address)
+            .map_or(false, |data| is_intact(&data));
+        if !intact {
+            error!(
+                "{}: Chunk found corrupted while scrubbing, quarantining it: {:?}",
+                self, address
+            );
+            self.cache.remove(address);
+            self.chunks.quarantine(address).await?;
+        }
+        Ok(intact)
+    }
+
+    /// Lists the addresses of the chunks held by this node, in pages of at most
+    /// `CHUNK_LIST_PAGE_SIZE`, starting after the `after` address (or from the first one).
+    /// Used by Elders to reconcile their metadata with the chunks actually held.
+    pub(crate) fn list_chunks(&self, after: Option<BlobAddress>) -> ChunkListPage {
+        let mut addresses = self.chunks.keys();
+        addresses.sort();
+        let mut addresses: Vec<_> = addresses
+            .into_iter()
+            .filter(|address| after.map_or(true, |after| *address > after))
+            .collect();
+        let next = if addresses.len() > CHUNK_LIST_PAGE_SIZE {
+            addresses.truncate(CHUNK_LIST_PAGE_SIZE);
+            addresses.last().copied()
+        } else {
+            None
+        };
+        ChunkListPage { addresses, next }
+    }
+
+    /// Hit and miss counts of the in-memory chunk cache.
+    pub(crate) fn cache_stats(&self) -> CacheStats {
+        self.cache.stats()
+    }
+
+    /// Resizes the in-memory chunk cache.
+    pub(crate) fn set_cache_size(&mut self, cache_size: u64) {
+        self.cache.set_max_size(cache_size)
+    }
+
+    /// Sets the max capacity of the backend.
+    pub(crate) async fn set_max_capacity(&mut self, max_capacity: u64) {
+        self.chunks.set_max_capacity(max_capacity).await
     }
 
     pub async fn used_space_ratio(&self) -> f64 {
@@ -199,12 +693,13 @@ impl ChunkStorage {
     ) -> Result<NodeDuty> {
         if !self.chunks.has(&address) {
             info!("{}: Immutable chunk doesn't exist: {:?}", self, address);
-            return Ok(NodeDuty::NoOp);
+            return Ok(cmd_error(ErrorMessage::NoSuchData, msg_id, origin));
         }
 
         let result = match self.chunks.get(&address) {
             Ok(Blob::Private(data)) => {
                 if data.owner() == origin.id() {
+                    self.cache.remove(&address);
                     self.chunks
                         .delete(&address)
                         .await
@@ -224,24 +719,1107 @@ impl ChunkStorage {
         };
 
         if let Err(error) = result {
-            return Ok(NodeDuty::Send(OutgoingMsg {
-                msg: Message::CmdError {
-                    error: CmdError::Data(error),
-                    id: MessageId::in_response_to(&msg_id),
-                    correlation_id: msg_id,
-                    target_section_pk: None,
-                },
-                section_source: false, // sent as single node
-                dst: DstLocation::EndUser(origin),
-                aggregation: Aggregation::None, // TODO: to_be_aggregated: Aggregation::AtDestination,
-            }));
+            return Ok(cmd_error(error, msg_id, origin));
         }
         Ok(NodeDuty::NoOp)
     }
 }
 
+/// The duty answering a failed write of a chunk with the error, back to its origin.
+fn cmd_error(error: ErrorMessage, msg_id: MessageId, origin: EndUser) -> NodeDuty {
+    NodeDuty::Send(OutgoingMsg {
+        msg: Message::CmdError {
+            error: CmdError::Data(error),
+            id: MessageId::in_response_to(&msg_id),
+            correlation_id: msg_id,
+            target_section_pk: None,
+        },
+        section_source: false, // sent as single node
+        dst: DstLocation::EndUser(origin),
+        aggregation: Aggregation::None, // TODO: to_be_aggregated: Aggregation::AtDestination,
+    })
+}
+
+/// Duties reporting the failure to replicate the chunk to each of its requesters.
+fn report_failure(
+    address: BlobAddress,
+    error: ErrorMessage,
+    requesters: BTreeSet<MessageId>,
+) -> NodeDuties {
+    requesters
+        .into_iter()
+        .map(|correlation_id| NodeDuty::ReportReplicationFailure {
+            address,
+            error: error.clone(),
+            correlation_id,
+        })
+        .collect()
+}
+
+/// Rejects chunks exceeding the max chunk size, which no honest section would send us.
+fn validate_size(data: &Blob) -> Result<()> {
+    if data.value().len() as u64 > MAX_CHUNK_SIZE {
+        error!(
+            "Chunk of {} bytes exceeds the max chunk size: {:?}",
+            data.value().len(),
+            data.address()
+        );
+        return Err(Error::NetworkData(DtError::ExceededSize));
+    }
+    Ok(())
+}
+
 impl Display for ChunkStorage {
     fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
         write!(formatter, "ChunkStorage")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{validate_size, ChunkStorage, CHUNK_LIST_PAGE_SIZE};
+    use crate::{
+        capacity::MAX_CHUNK_SIZE,
+        chunk_store::{BlobChunkStore, UsedSpace},
+        chunks::{replication::ReplicationStats, write_batch::MAX_BATCH_LATENCY},
+        node_ops::{NodeDuties, NodeDuty, OutgoingMsg},
+        Error, Result,
+    };
+    use sn_data_types::{Blob, BlobAddress, Error as DtError, Keypair, PrivateBlob, PublicBlob};
+    use sn_messaging::{
+        client::{
+            CmdError, Error as ErrorMessage, Message, NodeDataQueryResponse, NodeQuery,
+            NodeQueryResponse, NodeSystemQuery, QueryResponse,
+        },
+        DstLocation, EndUser, MessageId,
+    };
+    use std::{
+        collections::{BTreeMap, BTreeSet},
+        fs,
+        path::Path,
+        time::{Duration, Instant},
+    };
+    use tempdir::TempDir;
+    use xor_name::XorName;
+
+    const REPLICATION_TIMEOUT: Duration = Duration::from_secs(10);
+    const MAX_REPLICATIONS: usize = 10;
+    const REPLICATION_QUEUE_SIZE: usize = 10;
+
+    fn temp_dir() -> Result<TempDir> {
+        TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))
+    }
+
+    fn new_storage(chunks: BlobChunkStore) -> ChunkStorage {
+        ChunkStorage::new(
+            XorName::random(),
+            Box::new(chunks),
+            0,
+            REPLICATION_TIMEOUT,
+            MAX_REPLICATIONS,
+            REPLICATION_QUEUE_SIZE,
+        )
+    }
+
+    fn client() -> EndUser {
+        EndUser::AllClients(Keypair::new_ed25519(&mut rand::thread_rng()).public_key())
+    }
+
+    // Replicates `data`, handing its copy over as if received from the queried holder.
+    async fn replicate(storage: &mut ChunkStorage, data: &Blob) -> Result<NodeDuties> {
+        let holder = XorName::random();
+        let msg_id = MessageId::new();
+        let _ = storage
+            .replicate_chunk(*data.address(), std::iter::once(holder).collect(), msg_id)
+            .await?;
+        storage
+            .store_for_replication(data.clone(), msg_id, holder)
+            .await
+    }
+
+    // The error of the failed write answered by the duty, checking it goes back to its origin.
+    fn write_error(duty: NodeDuty, msg_id: MessageId, origin: EndUser) -> Result<ErrorMessage> {
+        match duty {
+            NodeDuty::Send(out) => match (out.msg, out.dst) {
+                (
+                    Message::CmdError {
+                        error: CmdError::Data(error),
+                        correlation_id,
+                        ..
+                    },
+                    DstLocation::EndUser(dst),
+                ) if correlation_id == msg_id && dst == origin => Ok(error),
+                other => Err(Error::Logic(format!("Unexpected: {:?}", other))),
+            },
+            other => Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+    }
+
+    // Flips a byte of the chunk value in every chunk file found under `root`.
+    fn corrupt_chunk_files(root: &Path, value: &[u8]) -> Result<()> {
+        let dir = root.join("chunks").join("immutable");
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let mut contents = fs::read(&path)?;
+            if let Some(pos) = contents
+                .windows(value.len())
+                .position(|window| window == value)
+            {
+                contents[pos] ^= 0xff;
+                fs::write(&path, contents)?;
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn chunk_at_max_size_is_accepted() -> Result<()> {
+        let data = Blob::Public(PublicBlob::new(vec![0; MAX_CHUNK_SIZE as usize]));
+        validate_size(&data)
+    }
+
+    #[test]
+    fn chunk_over_max_size_is_rejected() -> Result<()> {
+        let data = Blob::Public(PublicBlob::new(vec![0; MAX_CHUNK_SIZE as usize + 1]));
+        match validate_size(&data) {
+            Err(Error::NetworkData(DtError::ExceededSize)) => Ok(()),
+            x => Err(Error::Logic(format!("Unexpected: {:?}", x))),
+        }
+    }
+
+    #[tokio::test]
+    async fn storing_held_chunk_again_does_not_rewrite_it() -> Result<()> {
+        let root = temp_dir()?;
+        let used_space = UsedSpace::new(u64::MAX);
+        let chunks = BlobChunkStore::new(root.path(), used_space.clone()).await?;
+        let mut storage = new_storage(chunks);
+
+        let data = Blob::Public(PublicBlob::new(vec![3_u8; 64]));
+        storage.store_chunk(&data).await?;
+        let used_once = used_space.total().await;
+        assert!(used_once > 0);
+
+        storage.store_chunk(&data).await?;
+        assert_eq!(used_space.total().await, used_once);
+        assert_eq!(storage.get_chunk(data.address()).await?, data);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn replicated_chunks_are_written_in_batches() -> Result<()> {
+        let root = temp_dir()?;
+        let used_space = UsedSpace::new(u64::MAX);
+        let chunks = BlobChunkStore::new(root.path(), used_space.clone()).await?;
+        let mut storage = new_storage(chunks);
+
+        let data = Blob::Public(PublicBlob::new(vec![5_u8; 64]));
+        let _ = replicate(&mut storage, &data).await?;
+
+        // Queued chunks are served, though not yet written.
+        assert!(!storage.chunks.has(data.address()));
+        assert_eq!(storage.get_chunk(data.address()).await?, data);
+
+        // Not due until its max latency has passed.
+        let _ = storage.flush_writes().await?;
+        assert!(!storage.chunks.has(data.address()));
+
+        tokio::time::sleep(MAX_BATCH_LATENCY).await;
+        let _ = storage.flush_writes().await?;
+        assert!(storage.chunks.has(data.address()));
+        assert!(storage.batch.is_empty());
+        assert!(used_space.total().await > 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn queued_chunks_are_kept_when_storage_is_dropped() -> Result<()> {
+        let root = temp_dir()?;
+        let chunks = BlobChunkStore::new(root.path(), UsedSpace::new(u64::MAX)).await?;
+        let mut storage = new_storage(chunks);
+
+        let data = Blob::Public(PublicBlob::new(vec![6_u8; 64]));
+        let _ = replicate(&mut storage, &data).await?;
+        match storage.flush_all_writes().await.as_slice() {
+            [NodeDuty::ReportReplicationCompleted { address, .. }] if address == data.address() => {
+            }
+            x => return Err(Error::Logic(format!("Unexpected: {:?}", x))),
+        }
+        drop(storage);
+
+        let used_space = UsedSpace::new(u64::MAX);
+        let chunks = BlobChunkStore::new(root.path(), used_space.clone()).await?;
+        assert!(chunks.has(data.address()));
+        assert!(used_space.total().await > 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn pending_state_is_settled_on_close() -> Result<()> {
+        let root = temp_dir()?;
+        let chunks = BlobChunkStore::new(root.path(), UsedSpace::new(u64::MAX)).await?;
+        let mut storage = new_storage(chunks);
+
+        let queued = Blob::Public(PublicBlob::new(vec![8_u8; 64]));
+        let _ = replicate(&mut storage, &queued).await?;
+        let awaited = Blob::Public(PublicBlob::new(vec![9_u8; 64]));
+        let msg_id = MessageId::new();
+        let _ = storage
+            .replicate_chunk(
+                *awaited.address(),
+                std::iter::once(XorName::random()).collect(),
+                msg_id,
+            )
+            .await?;
+
+        match storage.close().await.as_slice() {
+            [NodeDuty::ReportReplicationCompleted { address, .. }, NodeDuty::ReportReplicationFailure {
+                address: failed,
+                correlation_id,
+                ..
+            }] if address == queued.address()
+                && failed == awaited.address()
+                && *correlation_id == msg_id => {}
+            x => return Err(Error::Logic(format!("Unexpected: {:?}", x))),
+        }
+        assert!(storage.replications.is_empty());
+        drop(storage);
+
+        let chunks = BlobChunkStore::new(root.path(), UsedSpace::new(u64::MAX)).await?;
+        assert!(chunks.has(queued.address()));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn replication_of_held_chunk_completes_without_fetching() -> Result<()> {
+        let root = temp_dir()?;
+        let chunks = BlobChunkStore::new(root.path(), UsedSpace::new(u64::MAX)).await?;
+        let mut storage = new_storage(chunks);
+
+        let data = Blob::Public(PublicBlob::new(vec![10_u8; 64]));
+        storage.store_chunk(&data).await?;
+        assert!(storage.has_chunk(data.address()));
+
+        let holders = std::iter::once(XorName::random()).collect();
+        match storage
+            .replicate_chunk(*data.address(), holders, MessageId::new())
+            .await?
+            .as_slice()
+        {
+            [NodeDuty::ReportReplicationCompleted { address, .. }] if address == data.address() => {
+            }
+            x => return Err(Error::Logic(format!("Unexpected: {:?}", x))),
+        }
+        assert!(storage.replications.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn replication_of_chunk_not_held_queries_holder() -> Result<()> {
+        let root = temp_dir()?;
+        let chunks = BlobChunkStore::new(root.path(), UsedSpace::new(u64::MAX)).await?;
+        let mut storage = new_storage(chunks);
+
+        let data = Blob::Public(PublicBlob::new(vec![11_u8; 64]));
+        assert!(!storage.has_chunk(data.address()));
+
+        let holder = XorName::random();
+        let msg_id = MessageId::new();
+        match storage
+            .replicate_chunk(*data.address(), std::iter::once(holder).collect(), msg_id)
+            .await?
+            .as_slice()
+        {
+            [NodeDuty::SendToNodes { targets, .. }] if targets.contains(&holder) => {}
+            x => return Err(Error::Logic(format!("Unexpected: {:?}", x))),
+        }
+        assert_eq!(storage.replications.address(&msg_id), Some(*data.address()));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn replication_of_corrupted_chunk_fetches_it_anew() -> Result<()> {
+        let root = temp_dir()?;
+        let used_space = UsedSpace::new(u64::MAX);
+        let mut chunks = BlobChunkStore::new(root.path(), used_space).await?;
+        chunks.set_quarantine_capacity(u64::MAX);
+        let mut storage = new_storage(chunks);
+
+        let value = vec![12_u8; 64];
+        let data = Blob::Public(PublicBlob::new(value.clone()));
+        storage.chunks.put(&data).await?;
+        corrupt_chunk_files(root.path(), &value)?;
+
+        let msg_id = MessageId::new();
+        let holders = std::iter::once(XorName::random()).collect();
+        match storage
+            .replicate_chunk(*data.address(), holders, msg_id)
+            .await?
+            .as_slice()
+        {
+            [NodeDuty::SendToNodes { .. }] => {}
+            x => return Err(Error::Logic(format!("Unexpected: {:?}", x))),
+        }
+        assert!(!storage.has_chunk(data.address()));
+        assert_eq!(storage.replications.address(&msg_id), Some(*data.address()));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn overlapping_replications_of_chunk_are_coalesced() -> Result<()> {
+        let root = temp_dir()?;
+        let chunks = BlobChunkStore::new(root.path(), UsedSpace::new(u64::MAX)).await?;
+        let mut storage = new_storage(chunks);
+
+        let data = Blob::Public(PublicBlob::new(vec![14_u8; 64]));
+        let holder = XorName::random();
+        let first = MessageId::new();
+        let second = MessageId::new();
+        let duties = storage
+            .replicate_chunk(*data.address(), std::iter::once(holder).collect(), first)
+            .await?;
+        assert_eq!(queried_holders(duties)?.len(), 1);
+        // Not fetched a second time.
+        assert!(storage
+            .replicate_chunk(*data.address(), std::iter::once(holder).collect(), second)
+            .await?
+            .is_empty());
+        assert_eq!(storage.replication_stats().in_flight, 1);
+
+        let _ = storage
+            .store_for_replication(data.clone(), first, holder)
+            .await?;
+        let answered: BTreeSet<_> = storage
+            .flush_batch()
+            .await
+            .into_iter()
+            .map(|duty| match duty {
+                NodeDuty::ReportReplicationCompleted {
+                    address,
+                    correlation_id,
+                } if address == *data.address() => Ok(correlation_id),
+                x => Err(Error::Logic(format!("Unexpected: {:?}", x))),
+            })
+            .collect::<Result<_>>()?;
+        assert_eq!(answered, vec![first, second].into_iter().collect());
+        assert!(storage.replications.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn replication_without_response_expires() -> Result<()> {
+        let root = temp_dir()?;
+        let chunks = BlobChunkStore::new(root.path(), UsedSpace::new(u64::MAX)).await?;
+        let mut storage = new_storage(chunks);
+
+        let data = Blob::Public(PublicBlob::new(vec![6_u8; 64]));
+        let holders: BTreeSet<_> = vec![XorName::random()].into_iter().collect();
+        let _ = storage
+            .replicate_chunk(*data.address(), holders, MessageId::new())
+            .await?;
+
+        assert!(storage.check_pending_ops(Instant::now()).is_empty());
+        match storage
+            .check_pending_ops(Instant::now() + REPLICATION_TIMEOUT)
+            .as_slice()
+        {
+            [NodeDuty::ReportReplicationFailure { address, .. }] if address == data.address() => (),
+            x => return Err(Error::Logic(format!("Unexpected: {:?}", x))),
+        }
+        // Reported only once.
+        assert!(storage.replications.is_empty());
+        assert!(storage
+            .check_pending_ops(Instant::now() + REPLICATION_TIMEOUT)
+            .is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn replications_beyond_limit_are_queued_then_refused() -> Result<()> {
+        let root = temp_dir()?;
+        let chunks = BlobChunkStore::new(root.path(), UsedSpace::new(u64::MAX)).await?;
+        let mut storage = ChunkStorage::new(
+            XorName::random(),
+            Box::new(chunks),
+            0,
+            REPLICATION_TIMEOUT,
+            1,
+            1,
+        );
+
+        let addresses: Vec<_> = (0..3_u8)
+            .map(|byte| *PublicBlob::new(vec![byte; 64]).address())
+            .collect();
+        let mut duties = vec![];
+        for address in &addresses {
+            let holders = std::iter::once(XorName::random()).collect();
+            duties.extend(
+                storage
+                    .replicate_chunk(*address, holders, MessageId::new())
+                    .await?,
+            );
+        }
+        match duties.as_slice() {
+            [NodeDuty::SendToNodes { .. }, NodeDuty::ReportReplicationFailure {
+                address,
+                error: ErrorMessage::TooManyEntries,
+                ..
+            }] if *address == addresses[2] => {}
+            x => return Err(Error::Logic(format!("Unexpected: {:?}", x))),
+        }
+
+        // The queued replication starts once the one in flight times out.
+        match storage
+            .check_pending_ops(Instant::now() + REPLICATION_TIMEOUT)
+            .as_slice()
+        {
+            [NodeDuty::ReportReplicationFailure { address, .. }, NodeDuty::SendToNodes { .. }]
+                if *address == addresses[0] => {}
+            x => return Err(Error::Logic(format!("Unexpected: {:?}", x))),
+        }
+        assert_eq!(
+            storage.replication_stats(),
+            ReplicationStats {
+                in_flight: 1,
+                queued: 0
+            }
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn copies_from_other_holders_are_dropped_once_replication_completes() -> Result<()> {
+        let root = temp_dir()?;
+        let chunks = BlobChunkStore::new(root.path(), UsedSpace::new(u64::MAX)).await?;
+        let mut storage = new_storage(chunks);
+
+        let data = Blob::Public(PublicBlob::new(vec![11_u8; 64]));
+        let holders: Vec<_> = (0..3).map(|_| XorName::random()).collect();
+        let msg_id = MessageId::new();
+        let _ = storage
+            .replicate_chunk(*data.address(), holders.iter().cloned().collect(), msg_id)
+            .await?;
+
+        for holder in &holders[..2] {
+            assert!(storage
+                .store_for_replication(data.clone(), msg_id, *holder)
+                .await?
+                .is_empty());
+        }
+        match storage.flush_batch().await.as_slice() {
+            [NodeDuty::ReportReplicationCompleted { correlation_id, .. }]
+                if *correlation_id == msg_id => {}
+            x => return Err(Error::Logic(format!("Unexpected: {:?}", x))),
+        }
+
+        // Late answers, be they copies or failures, are neither stored nor reported again.
+        assert!(storage
+            .store_for_replication(data.clone(), msg_id, holders[2])
+            .await?
+            .is_empty());
+        assert!(storage
+            .handle_copy_failure(msg_id, holders[2])
+            .await?
+            .is_empty());
+        assert!(storage.flush_batch().await.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn replication_completes_once_copy_is_stored() -> Result<()> {
+        let root = temp_dir()?;
+        let chunks = BlobChunkStore::new(root.path(), UsedSpace::new(u64::MAX)).await?;
+        let mut storage = new_storage(chunks);
+
+        let data = Blob::Public(PublicBlob::new(vec![7_u8; 64]));
+        let holder = XorName::random();
+        let msg_id = MessageId::new();
+        let _ = storage
+            .replicate_chunk(*data.address(), std::iter::once(holder).collect(), msg_id)
+            .await?;
+
+        let _ = storage
+            .store_for_replication(data.clone(), msg_id, holder)
+            .await?;
+        // Still pending until the copy is written.
+        assert!(!storage.replications.is_empty());
+        match storage.flush_batch().await.as_slice() {
+            [NodeDuty::ReportReplicationCompleted {
+                address,
+                correlation_id,
+            }] if address == data.address() && *correlation_id == msg_id => {}
+            x => return Err(Error::Logic(format!("Unexpected: {:?}", x))),
+        }
+
+        assert!(storage.replications.is_empty());
+        assert!(storage
+            .check_pending_ops(Instant::now() + REPLICATION_TIMEOUT)
+            .is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn late_copy_is_dropped_after_expiry() -> Result<()> {
+        let root = temp_dir()?;
+        let chunks = BlobChunkStore::new(root.path(), UsedSpace::new(u64::MAX)).await?;
+        let mut storage = new_storage(chunks);
+
+        let data = Blob::Public(PublicBlob::new(vec![8_u8; 64]));
+        let holder = XorName::random();
+        let msg_id = MessageId::new();
+        let _ = storage
+            .replicate_chunk(*data.address(), std::iter::once(holder).collect(), msg_id)
+            .await?;
+        assert_eq!(
+            storage
+                .check_pending_ops(Instant::now() + REPLICATION_TIMEOUT)
+                .len(),
+            1
+        );
+
+        // The replication was reported failed, for Elders to issue it anew, so the copy
+        // arriving after the timeout isn't held.
+        assert!(storage
+            .store_for_replication(data.clone(), msg_id, holder)
+            .await?
+            .is_empty());
+        let _ = storage.flush_batch().await;
+
+        assert!(!storage.chunks.has(data.address()));
+        assert!(storage.replications.is_empty());
+
+        Ok(())
+    }
+
+    // The holders queried by a duty to send a message to nodes.
+    fn queried_holders(duties: NodeDuties) -> Result<BTreeSet<XorName>> {
+        match duties.as_slice() {
+            [NodeDuty::SendToNodes { targets, .. }] => Ok(targets.clone()),
+            x => Err(Error::Logic(format!("Unexpected: {:?}", x))),
+        }
+    }
+
+    #[tokio::test]
+    async fn replication_retries_next_holder_on_failure() -> Result<()> {
+        let root = temp_dir()?;
+        let chunks = BlobChunkStore::new(root.path(), UsedSpace::new(u64::MAX)).await?;
+        let mut storage = new_storage(chunks);
+
+        let data = Blob::Public(PublicBlob::new(vec![10_u8; 64]));
+        let holders: BTreeSet<_> = (0..2).map(|_| XorName::random()).collect();
+        let msg_id = MessageId::new();
+        let duties = storage
+            .replicate_chunk(*data.address(), holders.clone(), msg_id)
+            .await?;
+        let first = queried_holders(duties)?;
+        assert_eq!(first.len(), 1);
+
+        let failed = *first
+            .iter()
+            .next()
+            .ok_or_else(|| Error::Logic("No holder queried".to_string()))?;
+        let duties = storage.handle_copy_failure(msg_id, failed).await?;
+        let second = queried_holders(duties)?;
+        assert_eq!(
+            first.union(&second).cloned().collect::<BTreeSet<_>>(),
+            holders
+        );
+        let holder = *second
+            .iter()
+            .next()
+            .ok_or_else(|| Error::Logic("No holder queried".to_string()))?;
+
+        let _ = storage
+            .store_for_replication(data.clone(), msg_id, holder)
+            .await?;
+        let _ = storage.flush_batch().await;
+        assert!(storage.chunks.has(data.address()));
+        assert!(storage.replications.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn replicated_chunk_not_requested_is_discarded() -> Result<()> {
+        let root = temp_dir()?;
+        let chunks = BlobChunkStore::new(root.path(), UsedSpace::new(u64::MAX)).await?;
+        let mut storage = new_storage(chunks);
+
+        let data = Blob::Public(PublicBlob::new(vec![12_u8; 64]));
+        let other = Blob::Public(PublicBlob::new(vec![13_u8; 64]));
+        let holders: BTreeSet<_> = (0..2).map(|_| XorName::random()).collect();
+        let msg_id = MessageId::new();
+        let duties = storage
+            .replicate_chunk(*data.address(), holders, msg_id)
+            .await?;
+        let first = queried_holders(duties)?;
+        let holder = *first
+            .iter()
+            .next()
+            .ok_or_else(|| Error::Logic("No holder queried".to_string()))?;
+
+        // A valid chunk, but not the one requested, moves on to the next holder.
+        let duties = storage
+            .store_for_replication(other.clone(), msg_id, holder)
+            .await?;
+        let second = queried_holders(duties)?;
+        assert!(second.is_disjoint(&first));
+        assert_eq!(storage.replications.address(&msg_id), Some(*data.address()));
+
+        let _ = storage.flush_batch().await;
+        assert!(!storage.chunks.has(other.address()));
+        assert!(!storage.chunks.has(data.address()));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn replicated_chunk_without_pending_replication_is_dropped() -> Result<()> {
+        let root = temp_dir()?;
+        let used_space = UsedSpace::new(u64::MAX);
+        let chunks = BlobChunkStore::new(root.path(), used_space.clone()).await?;
+        let mut storage = new_storage(chunks);
+
+        let data = Blob::Public(PublicBlob::new(vec![15_u8; 64]));
+        assert!(storage
+            .store_for_replication(data.clone(), MessageId::new(), XorName::random())
+            .await?
+            .is_empty());
+        assert!(storage.flush_all_writes().await.is_empty());
+        assert!(!storage.chunks.has(data.address()));
+        assert_eq!(used_space.total().await, 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn oversized_replicated_chunk_moves_on_to_next_holder() -> Result<()> {
+        let root = temp_dir()?;
+        let chunks = BlobChunkStore::new(root.path(), UsedSpace::new(u64::MAX)).await?;
+        let mut storage = new_storage(chunks);
+
+        let data = Blob::Public(PublicBlob::new(vec![14_u8; MAX_CHUNK_SIZE as usize + 1]));
+        let holders: BTreeSet<_> = (0..2).map(|_| XorName::random()).collect();
+        let msg_id = MessageId::new();
+        let duties = storage
+            .replicate_chunk(*data.address(), holders, msg_id)
+            .await?;
+        let first = queried_holders(duties)?;
+        let holder = *first
+            .iter()
+            .next()
+            .ok_or_else(|| Error::Logic("No holder queried".to_string()))?;
+
+        let duties = storage
+            .store_for_replication(data.clone(), msg_id, holder)
+            .await?;
+        let second = queried_holders(duties)?;
+        assert!(second.is_disjoint(&first));
+
+        let _ = storage.flush_batch().await;
+        assert!(!storage.chunks.has(data.address()));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn replication_fails_once_all_holders_fail() -> Result<()> {
+        let root = temp_dir()?;
+        let chunks = BlobChunkStore::new(root.path(), UsedSpace::new(u64::MAX)).await?;
+        let mut storage = new_storage(chunks);
+
+        let data = Blob::Public(PublicBlob::new(vec![11_u8; 64]));
+        let holders: BTreeSet<_> = (0..2).map(|_| XorName::random()).collect();
+        let msg_id = MessageId::new();
+        let mut duties = storage
+            .replicate_chunk(*data.address(), holders.clone(), msg_id)
+            .await?;
+
+        for _ in 0..holders.len() {
+            let holder = *queried_holders(duties)?
+                .iter()
+                .next()
+                .ok_or_else(|| Error::Logic("No holder queried".to_string()))?;
+            // A failure from a holder not being queried is ignored.
+            assert!(storage
+                .handle_copy_failure(msg_id, XorName::random())
+                .await?
+                .is_empty());
+            duties = storage.handle_copy_failure(msg_id, holder).await?;
+        }
+
+        match duties.as_slice() {
+            [NodeDuty::ReportReplicationFailure { address, .. }] if address == data.address() => (),
+            x => return Err(Error::Logic(format!("Unexpected: {:?}", x))),
+        }
+        assert!(storage.replications.is_empty());
+
+        Ok(())
+    }
+
+    // Delivers the msgs sent by the adults to one another, each duty being tagged with the
+    // adult it's from, and returns the reports meant for the Elders.
+    async fn route_between_adults(
+        adults: &mut BTreeMap<XorName, ChunkStorage>,
+        mut duties: Vec<(XorName, NodeDuty)>,
+    ) -> Result<NodeDuties> {
+        let mut reports = NodeDuties::new();
+        while let Some((sender, duty)) = duties.pop() {
+            let (dst, next) = match duty {
+                NodeDuty::SendToNodes {
+                    targets,
+                    msg:
+                        Message::NodeQuery {
+                            query:
+                                NodeQuery::System(NodeSystemQuery::GetChunk {
+                                    address,
+                                    new_holder,
+                                    ..
+                                }),
+                            id,
+                            ..
+                        },
+                } => {
+                    let mut next = NodeDuties::new();
+                    for target in &targets {
+                        let holder = adults
+                            .get_mut(target)
+                            .ok_or_else(|| Error::Logic(format!("Unknown adult {}", target)))?;
+                        next.extend(holder.get_for_replication(address, id, new_holder).await?);
+                    }
+                    (targets.into_iter().next(), next)
+                }
+                NodeDuty::Send(OutgoingMsg {
+                    msg:
+                        Message::NodeQueryResponse {
+                            response:
+                                NodeQueryResponse::Data(NodeDataQueryResponse::GetChunk(result)),
+                            correlation_id,
+                            ..
+                        },
+                    dst: DstLocation::Node(dst),
+                    ..
+                }) => {
+                    let new_holder = adults
+                        .get_mut(&dst)
+                        .ok_or_else(|| Error::Logic(format!("Unknown adult {}", dst)))?;
+                    let mut next = match result {
+                        Ok(data) => {
+                            new_holder
+                                .store_for_replication(data, correlation_id, sender)
+                                .await?
+                        }
+                        Err(_) => {
+                            new_holder
+                                .handle_copy_failure(correlation_id, sender)
+                                .await?
+                        }
+                    };
+                    next.extend(new_holder.flush_batch().await);
+                    (Some(dst), next)
+                }
+                report => {
+                    reports.push(report);
+                    continue;
+                }
+            };
+            let dst = dst.ok_or_else(|| Error::Logic("Msg without target".to_string()))?;
+            duties.extend(next.into_iter().map(|duty| (dst, duty)));
+        }
+        Ok(reports)
+    }
+
+    #[tokio::test]
+    async fn chunk_is_replicated_between_adults_from_the_holder_having_it() -> Result<()> {
+        let roots = vec![temp_dir()?, temp_dir()?, temp_dir()?];
+        let mut adults = BTreeMap::new();
+        for root in &roots {
+            let chunks = BlobChunkStore::new(root.path(), UsedSpace::new(u64::MAX)).await?;
+            let storage = new_storage(chunks);
+            let _ = adults.insert(storage.node_name, storage);
+        }
+        let names: Vec<_> = adults.keys().cloned().collect();
+        let (lacking, holding, new_holder) = (names[0], names[1], names[2]);
+
+        let data = Blob::Public(PublicBlob::new(vec![12_u8; 64]));
+        if let Some(holder) = adults.get_mut(&holding) {
+            holder.store_chunk(&data).await?;
+        }
+
+        // Both are listed as holders by the Elders, only one of them actually having it.
+        let holders = vec![lacking, holding].into_iter().collect();
+        let msg_id = MessageId::new();
+        let duties = match adults.get_mut(&new_holder) {
+            Some(storage) => {
+                storage
+                    .replicate_chunk(*data.address(), holders, msg_id)
+                    .await?
+            }
+            None => return Err(Error::Logic("New holder missing".to_string())),
+        };
+        let duties = duties.into_iter().map(|duty| (new_holder, duty)).collect();
+
+        match route_between_adults(&mut adults, duties).await?.as_slice() {
+            [NodeDuty::ReportReplicationCompleted {
+                address,
+                correlation_id,
+            }] if address == data.address() && *correlation_id == msg_id => {}
+            x => return Err(Error::Logic(format!("Unexpected: {:?}", x))),
+        }
+        match adults.get_mut(&new_holder) {
+            Some(storage) => assert_eq!(storage.get_chunk(data.address()).await?, data),
+            None => return Err(Error::Logic("New holder missing".to_string())),
+        }
+        assert!(!adults[&lacking].has_chunk(data.address()));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn chunks_are_listed_in_pages() -> Result<()> {
+        let root = temp_dir()?;
+        let used_space = UsedSpace::new(u64::MAX);
+        let chunks = BlobChunkStore::new(root.path(), used_space).await?;
+        let mut storage = new_storage(chunks);
+
+        let count = CHUNK_LIST_PAGE_SIZE + 1;
+        for i in 0..count {
+            let data = Blob::Public(PublicBlob::new(i.to_be_bytes().to_vec()));
+            storage.chunks.put(&data).await?;
+        }
+
+        let first = storage.list_chunks(None);
+        assert_eq!(first.addresses.len(), CHUNK_LIST_PAGE_SIZE);
+        assert_eq!(first.next, first.addresses.last().copied());
+
+        let second = storage.list_chunks(first.next);
+        assert_eq!(second.addresses.len(), 1);
+        assert_eq!(second.next, None);
+        assert!(!first.addresses.contains(&second.addresses[0]));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn corrupted_chunk_is_not_served() -> Result<()> {
+        let root = temp_dir()?;
+        let used_space = UsedSpace::new(u64::MAX);
+        let chunks = BlobChunkStore::new(root.path(), used_space.clone()).await?;
+        let mut storage = new_storage(chunks);
+
+        let value = vec![7_u8; 64];
+        let data = Blob::Public(PublicBlob::new(value.clone()));
+        storage.chunks.put(&data).await?;
+        assert_eq!(storage.get_chunk(data.address()).await?, data);
+
+        corrupt_chunk_files(root.path(), &value)?;
+
+        match storage.get_chunk(data.address()).await {
+            Err(Error::CorruptedChunk) => (),
+            x => return Err(Error::Logic(format!("Unexpected: {:?}", x))),
+        }
+        assert!(!storage.chunks.has(data.address()));
+        assert_eq!(used_space.total().await, 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unreadable_chunk_is_quarantined() -> Result<()> {
+        let root = temp_dir()?;
+        let used_space = UsedSpace::new(u64::MAX);
+        let mut chunks = BlobChunkStore::new(root.path(), used_space.clone()).await?;
+        chunks.set_quarantine_capacity(u64::MAX);
+        let mut storage = new_storage(chunks);
+
+        let data = Blob::Public(PublicBlob::new(vec![9_u8; 64]));
+        storage.chunks.put(&data).await?;
+
+        // Truncate the chunk file so that it fails to deserialise.
+        let dir = root.path().join("chunks").join("immutable");
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_file() && path.file_name().map_or(false, |name| name != "used_space") {
+                fs::write(&path, &[0_u8; 3])?;
+            }
+        }
+
+        match storage
+            .get_for_replication(*data.address(), MessageId::new(), XorName::random())
+            .await?
+            .as_slice()
+        {
+            [NodeDuty::Send(_)] => {}
+            x => return Err(Error::Logic(format!("Unexpected: {:?}", x))),
+        }
+        assert!(!storage.chunks.has(data.address()));
+        assert_eq!(used_space.total().await, 0);
+        assert_eq!(fs::read_dir(dir.join("corrupt"))?.count(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn corrupted_copy_is_replaced_by_the_chunk_stored() -> Result<()> {
+        let root = temp_dir()?;
+        let chunks = BlobChunkStore::new(root.path(), UsedSpace::new(u64::MAX)).await?;
+        let mut storage = new_storage(chunks);
+
+        let data = Blob::Public(PublicBlob::new(vec![6_u8; 64]));
+        storage.chunks.put(&data).await?;
+        let dir = root.path().join("chunks").join("immutable");
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_file() && path.file_name().map_or(false, |name| name != "used_space") {
+                fs::write(&path, &[0_u8; 3])?;
+            }
+        }
+
+        storage.store_chunk(&data).await?;
+        assert_eq!(storage.chunks.get(data.address())?, data);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn chunk_written_is_stored_without_answer() -> Result<()> {
+        let root = temp_dir()?;
+        let chunks = BlobChunkStore::new(root.path(), UsedSpace::new(u64::MAX)).await?;
+        let mut storage = new_storage(chunks);
+
+        let data = Blob::Public(PublicBlob::new(vec![5_u8; 64]));
+        match storage.store(&data, MessageId::new(), client()).await? {
+            NodeDuty::NoOp => (),
+            other => return Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+        assert_eq!(storage.get_chunk(data.address()).await?, data);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn chunk_written_when_full_is_answered_with_an_error() -> Result<()> {
+        let root = temp_dir()?;
+        let chunks = BlobChunkStore::new(root.path(), UsedSpace::new(16)).await?;
+        let mut storage = new_storage(chunks);
+
+        let data = Blob::Public(PublicBlob::new(vec![5_u8; 64]));
+        let (msg_id, origin) = (MessageId::new(), client());
+        let duty = storage.store(&data, msg_id, origin).await?;
+        match write_error(duty, msg_id, origin)? {
+            ErrorMessage::NotEnoughSpace => Ok(()),
+            other => Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+    }
+
+    #[tokio::test]
+    async fn delete_of_missing_chunk_is_answered_with_an_error() -> Result<()> {
+        let root = temp_dir()?;
+        let chunks = BlobChunkStore::new(root.path(), UsedSpace::new(u64::MAX)).await?;
+        let mut storage = new_storage(chunks);
+
+        let address = BlobAddress::Private(XorName::random());
+        let (msg_id, origin) = (MessageId::new(), client());
+        let duty = storage.delete(address, msg_id, origin).await?;
+        match write_error(duty, msg_id, origin)? {
+            ErrorMessage::NoSuchData => Ok(()),
+            other => Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+    }
+
+    // The chunk read answered by the first duty, checking it goes back to its origin.
+    fn read_result(
+        duties: NodeDuties,
+        msg_id: MessageId,
+        origin: EndUser,
+    ) -> Result<std::result::Result<Blob, ErrorMessage>> {
+        match duties.into_iter().next() {
+            Some(NodeDuty::Send(out)) => match (out.msg, out.dst) {
+                (
+                    Message::QueryResponse {
+                        response: QueryResponse::GetBlob(result),
+                        correlation_id,
+                        ..
+                    },
+                    DstLocation::EndUser(dst),
+                ) if correlation_id == msg_id && dst == origin => Ok(result),
+                other => Err(Error::Logic(format!("Unexpected: {:?}", other))),
+            },
+            other => Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+    }
+
+    #[tokio::test]
+    async fn private_chunk_is_served_to_its_owner_only() -> Result<()> {
+        let root = temp_dir()?;
+        let chunks = BlobChunkStore::new(root.path(), UsedSpace::new(u64::MAX)).await?;
+        let mut storage = new_storage(chunks);
+
+        let owner = client();
+        let data = Blob::Private(PrivateBlob::new(vec![4_u8; 64], *owner.id()));
+        storage.store_chunk(&data).await?;
+
+        let msg_id = MessageId::new();
+        let duties = storage.get(data.address(), msg_id, owner).await?;
+        assert_eq!(read_result(duties, msg_id, owner)?, Ok(data.clone()));
+
+        let (msg_id, stranger) = (MessageId::new(), client());
+        let duties = storage.get(data.address(), msg_id, stranger).await?;
+        assert_eq!(
+            read_result(duties, msg_id, stranger)?,
+            Err(ErrorMessage::AccessDenied(*stranger.id()))
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn public_chunk_is_served_to_anyone() -> Result<()> {
+        let root = temp_dir()?;
+        let chunks = BlobChunkStore::new(root.path(), UsedSpace::new(u64::MAX)).await?;
+        let mut storage = new_storage(chunks);
+
+        let data = Blob::Public(PublicBlob::new(vec![4_u8; 64]));
+        storage.store_chunk(&data).await?;
+
+        let (msg_id, reader) = (MessageId::new(), client());
+        let duties = storage.get(data.address(), msg_id, reader).await?;
+        assert_eq!(read_result(duties, msg_id, reader)?, Ok(data));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn chunks_held_are_counted_with_the_space_they_use() -> Result<()> {
+        let root = temp_dir()?;
+        let used_space = UsedSpace::new(u64::MAX);
+        let chunks = BlobChunkStore::new(root.path(), used_space.clone()).await?;
+        let mut storage = new_storage(chunks);
+        assert_eq!(storage.count(), 0);
+        assert_eq!(storage.used_space().await, 0);
+
+        let first = Blob::Public(PublicBlob::new(vec![1_u8; 64]));
+        let second = Blob::Public(PublicBlob::new(vec![2_u8; 128]));
+        storage.store_chunk(&first).await?;
+        storage.store_chunk(&second).await?;
+        assert!(storage.has_chunk(first.address()));
+        assert!(!storage.has_chunk(&BlobAddress::Public(XorName::random())));
+        assert_eq!(storage.count(), 2);
+        let used = storage.used_space().await;
+        assert!(used > 64 + 128);
+        assert_eq!(used, used_space.total().await);
+
+        storage.chunks.delete(first.address()).await?;
+        assert!(!storage.has_chunk(first.address()));
+        assert_eq!(storage.count(), 1);
+        assert!(storage.used_space().await < used);
+        Ok(())
+    }
+}
@@ -6,17 +6,31 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-mod chunk_storage;
+mod chunk_backend;
+mod chunk_cache;
+mod chunk_scrubber;
+pub(crate) mod chunk_storage;
 mod reading;
+mod recent_msgs;
+mod replication;
+mod storage_proof;
+mod storage_thresholds;
+mod write_batch;
 mod writing;
 
 use crate::{
-    chunk_store::UsedSpace,
+    chunk_store::{BlobChunkStore, UsedSpace},
     node_ops::{NodeDuties, NodeDuty},
-    NodeInfo, Result,
+    Error, NodeInfo, Result,
 };
-use chunk_storage::ChunkStorage;
-use log::info;
+use chunk_backend::{ChunkBackend, MemoryChunkStore};
+pub use chunk_cache::CacheStats;
+use chunk_scrubber::ChunkScrubber;
+pub use chunk_scrubber::ScrubStats;
+use chunk_storage::{ChunkListPage, ChunkStorage};
+use log::{debug, info, warn};
+use recent_msgs::{Outcome, RecentMsgs};
+use replication::ReplicationStats;
 use sn_data_types::{Blob, BlobAddress};
 use sn_messaging::{
     client::{BlobRead, BlobWrite},
@@ -25,45 +39,145 @@ use sn_messaging::{
 use std::{
     collections::BTreeSet,
     fmt::{self, Display, Formatter},
-    path::Path,
+    time::{Duration, Instant},
 };
+pub use storage_proof::StorageProof;
+use storage_thresholds::{Crossing, StorageThresholds};
 use xor_name::XorName;
 
 pub const MAX_STORAGE_USAGE_RATIO: f64 = 0.8;
 
+/// The chunks held by an Adult, for health probes and status reports.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChunkStatus {
+    /// The number of chunks written to the store.
+    pub count: usize,
+    /// The space consumed by them.
+    pub used_space: u64,
+    /// The ratio of the space consumed by all stores to the max capacity.
+    pub used_space_ratio: f64,
+    /// Hit and miss counts of the in-memory chunk cache.
+    pub cache: CacheStats,
+    /// Progress and error counts of the chunk scrubbing.
+    pub scrub: ScrubStats,
+}
+
 /// Operations on data chunks.
 pub(crate) struct Chunks {
     chunk_storage: ChunkStorage,
+    scrubber: ChunkScrubber,
+    thresholds: StorageThresholds,
+    recent_msgs: RecentMsgs,
 }
 
 impl Chunks {
-    pub async fn new(node_name: XorName, path: &Path, used_space: UsedSpace) -> Result<Self> {
+    /// Opens the chunks held by the node, compacting their store first if `compact` is set.
+    pub async fn new(node_info: &NodeInfo, used_space: UsedSpace, compact: bool) -> Result<Self> {
+        let path = node_info.chunk_path();
+        let backend: Box<dyn ChunkBackend> = if node_info.in_memory_chunks {
+            Box::new(MemoryChunkStore::new(used_space.max_capacity().await))
+        } else {
+            let mut store = BlobChunkStore::new(path, used_space).await?;
+            store.set_quarantine_capacity(node_info.chunk_quarantine_capacity);
+            if compact {
+                let _ = store.compact().await?;
+            }
+            Box::new(store)
+        };
         Ok(Self {
-            chunk_storage: ChunkStorage::new(node_name, path, used_space).await?,
+            chunk_storage: ChunkStorage::new(
+                node_info.node_name,
+                backend,
+                node_info.chunk_cache_size,
+                Duration::from_secs(node_info.chunk_replication_timeout),
+                node_info.max_chunk_replications,
+                node_info.chunk_replication_queue_size,
+            ),
+            scrubber: ChunkScrubber::new(path, node_info.chunk_scrub_rate),
+            thresholds: StorageThresholds::new(node_info.storage_thresholds.clone()),
+            recent_msgs: RecentMsgs::new(
+                Duration::from_secs(node_info.recent_msg_timeout),
+                node_info.max_recent_msgs,
+            ),
         })
     }
 
+    /// Reads a chunk for a client. A redelivery of a read already answered is answered
+    /// again, to whom the read was, with the chunk read anew.
     pub async fn read(
         &mut self,
         read: &BlobRead,
         msg_id: MessageId,
         origin: EndUser,
-    ) -> Result<NodeDuty> {
-        reading::get_result(read, msg_id, origin, &self.chunk_storage).await
+    ) -> Result<NodeDuties> {
+        let now = Instant::now();
+        match self.recent_msgs.get(&msg_id, now) {
+            Some(Outcome::Read { address, origin }) => {
+                debug!("Answering redelivered chunk read {:?} again", msg_id);
+                return self.chunk_storage.get(&address, msg_id, *origin).await;
+            }
+            Some(Outcome::Write(_)) => return Ok(vec![]),
+            None => (),
+        }
+        let duties = reading::get_result(read, msg_id, origin, &mut self.chunk_storage).await?;
+        let BlobRead::Get(address) = read;
+        let outcome = Outcome::Read {
+            address: *address,
+            origin: Box::new(origin),
+        };
+        self.recent_msgs.insert(msg_id, outcome, now);
+        Ok(duties)
     }
 
+    /// Writes a chunk for a client. A redelivery of a write already handled is answered
+    /// as the write was, without being written again.
     pub async fn write(
         &mut self,
         write: &BlobWrite,
         msg_id: MessageId,
         origin: EndUser,
     ) -> Result<NodeDuty> {
-        writing::get_result(write, msg_id, origin, &mut self.chunk_storage).await
+        let now = Instant::now();
+        match self.recent_msgs.get(&msg_id, now) {
+            Some(Outcome::Write(answer)) => {
+                debug!(
+                    "Replaying the outcome of redelivered chunk write {:?}",
+                    msg_id
+                );
+                return Ok(answer.map_or(NodeDuty::NoOp, |msg| NodeDuty::Send(*msg)));
+            }
+            Some(Outcome::Read { .. }) => return Ok(NodeDuty::NoOp),
+            None => (),
+        }
+        let duty = writing::get_result(write, msg_id, origin, &mut self.chunk_storage).await?;
+        let answer = match &duty {
+            NodeDuty::Send(msg) => Some(Box::new(msg.clone())),
+            _ => None,
+        };
+        self.recent_msgs.insert(msg_id, Outcome::Write(answer), now);
+        Ok(duty)
     }
 
-    pub async fn check_storage(&self) -> Result<NodeDuties> {
-        info!("Checking used storage");
-        if self.chunk_storage.used_space_ratio().await > MAX_STORAGE_USAGE_RATIO {
+    /// Checks the used space against the storage thresholds, after chunks were stored or
+    /// deleted. Elders are notified once of each threshold reached, as far as the notification
+    /// they understand goes, i.e. that we are nearly full, which is from
+    /// `MAX_STORAGE_USAGE_RATIO` on. A threshold is notified anew once the used space
+    /// dropped back below it.
+    pub async fn check_storage(&mut self) -> Result<NodeDuties> {
+        let ratio = self.chunk_storage.used_space_ratio().await;
+        let mut nearly_full = false;
+        for crossing in self.thresholds.check(ratio) {
+            match crossing {
+                Crossing::Above(threshold) => {
+                    info!("Used storage reached {}% of our capacity", threshold);
+                    nearly_full |= f64::from(threshold) / 100.0 >= MAX_STORAGE_USAGE_RATIO;
+                }
+                Crossing::Below(threshold) => {
+                    info!("Used storage dropped below {}% of our capacity", threshold)
+                }
+            }
+        }
+        if nearly_full {
             Ok(NodeDuties::from(NodeDuty::ReachingMaxCapacity))
         } else {
             Ok(vec![])
@@ -72,33 +186,155 @@ impl Chunks {
 
     ///
     pub async fn replicate_chunk(
-        &self,
+        &mut self,
         address: BlobAddress,
         current_holders: BTreeSet<XorName>,
         msg_id: MessageId,
-    ) -> Result<NodeDuty> {
+    ) -> Result<NodeDuties> {
         info!("Creating new Message for acquiring chunk from current_holders");
         self.chunk_storage
             .replicate_chunk(address, current_holders, msg_id)
             .await
     }
 
-    ///
+    /// Sends a chunk to the new holder it's being replicated to, as delegated by our section.
+    /// Fetches by any other origin, e.g. a node asking on behalf of another, are refused.
     pub async fn get_chunk_for_replication(
-        &self,
+        &mut self,
         address: BlobAddress,
         msg_id: MessageId,
         new_holder: XorName,
-    ) -> Result<NodeDuty> {
+        origin: SrcLocation,
+        our_adults: &[XorName],
+    ) -> Result<NodeDuties> {
+        if !is_delegated_fetch(&origin, &new_holder, our_adults) {
+            warn!(
+                "Refusing to send chunk {:?} to {}, as not fetched by it as an adult of our section: {:?}",
+                address, new_holder, origin
+            );
+            return Err(Error::InvalidOperation(format!(
+                "Chunk fetch not delegated by our section: {:?}",
+                origin
+            )));
+        }
         info!("Send blob for replication to the new holder.");
         self.chunk_storage
             .get_for_replication(address, msg_id, new_holder)
             .await
     }
 
-    ///
-    pub async fn store_replicated_chunk(&mut self, blob: Blob) -> Result<NodeDuty> {
-        self.chunk_storage.store_for_replication(blob).await
+    /// Queries the next holder for a copy of a chunk being replicated to us,
+    /// after `holder` failed to provide one.
+    pub async fn handle_copy_failure(
+        &mut self,
+        msg_id: MessageId,
+        holder: XorName,
+    ) -> Result<NodeDuties> {
+        self.chunk_storage.handle_copy_failure(msg_id, holder).await
+    }
+
+    /// Verifies the next stored chunks against their addresses, as the scrub rate allows.
+    pub async fn scrub(&mut self) {
+        self.scrubber.scrub(&mut self.chunk_storage).await
+    }
+
+    /// Writes the chunks queued for replication to the store, once their max latency has passed,
+    /// and starts the queued replications for which slots have freed up.
+    pub async fn flush_writes(&mut self) -> Result<NodeDuties> {
+        self.chunk_storage.flush_writes().await
+    }
+
+    /// Writes all the queued replicated chunks, for none to be lost when the chunks
+    /// are no longer handled.
+    pub async fn flush_all_writes(&mut self) -> NodeDuties {
+        self.chunk_storage.flush_all_writes().await
+    }
+
+    /// Writes all the queued replicated chunks and abandons the replications in flight,
+    /// for the chunks to be left consistent on disk when the node shuts down.
+    pub async fn close(&mut self) -> NodeDuties {
+        self.chunk_storage.close().await
+    }
+
+    /// Drops the replications to this node whose copy hasn't arrived in time,
+    /// so that they can be re-issued by Elders.
+    pub fn check_pending_ops(&mut self) -> NodeDuties {
+        self.chunk_storage.check_pending_ops(Instant::now())
+    }
+
+    /// Sets the max capacity of the chunks, e.g. as changed in the config of the running node.
+    /// The chunks already held are kept even if beyond it, only further writes being refused.
+    pub async fn set_max_capacity(&mut self, max_capacity: u64) {
+        self.chunk_storage.set_max_capacity(max_capacity).await
+    }
+
+    /// Resizes the in-memory chunk cache.
+    pub fn set_cache_size(&mut self, cache_size: u64) {
+        self.chunk_storage.set_cache_size(cache_size)
+    }
+
+    /// Replaces the thresholds of used space the Elders are told of reaching.
+    pub fn set_storage_thresholds(&mut self, thresholds: Vec<u8>) {
+        self.thresholds.set(thresholds)
+    }
+
+    /// Numbers of replications to this node in flight and queued, for diagnostics.
+    pub fn replication_stats(&self) -> ReplicationStats {
+        self.chunk_storage.replication_stats()
+    }
+
+    /// Whether the chunk is held by this node, be it written to the store or queued for writing.
+    pub fn has_chunk(&self, address: &BlobAddress) -> bool {
+        self.chunk_storage.has_chunk(address)
+    }
+
+    /// The number of chunks held by this node, and the space they consume,
+    /// read without loading any chunk, along with the hits of the chunk cache
+    /// and the progress of the scrubbing.
+    pub async fn status(&self) -> ChunkStatus {
+        ChunkStatus {
+            count: self.chunk_storage.count(),
+            used_space: self.chunk_storage.used_space().await,
+            used_space_ratio: self.chunk_storage.used_space_ratio().await,
+            cache: self.chunk_storage.cache_stats(),
+            scrub: self.scrubber.stats(),
+        }
+    }
+
+    /// Proves holding the chunk, in answer to an audit challenge with the nonce.
+    pub fn prove_storage(&self, address: BlobAddress, nonce: Vec<u8>) -> Result<StorageProof> {
+        let digest = self.chunk_storage.digest(&address, &nonce)?;
+        Ok(StorageProof {
+            address,
+            nonce,
+            digest,
+        })
+    }
+
+    /// Lists a page of the addresses of chunks held by this node.
+    pub fn list_chunks(&self, after: Option<BlobAddress>) -> ChunkListPage {
+        self.chunk_storage.list_chunks(after)
+    }
+
+    /// Stores a copy of a chunk replicated to us, provided it is the chunk requested.
+    pub async fn store_replicated_chunk(
+        &mut self,
+        blob: Blob,
+        msg_id: MessageId,
+        holder: XorName,
+    ) -> Result<NodeDuties> {
+        self.chunk_storage
+            .store_for_replication(blob, msg_id, holder)
+            .await
+    }
+}
+
+/// Whether a holder-to-holder fetch of a chunk was delegated by our section, i.e. sent by the
+/// new holder itself, our section replicating chunks to its adults only.
+fn is_delegated_fetch(origin: &SrcLocation, new_holder: &XorName, our_adults: &[XorName]) -> bool {
+    match origin {
+        SrcLocation::Node(name) => name == new_holder && our_adults.contains(name),
+        _ => false,
     }
 }
 
@@ -107,3 +343,206 @@ impl Display for Chunks {
         write!(formatter, "Chunks")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        chunk_backend::MemoryChunkStore, chunk_scrubber::ChunkScrubber,
+        chunk_storage::ChunkStorage, is_delegated_fetch, recent_msgs::RecentMsgs,
+        storage_thresholds::StorageThresholds, CacheStats, Chunks,
+    };
+    use crate::{node_ops::NodeDuty, Error, Result};
+    use sn_data_types::{Blob, Keypair, PrivateBlob, PublicBlob};
+    use sn_messaging::{
+        client::{BlobRead, BlobWrite, CmdError, Error as ErrorMessage, Message, QueryResponse},
+        EndUser, MessageId, SrcLocation,
+    };
+    use std::time::Duration;
+    use tempdir::TempDir;
+    use xor_name::XorName;
+
+    fn new_chunks(root: &TempDir, max_capacity: u64) -> Chunks {
+        Chunks {
+            chunk_storage: ChunkStorage::new(
+                XorName::random(),
+                Box::new(MemoryChunkStore::new(max_capacity)),
+                0,
+                Duration::from_secs(10),
+                10,
+                10,
+            ),
+            scrubber: ChunkScrubber::new(root.path(), 0),
+            thresholds: StorageThresholds::new(vec![]),
+            recent_msgs: RecentMsgs::new(Duration::from_secs(60), 10),
+        }
+    }
+
+    fn client() -> EndUser {
+        EndUser::AllClients(Keypair::new_ed25519(&mut rand::thread_rng()).public_key())
+    }
+
+    #[tokio::test]
+    async fn redelivered_read_is_answered_again_with_the_chunk_read_anew() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let mut chunks = new_chunks(&root, u64::MAX);
+        let data = Blob::Public(PublicBlob::new(vec![1_u8; 64]));
+        let _ = chunks
+            .write(&BlobWrite::New(data.clone()), MessageId::new(), client())
+            .await?;
+
+        let read = BlobRead::Get(*data.address());
+        let (msg_id, origin) = (MessageId::new(), client());
+        for _ in 0..2 {
+            match chunks.read(&read, msg_id, origin).await?.as_slice() {
+                [NodeDuty::Send(out)] => match &out.msg {
+                    Message::QueryResponse {
+                        response: QueryResponse::GetBlob(Ok(blob)),
+                        correlation_id,
+                        ..
+                    } if *correlation_id == msg_id && *blob == data => (),
+                    other => return Err(Error::Logic(format!("Unexpected: {:?}", other))),
+                },
+                other => return Err(Error::Logic(format!("Unexpected: {:?}", other))),
+            }
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn redelivered_write_is_answered_as_it_was_without_being_redone() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let mut chunks = new_chunks(&root, 16);
+
+        // A failed write is answered with the same error again.
+        let write = BlobWrite::New(Blob::Public(PublicBlob::new(vec![2_u8; 64])));
+        let (msg_id, origin) = (MessageId::new(), client());
+        for _ in 0..2 {
+            match chunks.write(&write, msg_id, origin).await? {
+                NodeDuty::Send(out) => match out.msg {
+                    Message::CmdError {
+                        error: CmdError::Data(ErrorMessage::NotEnoughSpace),
+                        correlation_id,
+                        ..
+                    } if correlation_id == msg_id => (),
+                    other => return Err(Error::Logic(format!("Unexpected: {:?}", other))),
+                },
+                other => return Err(Error::Logic(format!("Unexpected: {:?}", other))),
+            }
+        }
+
+        // A delete redone would fail, the chunk being gone.
+        let mut chunks = new_chunks(&root, u64::MAX);
+        let owner = client();
+        let data = Blob::Private(PrivateBlob::new(vec![3_u8; 64], *owner.id()));
+        let _ = chunks
+            .write(&BlobWrite::New(data.clone()), MessageId::new(), owner)
+            .await?;
+        let delete = BlobWrite::DeletePrivate(*data.address());
+        let msg_id = MessageId::new();
+        for _ in 0..2 {
+            match chunks.write(&delete, msg_id, owner).await? {
+                NodeDuty::NoOp => (),
+                other => return Err(Error::Logic(format!("Unexpected: {:?}", other))),
+            }
+        }
+        assert!(!chunks.has_chunk(data.address()));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn capacity_changed_at_runtime_applies_to_further_writes() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let mut chunks = new_chunks(&root, 16);
+        let first = Blob::Public(PublicBlob::new(vec![4_u8; 64]));
+        let second = Blob::Public(PublicBlob::new(vec![5_u8; 64]));
+        let is_full = |duty: NodeDuty| match duty {
+            NodeDuty::Send(out) => matches!(
+                out.msg,
+                Message::CmdError {
+                    error: CmdError::Data(ErrorMessage::NotEnoughSpace),
+                    ..
+                }
+            ),
+            _ => false,
+        };
+
+        let write = BlobWrite::New(first.clone());
+        assert!(is_full(
+            chunks.write(&write, MessageId::new(), client()).await?
+        ));
+
+        chunks.set_max_capacity(150).await;
+        assert!(!is_full(
+            chunks.write(&write, MessageId::new(), client()).await?
+        ));
+        assert!(chunks.has_chunk(first.address()));
+
+        // Lowered below the space used: the chunks held are kept, further ones refused.
+        chunks.set_max_capacity(16).await;
+        let write = BlobWrite::New(second.clone());
+        assert!(is_full(
+            chunks.write(&write, MessageId::new(), client()).await?
+        ));
+        assert!(chunks.has_chunk(first.address()));
+        assert!(!chunks.has_chunk(second.address()));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn status_reports_the_chunk_cache_hits() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let mut chunks = new_chunks(&root, u64::MAX);
+        chunks.set_cache_size(1024);
+        let data = Blob::Public(PublicBlob::new(vec![6_u8; 64]));
+        let _ = chunks
+            .write(&BlobWrite::New(data.clone()), MessageId::new(), client())
+            .await?;
+
+        let read = BlobRead::Get(*data.address());
+        for _ in 0..3 {
+            let _ = chunks.read(&read, MessageId::new(), client()).await?;
+        }
+        let status = chunks.status().await;
+        assert_eq!(status.count, 1);
+        // Written chunks are cached, so all reads hit.
+        assert_eq!(status.cache, CacheStats { hits: 3, misses: 0 });
+        Ok(())
+    }
+
+    #[test]
+    fn holder_fetch_by_an_adult_of_ours_is_served() {
+        let new_holder = XorName::random();
+        let our_adults = vec![XorName::random(), new_holder];
+        assert!(is_delegated_fetch(
+            &SrcLocation::Node(new_holder),
+            &new_holder,
+            &our_adults
+        ));
+    }
+
+    #[test]
+    fn direct_node_fetch_is_refused() {
+        let new_holder = XorName::random();
+        let other = XorName::random();
+        let our_adults = vec![new_holder, other];
+        // on behalf of another node
+        assert!(!is_delegated_fetch(
+            &SrcLocation::Node(other),
+            &new_holder,
+            &our_adults
+        ));
+        // by a node not among our adults
+        let stranger = XorName::random();
+        assert!(!is_delegated_fetch(
+            &SrcLocation::Node(stranger),
+            &stranger,
+            &our_adults
+        ));
+        // by a section rather than the new holder
+        assert!(!is_delegated_fetch(
+            &SrcLocation::Section(new_holder),
+            &new_holder,
+            &our_adults
+        ));
+    }
+}
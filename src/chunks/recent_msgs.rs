@@ -0,0 +1,116 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::node_ops::OutgoingMsg;
+use sn_data_types::BlobAddress;
+use sn_messaging::{EndUser, MessageId};
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+/// How a client msg was handled, for its redeliveries to be answered the same way.
+#[derive(Debug, Clone)]
+pub(super) enum Outcome {
+    /// A read, with the chunk it was for and whom it was answered to. The answer holds
+    /// the chunk, so isn't kept, the chunk being read anew to answer a redelivery.
+    Read {
+        address: BlobAddress,
+        origin: Box<EndUser>,
+    },
+    /// A write, with the error it was answered with, if it failed.
+    Write(Option<Box<OutgoingMsg>>),
+}
+
+/// The client msgs handled recently, by id, so that those redelivered by routing
+/// are not handled twice. Entries are forgotten after the timeout, or oldest first
+/// once there are more than the max number of them.
+pub(super) struct RecentMsgs {
+    timeout: Duration,
+    max_entries: usize,
+    outcomes: HashMap<MessageId, Outcome>,
+    // The ids in the order they were handled, with the time they were.
+    handled: VecDeque<(MessageId, Instant)>,
+}
+
+impl RecentMsgs {
+    pub fn new(timeout: Duration, max_entries: usize) -> Self {
+        Self {
+            timeout,
+            max_entries,
+            outcomes: HashMap::new(),
+            handled: VecDeque::new(),
+        }
+    }
+
+    /// The outcome of the msg, if it was handled within the timeout.
+    pub fn get(&mut self, msg_id: &MessageId, now: Instant) -> Option<Outcome> {
+        self.forget_expired(now);
+        self.outcomes.get(msg_id).cloned()
+    }
+
+    /// Records the outcome of a msg just handled.
+    pub fn insert(&mut self, msg_id: MessageId, outcome: Outcome, now: Instant) {
+        if self.max_entries == 0 {
+            return;
+        }
+        self.forget_expired(now);
+        if self.outcomes.insert(msg_id, outcome).is_none() {
+            self.handled.push_back((msg_id, now));
+        }
+        while self.handled.len() > self.max_entries {
+            if let Some((oldest, _)) = self.handled.pop_front() {
+                let _ = self.outcomes.remove(&oldest);
+            }
+        }
+    }
+
+    fn forget_expired(&mut self, now: Instant) {
+        while let Some((msg_id, handled_at)) = self.handled.front() {
+            if now.saturating_duration_since(*handled_at) < self.timeout {
+                break;
+            }
+            let _ = self.outcomes.remove(msg_id);
+            let _ = self.handled.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Outcome, RecentMsgs};
+    use sn_messaging::MessageId;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn msgs_are_forgotten_after_the_timeout() {
+        let timeout = Duration::from_secs(60);
+        let mut recent = RecentMsgs::new(timeout, 10);
+        let now = Instant::now();
+        let msg_id = MessageId::new();
+        recent.insert(msg_id, Outcome::Write(None), now);
+
+        assert!(recent.get(&msg_id, now + timeout / 2).is_some());
+        assert!(recent.get(&msg_id, now + timeout).is_none());
+        assert!(recent.get(&MessageId::new(), now).is_none());
+    }
+
+    #[test]
+    fn oldest_msgs_are_forgotten_beyond_the_max() {
+        let mut recent = RecentMsgs::new(Duration::from_secs(60), 2);
+        let now = Instant::now();
+        let ids: Vec<_> = (0..3).map(|_| MessageId::new()).collect();
+        for msg_id in &ids {
+            recent.insert(*msg_id, Outcome::Write(None), now);
+        }
+
+        assert!(recent.get(&ids[0], now).is_none());
+        assert!(recent.get(&ids[1], now).is_some());
+        assert!(recent.get(&ids[2], now).is_some());
+    }
+}
@@ -0,0 +1,425 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use sn_data_types::BlobAddress;
+use sn_messaging::MessageId;
+use std::{
+    collections::{BTreeSet, HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+use xor_name::XorName;
+
+/// A chunk being replicated to this node, for which a copy was requested from its holders,
+/// one at a time.
+#[derive(Debug)]
+struct PendingReplication {
+    address: BlobAddress,
+    requested_at: Instant,
+    // The holder currently queried for a copy.
+    queried: XorName,
+    // The holders yet to be queried, should the current one fail.
+    untried: VecDeque<XorName>,
+}
+
+/// A replication waiting for the number of replications in flight to drop below the limit.
+#[derive(Debug)]
+pub(crate) struct QueuedReplication {
+    pub msg_id: MessageId,
+    pub address: BlobAddress,
+    pub holders: BTreeSet<XorName>,
+}
+
+/// Numbers of replications to this node, for diagnostics.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ReplicationStats {
+    /// Replications awaiting a copy from the holders.
+    pub in_flight: usize,
+    /// Replications waiting for a slot, before any holder is queried.
+    pub queued: usize,
+}
+
+/// How to proceed with a replication after a holder failed to provide a copy.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Retry {
+    /// Query the next holder for a copy.
+    Next {
+        address: BlobAddress,
+        holder: XorName,
+    },
+    /// All holders failed, so the chunk cannot be recovered by us.
+    Exhausted {
+        address: BlobAddress,
+        requesters: BTreeSet<MessageId>,
+    },
+}
+
+/// Replications of chunks to this node awaiting a copy from the current holders.
+/// Entries not completed within the timeout are dropped, so that they can be
+/// re-issued by Elders rather than waited on forever.
+///
+/// At most `max_in_flight` replications query holders at once, the others being
+/// queued in the order received, up to `max_queued`.
+///
+/// Replications of the same chunk requested under different ids, e.g. by several Elders
+/// or on retry, are coalesced into one, all of its requesters being answered once it ends.
+pub(crate) struct PendingReplications {
+    timeout: Duration,
+    max_in_flight: usize,
+    max_queued: usize,
+    ops: HashMap<MessageId, PendingReplication>,
+    queue: VecDeque<QueuedReplication>,
+    // The ids of the replication cmds received for each chunk queued or in flight.
+    requesters: HashMap<BlobAddress, BTreeSet<MessageId>>,
+    // The ids of the replications completed within the timeout, with their completion time.
+    completed: HashMap<MessageId, Instant>,
+}
+
+impl PendingReplications {
+    pub fn new(timeout: Duration, max_in_flight: usize, max_queued: usize) -> Self {
+        Self {
+            timeout,
+            max_in_flight,
+            max_queued,
+            ops: HashMap::new(),
+            queue: VecDeque::new(),
+            requesters: HashMap::new(),
+            completed: HashMap::new(),
+        }
+    }
+
+    /// Registers `msg_id` as a requester of the replication of the chunk, should one be
+    /// queued or in flight already. Returns false otherwise, for a replication to be started.
+    pub fn join(&mut self, msg_id: MessageId, address: BlobAddress) -> bool {
+        match self.requesters.get_mut(&address) {
+            Some(requesters) => {
+                let _ = requesters.insert(msg_id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether a replication can start right away, rather than be queued.
+    /// Once a queue has formed, new replications wait their turn.
+    pub fn has_capacity(&self) -> bool {
+        self.queue.is_empty() && self.ops.len() < self.max_in_flight
+    }
+
+    /// Queues the replication until a slot frees up.
+    /// Returns it back if the queue is full.
+    pub fn enqueue(&mut self, replication: QueuedReplication) -> Result<(), QueuedReplication> {
+        if self.queue.len() >= self.max_queued {
+            return Err(replication);
+        }
+        let _ = self
+            .requesters
+            .entry(replication.address)
+            .or_default()
+            .insert(replication.msg_id);
+        self.queue.push_back(replication);
+        Ok(())
+    }
+
+    /// Takes the oldest queued replication, if a slot is free for it.
+    pub fn dequeue(&mut self) -> Option<QueuedReplication> {
+        if self.ops.len() < self.max_in_flight {
+            self.queue.pop_front()
+        } else {
+            None
+        }
+    }
+
+    pub fn stats(&self) -> ReplicationStats {
+        ReplicationStats {
+            in_flight: self.ops.len(),
+            queued: self.queue.len(),
+        }
+    }
+
+    /// Records the replication of the chunk under `msg_id`, returning the holder to query
+    /// first, the closest to the chunk. Returns `None` if there are no holders, in which
+    /// case nothing is recorded.
+    pub fn insert(
+        &mut self,
+        msg_id: MessageId,
+        address: BlobAddress,
+        holders: impl IntoIterator<Item = XorName>,
+        now: Instant,
+    ) -> Option<XorName> {
+        let mut holders: Vec<_> = holders.into_iter().collect();
+        holders.sort_by(|lhs, rhs| address.name().cmp_distance(lhs, rhs));
+        let mut untried: VecDeque<_> = holders.into();
+        let queried = untried.pop_front()?;
+        let _ = self.requesters.entry(address).or_default().insert(msg_id);
+        let _ = self.ops.insert(
+            msg_id,
+            PendingReplication {
+                address,
+                requested_at: now,
+                queried,
+                untried,
+            },
+        );
+        Some(queried)
+    }
+
+    /// Moves the replication on to the next holder, after `holder` failed to provide a copy.
+    /// The replication is dropped once all holders have failed. Returns `None` if no
+    /// replication is pending under `msg_id`, or if `holder` isn't the one being queried.
+    pub fn fail(&mut self, msg_id: &MessageId, holder: &XorName) -> Option<Retry> {
+        let op = self.ops.get_mut(msg_id)?;
+        if op.queried != *holder {
+            return None;
+        }
+        let address = op.address;
+        match op.untried.pop_front() {
+            Some(next) => {
+                op.queried = next;
+                Some(Retry::Next {
+                    address,
+                    holder: next,
+                })
+            }
+            None => {
+                let _ = self.ops.remove(msg_id);
+                let requesters = self.requesters.remove(&address).unwrap_or_default();
+                Some(Retry::Exhausted {
+                    address,
+                    requesters,
+                })
+            }
+        }
+    }
+
+    /// The address of the chunk being replicated under `msg_id`, if still pending.
+    pub fn address(&self, msg_id: &MessageId) -> Option<BlobAddress> {
+        self.ops.get(msg_id).map(|op| op.address)
+    }
+
+    /// Removes the replication of the chunk, queued or in flight, e.g. once its copy is
+    /// stored, returning its requesters. None are returned if it wasn't pending, e.g. as it
+    /// expired before the copy arrived.
+    pub fn remove(&mut self, address: &BlobAddress) -> BTreeSet<MessageId> {
+        self.ops.retain(|_, op| op.address != *address);
+        self.queue.retain(|queued| queued.address != *address);
+        self.requesters.remove(address).unwrap_or_default()
+    }
+
+    /// Removes the replication of the chunk once its copy is stored, as `remove` does,
+    /// remembering its requesters for the timeout, for the copies other holders may still
+    /// send to be told apart from those of unknown replications.
+    pub fn complete(&mut self, address: &BlobAddress, now: Instant) -> BTreeSet<MessageId> {
+        let requesters = self.remove(address);
+        let timeout = self.timeout;
+        self.completed
+            .retain(|_, completed_at| now.saturating_duration_since(*completed_at) < timeout);
+        for msg_id in &requesters {
+            let _ = self.completed.insert(*msg_id, now);
+        }
+        requesters
+    }
+
+    /// Whether the replication under `msg_id` completed within the timeout.
+    pub fn was_completed(&self, msg_id: &MessageId, now: Instant) -> bool {
+        self.completed.get(msg_id).map_or(false, |completed_at| {
+            now.saturating_duration_since(*completed_at) < self.timeout
+        })
+    }
+
+    /// Removes the replications pending for longer than the timeout, returning their chunks
+    /// along with their requesters.
+    pub fn expire(&mut self, now: Instant) -> Vec<(BlobAddress, BTreeSet<MessageId>)> {
+        let timeout = self.timeout;
+        let expired: Vec<_> = self
+            .ops
+            .values()
+            .filter(|op| now.saturating_duration_since(op.requested_at) >= timeout)
+            .map(|op| op.address)
+            .collect();
+        expired
+            .into_iter()
+            .map(|address| {
+                let requesters = self.remove(&address);
+                (address, requesters)
+            })
+            .collect()
+    }
+
+    /// Removes all the replications, queued or in flight, returning their chunks
+    /// along with their requesters.
+    pub fn abandon_all(&mut self) -> Vec<(BlobAddress, BTreeSet<MessageId>)> {
+        self.ops.clear();
+        self.queue.clear();
+        self.requesters.drain().collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty() && self.queue.is_empty() && self.requesters.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PendingReplications, QueuedReplication, ReplicationStats, Retry};
+    use crate::{Error, Result};
+    use sn_data_types::PublicBlob;
+    use sn_messaging::MessageId;
+    use std::time::{Duration, Instant};
+    use xor_name::XorName;
+
+    fn queued(byte: u8) -> QueuedReplication {
+        QueuedReplication {
+            msg_id: MessageId::new(),
+            address: *PublicBlob::new(vec![byte]).address(),
+            holders: std::iter::once(XorName::random()).collect(),
+        }
+    }
+
+    #[test]
+    fn completed_replications_are_remembered_for_the_timeout() {
+        let now = Instant::now();
+        let timeout = Duration::from_secs(10);
+        let mut replications = PendingReplications::new(timeout, 1, 10);
+        let replication = queued(0);
+        let _ = replications.insert(
+            replication.msg_id,
+            replication.address,
+            replication.holders,
+            now,
+        );
+
+        assert!(!replications.was_completed(&replication.msg_id, now));
+        let requesters = replications.complete(&replication.address, now);
+        assert_eq!(requesters, std::iter::once(replication.msg_id).collect());
+        assert!(replications.address(&replication.msg_id).is_none());
+        assert!(replications.was_completed(&replication.msg_id, now + timeout / 2));
+        assert!(!replications.was_completed(&replication.msg_id, now + timeout));
+    }
+
+    #[test]
+    fn queued_replications_start_in_order_as_slots_free_up() -> Result<()> {
+        let now = Instant::now();
+        let mut replications = PendingReplications::new(Duration::from_secs(10), 1, 10);
+        let mut in_flight = queued(0);
+        assert!(replications.has_capacity());
+        let _ = replications.insert(
+            in_flight.msg_id,
+            in_flight.address,
+            in_flight.holders.clone(),
+            now,
+        );
+        assert!(!replications.has_capacity());
+
+        let mut addresses = vec![];
+        for byte in 1..4 {
+            let replication = queued(byte);
+            addresses.push(replication.address);
+            if replications.enqueue(replication).is_err() {
+                return Err(Error::Logic("Queue unexpectedly full".to_string()));
+            }
+        }
+        assert_eq!(
+            replications.stats(),
+            ReplicationStats {
+                in_flight: 1,
+                queued: 3
+            }
+        );
+        assert!(replications.dequeue().is_none());
+
+        for address in addresses {
+            assert!(!replications.remove(&in_flight.address).is_empty());
+            in_flight = replications
+                .dequeue()
+                .ok_or_else(|| Error::Logic("Nothing dequeued".to_string()))?;
+            assert_eq!(in_flight.address, address);
+            let _ = replications.insert(
+                in_flight.msg_id,
+                in_flight.address,
+                in_flight.holders.clone(),
+                now,
+            );
+        }
+        assert_eq!(
+            replications.stats(),
+            ReplicationStats {
+                in_flight: 1,
+                queued: 0
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn queue_is_capped() -> Result<()> {
+        let mut replications = PendingReplications::new(Duration::from_secs(10), 0, 2);
+        for byte in 1..3 {
+            if replications.enqueue(queued(byte)).is_err() {
+                return Err(Error::Logic("Queue unexpectedly full".to_string()));
+            }
+        }
+        let overflow = queued(3);
+        let address = overflow.address;
+        match replications.enqueue(overflow) {
+            Err(replication) if replication.address == address => (),
+            x => return Err(Error::Logic(format!("Unexpected: {:?}", x))),
+        }
+        assert_eq!(replications.stats().queued, 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn coalesced_replication_is_cleaned_up_on_failure_and_expiry() -> Result<()> {
+        let now = Instant::now();
+        let timeout = Duration::from_secs(10);
+        let mut replications = PendingReplications::new(timeout, 10, 10);
+        let replication = queued(0);
+        let holder = replications
+            .insert(
+                replication.msg_id,
+                replication.address,
+                replication.holders.clone(),
+                now,
+            )
+            .ok_or_else(|| Error::Logic("No holder to query".to_string()))?;
+        let other = MessageId::new();
+        assert!(replications.join(other, replication.address));
+        assert!(!replications.join(MessageId::new(), queued(1).address));
+
+        // Both requesters are answered once the only holder fails.
+        match replications.fail(&replication.msg_id, &holder) {
+            Some(Retry::Exhausted {
+                address,
+                requesters,
+            }) if address == replication.address
+                && requesters == vec![replication.msg_id, other].into_iter().collect() => {}
+            x => return Err(Error::Logic(format!("Unexpected: {:?}", x))),
+        }
+        assert!(replications.is_empty());
+
+        // Likewise once it times out.
+        let _ = replications.insert(
+            replication.msg_id,
+            replication.address,
+            replication.holders.clone(),
+            now,
+        );
+        assert!(replications.join(other, replication.address));
+        match replications.expire(now + timeout).as_slice() {
+            [(address, requesters)] if *address == replication.address => {
+                assert_eq!(requesters.len(), 2)
+            }
+            x => return Err(Error::Logic(format!("Unexpected: {:?}", x))),
+        }
+        assert!(replications.is_empty());
+
+        Ok(())
+    }
+}
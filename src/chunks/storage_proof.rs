@@ -0,0 +1,127 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{utils, Result};
+use serde::{Deserialize, Serialize};
+use sn_data_types::{Blob, BlobAddress, PublicKey, Signature};
+use std::collections::HashMap;
+
+/// Proof of holding a chunk, in answer to an audit challenge, without sending the chunk.
+/// Only a holder of the whole chunk can compute it, the nonce being picked anew by
+/// Elders for every challenge.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageProof {
+    /// The address of the chunk.
+    pub address: BlobAddress,
+    /// The nonce of the challenge.
+    pub nonce: Vec<u8>,
+    /// SHA3-256 of the nonce followed by the serialised chunk.
+    pub digest: [u8; 32],
+}
+
+impl StorageProof {
+    /// The proof expected from a holder of the chunk, as computed from a copy of it.
+    pub fn expected(chunk: &Blob, nonce: Vec<u8>) -> Result<Self> {
+        let digest = utils::digest(&nonce, &utils::serialise(chunk)?[..])?;
+        Ok(Self {
+            address: *chunk.address(),
+            nonce,
+            digest,
+        })
+    }
+
+    /// Whether the proof was signed by the holder with its node key.
+    pub fn is_signed_by(&self, holder: &PublicKey, signature: &Signature) -> bool {
+        match utils::serialise(self) {
+            Ok(data) => holder.verify(signature, &data).is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    /// The digest given by a majority of the holders answering the same challenge,
+    /// for when no copy of the chunk is at hand. None without a majority.
+    pub fn majority_digest(proofs: &[StorageProof]) -> Option<[u8; 32]> {
+        let mut counts = HashMap::new();
+        for proof in proofs {
+            *counts.entry(proof.digest).or_insert(0) += 1;
+        }
+        counts
+            .into_iter()
+            .find(|(_, count)| 2 * count > proofs.len())
+            .map(|(digest, _)| digest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StorageProof;
+    use crate::{
+        chunk_store::{BlobChunkStore, UsedSpace},
+        chunks::chunk_backend::{ChunkBackend, MemoryChunkStore},
+        utils, Error, Result,
+    };
+    use sn_data_types::{Blob, Keypair, PublicBlob};
+    use tempdir::TempDir;
+
+    #[tokio::test]
+    async fn proof_of_stored_chunk_is_the_one_expected() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let mut store = BlobChunkStore::new(root.path(), UsedSpace::new(u64::MAX)).await?;
+        let chunk = Blob::Public(PublicBlob::new(vec![1_u8; 200_000]));
+        store.put(&chunk).await?;
+
+        let nonce = vec![7_u8; 32];
+        let expected = StorageProof::expected(&chunk, nonce.clone())?;
+        assert_eq!(
+            ChunkBackend::digest(&store, chunk.address(), &nonce)?,
+            expected.digest
+        );
+        // Another challenge calls for another proof.
+        assert_ne!(
+            ChunkBackend::digest(&store, chunk.address(), &[8_u8; 32])?,
+            expected.digest
+        );
+
+        let mut memory = MemoryChunkStore::new(u64::MAX);
+        ChunkBackend::put(&mut memory, &chunk).await?;
+        assert_eq!(memory.digest(chunk.address(), &nonce)?, expected.digest);
+
+        let keypair = Keypair::new_ed25519(&mut rand::thread_rng());
+        let signature = keypair.sign(&utils::serialise(&expected)?);
+        assert!(expected.is_signed_by(&keypair.public_key(), &signature));
+        Ok(())
+    }
+
+    #[test]
+    fn fabricated_proof_is_detected() -> Result<()> {
+        let chunk = Blob::Public(PublicBlob::new(vec![1_u8; 64]));
+        let other = Blob::Public(PublicBlob::new(vec![2_u8; 64]));
+        let nonce = vec![7_u8; 32];
+        let expected = StorageProof::expected(&chunk, nonce.clone())?;
+
+        // Computed from another chunk, passed off as the challenged one.
+        let mut fabricated = StorageProof::expected(&other, nonce)?;
+        fabricated.address = *chunk.address();
+        assert_ne!(fabricated, expected);
+
+        // Signed by another node than the holder challenged.
+        let holder = Keypair::new_ed25519(&mut rand::thread_rng());
+        let impostor = Keypair::new_ed25519(&mut rand::thread_rng());
+        let signature = impostor.sign(&utils::serialise(&expected)?);
+        assert!(!expected.is_signed_by(&holder.public_key(), &signature));
+
+        // Outvoted by the honest holders.
+        let proofs = vec![expected.clone(), fabricated, expected.clone()];
+        assert_eq!(
+            StorageProof::majority_digest(&proofs),
+            Some(expected.digest)
+        );
+        assert_eq!(StorageProof::majority_digest(&proofs[..2]), None);
+        Ok(())
+    }
+}
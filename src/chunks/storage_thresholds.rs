@@ -0,0 +1,105 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use std::collections::BTreeSet;
+
+/// A change of the used space across one of the thresholds, in percent of the max capacity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Crossing {
+    /// The used space reached the threshold.
+    Above(u8),
+    /// The used space dropped back below the threshold, e.g. after chunks were deleted.
+    Below(u8),
+}
+
+/// The thresholds of used space the Elders are told of reaching, once each, until the used
+/// space drops back below them.
+pub(super) struct StorageThresholds {
+    thresholds: Vec<u8>,
+    reached: BTreeSet<u8>,
+}
+
+impl StorageThresholds {
+    pub fn new(mut thresholds: Vec<u8>) -> Self {
+        thresholds.retain(|threshold| *threshold <= 100);
+        thresholds.sort_unstable();
+        thresholds.dedup();
+        Self {
+            thresholds,
+            reached: BTreeSet::new(),
+        }
+    }
+
+    /// Replaces the thresholds, those kept remaining reached if they were.
+    pub fn set(&mut self, thresholds: Vec<u8>) {
+        let reached = std::mem::take(&mut self.reached);
+        *self = Self::new(thresholds);
+        self.reached = reached
+            .into_iter()
+            .filter(|threshold| self.thresholds.contains(threshold))
+            .collect();
+    }
+
+    /// The thresholds crossed since the last check, given the used space ratio now,
+    /// in ascending order of threshold.
+    pub fn check(&mut self, used_space_ratio: f64) -> Vec<Crossing> {
+        let percent = used_space_ratio * 100.0;
+        let mut crossings = vec![];
+        for threshold in &self.thresholds {
+            let above = percent >= f64::from(*threshold);
+            if above && self.reached.insert(*threshold) {
+                crossings.push(Crossing::Above(*threshold));
+            } else if !above && self.reached.remove(threshold) {
+                crossings.push(Crossing::Below(*threshold));
+            }
+        }
+        crossings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Crossing, StorageThresholds};
+
+    #[test]
+    fn each_threshold_is_crossed_once() {
+        let mut thresholds = StorageThresholds::new(vec![80, 50, 95]);
+        assert!(thresholds.check(0.3).is_empty());
+        assert_eq!(
+            thresholds.check(0.85),
+            vec![Crossing::Above(50), Crossing::Above(80)]
+        );
+        // still above them
+        assert!(thresholds.check(0.86).is_empty());
+        assert_eq!(thresholds.check(0.95), vec![Crossing::Above(95)]);
+        assert!(thresholds.check(1.0).is_empty());
+    }
+
+    #[test]
+    fn threshold_is_rearmed_below_it() {
+        let mut thresholds = StorageThresholds::new(vec![50, 80]);
+        assert_eq!(
+            thresholds.check(0.9),
+            vec![Crossing::Above(50), Crossing::Above(80)]
+        );
+        // chunks deleted
+        assert_eq!(thresholds.check(0.6), vec![Crossing::Below(80)]);
+        assert!(thresholds.check(0.7).is_empty());
+        assert_eq!(thresholds.check(0.8), vec![Crossing::Above(80)]);
+    }
+
+    #[test]
+    fn changed_thresholds_keep_those_reached() {
+        let mut thresholds = StorageThresholds::new(vec![50, 80]);
+        assert_eq!(thresholds.check(0.6), vec![Crossing::Above(50)]);
+        thresholds.set(vec![50, 55, 90]);
+        // 50 isn't told of again
+        assert_eq!(thresholds.check(0.6), vec![Crossing::Above(55)]);
+        assert!(thresholds.check(0.85).is_empty());
+    }
+}
@@ -0,0 +1,114 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use sn_data_types::{Blob, BlobAddress};
+use std::time::{Duration, Instant};
+
+/// Max number of chunks queued before the batch is flushed.
+const MAX_BATCH_COUNT: usize = 64;
+
+/// Max total bytes of chunk values queued before the batch is flushed.
+const MAX_BATCH_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Max time a chunk is queued before the batch is flushed.
+pub(crate) const MAX_BATCH_LATENCY: Duration = Duration::from_millis(50);
+
+/// Chunks received for replication, queued to be written to the store together.
+/// Client stores are never queued, as their latency matters more than throughput.
+#[derive(Default)]
+pub(crate) struct WriteBatch {
+    chunks: Vec<Blob>,
+    bytes: u64,
+    opened: Option<Instant>,
+}
+
+impl WriteBatch {
+    /// Queues the chunk, unless a chunk with the same address is already queued.
+    pub fn push(&mut self, chunk: Blob, now: Instant) {
+        if self.get(chunk.address()).is_some() {
+            return;
+        }
+        self.bytes += chunk.value().len() as u64;
+        self.chunks.push(chunk);
+        if self.opened.is_none() {
+            self.opened = Some(now);
+        }
+    }
+
+    /// Whether the batch has reached its max count or size.
+    pub fn is_full(&self) -> bool {
+        self.chunks.len() >= MAX_BATCH_COUNT || self.bytes >= MAX_BATCH_BYTES
+    }
+
+    /// Whether the batch is full, or its oldest chunk has been queued for the max latency.
+    pub fn is_due(&self, now: Instant) -> bool {
+        self.is_full()
+            || self.opened.map_or(false, |opened| {
+                now.saturating_duration_since(opened) >= MAX_BATCH_LATENCY
+            })
+    }
+
+    /// Returns the queued chunk at `address`, if any.
+    pub fn get(&self, address: &BlobAddress) -> Option<&Blob> {
+        self.chunks.iter().find(|chunk| chunk.address() == address)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Empties the batch, returning the queued chunks in the order received.
+    pub fn take(&mut self) -> Vec<Blob> {
+        self.bytes = 0;
+        self.opened = None;
+        std::mem::take(&mut self.chunks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{WriteBatch, MAX_BATCH_COUNT, MAX_BATCH_LATENCY};
+    use sn_data_types::{Blob, PublicBlob};
+    use std::time::Instant;
+
+    #[test]
+    fn batch_is_due_when_full_or_after_max_latency() {
+        let start = Instant::now();
+        let mut batch = WriteBatch::default();
+        assert!(!batch.is_due(start + MAX_BATCH_LATENCY));
+
+        batch.push(Blob::Public(PublicBlob::new(vec![0])), start);
+        assert!(!batch.is_due(start));
+        assert!(batch.is_due(start + MAX_BATCH_LATENCY));
+
+        for i in 1..MAX_BATCH_COUNT {
+            assert!(!batch.is_full());
+            batch.push(
+                Blob::Public(PublicBlob::new(i.to_be_bytes().to_vec())),
+                start,
+            );
+        }
+        assert!(batch.is_full());
+        assert!(batch.is_due(start));
+
+        assert_eq!(batch.take().len(), MAX_BATCH_COUNT);
+        assert!(batch.is_empty());
+        assert!(!batch.is_due(start + MAX_BATCH_LATENCY));
+    }
+
+    #[test]
+    fn same_chunk_is_queued_once() {
+        let now = Instant::now();
+        let mut batch = WriteBatch::default();
+        let chunk = Blob::Public(PublicBlob::new(vec![1; 10]));
+        batch.push(chunk.clone(), now);
+        batch.push(chunk.clone(), now);
+        assert_eq!(batch.get(chunk.address()), Some(&chunk));
+        assert_eq!(batch.take(), vec![chunk]);
+    }
+}
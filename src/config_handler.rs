@@ -21,6 +21,7 @@ use std::{
     io::{self, BufReader},
     net::{IpAddr, Ipv4Addr, SocketAddr},
     path::PathBuf,
+    time::SystemTime,
 };
 use structopt::StructOpt;
 
@@ -28,6 +29,21 @@ const CONFIG_FILE: &str = "node.config";
 const CONNECTION_INFO_FILE: &str = "node_connection_info.config";
 const DEFAULT_ROOT_DIR_NAME: &str = "root_dir";
 const DEFAULT_MAX_CAPACITY: u64 = 2 * 1024 * 1024 * 1024;
+const DEFAULT_CHUNK_SCRUB_RATE: u64 = 60;
+const DEFAULT_CHUNK_QUARANTINE_CAPACITY: u64 = 100 * 1024 * 1024;
+const DEFAULT_CHUNK_REPLICATION_TIMEOUT: u64 = 120;
+const DEFAULT_MAX_CHUNK_REPLICATIONS: usize = 16;
+const DEFAULT_CHUNK_REPLICATION_QUEUE_SIZE: usize = 1000;
+const DEFAULT_STORAGE_THRESHOLDS: [u8; 3] = [50, 80, 95];
+const DEFAULT_RECENT_MSG_TIMEOUT: u64 = 60;
+const DEFAULT_MAX_RECENT_MSGS: usize = 10_000;
+const DEFAULT_CHUNK_REPLICATION_RATE: usize = 1000;
+const DEFAULT_MAX_CHUNK_REPLICATION_FAILURES: usize = 8;
+const DEFAULT_MAX_MAPS_PER_OWNER: u64 = 0;
+const DEFAULT_MAX_SEQUENCE_RANGE_ENTRIES: usize = 1000;
+const DEFAULT_MAX_SEQUENCE_RANGE_SIZE: u64 = 1024 * 1024;
+const DEFAULT_MAX_CLIENT_REQUESTS_PER_SEC: usize = 0;
+const DEFAULT_REWARD_PAYOUT_TIMEOUT: u64 = 120;
 
 /// Node configuration
 #[derive(Default, Clone, Debug, Serialize, Deserialize, Eq, PartialEq, StructOpt)]
@@ -41,6 +57,71 @@ pub struct Config {
     /// Upper limit in bytes for allowed network storage on this node.
     #[structopt(short, long)]
     pub max_capacity: Option<u64>,
+    /// Size in bytes of the in-memory cache of frequently read chunks. Disabled if not set.
+    #[structopt(long)]
+    pub chunk_cache_size: Option<u64>,
+    /// Number of stored chunks per minute to verify against their addresses in the background.
+    /// Defaults to 60. A value of 0 disables this feature.
+    #[structopt(long)]
+    pub chunk_scrub_rate: Option<u64>,
+    /// Upper limit in bytes for the space taken by corrupted chunks set aside for inspection.
+    /// Oldest ones are removed first when exceeded. Defaults to 100 MiB.
+    #[structopt(long)]
+    pub chunk_quarantine_capacity: Option<u64>,
+    /// Seconds to wait for a copy of a chunk being replicated to this node before giving up
+    /// and reporting the failure to Elders. Defaults to 120.
+    #[structopt(long)]
+    pub chunk_replication_timeout: Option<u64>,
+    /// Max number of chunks being replicated to this node at once, further ones being queued
+    /// until those in flight complete or time out. Defaults to 16.
+    #[structopt(long)]
+    pub max_chunk_replications: Option<usize>,
+    /// Max number of chunk replications to queue. Replications beyond it are reported
+    /// as failed to Elders, for them to re-issue later. Defaults to 1000.
+    #[structopt(long)]
+    pub chunk_replication_queue_size: Option<usize>,
+    /// Percentages of the max capacity whose reaching by the used space is logged, Elders
+    /// being told once we're nearly full. Each is notified again only after the used space
+    /// dropped back below it. Defaults to 50 80 95.
+    #[structopt(long)]
+    pub storage_thresholds: Option<Vec<u8>>,
+    /// Seconds for which, as an Adult, the chunk reads and writes handled are remembered,
+    /// so that redeliveries of them are answered as they were, not handled again. Defaults to 60.
+    #[structopt(long)]
+    pub recent_msg_timeout: Option<u64>,
+    /// Max number of chunk reads and writes remembered, oldest ones being forgotten first.
+    /// Defaults to 10000. A value of 0 disables this feature.
+    #[structopt(long)]
+    pub max_recent_msgs: Option<usize>,
+    /// Max number of chunk replication cmds sent per second as an Elder, when replicating
+    /// the chunks of nodes that left. The rest wait their turn. Defaults to 1000.
+    #[structopt(long)]
+    pub chunk_replication_rate: Option<usize>,
+    /// Number of failures reported by Adults replicating a chunk after which, as an Elder,
+    /// the chunk is deemed lost and no longer replicated. Defaults to 8.
+    #[structopt(long)]
+    pub max_chunk_replication_failures: Option<usize>,
+    /// Max number of Maps a single owner may have stored, as an Elder, 0 meaning unlimited.
+    /// Defaults to 0.
+    #[structopt(long)]
+    pub max_maps_per_owner: Option<u64>,
+    /// Max number of Sequence entries in a range response, as an Elder, longer ranges being
+    /// truncated. Defaults to 1000.
+    #[structopt(long)]
+    pub max_sequence_range_entries: Option<usize>,
+    /// Max size in bytes of the Sequence entries in a range response, as an Elder, larger
+    /// ranges being truncated. Defaults to 1 MiB.
+    #[structopt(long)]
+    pub max_sequence_range_size: Option<u64>,
+    /// Max number of data requests per second a single client may send, as an Elder, those
+    /// beyond it being rejected. 0 means unlimited. Defaults to 0.
+    #[structopt(long)]
+    pub max_client_requests_per_sec: Option<usize>,
+    /// Seconds to wait, as an Elder, for the other Elders to agree on a reward payout before
+    /// sending our signature shares of it anew, e.g. after an Elder churn mid-payout, giving
+    /// up after a few times. Defaults to 120.
+    #[structopt(long)]
+    pub reward_payout_timeout: Option<u64>,
     /// Root directory for ChunkStores and cached state. If not set, it defaults to "root_dir"
     /// within the sn_node project data directory, located at:
     /// Linux: $HOME/.safe/node/root_dir
@@ -48,6 +129,14 @@ pub struct Config {
     /// MacOS: $HOME/.safe/node/root_dir
     #[structopt(short, long, parse(from_os_str))]
     pub root_dir: Option<PathBuf>,
+    /// Root directory for the chunks held as an Adult, e.g. on a larger disk than the rest.
+    /// Defaults to the root directory.
+    #[structopt(long, parse(from_os_str))]
+    pub chunk_root_dir: Option<PathBuf>,
+    /// Root directory for the Maps, Sequences and chunk metadata held as an Elder, e.g. on a
+    /// faster disk than the chunks. Defaults to the root directory.
+    #[structopt(long, parse(from_os_str))]
+    pub metadata_root_dir: Option<PathBuf>,
     /// Verbose output. `-v` is equivalent to logging with `warn`, `-vv` to `info`, `-vvv` to
     /// `debug`, `-vvvv` to `trace`. This flag overrides RUST_LOG.
     #[structopt(short, long, parse(from_occurrences))]
@@ -67,6 +156,39 @@ pub struct Config {
     /// Delete all data from a previous node running on the same PC
     #[structopt(long)]
     pub clear_data: bool,
+    /// On startup, remove stray files from the chunk store and recompute its used space
+    /// from the chunks actually held. This can take a while for large stores.
+    #[structopt(long)]
+    pub compact_chunk_store: bool,
+    /// On startup, trust the space recorded as used by each chunk store rather than checking
+    /// it against the sizes of the chunk files held.
+    #[structopt(long)]
+    pub skip_used_space_check: bool,
+    /// On startup, check the space recorded as used by each chunk store against a sample of
+    /// the chunk files held only, summing up all of them if the record is obviously wrong.
+    /// Meant for very large stores.
+    #[structopt(long)]
+    pub fast_used_space_check: bool,
+    /// Hold chunks in memory rather than on disk, e.g. for tests and ephemeral nodes.
+    /// Chunks held this way are lost when the node stops.
+    #[structopt(long)]
+    pub in_memory_chunks: bool,
+    /// Write the data held by the node to an archive at the given path, e.g. to move the node
+    /// to another machine, then exit without starting the node.
+    #[structopt(long, parse(from_os_str))]
+    pub export_data: Option<PathBuf>,
+    /// Restore the data held by the node from an archive written with `--export-data` before
+    /// starting. The archive is refused if incomplete, corrupted or overlapping existing data.
+    #[structopt(long, parse(from_os_str))]
+    pub import_data: Option<PathBuf>,
+    /// Reject the data cmds whose payment wasn't signed by the requester. Off by default,
+    /// as some clients pay from another key than the one they send requests with.
+    #[structopt(long)]
+    pub strict_payment_checks: bool,
+    /// A hex formatted BLS public key allowed to query the section funds of this node as an
+    /// Elder, besides the members of its section, e.g. for monitoring.
+    #[structopt(long)]
+    pub funds_monitor_key: Option<String>,
     /// If the node is the first node on the network, the local address to be used should be passed.
     /// To use a random port number, use 0. If this argument is passed `--local-ip` and `--local-port`
     /// is not requried, however if they are passed, they should match the value provided here.
@@ -127,12 +249,38 @@ impl Config {
     /// Returns a new `Config` instance.  Tries to read from the default node config file location,
     /// and overrides values with any equivalent command line args.
     pub fn new() -> Result<Self, Error> {
+        let config = Self::load(Config::from_args())?;
+
+        config.clear_data_from_disk().unwrap_or_else(|_| {
+            log::error!("Error deleting data file from disk");
+        });
+
+        Ok(config)
+    }
+
+    /// Reads the config anew, as `new` does, e.g. for a running node to pick up the changes
+    /// made to the config file. No data is deleted, even with `--clear-data`, and invalid
+    /// command line args are returned as an error rather than exiting.
+    pub fn reload() -> Result<Self, Error> {
+        let command_line_args = Config::from_iter_safe(std::env::args())
+            .map_err(|e| Error::Configuration(e.to_string()))?;
+        Self::load(command_line_args)
+    }
+
+    /// Time the default node config file was last modified, if there is one.
+    pub fn file_modified() -> Option<SystemTime> {
+        let path = project_dirs().ok()?.join(CONFIG_FILE);
+        fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+
+    fn load(mut command_line_args: Config) -> Result<Self, Error> {
         let mut config = match Self::read_from_file() {
             Ok(Some(config)) => config,
             Ok(None) | Err(_) => Default::default(),
         };
 
-        let mut command_line_args = Config::from_args();
         command_line_args.validate()?;
 
         if let Some(socket_addr) = command_line_args.first {
@@ -141,10 +289,6 @@ impl Config {
 
         config.merge(command_line_args);
 
-        config.clear_data_from_disk().unwrap_or_else(|_| {
-            log::error!("Error deleting data file from disk");
-        });
-
         Ok(config)
     }
 
@@ -172,10 +316,82 @@ impl Config {
             self.max_capacity = Some(*max_capacity);
         }
 
+        if let Some(chunk_cache_size) = &config.chunk_cache_size {
+            self.chunk_cache_size = Some(*chunk_cache_size);
+        }
+
+        if let Some(chunk_scrub_rate) = &config.chunk_scrub_rate {
+            self.chunk_scrub_rate = Some(*chunk_scrub_rate);
+        }
+
+        if let Some(chunk_quarantine_capacity) = &config.chunk_quarantine_capacity {
+            self.chunk_quarantine_capacity = Some(*chunk_quarantine_capacity);
+        }
+
+        if let Some(chunk_replication_timeout) = &config.chunk_replication_timeout {
+            self.chunk_replication_timeout = Some(*chunk_replication_timeout);
+        }
+
+        if let Some(max_chunk_replications) = &config.max_chunk_replications {
+            self.max_chunk_replications = Some(*max_chunk_replications);
+        }
+
+        if let Some(chunk_replication_queue_size) = &config.chunk_replication_queue_size {
+            self.chunk_replication_queue_size = Some(*chunk_replication_queue_size);
+        }
+
+        if let Some(storage_thresholds) = &config.storage_thresholds {
+            self.storage_thresholds = Some(storage_thresholds.clone());
+        }
+
+        if let Some(recent_msg_timeout) = &config.recent_msg_timeout {
+            self.recent_msg_timeout = Some(*recent_msg_timeout);
+        }
+
+        if let Some(max_recent_msgs) = &config.max_recent_msgs {
+            self.max_recent_msgs = Some(*max_recent_msgs);
+        }
+
+        if let Some(chunk_replication_rate) = &config.chunk_replication_rate {
+            self.chunk_replication_rate = Some(*chunk_replication_rate);
+        }
+
+        if let Some(max_chunk_replication_failures) = &config.max_chunk_replication_failures {
+            self.max_chunk_replication_failures = Some(*max_chunk_replication_failures);
+        }
+
+        if let Some(max_maps_per_owner) = &config.max_maps_per_owner {
+            self.max_maps_per_owner = Some(*max_maps_per_owner);
+        }
+
+        if let Some(max_sequence_range_entries) = &config.max_sequence_range_entries {
+            self.max_sequence_range_entries = Some(*max_sequence_range_entries);
+        }
+
+        if let Some(max_sequence_range_size) = &config.max_sequence_range_size {
+            self.max_sequence_range_size = Some(*max_sequence_range_size);
+        }
+
+        if let Some(max_client_requests_per_sec) = &config.max_client_requests_per_sec {
+            self.max_client_requests_per_sec = Some(*max_client_requests_per_sec);
+        }
+
+        if let Some(reward_payout_timeout) = &config.reward_payout_timeout {
+            self.reward_payout_timeout = Some(*reward_payout_timeout);
+        }
+
         if let Some(root_dir) = &config.root_dir {
             self.root_dir = Some(root_dir.clone());
         }
 
+        if let Some(chunk_root_dir) = &config.chunk_root_dir {
+            self.chunk_root_dir = Some(chunk_root_dir.clone());
+        }
+
+        if let Some(metadata_root_dir) = &config.metadata_root_dir {
+            self.metadata_root_dir = Some(metadata_root_dir.clone());
+        }
+
         if config.verbose > 0 {
             self.verbose = config.verbose;
         }
@@ -191,6 +407,24 @@ impl Config {
         self.update = config.update || self.update;
         self.update_only = config.update_only || self.update_only;
         self.clear_data = config.clear_data || self.clear_data;
+        self.compact_chunk_store = config.compact_chunk_store || self.compact_chunk_store;
+        self.skip_used_space_check = config.skip_used_space_check || self.skip_used_space_check;
+        self.fast_used_space_check = config.fast_used_space_check || self.fast_used_space_check;
+        self.in_memory_chunks = config.in_memory_chunks || self.in_memory_chunks;
+
+        if let Some(export_data) = &config.export_data {
+            self.export_data = Some(export_data.clone());
+        }
+
+        if let Some(import_data) = &config.import_data {
+            self.import_data = Some(import_data.clone());
+        }
+
+        self.strict_payment_checks = config.strict_payment_checks || self.strict_payment_checks;
+
+        if let Some(funds_monitor_key) = &config.funds_monitor_key {
+            self.funds_monitor_key = Some(funds_monitor_key.clone());
+        }
 
         if let Some(socket_addr) = config.first {
             self.first = Some(socket_addr);
@@ -251,6 +485,101 @@ impl Config {
         self.max_capacity.unwrap_or(DEFAULT_MAX_CAPACITY)
     }
 
+    /// Size in bytes of the in-memory chunk cache. Zero when the cache is disabled.
+    pub fn chunk_cache_size(&self) -> u64 {
+        self.chunk_cache_size.unwrap_or(0)
+    }
+
+    /// Number of stored chunks per minute to verify in the background. Zero when disabled.
+    pub fn chunk_scrub_rate(&self) -> u64 {
+        self.chunk_scrub_rate.unwrap_or(DEFAULT_CHUNK_SCRUB_RATE)
+    }
+
+    /// Upper limit in bytes for the space taken by quarantined corrupted chunks.
+    pub fn chunk_quarantine_capacity(&self) -> u64 {
+        self.chunk_quarantine_capacity
+            .unwrap_or(DEFAULT_CHUNK_QUARANTINE_CAPACITY)
+    }
+
+    /// Seconds to wait for a copy of a chunk being replicated to this node.
+    pub fn chunk_replication_timeout(&self) -> u64 {
+        self.chunk_replication_timeout
+            .unwrap_or(DEFAULT_CHUNK_REPLICATION_TIMEOUT)
+    }
+
+    /// Max number of chunks being replicated to this node at once.
+    pub fn max_chunk_replications(&self) -> usize {
+        self.max_chunk_replications
+            .unwrap_or(DEFAULT_MAX_CHUNK_REPLICATIONS)
+    }
+
+    /// Max number of chunk replications queued awaiting a slot.
+    pub fn chunk_replication_queue_size(&self) -> usize {
+        self.chunk_replication_queue_size
+            .unwrap_or(DEFAULT_CHUNK_REPLICATION_QUEUE_SIZE)
+    }
+
+    /// Percentages of the max capacity whose reaching by the used space is notified.
+    pub fn storage_thresholds(&self) -> Vec<u8> {
+        self.storage_thresholds
+            .clone()
+            .unwrap_or_else(|| DEFAULT_STORAGE_THRESHOLDS.to_vec())
+    }
+
+    /// Seconds for which the chunk reads and writes handled are remembered.
+    pub fn recent_msg_timeout(&self) -> u64 {
+        self.recent_msg_timeout
+            .unwrap_or(DEFAULT_RECENT_MSG_TIMEOUT)
+    }
+
+    /// Max number of chunk reads and writes remembered.
+    pub fn max_recent_msgs(&self) -> usize {
+        self.max_recent_msgs.unwrap_or(DEFAULT_MAX_RECENT_MSGS)
+    }
+
+    /// Max number of chunk replication cmds sent per second as an Elder.
+    pub fn chunk_replication_rate(&self) -> usize {
+        self.chunk_replication_rate
+            .unwrap_or(DEFAULT_CHUNK_REPLICATION_RATE)
+    }
+
+    /// Number of replication failures after which a chunk is deemed lost.
+    pub fn max_chunk_replication_failures(&self) -> usize {
+        self.max_chunk_replication_failures
+            .unwrap_or(DEFAULT_MAX_CHUNK_REPLICATION_FAILURES)
+    }
+
+    /// Max number of Maps a single owner may have stored, 0 meaning unlimited.
+    pub fn max_maps_per_owner(&self) -> u64 {
+        self.max_maps_per_owner
+            .unwrap_or(DEFAULT_MAX_MAPS_PER_OWNER)
+    }
+
+    /// Max number of Sequence entries in a range response.
+    pub fn max_sequence_range_entries(&self) -> usize {
+        self.max_sequence_range_entries
+            .unwrap_or(DEFAULT_MAX_SEQUENCE_RANGE_ENTRIES)
+    }
+
+    /// Max size in bytes of the Sequence entries in a range response.
+    pub fn max_sequence_range_size(&self) -> u64 {
+        self.max_sequence_range_size
+            .unwrap_or(DEFAULT_MAX_SEQUENCE_RANGE_SIZE)
+    }
+
+    /// Max number of data requests per second a single client may send, 0 meaning unlimited.
+    pub fn max_client_requests_per_sec(&self) -> usize {
+        self.max_client_requests_per_sec
+            .unwrap_or(DEFAULT_MAX_CLIENT_REQUESTS_PER_SEC)
+    }
+
+    /// Seconds to wait for the other Elders to agree on a reward payout, before our
+    /// signature shares of it are sent anew.
+    pub fn reward_payout_timeout(&self) -> u64 {
+        self.reward_payout_timeout
+            .unwrap_or(DEFAULT_REWARD_PAYOUT_TIMEOUT)
+    }
+
     /// Root directory for `ChunkStore`s and cached state. If not set, it defaults to
     /// `DEFAULT_ROOT_DIR_NAME` within the project's data directory (see `Config::root_dir` for the
     /// directories on each platform).
@@ -261,6 +590,23 @@ impl Config {
         })
     }
 
+    /// Root directory for the chunks held as an Adult, the root directory if not set.
+    pub fn chunk_root_dir(&self) -> Result<PathBuf> {
+        match &self.chunk_root_dir {
+            Some(chunk_root_dir) => Ok(chunk_root_dir.clone()),
+            None => self.root_dir(),
+        }
+    }
+
+    /// Root directory for the Maps, Sequences and chunk metadata held as an Elder,
+    /// the root directory if not set.
+    pub fn metadata_root_dir(&self) -> Result<PathBuf> {
+        match &self.metadata_root_dir {
+            Some(metadata_root_dir) => Ok(metadata_root_dir.clone()),
+            None => self.root_dir(),
+        }
+    }
+
     /// Set the root directory for `ChunkStore`s and cached state.
     pub fn set_root_dir<P: Into<PathBuf>>(&mut self, path: P) {
         self.root_dir = Some(path.into())
@@ -312,6 +658,16 @@ impl Config {
         self.update_only
     }
 
+    /// Archive to write the data held by the node to, instead of starting it.
+    pub fn export_data(&self) -> &Option<PathBuf> {
+        &self.export_data
+    }
+
+    /// Archive to restore the data held by the node from, before starting it.
+    pub fn import_data(&self) -> &Option<PathBuf> {
+        &self.import_data
+    }
+
     /// Set the Quic-P2P `ip` configuration to 127.0.0.1.
     pub fn listen_on_loopback(&mut self) {
         self.network_config.local_ip = Some(IpAddr::V4(Ipv4Addr::LOCALHOST));
@@ -426,7 +782,20 @@ fn smoke() {
     // NOTE: IF this value is being changed due to a change in the config,
     // the change in config also be handled in Config::merge()
     // and in examples/config_handling.rs
-    let expected_size = 504;
+    let expected_size = 888;
 
     assert_eq!(std::mem::size_of::<Config>(), expected_size);
 }
+
+#[test]
+fn store_roots_default_to_the_root_dir() -> Result<()> {
+    let mut config = Config::default();
+    config.set_root_dir("root");
+    config.chunk_root_dir = Some(PathBuf::from("hdd"));
+    assert_eq!(config.chunk_root_dir()?, PathBuf::from("hdd"));
+    assert_eq!(config.metadata_root_dir()?, PathBuf::from("root"));
+
+    config.metadata_root_dir = Some(PathBuf::from("ssd"));
+    assert_eq!(config.metadata_root_dir()?, PathBuf::from("ssd"));
+    Ok(())
+}
@@ -0,0 +1,499 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Export of a node's data to a single archive, and its import on another machine.
+//!
+//! The archive holds every file under the node's root directories (chunks, Elder metadata,
+//! used space records, keys), preceded by a manifest listing them with their sizes and hashes.
+
+use crate::{chunk_store, utils, Config, Error, Result};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Component, Path, PathBuf},
+};
+
+/// Leading bytes of an archive.
+const ARCHIVE_MAGIC: &[u8; 8] = b"SNDATA\0\0";
+
+/// Version of the archive format.
+const ARCHIVE_FORMAT_VERSION: u32 = 1;
+
+/// Subdirectory of a root where an archive is unpacked and verified before being moved
+/// into place.
+const IMPORT_STAGING_DIR: &str = "import_staging";
+
+/// Extension of the temporary files archives are written to before being moved into place.
+const TEMP_FILE_EXTENSION: &str = "tmp";
+
+/// The root directory of the node a file was archived from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ArchiveRoot {
+    /// The root directory.
+    Root,
+    /// The root directory for the chunks held as an Adult, when set apart.
+    Chunks,
+    /// The root directory for the metadata held as an Elder, when set apart.
+    Metadata,
+}
+
+/// A file in an archive.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchivedFile {
+    /// The root directory it is held in.
+    pub root: ArchiveRoot,
+    /// Its path relative to that root.
+    pub path: PathBuf,
+    /// Its size, in bytes.
+    pub size: u64,
+    /// SHA3-256 of its contents.
+    pub digest: [u8; 32],
+}
+
+/// Manifest of an archive of a node's data.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    /// Version of the archive format.
+    pub version: u32,
+    /// Number of chunks in the archive, as held by the chunk stores.
+    pub chunk_count: u64,
+    /// Total size of the files in the archive, in bytes.
+    pub total_size: u64,
+    /// The files in the archive, in the order their contents follow the manifest.
+    pub files: Vec<ArchivedFile>,
+}
+
+/// Writes the data of the node configured by `config` to a single archive at `archive`, to be
+/// imported on another machine with `import_data`. The node must not be running meanwhile.
+///
+/// The archive is written to a temporary file first, so that an interrupted export doesn't
+/// leave an archive behind.
+pub fn export_data(config: &Config, archive: &Path) -> Result<ArchiveManifest> {
+    let roots = data_roots(config)?;
+    let mut files = vec![];
+    for (root, dir) in &roots {
+        let others: Vec<_> = roots
+            .iter()
+            .filter(|(other, _)| other != root)
+            .map(|(_, dir)| dir.as_path())
+            .collect();
+        list_files(*root, dir, Path::new(""), &others, &mut files)?;
+    }
+    files.sort_by(|lhs, rhs| (lhs.root, &lhs.path).cmp(&(rhs.root, &rhs.path)));
+
+    let manifest = ArchiveManifest {
+        version: ARCHIVE_FORMAT_VERSION,
+        chunk_count: files
+            .iter()
+            .filter(|file| chunk_store::is_chunk_file(&file.path))
+            .count() as u64,
+        total_size: files.iter().map(|file| file.size).sum(),
+        files,
+    };
+
+    let temp_path = archive.with_extension(TEMP_FILE_EXTENSION);
+    let result = write_archive(&manifest, &roots, &temp_path);
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e);
+    }
+    fs::rename(&temp_path, archive)?;
+    info!(
+        "Exported {} files, {} chunks, {} bytes to {:?}",
+        manifest.files.len(),
+        manifest.chunk_count,
+        manifest.total_size,
+        archive
+    );
+    Ok(manifest)
+}
+
+/// Restores the data in the archive at `archive`, as written by `export_data`, into the root
+/// directories of the node configured by `config`. Files archived from a root directory go to
+/// the same root directory of this node, e.g. the chunks to its chunk root directory.
+///
+/// The whole archive is unpacked and verified against its manifest before any file is moved
+/// into place, so that an incomplete or corrupted archive is refused without touching the data
+/// already held. So is an archive that would overwrite any of that data.
+pub fn import_data(config: &Config, archive: &Path) -> Result<ArchiveManifest> {
+    let roots = data_roots(config)?;
+    let invalid = |reason: String| Error::InvalidArchive(archive.display().to_string(), reason);
+
+    let mut reader = BufReader::new(File::open(archive)?);
+    let manifest = read_manifest(&mut reader, fs::metadata(archive)?.len())
+        .map_err(|e| invalid(e.to_string()))?;
+
+    let mut targets = Vec::with_capacity(manifest.files.len());
+    for file in &manifest.files {
+        if !file
+            .path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(invalid(format!("{:?} is not a relative path", file.path)));
+        }
+        let root = root_dir(&roots, file.root);
+        let target = root.join(&file.path);
+        if target.exists() {
+            return Err(Error::ImportConflict(target.display().to_string()));
+        }
+        targets.push((root, target));
+    }
+
+    // Roots created here are removed along with the staging directories if the import fails.
+    let missing_roots: Vec<_> = roots
+        .iter()
+        .map(|(_, dir)| dir.clone())
+        .filter(|dir| !dir.exists())
+        .collect();
+    let clean_up_staging = || {
+        for (_, dir) in &roots {
+            let _ = fs::remove_dir_all(dir.join(IMPORT_STAGING_DIR));
+        }
+    };
+    let clean_up_roots = || {
+        for dir in &missing_roots {
+            let _ = fs::remove_dir_all(dir);
+        }
+    };
+
+    if let Err(e) = unpack(&mut reader, &manifest, &roots) {
+        clean_up_staging();
+        clean_up_roots();
+        return Err(invalid(e.to_string()));
+    }
+
+    let moves: Vec<_> = manifest
+        .files
+        .iter()
+        .zip(targets)
+        .map(|(file, (root, target))| (root.join(IMPORT_STAGING_DIR).join(&file.path), target))
+        .collect();
+    let result = move_into_place(&moves);
+    clean_up_staging();
+    if let Err(e) = result {
+        clean_up_roots();
+        return Err(e.into());
+    }
+    info!(
+        "Imported {} files, {} chunks, {} bytes from {:?}",
+        manifest.files.len(),
+        manifest.chunk_count,
+        manifest.total_size,
+        archive
+    );
+    Ok(manifest)
+}
+
+// Moves each staged file to its target, undoing the moves already done if one fails, so that
+// the data held is left as it was.
+fn move_into_place(moves: &[(PathBuf, PathBuf)]) -> io::Result<()> {
+    let mut created_dirs = vec![];
+    for (done, (staged, target)) in moves.iter().enumerate() {
+        if let Err(e) = move_file(staged, target, &mut created_dirs) {
+            for (staged, target) in moves[..done].iter().rev() {
+                let _ = fs::rename(target, staged);
+            }
+            for dir in created_dirs.iter().rev() {
+                let _ = fs::remove_dir_all(dir);
+            }
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+// Moves a staged file to its target, noting the outermost directory created for it.
+fn move_file(staged: &Path, target: &Path, created_dirs: &mut Vec<PathBuf>) -> io::Result<()> {
+    if let Some(parent) = target.parent() {
+        if let Some(missing) = parent.ancestors().take_while(|dir| !dir.exists()).last() {
+            created_dirs.push(missing.to_path_buf());
+            fs::create_dir_all(parent)?;
+        }
+    }
+    fs::rename(staged, target)
+}
+
+// The distinct root directories of the node, the same directory counting as the root directory
+// when configured for several kinds of data.
+fn data_roots(config: &Config) -> Result<Vec<(ArchiveRoot, PathBuf)>> {
+    let mut roots = vec![(ArchiveRoot::Root, config.root_dir()?)];
+    for (root, dir) in [
+        (ArchiveRoot::Chunks, config.chunk_root_dir()?),
+        (ArchiveRoot::Metadata, config.metadata_root_dir()?),
+    ] {
+        if roots.iter().all(|(_, other)| other != &dir) {
+            roots.push((root, dir));
+        }
+    }
+    Ok(roots)
+}
+
+// The directory files archived from `root` go to, the root directory if it isn't set apart.
+fn root_dir(roots: &[(ArchiveRoot, PathBuf)], root: ArchiveRoot) -> PathBuf {
+    roots
+        .iter()
+        .find(|(other, _)| *other == root)
+        .unwrap_or(&roots[0])
+        .1
+        .clone()
+}
+
+// Lists the files under `dir`, skipping the other roots nested in it and leftover temp files.
+fn list_files(
+    root: ArchiveRoot,
+    dir: &Path,
+    relative: &Path,
+    others: &[&Path],
+    files: &mut Vec<ArchivedFile>,
+) -> Result<()> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+    for entry in entries {
+        let entry = entry?;
+        let path = entry.path();
+        let relative = relative.join(entry.file_name());
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            if !others.contains(&path.as_path()) && entry.file_name() != IMPORT_STAGING_DIR {
+                list_files(root, &path, &relative, others, files)?;
+            }
+        } else if path
+            .extension()
+            .map_or(false, |ext| ext == TEMP_FILE_EXTENSION)
+        {
+            warn!("Not exporting temporary file {:?}", path);
+        } else {
+            files.push(ArchivedFile {
+                root,
+                path: relative,
+                size: metadata.len(),
+                digest: utils::digest(&[], BufReader::new(File::open(&path)?))?,
+            });
+        }
+    }
+    Ok(())
+}
+
+fn write_archive(
+    manifest: &ArchiveManifest,
+    roots: &[(ArchiveRoot, PathBuf)],
+    path: &Path,
+) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    let serialised_manifest = utils::serialise(manifest)?;
+    writer.write_all(ARCHIVE_MAGIC)?;
+    writer.write_all(&(serialised_manifest.len() as u64).to_le_bytes())?;
+    writer.write_all(&serialised_manifest)?;
+    for file in &manifest.files {
+        let source = File::open(root_dir(roots, file.root).join(&file.path))?;
+        let copied = io::copy(&mut source.take(file.size), &mut writer)?;
+        if copied != file.size {
+            return Err(Error::Logic(format!(
+                "{:?} changed while being exported",
+                file.path
+            )));
+        }
+    }
+    writer
+        .into_inner()
+        .map_err(|e| e.into_error())?
+        .sync_all()?;
+    Ok(())
+}
+
+fn read_manifest<R: Read>(reader: &mut R, archive_size: u64) -> Result<ArchiveManifest> {
+    let mut magic = [0; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic != ARCHIVE_MAGIC {
+        return Err(Error::Logic("not an archive of node data".to_string()));
+    }
+    let mut len = [0; 8];
+    reader.read_exact(&mut len)?;
+    let len = u64::from_le_bytes(len);
+    if len > archive_size {
+        return Err(Error::Logic("manifest is truncated".to_string()));
+    }
+    let mut serialised_manifest = vec![0; len as usize];
+    reader.read_exact(&mut serialised_manifest)?;
+    let manifest: ArchiveManifest = bincode::deserialize(&serialised_manifest)?;
+    if manifest.version != ARCHIVE_FORMAT_VERSION {
+        return Err(Error::UnsupportedFormatVersion(
+            manifest.version,
+            ARCHIVE_FORMAT_VERSION,
+        ));
+    }
+    Ok(manifest)
+}
+
+// Unpacks the files to the staging directories, verifying each against the manifest.
+fn unpack<R: Read>(
+    reader: &mut R,
+    manifest: &ArchiveManifest,
+    roots: &[(ArchiveRoot, PathBuf)],
+) -> Result<()> {
+    for file in &manifest.files {
+        let staged = root_dir(roots, file.root)
+            .join(IMPORT_STAGING_DIR)
+            .join(&file.path);
+        if let Some(parent) = staged.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut writer = BufWriter::new(File::create(&staged)?);
+        let copied = io::copy(&mut reader.take(file.size), &mut writer)?;
+        writer
+            .into_inner()
+            .map_err(|e| e.into_error())?
+            .sync_all()?;
+        if copied != file.size {
+            return Err(Error::Logic(format!("{:?} is truncated", file.path)));
+        }
+        if utils::digest(&[], BufReader::new(File::open(&staged)?))? != file.digest {
+            return Err(Error::Logic(format!("{:?} is corrupted", file.path)));
+        }
+    }
+    if reader.read(&mut [0])? != 0 {
+        return Err(Error::Logic(
+            "unexpected data after the last file".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{export_data, import_data};
+    use crate::{
+        capacity::ChunkHolderDbs,
+        chunk_store::{BlobChunkStore, UsedSpace},
+        Config, Error, Result,
+    };
+    use sn_data_types::{Blob, PublicBlob};
+    use std::{fs, path::Path};
+    use tempdir::TempDir;
+
+    fn config(root: &Path) -> Config {
+        let mut config = Config::default();
+        config.set_root_dir(root.join("root"));
+        config.chunk_root_dir = Some(root.join("hdd"));
+        config
+    }
+
+    #[tokio::test]
+    async fn exported_store_is_imported_chunk_by_chunk() -> Result<()> {
+        let old = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let new = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let (old_config, new_config) = (config(old.path()), config(new.path()));
+
+        let mut store =
+            BlobChunkStore::new(old_config.chunk_root_dir()?, UsedSpace::new(u64::MAX)).await?;
+        let chunks: Vec<_> = (0..5_u8)
+            .map(|i| Blob::Public(PublicBlob::new(vec![i; 1000])))
+            .collect();
+        for chunk in &chunks {
+            store.put(chunk).await?;
+        }
+        let _ = ChunkHolderDbs::new(&old_config.metadata_root_dir()?)?;
+
+        let archive = old.path().join("archive");
+        let exported = export_data(&old_config, &archive)?;
+        assert_eq!(exported.chunk_count, chunks.len() as u64);
+
+        let imported = import_data(&new_config, &archive)?;
+        assert_eq!(imported, exported);
+
+        let restored =
+            BlobChunkStore::new(new_config.chunk_root_dir()?, UsedSpace::new(u64::MAX)).await?;
+        assert_eq!(
+            restored.local_used_space().await,
+            store.local_used_space().await
+        );
+        assert_eq!(restored.keys().len(), chunks.len());
+        for chunk in &chunks {
+            assert_eq!(restored.get(chunk.address())?, store.get(chunk.address())?);
+        }
+        assert!(new_config.root_dir()?.join("immutable_data.db").exists());
+
+        // The data now held isn't overwritten by another import.
+        match import_data(&new_config, &archive) {
+            Err(Error::ImportConflict(_)) => Ok(()),
+            other => Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+    }
+
+    #[tokio::test]
+    async fn partial_or_corrupted_archive_is_refused() -> Result<()> {
+        let old = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let new = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let (old_config, new_config) = (config(old.path()), config(new.path()));
+
+        let mut store =
+            BlobChunkStore::new(old_config.chunk_root_dir()?, UsedSpace::new(u64::MAX)).await?;
+        store
+            .put(&Blob::Public(PublicBlob::new(vec![1; 1000])))
+            .await?;
+        let archive = old.path().join("archive");
+        let _ = export_data(&old_config, &archive)?;
+        let bytes = fs::read(&archive)?;
+
+        let partial = old.path().join("partial");
+        fs::write(&partial, &bytes[..bytes.len() - 100])?;
+        let mut corrupted_bytes = bytes.clone();
+        let last = corrupted_bytes.len() - 1;
+        corrupted_bytes[last] ^= 1;
+        let corrupted = old.path().join("corrupted");
+        fs::write(&corrupted, corrupted_bytes)?;
+
+        for archive in &[partial, corrupted] {
+            match import_data(&new_config, archive) {
+                Err(Error::InvalidArchive(..)) => (),
+                other => return Err(Error::Logic(format!("Unexpected: {:?}", other))),
+            }
+            assert!(!new_config.root_dir()?.exists());
+            assert!(!new_config.chunk_root_dir()?.exists());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn failed_import_is_undone() -> Result<()> {
+        let old = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let new = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let (old_config, new_config) = (config(old.path()), config(new.path()));
+
+        for dir in &["a", "z"] {
+            let dir = old_config.root_dir()?.join(dir);
+            fs::create_dir_all(&dir)?;
+            fs::write(dir.join("file"), [1_u8; 10])?;
+        }
+        let archive = old.path().join("archive");
+        let _ = export_data(&old_config, &archive)?;
+
+        // A file where the directory of the last archived file goes, for it not to be moved
+        // into place after the first one was.
+        let root = new_config.root_dir()?;
+        fs::create_dir_all(&root)?;
+        fs::write(root.join("z"), [2_u8; 10])?;
+
+        match import_data(&new_config, &archive) {
+            Err(Error::Io(_)) => (),
+            other => return Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+        let left: Vec<_> = fs::read_dir(&root)?
+            .map(|entry| entry.map(|entry| entry.file_name()))
+            .collect::<std::io::Result<_>>()?;
+        assert_eq!(left, vec!["z"]);
+        assert!(!new_config.chunk_root_dir()?.exists());
+        Ok(())
+    }
+}
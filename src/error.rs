@@ -6,7 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use sn_data_types::{Error as DtError, PublicKey};
+use sn_data_types::{Error as DtError, PublicKey, Token};
 use sn_messaging::{client::Error as ErrorMessage, MessageId};
 use std::io;
 use thiserror::Error;
@@ -44,12 +44,40 @@ pub enum Error {
     /// Unable to parse reward proposal.
     #[error("Cannot parse reward proposal at this stage")]
     InvalidRewardStage,
+    /// A signature share over a reward credit is not valid for the Elder of its index.
+    #[error("Invalid signature share by Elder {0} over reward credit")]
+    InvalidRewardSignatureShare(usize),
+    /// A signature share over a reward credit is by an Elder of a key set since replaced.
+    #[error("Stale signature share by Elder {0} over reward credit")]
+    StaleRewardSignatureShare(usize),
     /// Node not found for rewarding
     #[error("Node not found for rewards")]
     NodeNotFoundForReward,
     /// Key, Value pair not found in `ChunkStore`.
     #[error("No such chunk")]
     NoSuchChunk,
+    /// Chunk failed to deserialise or its content doesn't match its address.
+    #[error("Chunk is corrupted")]
+    CorruptedChunk,
+    /// Chunks can't be written to the store directory, e.g. its volume is read-only.
+    #[error("Chunk store at {0} is not writable: {1}")]
+    ChunkStoreNotWritable(String, String),
+    /// The directory of a store opened before is missing, e.g. as its volume didn't mount.
+    #[error("Chunk store at {0} is missing")]
+    ChunkStoreMissing(String),
+    /// A store opened before lost the record of the space used by its chunks, e.g. as its
+    /// directory was emptied or replaced.
+    #[error("Chunk store at {0} lost the record of the space used by its chunks")]
+    ChunkStoreEmptied(String),
+    /// The DBs were written by a newer version of the node, in a format it doesn't know.
+    #[error("DBs are in format version {0}, newer than the supported version {1}")]
+    UnsupportedFormatVersion(u32, u32),
+    /// The archive of a node's data is incomplete, corrupted or not an archive at all.
+    #[error("Archive at {0} is invalid: {1}")]
+    InvalidArchive(String, String),
+    /// Importing an archive would overwrite data the node already holds.
+    #[error("Cannot import the archive over existing data at {0}")]
+    ImportConflict(String),
     /// This node does not know or manage any chunks
     #[error("No chunks")]
     NoChunks,
@@ -62,6 +90,12 @@ pub enum Error {
     /// This node does not know or manage any section funds
     #[error("No section funds")]
     NoSectionFunds,
+    /// The section funds lack the balance for a payout drawn from them.
+    #[error("Insufficient section funds: {0} requested, {1} available")]
+    InsufficientSectionFunds(Token, Token),
+    /// The requester is not allowed to query the section funds.
+    #[error("Access denied to the section funds for {0}")]
+    AccessDenied(PublicKey),
     /// Unable to process fund churn message.
     #[error("Cannot process fund churn message")]
     NotChurningFunds,
@@ -77,6 +111,12 @@ pub enum Error {
     /// Chunk already exists for this node
     #[error("Data already exists at this node")]
     DataExists,
+    /// A data structure, e.g. a Map, would exceed its max number of entries.
+    #[error("Too many entries")]
+    TooManyEntries,
+    /// The owner has as many Maps stored as allowed.
+    #[error("Quota of Maps exceeded for owner {0}")]
+    MapQuotaExceeded(PublicKey),
     /// I/O error.
     #[error("I/O error: {0}")]
     Io(#[from] io::Error),
@@ -142,15 +182,20 @@ pub(crate) fn convert_to_error_message(error: Error) -> Result<sn_messaging::cli
         Error::InvalidOwners(key) => Ok(ErrorMessage::InvalidOwners(key)),
         Error::InvalidSignedTransfer(_) => Ok(ErrorMessage::InvalidSignature),
         Error::TransferAlreadyRegistered => Ok(ErrorMessage::TransactionIdExists),
-        Error::NoSuchChunk => Ok(ErrorMessage::NoSuchData),
+        Error::NoSuchChunk | Error::CorruptedChunk => Ok(ErrorMessage::NoSuchData),
         Error::NotEnoughSpace => Ok(ErrorMessage::NotEnoughSpace),
         Error::BalanceExists => Ok(ErrorMessage::BalanceExists),
+        Error::InsufficientSectionFunds(..) => Ok(ErrorMessage::InsufficientBalance),
+        Error::AccessDenied(key) => Ok(ErrorMessage::AccessDenied(key)),
         Error::TempDirCreationFailed(_) => Ok(ErrorMessage::FailedToWriteFile),
         Error::DataExists => Ok(ErrorMessage::DataExists),
+        Error::TooManyEntries | Error::MapQuotaExceeded(_) => Ok(ErrorMessage::TooManyEntries),
         Error::NetworkData(error) => convert_dt_error_to_error_message(error),
         error => Err(Error::NoErrorMapping(error.to_string())),
     }
 }
+/// Entry actions failing are reported by the error of the first failing key,
+/// as client errors carry no per-key details.
 pub(crate) fn convert_dt_error_to_error_message(
     error: DtError,
 ) -> Result<sn_messaging::client::Error> {
@@ -161,6 +206,14 @@ pub(crate) fn convert_dt_error_to_error_message(
         DtError::CrdtUnexpectedState => Ok(ErrorMessage::CrdtUnexpectedState),
         DtError::OpNotCausallyReady => Ok(ErrorMessage::OpNotCausallyReady),
         DtError::AccessDenied(pk) => Ok(ErrorMessage::AccessDenied(pk)),
+        DtError::ExceededSize => Ok(ErrorMessage::ExceededSize),
+        DtError::NoSuchKey => Ok(ErrorMessage::NoSuchKey),
+        DtError::EntryExists(version) => Ok(ErrorMessage::EntryExists(version)),
+        DtError::InvalidSuccessor(version) => Ok(ErrorMessage::InvalidSuccessor(version)),
+        DtError::InvalidEntryActions(errors) => match errors.into_iter().next() {
+            Some((_, error)) => convert_dt_error_to_error_message(error),
+            None => Ok(ErrorMessage::InvalidOperation),
+        },
         error => Err(Error::NoErrorMapping(error.to_string())),
     }
 }
@@ -10,20 +10,23 @@ use std::thread::current;
 
 use super::{LazyError, Mapping, MsgContext};
 use crate::{
-    node_ops::{NodeDuties, NodeDuty},
+    node_ops::{NodeDuties, NodeDuty, OutgoingMsg},
     Error, Result,
 };
-use log::debug;
+use log::{debug, warn};
 use sn_messaging::{
     client::{
-        Cmd, Message, NodeCmd, NodeDataQueryResponse, NodeEvent, NodeQuery, NodeQueryResponse,
-        NodeRewardQuery, NodeSystemCmd, NodeSystemQuery, NodeSystemQueryResponse, NodeTransferCmd,
-        NodeTransferQuery, NodeTransferQueryResponse, Query, TransferCmd, TransferQuery,
+        Cmd, CmdError, Error as ErrorMessage, Message, NodeCmd, NodeCmdError, NodeDataError,
+        NodeDataQueryResponse, NodeEvent, NodeQuery, NodeQueryResponse, NodeRewardQuery,
+        NodeSystemCmd, NodeSystemQuery, NodeSystemQueryResponse, NodeTransferCmd,
+        NodeTransferError, NodeTransferQuery, NodeTransferQueryResponse, Query, TransferCmd,
+        TransferQuery,
     },
-    DstLocation, EndUser, SrcLocation,
+    Aggregation, DstLocation, EndUser, MessageId, SrcLocation,
 };
 
 pub fn match_user_sent_msg(msg: Message, dst: DstLocation, origin: EndUser) -> Mapping {
+    crate::trace_msg!("received", msg.id(), origin.id());
     match msg.to_owned() {
         Message::Query {
             query: Query::Data(query),
@@ -140,6 +143,39 @@ pub fn match_user_sent_msg(msg: Message, dst: DstLocation, origin: EndUser) -> M
                 src: SrcLocation::EndUser(origin),
             }),
         },
+        // Any other cmd or query is answered with an error, rather than leaving the client
+        // to time out.
+        Message::Cmd { .. } | Message::Query { .. } => {
+            warn!("Unknown user msg: {:?}", msg);
+            let answer = match &msg {
+                Message::Query { query, .. } => Message::QueryResponse {
+                    response: query.error(ErrorMessage::InvalidOperation),
+                    id: MessageId::in_response_to(&msg.id()),
+                    correlation_id: msg.id(),
+                    target_section_pk: None,
+                },
+                _ => Message::CmdError {
+                    error: CmdError::Data(ErrorMessage::InvalidOperation),
+                    id: MessageId::in_response_to(&msg.id()),
+                    correlation_id: msg.id(),
+                    target_section_pk: None,
+                },
+            };
+            Mapping::Ok {
+                op: NodeDuty::Send(OutgoingMsg {
+                    msg: answer,
+                    section_source: false,
+                    dst: DstLocation::EndUser(origin),
+                    aggregation: Aggregation::None,
+                }),
+                ctx: Some(MsgContext::Msg {
+                    msg,
+                    src: SrcLocation::EndUser(origin),
+                }),
+            }
+        }
+        // Responses and errors aren't answered, for clients not to have errors bounced at
+        // whoever they name.
         _ => Mapping::Error(LazyError {
             error: Error::InvalidMessage(msg.id(), format!("Unknown user msg: {:?}", msg)),
             msg: MsgContext::Msg {
@@ -288,6 +324,7 @@ fn match_section_msg(msg: Message, origin: SrcLocation) -> NodeDuty {
             address: *address,
             new_holder: *new_holder,
             id: *id,
+            origin,
         },
         // this cmd is accumulated, thus has authority
         Message::NodeCmd {
@@ -333,16 +370,76 @@ fn match_node_msg(msg: Message, origin: SrcLocation) -> NodeDuty {
             ..
         } => {
             log::info!("Verifying GetChunk NodeQueryResponse!");
-            if let Ok(data) = result {
-                NodeDuty::StoreChunkForReplication {
+            match (result, origin) {
+                (Ok(data), SrcLocation::Node(holder)) => NodeDuty::StoreChunkForReplication {
                     data: data.clone(),
                     correlation_id: *correlation_id,
+                    holder,
+                },
+                (Err(error), SrcLocation::Node(holder)) => {
+                    log::warn!("Got error when reading chunk for replication: {:?}", error);
+                    NodeDuty::ChunkCopyFailed {
+                        correlation_id: *correlation_id,
+                        holder,
+                    }
+                }
+                (result, origin) => {
+                    log::warn!(
+                        "Got chunk for replication from a non-node origin {:?}: {:?}",
+                        origin,
+                        result
+                    );
+                    NodeDuty::NoOp
                 }
-            } else {
-                log::warn!("Got error when reading chunk for replication: {:?}", result);
-                NodeDuty::NoOp
             }
         }
+        // error from adult cannot be accumulated
+        Message::NodeCmdError {
+            error: NodeCmdError::Data(NodeDataError::ChunkReplication { address, error }),
+            ..
+        } => match origin {
+            SrcLocation::Node(holder) => NodeDuty::RecordReplicationFailure {
+                address: *address,
+                holder,
+                error: error.clone(),
+            },
+            origin => {
+                log::warn!(
+                    "Got chunk replication failure from a non-node origin {:?}: {:?}",
+                    origin,
+                    address
+                );
+                NodeDuty::NoOp
+            }
+        },
+        // a reward payout failed at the Elders of its recipient
+        Message::NodeCmdError {
+            error: NodeCmdError::Transfers(NodeTransferError::TransferPropagation(error)),
+            correlation_id,
+            ..
+        } => NodeDuty::RecordPayoutFailure {
+            correlation_id: *correlation_id,
+            error: error.clone(),
+        },
+        // event from adult cannot be accumulated, the holder is told by its signature
+        Message::NodeEvent {
+            event: NodeEvent::ReplicationCompleted { chunk, proof },
+            ..
+        } => match origin {
+            SrcLocation::Node(holder) => NodeDuty::RecordChunkHolder {
+                address: *chunk,
+                holder,
+                proof: proof.clone(),
+            },
+            origin => {
+                log::warn!(
+                    "Got chunk replication completion from a non-node origin {:?}: {:?}",
+                    origin,
+                    chunk
+                );
+                NodeDuty::NoOp
+            }
+        },
         //
         // ------ transfers ------
         Message::NodeQuery {
@@ -375,3 +472,31 @@ fn match_node_msg(msg: Message, origin: SrcLocation) -> NodeDuty {
         _ => NodeDuty::NoOp,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{match_user_sent_msg, Mapping};
+    use crate::{Error, Result};
+    use sn_data_types::Keypair;
+    use sn_messaging::{
+        client::{CmdError, Error as ErrorMessage, Message},
+        DstLocation, EndUser, MessageId,
+    };
+    use xor_name::XorName;
+
+    #[test]
+    fn error_sent_by_a_client_is_not_answered() -> Result<()> {
+        let origin =
+            EndUser::AllClients(Keypair::new_ed25519(&mut rand::thread_rng()).public_key());
+        let msg = Message::CmdError {
+            error: CmdError::Data(ErrorMessage::NoSuchData),
+            id: MessageId::new(),
+            correlation_id: MessageId::new(),
+            target_section_pk: None,
+        };
+        match match_user_sent_msg(msg, DstLocation::Node(XorName::random()), origin) {
+            Mapping::Error(_) => Ok(()),
+            other => Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+    }
+}
@@ -6,7 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-mod map_msg;
+pub(crate) mod map_msg;
 
 use super::node_ops::{NodeDuties, NodeDuty};
 use crate::{Network, Result};
@@ -178,18 +178,13 @@ pub async fn map_routing_event(event: RoutingEvent, network_api: &Network) -> Ma
             previous_name,
             age,
             ..
-        } => {
-            let op = if let Some(prev_name) = previous_name {
-                trace!("A relocated node has joined the section.");
-                // Switch joins_allowed off a new adult joining.
-                NodeDuty::SetNodeJoinsAllowed(false)
-            } else if network_api.our_prefix().await.is_empty() {
-                NodeDuty::NoOp
-            } else {
-                NodeDuty::SetNodeJoinsAllowed(false)
-            };
-            Mapping::Ok { op, ctx: None }
-        }
+        } => Mapping::Ok {
+            op: NodeDuty::ProcessNewMember {
+                name: XorName(name.0),
+                relocated: previous_name.is_some(),
+            },
+            ctx: None,
+        },
         RoutingEvent::Relocated { .. } => {
             // Check our current status
             let age = network_api.age().await;
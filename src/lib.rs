@@ -35,9 +35,11 @@ mod chaos;
 mod chunk_store;
 mod chunks;
 mod config_handler;
+mod data_archive;
 mod error;
 mod event_mapping;
 mod metadata;
+mod msg_trace;
 mod network;
 mod node;
 mod section_funds;
@@ -53,9 +55,14 @@ pub mod utils;
 pub(crate) use to_db_key::ToDbKey;
 
 pub use crate::{
+    chunk_store::{StorageStats, StoreStats},
+    chunks::{CacheStats, ChunkStatus, ScrubStats, StorageProof},
     config_handler::{add_connection_info, set_connection_info, Config},
+    data_archive::{export_data, import_data, ArchiveManifest, ArchiveRoot, ArchivedFile},
     error::{Error, Result},
+    metadata::ReplicationStatus,
     network::Network,
     node::Node,
     node::NodeInfo,
+    section_funds::{PayoutRecord, PayoutStatus},
 };
@@ -10,11 +10,15 @@ use crate::{
     capacity::ChunkHolderDbs,
     error::convert_to_error_message,
     node_ops::{NodeDuties, NodeDuty, OutgoingMsg},
-    Error, Network, Result, ToDbKey,
+    to_db_key::from_db_key,
+    utils, Error, Network, Result, ToDbKey,
 };
+use ed25519_dalek::PublicKey as Ed25519PublicKey;
 use log::{info, trace, warn};
 use serde::{Deserialize, Serialize};
-use sn_data_types::{Blob, BlobAddress, Error as DtError, PublicKey, Result as NdResult};
+use sn_data_types::{
+    Blob, BlobAddress, Error as DtError, PublicKey, Result as NdResult, Signature,
+};
 use sn_messaging::{
     client::{
         BlobRead, BlobWrite, CmdError, Error as ErrorMessage, Message, NodeCmd, NodeQuery,
@@ -24,12 +28,17 @@ use sn_messaging::{
 };
 
 use std::{
+    cmp::Ordering,
     collections::{BTreeMap, BTreeSet},
     fmt::{self, Display, Formatter},
 };
 use xor_name::XorName;
 
-use super::adult_reader::AdultReader;
+use super::{
+    adult_reader::AdultReader,
+    replication_progress::{ReplicationProgress, ReplicationStatus},
+    replication_schedule::{ReplicationSchedule, ReplicationScheduleStats},
+};
 
 // The number of separate copies of a blob chunk which should be maintained.
 const CHUNK_COPY_COUNT: usize = 4;
@@ -45,15 +54,89 @@ struct HolderMetadata {
     chunks: BTreeSet<BlobAddress>,
 }
 
+/// The failures to replicate a chunk reported by Adults.
+#[derive(Default, Debug, Serialize, Deserialize)]
+struct ReplicationFailures {
+    count: usize,
+    // Whether the chunk is deemed lost, no longer to be replicated.
+    lost: bool,
+    owner: Option<PublicKey>,
+}
+
+/// The records found in the chunk holder DBs by `verify_metadata`.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct MetadataCheck {
+    /// Number of chunks recorded with their holders.
+    chunks: usize,
+    /// Number of holders recorded with their chunks.
+    holders: usize,
+    /// Number of records that couldn't be read, left as they are.
+    unreadable: usize,
+    /// Number of holder records rewritten to match the chunk records.
+    repaired: usize,
+}
+
 /// Operations over the data type Blob.
 pub(super) struct BlobRegister {
     dbs: ChunkHolderDbs,
     reader: AdultReader,
+    // Mirrored in the pending replications DB, to be resumed after a restart.
+    replications: ReplicationSchedule,
+    // The replications issued to Adults, awaiting their report.
+    progress: ReplicationProgress,
+    // Max number of replication cmds sent per batch.
+    replication_rate: usize,
+    // Number of replication failures after which a chunk is deemed lost.
+    max_replication_failures: usize,
 }
 
 impl BlobRegister {
-    pub(super) fn new(dbs: ChunkHolderDbs, reader: AdultReader) -> Self {
-        Self { dbs, reader }
+    /// Resumes the replications left pending in the DBs, e.g. by a restart mid-churn,
+    /// once their records were verified.
+    pub(super) async fn new(
+        dbs: ChunkHolderDbs,
+        reader: AdultReader,
+        replication_rate: usize,
+        max_replication_failures: usize,
+    ) -> Result<Self> {
+        let _ = verify_metadata(&dbs).await?;
+        let replications = load_replications(&dbs).await?;
+        if !replications.is_empty() {
+            info!(
+                "Resuming replication of {} chunks after restart",
+                replications.len()
+            );
+        }
+        Ok(Self {
+            dbs,
+            reader,
+            replications,
+            progress: ReplicationProgress::default(),
+            replication_rate,
+            max_replication_failures,
+        })
+    }
+
+    /// Sets the max number of replication cmds sent per second.
+    pub(super) fn set_replication_rate(&mut self, replication_rate: usize) {
+        self.replication_rate = replication_rate;
+    }
+
+    /// Schedules the replication of the chunk from its remaining holders, durably.
+    async fn schedule_replication(
+        &mut self,
+        address: BlobAddress,
+        holders: BTreeSet<XorName>,
+    ) -> Result<()> {
+        persist_replication(&self.dbs, address, &holders).await?;
+        self.replications.schedule(address, holders);
+        Ok(())
+    }
+
+    /// Drops the chunk from the replication schedule, durably.
+    async fn cancel_replication(&mut self, address: BlobAddress) -> Result<()> {
+        self.replications.cancel(&address);
+        forget_replication(&self.dbs, address).await
     }
 
     pub(super) async fn write(
@@ -116,7 +199,13 @@ impl BlobRegister {
                 .collect::<BTreeSet<_>>()
         };
 
-        info!("Storing {} copies of the data", target_holders.len());
+        crate::trace_msg!(
+            "sent_to_holders",
+            msg_id,
+            origin.id(),
+            "copies={}",
+            target_holders.len()
+        );
 
         let mut results = vec![];
         for holder in &target_holders {
@@ -196,6 +285,8 @@ impl BlobRegister {
 
         if !results.is_empty() {}
 
+        self.cancel_replication(address).await?;
+
         let msg = Message::NodeCmd {
             cmd: NodeCmd::Chunks {
                 cmd: BlobWrite::DeletePrivate(address),
@@ -263,9 +354,25 @@ impl BlobRegister {
         let db_key = blob_address.to_db_key()?;
         let metadata = self.get_metadata_for(blob_address).await;
         if let Ok(mut metadata) = metadata {
-            let holder = self.get_holder(holder_name).await;
+            // Remove the holder from the chunk metadata, first, as the holder metadata
+            // is rebuilt from it should we stop in between.
+            let _ = metadata.holders.remove(&holder_name);
+            if metadata.holders.is_empty() {
+                if let Err(error) = self.dbs.metadata.lock().await.rem(&db_key) {
+                    warn!(
+                        "{}: Failed to delete chunk metadata from DB: {:?}",
+                        self, error
+                    );
+                }
+            } else if let Err(error) = self.dbs.metadata.lock().await.set(&db_key, &metadata) {
+                warn!(
+                    "{}: Failed to write chunk metadata to DB: {:?}",
+                    self, error
+                );
+            }
 
             // Remove the chunk from the holder metadata
+            let holder = self.get_holder(holder_name).await;
             if let Ok(mut holder) = holder {
                 let _ = holder.chunks.remove(&blob_address);
                 if holder.chunks.is_empty() {
@@ -289,48 +396,153 @@ impl BlobRegister {
                     );
                 }
             }
-
-            // Remove the holder from the chunk metadata
-            let _ = metadata.holders.remove(&holder_name);
-            if metadata.holders.is_empty() {
-                if let Err(error) = self.dbs.metadata.lock().await.rem(&db_key) {
-                    warn!(
-                        "{}: Failed to delete chunk metadata from DB: {:?}",
-                        self, error
-                    );
-                }
-            } else if let Err(error) = self.dbs.metadata.lock().await.set(&db_key, &metadata) {
-                warn!(
-                    "{}: Failed to write chunk metadata to DB: {:?}",
-                    self, error
-                );
-            }
         }
         Ok(())
     }
 
+    /// Schedules the replication of the chunks held by the departed `holder`, and issues
+    /// the first batch of it, the chunks with the fewest remaining holders first.
     pub(super) async fn replicate_chunks(&mut self, holder: XorName) -> Result<NodeDuties> {
+        self.progress.holder_left(holder);
         let chunks_stored = match self.remove_holder(holder).await {
             Ok(chunks) => chunks,
             _ => return Ok(vec![]),
         };
-        let mut cmds = Vec::new();
         for (address, holders) in chunks_stored {
-            cmds.extend(self.get_replication_msgs(address, holders).await);
+            if is_lost(&self.dbs, address).await? {
+                trace!("{}: Not replicating lost chunk {:?}", self, address);
+                continue;
+            }
+            self.schedule_replication(address, holders).await?;
+        }
+        self.replicate_next_batch().await
+    }
+
+    /// Schedules the replication to the newly joined `node` of the chunks it is now closer to
+    /// than one of their holders, and issues the first batch of it, so that each chunk stays
+    /// held by the Adults closest to it. Batched like the replication after nodes left.
+    pub(super) async fn redistribute_chunks(&mut self, node: XorName) -> Result<NodeDuties> {
+        let chunks = chunks_to_redistribute(&self.dbs, node).await?;
+        info!(
+            "{}: Redistributing {} chunks to new node {}",
+            self,
+            chunks.len(),
+            node
+        );
+        for (address, holders) in chunks {
+            if is_lost(&self.dbs, address).await? {
+                trace!("{}: Not redistributing lost chunk {:?}", self, address);
+                continue;
+            }
+            self.schedule_replication(address, holders).await?;
+        }
+        self.replicate_next_batch().await
+    }
+
+    /// Counts the failure of an Adult to replicate the chunk, the chunk being deemed lost
+    /// once the max number of failures is reached. A replication refused by the Adult for
+    /// lack of room isn't counted, but scheduled again.
+    pub(super) async fn record_replication_failure(
+        &mut self,
+        address: BlobAddress,
+        holder: XorName,
+        error: ErrorMessage,
+    ) -> Result<NodeDuties> {
+        self.progress.failed(address, holder);
+        let metadata = self.get_metadata_for(address).await.ok();
+        if let ErrorMessage::TooManyEntries = error {
+            if let Some(metadata) = metadata {
+                info!(
+                    "{}: Rescheduling refused replication of {:?}",
+                    self, address
+                );
+                self.schedule_replication(address, metadata.holders).await?;
+            }
+            return Ok(vec![]);
+        }
+        let owner = metadata.and_then(|metadata| metadata.owner);
+        if record_failure(&self.dbs, address, owner, self.max_replication_failures).await? {
+            warn!(
+                "{}: Too many failures to replicate chunk {:?}, deeming it lost",
+                self, address
+            );
+            self.cancel_replication(address).await?;
+        }
+        Ok(vec![])
+    }
+
+    /// The chunks deemed lost, as they could no longer be replicated.
+    pub(super) async fn lost_chunks(&self) -> Result<BTreeSet<BlobAddress>> {
+        lost_chunks(&self.dbs).await
+    }
+
+    /// Issues the next batch of scheduled chunk replications, if any, made of no more
+    /// replication cmds than the replication rate, though of at least one chunk.
+    pub(super) async fn replicate_next_batch(&mut self) -> Result<NodeDuties> {
+        let mut cmds = Vec::new();
+        while let Some((address, holders)) = self.replications.peek() {
+            let new_holders = self.get_new_holders_for_chunk(&address).await;
+            if !cmds.is_empty() && cmds.len() + new_holders.len() > self.replication_rate {
+                break;
+            }
+            let _ = self.replications.pop();
+            forget_replication(&self.dbs, address).await?;
+            for new_holder in &new_holders {
+                self.progress.issued(address, *new_holder);
+            }
+            cmds.extend(self.get_replication_msgs(address, holders, new_holders));
+        }
+        if !self.replications.is_empty() {
+            info!(
+                "{}: {} chunks left to replicate",
+                self,
+                self.replications.len()
+            );
         }
         Ok(cmds)
     }
 
-    async fn get_replication_msgs(
+    /// Counts `holder` among the holders of the chunk, once it reported holding a copy
+    /// replicated to it, signed with its node key. A repeated report changes nothing.
+    pub(super) async fn record_chunk_holder(
+        &mut self,
+        address: BlobAddress,
+        holder: XorName,
+        proof: Signature,
+    ) -> Result<()> {
+        // A node's name is its public key.
+        let holder_key = Ed25519PublicKey::from_bytes(&holder.0)
+            .map_err(|_| Error::NetworkData(DtError::InvalidSignature))?;
+        PublicKey::Ed25519(holder_key).verify(&proof, utils::serialise(&address)?)?;
+        self.progress.completed(address, holder);
+
+        if add_chunk_holder(&self.dbs, address, holder).await? {
+            info!("{}: {} now holds chunk {:?}", self, holder, address);
+        } else {
+            trace!("{}: {} already holds chunk {:?}", self, holder, address);
+        }
+        Ok(())
+    }
+
+    /// Counts of the chunk replications scheduled after losing holders, for diagnostics.
+    pub(super) fn replication_stats(&self) -> ReplicationScheduleStats {
+        self.replications.stats()
+    }
+
+    /// How far along the replication of chunks after churn is.
+    pub(super) fn replication_status(&self) -> ReplicationStatus {
+        self.progress.status(self.replications.len())
+    }
+
+    fn get_replication_msgs(
         &self,
         address: BlobAddress,
         current_holders: BTreeSet<XorName>,
+        new_holders: BTreeSet<XorName>,
     ) -> NodeDuties {
         use NodeCmd::*;
         let mut node_ops = Vec::new();
-        let messages = self
-            .get_new_holders_for_chunk(&address)
-            .await
+        let messages = new_holders
             .into_iter()
             .map(|new_holder| {
                 let message_id = MessageId::combine(vec![*address.name(), new_holder]);
@@ -474,15 +686,19 @@ impl BlobRegister {
                         warn!("doesn't contain the holder",);
                     }
 
-                    let _ = blob_addresses.insert(chunk_address, metadata.holders.clone());
-
                     if metadata.holders.is_empty() {
                         if let Err(error) = self.dbs.metadata.lock().await.rem(&db_key) {
                             warn!("{}: Failed to write metadata to DB: {:?}", self, error);
                         }
-                    } else if let Err(error) =
-                        self.dbs.metadata.lock().await.set(&db_key, &metadata)
-                    {
+                        // No holder left to replicate it from.
+                        warn!("{}: Last holder of chunk {:?} left", self, chunk_address);
+                        mark_lost(&self.dbs, chunk_address, metadata.owner).await?;
+                        continue;
+                    }
+
+                    let _ = blob_addresses.insert(chunk_address, metadata.holders.clone());
+
+                    if let Err(error) = self.dbs.metadata.lock().await.set(&db_key, &metadata) {
                         warn!("{}: Failed to write metadata to DB: {:?}", self, error);
                     }
                 }
@@ -545,6 +761,8 @@ impl BlobRegister {
 
     // Returns `XorName`s of the target holders for an Blob chunk.
     // Used to fetch the list of holders for a new chunk.
+    // Only depends on the section's Adults, as each Elder must choose the same holders
+    // for the Put to reach its aggregation threshold at them.
     async fn get_holders_for_chunk(&self, target: &XorName) -> Vec<XorName> {
         self.reader
             .our_adults_sorted_by_distance_to(&target, CHUNK_COPY_COUNT)
@@ -553,6 +771,8 @@ impl BlobRegister {
 
     // Returns `XorName`s of the new target holders for an Blob chunk.
     // Used to fetch the additional list of holders for existing chunks.
+    // As for new chunks, only depends on the section's Adults, for each Elder to choose
+    // the same new holders.
     async fn get_new_holders_for_chunk(&self, target: &BlobAddress) -> BTreeSet<XorName> {
         let closest_holders = self
             .get_holders_for_chunk(target.name())
@@ -570,8 +790,526 @@ impl BlobRegister {
     }
 }
 
+/// Adds `holder` to the holders of the chunk, returning false if already among them.
+/// A chunk deemed lost is recovered, its failures being forgotten. Fails if the chunk
+/// isn't known, e.g. as it was deleted meanwhile.
+async fn add_chunk_holder(
+    dbs: &ChunkHolderDbs,
+    address: BlobAddress,
+    holder: XorName,
+) -> Result<bool> {
+    let db_key = address.to_db_key()?;
+    let failures = dbs
+        .replication_failures
+        .lock()
+        .await
+        .get::<ReplicationFailures>(&db_key);
+    let mut metadata = match dbs.metadata.lock().await.get::<ChunkMetadata>(&db_key) {
+        Some(metadata) if !metadata.holders.is_empty() => metadata,
+        _ => match &failures {
+            Some(failures) if failures.lost => ChunkMetadata {
+                holders: BTreeSet::new(),
+                owner: failures.owner,
+            },
+            _ => return Err(Error::NoSuchChunk),
+        },
+    };
+    if failures.is_some() {
+        info!("Chunk {:?} recovered from {}", address, holder);
+        let _ = dbs.replication_failures.lock().await.rem(&db_key)?;
+    }
+    if !metadata.holders.insert(holder) {
+        return Ok(false);
+    }
+    dbs.metadata.lock().await.set(&db_key, &metadata)?;
+
+    let holder_key = holder.to_db_key()?;
+    let mut holder_metadata = dbs
+        .holders
+        .lock()
+        .await
+        .get::<HolderMetadata>(&holder_key)
+        .unwrap_or_default();
+    let _ = holder_metadata.chunks.insert(address);
+    dbs.holders
+        .lock()
+        .await
+        .set(&holder_key, &holder_metadata)?;
+    Ok(true)
+}
+
+/// The holder of the chunk displaced by `node`, being the furthest from the chunk
+/// if further than `node`. None if `node` already holds it.
+fn displaced_holder(
+    address: &BlobAddress,
+    holders: &BTreeSet<XorName>,
+    node: &XorName,
+) -> Option<XorName> {
+    if holders.contains(node) {
+        return None;
+    }
+    let target = address.name();
+    let furthest = holders
+        .iter()
+        .max_by(|lhs, rhs| target.cmp_distance(lhs, rhs))?;
+    if target.cmp_distance(node, furthest) == Ordering::Less {
+        Some(*furthest)
+    } else {
+        None
+    }
+}
+
+/// The chunks for which `node` displaces a holder, with their current holders.
+async fn chunks_to_redistribute(
+    dbs: &ChunkHolderDbs,
+    node: XorName,
+) -> Result<BTreeMap<BlobAddress, BTreeSet<XorName>>> {
+    let db = dbs.metadata.lock().await;
+    let mut chunks = BTreeMap::new();
+    for key in db.get_all() {
+        let metadata = match db.get::<ChunkMetadata>(&key) {
+            Some(metadata) => metadata,
+            None => continue,
+        };
+        let address = from_db_key::<BlobAddress>(&key)?;
+        if displaced_holder(&address, &metadata.holders, &node).is_some() {
+            let _ = chunks.insert(address, metadata.holders);
+        }
+    }
+    Ok(chunks)
+}
+
+/// Records the scheduled replication of the chunk, for it to survive a restart.
+async fn persist_replication(
+    dbs: &ChunkHolderDbs,
+    address: BlobAddress,
+    holders: &BTreeSet<XorName>,
+) -> Result<()> {
+    dbs.pending_replications
+        .lock()
+        .await
+        .set(&address.to_db_key()?, holders)?;
+    Ok(())
+}
+
+/// Removes the record of the scheduled replication of the chunk, once issued or cancelled.
+async fn forget_replication(dbs: &ChunkHolderDbs, address: BlobAddress) -> Result<()> {
+    let _ = dbs
+        .pending_replications
+        .lock()
+        .await
+        .rem(&address.to_db_key()?)?;
+    Ok(())
+}
+
+/// The replications recorded as scheduled but not yet issued.
+async fn load_replications(dbs: &ChunkHolderDbs) -> Result<ReplicationSchedule> {
+    let db = dbs.pending_replications.lock().await;
+    let mut replications = ReplicationSchedule::default();
+    for key in db.get_all() {
+        if let Some(holders) = db.get::<BTreeSet<XorName>>(&key) {
+            replications.schedule(from_db_key(&key)?, holders);
+        }
+    }
+    Ok(replications)
+}
+
+/// Counts the records of the chunk holder DBs, checking the chunks recorded for each holder
+/// against the holders recorded for each chunk. The chunk records are written first, so
+/// should the node stop before the holder records are written, the latter are rewritten
+/// after the former. Unreadable records are logged and left as they are.
+async fn verify_metadata(dbs: &ChunkHolderDbs) -> Result<MetadataCheck> {
+    let mut check = MetadataCheck::default();
+    let mut expected: BTreeMap<XorName, BTreeSet<BlobAddress>> = BTreeMap::new();
+    {
+        let db = dbs.metadata.lock().await;
+        for key in db.get_all() {
+            match (
+                from_db_key::<BlobAddress>(&key),
+                db.get::<ChunkMetadata>(&key),
+            ) {
+                (Ok(address), Some(metadata)) => {
+                    check.chunks += 1;
+                    for holder in metadata.holders {
+                        let _ = expected.entry(holder).or_default().insert(address);
+                    }
+                }
+                _ => {
+                    warn!("Unreadable chunk metadata record: {}", key);
+                    check.unreadable += 1;
+                }
+            }
+        }
+    }
+
+    let mut db = dbs.holders.lock().await;
+    for key in db.get_all() {
+        let (holder, metadata) =
+            match (from_db_key::<XorName>(&key), db.get::<HolderMetadata>(&key)) {
+                (Ok(holder), Some(metadata)) => (holder, metadata),
+                _ => {
+                    warn!("Unreadable holder metadata record: {}", key);
+                    check.unreadable += 1;
+                    continue;
+                }
+            };
+        check.holders += 1;
+        let chunks = expected.remove(&holder).unwrap_or_default();
+        if chunks == metadata.chunks {
+            continue;
+        }
+        warn!(
+            "Chunks recorded for holder {} don't match the chunk records, rewriting them",
+            holder
+        );
+        if chunks.is_empty() {
+            let _ = db.rem(&key)?;
+        } else {
+            db.set(&key, &HolderMetadata { chunks })?;
+        }
+        check.repaired += 1;
+    }
+    for (holder, chunks) in expected {
+        warn!(
+            "Chunks of holder {} not recorded for it, recording them",
+            holder
+        );
+        db.set(&holder.to_db_key()?, &HolderMetadata { chunks })?;
+        check.repaired += 1;
+    }
+
+    info!(
+        "Chunk metadata: {} chunks and {} holders recorded, {} unreadable, {} repaired",
+        check.chunks, check.holders, check.unreadable, check.repaired
+    );
+    Ok(check)
+}
+
+/// Counts a failure to replicate the chunk, deeming it lost once `max_failures` is reached.
+/// Returns whether it was deemed lost by this failure.
+async fn record_failure(
+    dbs: &ChunkHolderDbs,
+    address: BlobAddress,
+    owner: Option<PublicKey>,
+    max_failures: usize,
+) -> Result<bool> {
+    let db_key = address.to_db_key()?;
+    let mut db = dbs.replication_failures.lock().await;
+    let mut failures = db.get::<ReplicationFailures>(&db_key).unwrap_or_default();
+    if failures.lost {
+        return Ok(false);
+    }
+    failures.count += 1;
+    failures.lost = failures.count >= max_failures;
+    if owner.is_some() {
+        failures.owner = owner;
+    }
+    db.set(&db_key, &failures)?;
+    Ok(failures.lost)
+}
+
+/// Deems the chunk lost, e.g. as it has no holder left.
+async fn mark_lost(
+    dbs: &ChunkHolderDbs,
+    address: BlobAddress,
+    owner: Option<PublicKey>,
+) -> Result<()> {
+    let db_key = address.to_db_key()?;
+    let mut db = dbs.replication_failures.lock().await;
+    let mut failures = db.get::<ReplicationFailures>(&db_key).unwrap_or_default();
+    failures.lost = true;
+    if owner.is_some() {
+        failures.owner = owner;
+    }
+    db.set(&db_key, &failures)?;
+    Ok(())
+}
+
+async fn is_lost(dbs: &ChunkHolderDbs, address: BlobAddress) -> Result<bool> {
+    let failures = dbs
+        .replication_failures
+        .lock()
+        .await
+        .get::<ReplicationFailures>(&address.to_db_key()?);
+    Ok(matches!(
+        failures,
+        Some(ReplicationFailures { lost: true, .. })
+    ))
+}
+
+async fn lost_chunks(dbs: &ChunkHolderDbs) -> Result<BTreeSet<BlobAddress>> {
+    let db = dbs.replication_failures.lock().await;
+    let mut lost = BTreeSet::new();
+    for key in db.get_all() {
+        if let Some(ReplicationFailures { lost: true, .. }) = db.get(&key) {
+            let _ = lost.insert(from_db_key::<BlobAddress>(&key)?);
+        }
+    }
+    Ok(lost)
+}
+
 impl Display for BlobRegister {
     fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
         write!(formatter, "BlobRegister")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        add_chunk_holder, chunks_to_redistribute, displaced_holder, forget_replication, is_lost,
+        load_replications, lost_chunks, mark_lost, persist_replication, record_failure,
+        verify_metadata, ChunkMetadata, HolderMetadata, MetadataCheck, CHUNK_COPY_COUNT,
+    };
+    use crate::{capacity::ChunkHolderDbs, Error, Result, ToDbKey};
+    use pickledb::{PickleDb, PickleDbDumpPolicy};
+    use sn_data_types::{BlobAddress, PublicBlob};
+    use std::{collections::BTreeSet, iter};
+    use tempdir::TempDir;
+    use xor_name::XorName;
+
+    #[tokio::test]
+    async fn metadata_predating_versioning_is_loaded_and_repaired() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let address = *PublicBlob::new(vec![9_u8; 64]).address();
+        let (holder, new_holder, former_holder) =
+            (XorName::random(), XorName::random(), XorName::random());
+        // Written by a node predating the format version record, stopped after recording
+        // the holders of the chunk, before recording the chunk for them.
+        {
+            let policy = PickleDbDumpPolicy::AutoDump;
+            let mut chunks = PickleDb::new_bin(root.path().join("immutable_data.db"), policy);
+            let metadata = ChunkMetadata {
+                holders: vec![holder, new_holder].into_iter().collect(),
+                owner: None,
+            };
+            chunks.set(&address.to_db_key()?, &metadata)?;
+            let policy = PickleDbDumpPolicy::AutoDump;
+            let mut holders = PickleDb::new_bin(root.path().join("holder_data.db"), policy);
+            for name in &[holder, former_holder] {
+                let metadata = HolderMetadata {
+                    chunks: iter::once(address).collect(),
+                };
+                holders.set(&name.to_db_key()?, &metadata)?;
+            }
+        }
+
+        let dbs = ChunkHolderDbs::new(root.path())?;
+        assert_eq!(
+            verify_metadata(&dbs).await?,
+            MetadataCheck {
+                chunks: 1,
+                holders: 2,
+                unreadable: 0,
+                repaired: 2,
+            }
+        );
+        {
+            let holders = dbs.holders.lock().await;
+            let metadata = holders
+                .get::<HolderMetadata>(&new_holder.to_db_key()?)
+                .ok_or(Error::NoSuchChunk)?;
+            assert_eq!(metadata.chunks, iter::once(address).collect());
+            assert!(holders
+                .get::<HolderMetadata>(&former_holder.to_db_key()?)
+                .is_none());
+        }
+
+        // Nothing left to repair once reopened.
+        drop(dbs);
+        let dbs = ChunkHolderDbs::new(root.path())?;
+        assert_eq!(verify_metadata(&dbs).await?.repaired, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reported_holder_is_added_once() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let dbs = ChunkHolderDbs::new(root.path())?;
+        let address = *PublicBlob::new(vec![0_u8; 64]).address();
+        let metadata = ChunkMetadata {
+            holders: iter::once(XorName::random()).collect(),
+            owner: None,
+        };
+        dbs.metadata
+            .lock()
+            .await
+            .set(&address.to_db_key()?, &metadata)?;
+
+        let holder = XorName::random();
+        assert!(add_chunk_holder(&dbs, address, holder).await?);
+        // The same report received again.
+        assert!(!add_chunk_holder(&dbs, address, holder).await?);
+
+        let metadata = dbs
+            .metadata
+            .lock()
+            .await
+            .get::<ChunkMetadata>(&address.to_db_key()?)
+            .ok_or(Error::NoSuchChunk)?;
+        assert_eq!(metadata.holders.len(), 2);
+        assert!(metadata.holders.contains(&holder));
+        let holder_metadata = dbs
+            .holders
+            .lock()
+            .await
+            .get::<HolderMetadata>(&holder.to_db_key()?)
+            .ok_or(Error::NoSuchChunk)?;
+        assert_eq!(holder_metadata.chunks.len(), 1);
+        assert!(holder_metadata.chunks.contains(&address));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn holder_of_unknown_chunk_is_not_added() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let dbs = ChunkHolderDbs::new(root.path())?;
+        let address = *PublicBlob::new(vec![1_u8; 64]).address();
+
+        match add_chunk_holder(&dbs, address, XorName::random()).await {
+            Err(Error::NoSuchChunk) => {}
+            x => return Err(Error::Logic(format!("Unexpected: {:?}", x))),
+        }
+        assert!(dbs
+            .metadata
+            .lock()
+            .await
+            .get::<ChunkMetadata>(&address.to_db_key()?)
+            .is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn chunk_is_lost_once_max_failures_reached() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let dbs = ChunkHolderDbs::new(root.path())?;
+        let address = *PublicBlob::new(vec![2_u8; 64]).address();
+        let max_failures = 3;
+
+        assert!(!record_failure(&dbs, address, None, max_failures).await?);
+        assert!(!record_failure(&dbs, address, None, max_failures).await?);
+        assert!(!is_lost(&dbs, address).await?);
+        assert!(lost_chunks(&dbs).await?.is_empty());
+
+        assert!(record_failure(&dbs, address, None, max_failures).await?);
+        assert!(is_lost(&dbs, address).await?);
+        // Further failures don't deem it lost anew.
+        assert!(!record_failure(&dbs, address, None, max_failures).await?);
+        let lost = lost_chunks(&dbs).await?;
+        assert_eq!(lost.len(), 1);
+        assert!(lost.contains(&address));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn lost_chunk_is_recovered_when_a_holder_reports_it() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let dbs = ChunkHolderDbs::new(root.path())?;
+        let address = *PublicBlob::new(vec![3_u8; 64]).address();
+        // Its last holder left, so its metadata is gone.
+        mark_lost(&dbs, address, None).await?;
+        assert!(lost_chunks(&dbs).await?.contains(&address));
+
+        let holder = XorName::random();
+        assert!(add_chunk_holder(&dbs, address, holder).await?);
+
+        assert!(!is_lost(&dbs, address).await?);
+        assert!(lost_chunks(&dbs).await?.is_empty());
+        let metadata = dbs
+            .metadata
+            .lock()
+            .await
+            .get::<ChunkMetadata>(&address.to_db_key()?)
+            .ok_or(Error::NoSuchChunk)?;
+        assert_eq!(metadata.holders.len(), 1);
+        assert!(metadata.holders.contains(&holder));
+
+        Ok(())
+    }
+
+    // A name in a small synthetic address space, set by its first byte.
+    fn name(byte: u8) -> XorName {
+        let mut name = XorName::default();
+        name.0[0] = byte;
+        name
+    }
+
+    fn names(bytes: &[u8]) -> BTreeSet<XorName> {
+        bytes.iter().map(|byte| name(*byte)).collect()
+    }
+
+    #[test]
+    fn new_node_displaces_furthest_holder_only_if_closer() {
+        let address = BlobAddress::Public(name(0x00));
+        let holders = names(&[0x01, 0x02, 0x04, 0x40]);
+
+        assert_eq!(
+            displaced_holder(&address, &holders, &name(0x08)),
+            Some(name(0x40))
+        );
+        assert_eq!(displaced_holder(&address, &holders, &name(0x80)), None);
+        // Already a holder.
+        assert_eq!(displaced_holder(&address, &holders, &name(0x04)), None);
+        assert_eq!(
+            displaced_holder(&address, &BTreeSet::new(), &name(0x08)),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn chunks_closer_to_new_node_are_selected_for_redistribution() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let dbs = ChunkHolderDbs::new(root.path())?;
+        let holders = names(&[0x01, 0x02, 0x03, 0x04]);
+        let chunks: Vec<_> = [0x00, 0x80, 0x08, 0xf0]
+            .iter()
+            .map(|byte| BlobAddress::Public(name(*byte)))
+            .collect();
+        for address in &chunks {
+            let metadata = ChunkMetadata {
+                holders: holders.clone(),
+                owner: None,
+            };
+            dbs.metadata
+                .lock()
+                .await
+                .set(&address.to_db_key()?, &metadata)?;
+        }
+
+        // Closer than all holders to 0x80 and 0xf0, but further than all of them to 0x08.
+        let selected = chunks_to_redistribute(&dbs, name(0x88)).await?;
+        assert_eq!(
+            selected.keys().cloned().collect::<BTreeSet<_>>(),
+            vec![chunks[1], chunks[3]].into_iter().collect()
+        );
+        let selected = chunks_to_redistribute(&dbs, name(0x0a)).await?;
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected.get(&chunks[2]), Some(&holders));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn pending_replications_resume_after_restart() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let issued = BlobAddress::Public(name(0x01));
+        let pending = BlobAddress::Public(name(0x02));
+        {
+            let dbs = ChunkHolderDbs::new(root.path())?;
+            persist_replication(&dbs, issued, &names(&[0x10])).await?;
+            persist_replication(&dbs, pending, &names(&[0x20, 0x30])).await?;
+            // Issued before the restart.
+            forget_replication(&dbs, issued).await?;
+        }
+
+        let dbs = ChunkHolderDbs::new(root.path())?;
+        let mut replications = load_replications(&dbs).await?;
+        assert_eq!(replications.len(), 1);
+        assert_eq!(replications.pop(), Some((pending, names(&[0x20, 0x30]))));
+
+        Ok(())
+    }
+}
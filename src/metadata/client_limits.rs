@@ -0,0 +1,113 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use sn_data_types::PublicKey;
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+/// The requests of each client within the last `window`, for a single client not to
+/// starve the others, its requests beyond `max_per_window` being rejected.
+/// A max of 0 means unlimited. Kept in memory only.
+pub(super) struct ClientLimits {
+    window: Duration,
+    max_per_window: usize,
+    // The times of the requests admitted within the window, oldest first.
+    requests: HashMap<PublicKey, VecDeque<Instant>>,
+    last_pruned: Instant,
+}
+
+impl ClientLimits {
+    pub fn new(window: Duration, max_per_window: usize, now: Instant) -> Self {
+        Self {
+            window,
+            max_per_window,
+            requests: HashMap::new(),
+            last_pruned: now,
+        }
+    }
+
+    /// Sets the max number of requests per window of each client, 0 meaning unlimited.
+    pub fn set_max_per_window(&mut self, max_per_window: usize) {
+        self.max_per_window = max_per_window;
+    }
+
+    /// Whether the request is within the limit of the requester, counting it if so.
+    pub fn admit(&mut self, requester: PublicKey, now: Instant) -> bool {
+        if self.max_per_window == 0 {
+            return true;
+        }
+        self.prune(now);
+        let window = self.window;
+        let requests = self.requests.entry(requester).or_default();
+        while let Some(time) = requests.front() {
+            if now.saturating_duration_since(*time) < window {
+                break;
+            }
+            let _ = requests.pop_front();
+        }
+        if requests.len() >= self.max_per_window {
+            return false;
+        }
+        requests.push_back(now);
+        true
+    }
+
+    // Forgets the requesters with no requests left in the window, once per window.
+    fn prune(&mut self, now: Instant) {
+        if now.saturating_duration_since(self.last_pruned) < self.window {
+            return;
+        }
+        let window = self.window;
+        self.requests.retain(|_, requests| {
+            requests
+                .back()
+                .map_or(false, |time| now.saturating_duration_since(*time) < window)
+        });
+        self.last_pruned = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ClientLimits;
+    use sn_data_types::{Keypair, PublicKey};
+    use std::time::{Duration, Instant};
+
+    const WINDOW: Duration = Duration::from_secs(1);
+
+    fn requester() -> PublicKey {
+        Keypair::new_ed25519(&mut rand::thread_rng()).public_key()
+    }
+
+    #[test]
+    fn requests_beyond_the_limit_are_rejected_until_the_window_passes() {
+        let now = Instant::now();
+        let mut limits = ClientLimits::new(WINDOW, 3, now);
+        let (flooding, other) = (requester(), requester());
+
+        for _ in 0..3 {
+            assert!(limits.admit(flooding, now));
+        }
+        assert!(!limits.admit(flooding, now));
+        assert!(!limits.admit(flooding, now + WINDOW / 2));
+        // Others are still served.
+        assert!(limits.admit(other, now + WINDOW / 2));
+
+        assert!(limits.admit(flooding, now + WINDOW));
+    }
+
+    #[test]
+    fn zero_means_unlimited() {
+        let now = Instant::now();
+        let mut limits = ClientLimits::new(WINDOW, 0, now);
+        let requester = requester();
+        assert!((0..100).all(|_| limits.admit(requester, now)));
+    }
+}
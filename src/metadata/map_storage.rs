@@ -10,12 +10,14 @@ use crate::{
     chunk_store::{MapChunkStore, UsedSpace},
     error::convert_to_error_message,
     node_ops::{NodeDuty, OutgoingMsg},
-    Error, Network, Result,
+    to_db_key::ToDbKey,
+    utils, Error, Network, Result,
 };
 use log::info;
+use pickledb::PickleDb;
 use sn_data_types::{
     Error as DtError, Map, MapAction, MapAddress, MapEntryActions, MapPermissionSet, MapValue,
-    PublicKey, Result as NdResult,
+    PublicKey, MAX_BLOB_SIZE_IN_BYTES,
 };
 use sn_messaging::{
     client::{CmdError, MapRead, MapWrite, Message, QueryResponse},
@@ -27,15 +29,48 @@ use std::{
     path::Path,
 };
 
+/// Name of the db holding the number of Maps stored per owner.
+const MAP_OWNERS_DB_NAME: &str = "map_owners.db";
+
+/// The limits a Map must stay within after any mutation.
+const MAP_LIMITS: MapLimits = MapLimits {
+    max_entries: 1000,
+    max_value_size: 100 * 1024,
+    max_size: MAX_BLOB_SIZE_IN_BYTES,
+};
+
+#[derive(Debug, Clone, Copy)]
+struct MapLimits {
+    /// Max number of entries.
+    max_entries: usize,
+    /// Max size of a single entry value, in bytes.
+    max_value_size: usize,
+    /// Max size of the serialised Map as a whole, in bytes.
+    max_size: u64,
+}
+
 /// Operations over the data type Map.
 pub(super) struct MapStorage {
     chunks: MapChunkStore,
+    // The number of Maps stored per owner.
+    owners: PickleDb,
+    // Max number of Maps stored per owner, 0 meaning unlimited.
+    max_maps_per_owner: u64,
 }
 
 impl MapStorage {
-    pub(super) async fn new(path: &Path, used_space: UsedSpace) -> Result<Self> {
+    pub(super) async fn new(
+        path: &Path,
+        used_space: UsedSpace,
+        max_maps_per_owner: u64,
+    ) -> Result<Self> {
         let chunks = MapChunkStore::new(path, used_space).await?;
-        Ok(Self { chunks })
+        let owners = utils::new_auto_dump_db(path, MAP_OWNERS_DB_NAME)?;
+        Ok(Self {
+            chunks,
+            owners,
+            max_maps_per_owner,
+        })
     }
 
     pub(super) async fn read(
@@ -105,6 +140,11 @@ impl MapStorage {
     }
 
     /// Get Map from the chunk store, update it, and overwrite the stored chunk.
+    /// The mutation is applied to a copy of the stored Map and validated as a whole,
+    /// which is then persisted with a single put, so that a failing mutation, or a
+    /// crash before it is persisted, leaves the stored Map untouched.
+    /// As it holds `&mut self` until the put completes, mutations are validated and persisted
+    /// one at a time, each seeing the versions left by the previous one.
     async fn edit_chunk<F>(
         &mut self,
         address: &MapAddress,
@@ -113,12 +153,12 @@ impl MapStorage {
         mutation_fn: F,
     ) -> Result<NodeDuty>
     where
-        F: FnOnce(Map) -> NdResult<Map>,
+        F: FnOnce(Map) -> Result<Map>,
     {
         let result = match self.chunks.get(address) {
             Ok(data) => match mutation_fn(data) {
                 Ok(map) => self.chunks.put(&map).await,
-                Err(error) => Err(error.into()),
+                Err(error) => Err(error),
             },
             Err(error) => Err(error),
         };
@@ -130,8 +170,15 @@ impl MapStorage {
     async fn create(&mut self, data: &Map, msg_id: MessageId, origin: EndUser) -> Result<NodeDuty> {
         let result = if self.chunks.has(data.address()) {
             Err(Error::DataExists)
+        } else if let Err(error) = check_limits(data, MAP_LIMITS) {
+            Err(error)
+        } else if let Err(error) = self.check_quota(&data.owner()) {
+            Err(error)
         } else {
-            self.chunks.put(&data).await
+            match self.chunks.put(&data).await {
+                Ok(()) => self.count_map_added(&data.owner()),
+                Err(error) => Err(error),
+            }
         };
         self.ok_or_error(result, msg_id, origin).await
     }
@@ -146,7 +193,10 @@ impl MapStorage {
             Ok(map) => match map.check_is_owner(origin.id()) {
                 Ok(()) => {
                     info!("Deleting Map");
-                    self.chunks.delete(&address).await
+                    match self.chunks.delete(&address).await {
+                        Ok(()) => self.count_map_removed(&map.owner()),
+                        Err(error) => Err(error),
+                    }
                 }
                 Err(_e) => {
                     info!("Error: Delete Map called by non-owner");
@@ -194,6 +244,69 @@ impl MapStorage {
         .await
     }
 
+    /// Transfer ownership of the Map, which only its owner may do,
+    /// whatever the permissions granted to others.
+    pub(super) async fn change_owner(
+        &mut self,
+        address: MapAddress,
+        new_owner: PublicKey,
+        version: u64,
+        msg_id: MessageId,
+        origin: EndUser,
+    ) -> Result<NodeDuty> {
+        let quota = self.check_quota(&new_owner);
+        let duty = self
+            .edit_chunk(&address, origin, msg_id, move |mut data| {
+                data.check_is_owner(origin.id())?;
+                quota?;
+                match &mut data {
+                    Map::Seq(map) => map.change_owner(new_owner, version)?,
+                    Map::Unseq(map) => map.change_owner(new_owner, version)?,
+                }
+                Ok(data)
+            })
+            .await?;
+        if let NodeDuty::NoOp = duty {
+            self.count_map_removed(origin.id())?;
+            self.count_map_added(&new_owner)?;
+        }
+        Ok(duty)
+    }
+
+    /// Number of Maps stored for `owner`.
+    fn map_count(&self, owner: &PublicKey) -> Result<u64> {
+        Ok(self.owners.get::<u64>(&owner.to_db_key()?).unwrap_or(0))
+    }
+
+    fn set_map_count(&mut self, owner: &PublicKey, count: u64) -> Result<()> {
+        let db_key = owner.to_db_key()?;
+        if count == 0 {
+            let _ = self.owners.rem(&db_key)?;
+        } else {
+            self.owners.set(&db_key, &count)?;
+        }
+        Ok(())
+    }
+
+    fn count_map_added(&mut self, owner: &PublicKey) -> Result<()> {
+        let count = self.map_count(owner)?;
+        self.set_map_count(owner, count + 1)
+    }
+
+    fn count_map_removed(&mut self, owner: &PublicKey) -> Result<()> {
+        let count = self.map_count(owner)?;
+        self.set_map_count(owner, count.saturating_sub(1))
+    }
+
+    /// Checks `owner` may store one more Map.
+    fn check_quota(&self, owner: &PublicKey) -> Result<()> {
+        if self.max_maps_per_owner > 0 && self.map_count(owner)? >= self.max_maps_per_owner {
+            Err(Error::MapQuotaExceeded(*owner))
+        } else {
+            Ok(())
+        }
+    }
+
     /// Edit Map.
     async fn edit_entries(
         &mut self,
@@ -203,7 +316,13 @@ impl MapStorage {
         origin: EndUser,
     ) -> Result<NodeDuty> {
         self.edit_chunk(&address, origin, msg_id, move |mut data| {
-            data.mutate_entries(actions, origin.id())?;
+            if let Err(error) = data.mutate_entries(actions, origin.id()) {
+                if let DtError::InvalidEntryActions(errors) = &error {
+                    info!("Map entry actions rejected: {:?}", errors);
+                }
+                return Err(error.into());
+            }
+            check_limits(&data, MAP_LIMITS)?;
             Ok(data)
         })
         .await
@@ -234,7 +353,8 @@ impl MapStorage {
         }))
     }
 
-    /// Get Map shell.
+    /// Get Map shell, i.e. the Map without its entries, for its owner, version
+    /// and permissions to be queried without sending all of its entries.
     async fn get_shell(
         &self,
         address: MapAddress,
@@ -290,7 +410,8 @@ impl MapStorage {
         }))
     }
 
-    /// Get Map value.
+    /// Get Map value. A key missing from the Map is reported as `NoSuchEntry`, while
+    /// a missing Map, e.g. one of the other kind (sequenced or not), is `NoSuchData`.
     async fn get_value(
         &self,
         address: MapAddress,
@@ -414,7 +535,8 @@ impl MapStorage {
         }))
     }
 
-    /// Get Map permissions.
+    /// Get Map permissions. As the response carries no version, clients needing it along,
+    /// e.g. to then set permissions with the next version, get both at once from the shell.
     async fn list_permissions(
         &self,
         address: MapAddress,
@@ -442,7 +564,7 @@ impl MapStorage {
         }))
     }
 
-    /// Get Map user permissions.
+    /// Get Map user permissions, a user without any being reported as `NoSuchKey`.
     async fn list_user_permissions(
         &self,
         address: MapAddress,
@@ -502,8 +624,621 @@ impl MapStorage {
     }
 }
 
+/// Checks the Map is within `limits`, on the state it would be stored in,
+/// so that a batch of mutations is judged by its net effect.
+fn check_limits(map: &Map, limits: MapLimits) -> Result<()> {
+    let (count, max_value_size) = match map {
+        Map::Seq(map) => (
+            map.entries().len(),
+            map.entries().values().map(|value| value.data.len()).max(),
+        ),
+        Map::Unseq(map) => (
+            map.entries().len(),
+            map.entries().values().map(Vec::len).max(),
+        ),
+    };
+    if count > limits.max_entries {
+        return Err(Error::TooManyEntries);
+    }
+    if max_value_size.unwrap_or(0) > limits.max_value_size
+        || bincode::serialized_size(map)? > limits.max_size
+    {
+        return Err(Error::NetworkData(DtError::ExceededSize));
+    }
+    Ok(())
+}
+
 impl Display for MapStorage {
     fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
         write!(formatter, "MapStorage")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{check_limits, MapLimits, MapStorage};
+    use crate::{
+        chunk_store::UsedSpace,
+        node_ops::{NodeDuty, OutgoingMsg},
+        Error, Result,
+    };
+    use sn_data_types::{
+        Error as DtError, Map, MapAction, MapAddress, MapPermissionSet, MapSeqEntryActions,
+        MapSeqValue, MapUnseqEntryActions, MapValue, PublicKey, SeqMap, UnseqMap,
+    };
+    use sn_messaging::{
+        client::{CmdError, Error as ErrorMessage, Message, QueryResponse},
+        EndUser, MessageId,
+    };
+    use tempdir::TempDir;
+    use xor_name::XorName;
+
+    const LIMITS: MapLimits = MapLimits {
+        max_entries: 3,
+        max_value_size: 4,
+        max_size: 1024,
+    };
+
+    fn map_with(owner: PublicKey, keys: &[u8]) -> Result<Map> {
+        let mut map = Map::Unseq(UnseqMap::new(XorName::random(), 0, owner));
+        let actions = keys
+            .iter()
+            .fold(MapUnseqEntryActions::new(), |actions, key| {
+                actions.ins(vec![*key], vec![0; 4])
+            });
+        map.mutate_entries(actions.into(), &owner)?;
+        Ok(map)
+    }
+
+    fn random_key() -> PublicKey {
+        PublicKey::from(bls::SecretKey::random().public_key())
+    }
+
+    #[test]
+    fn entries_are_limited_on_exact_count() -> Result<()> {
+        let owner = random_key();
+        let mut map = map_with(owner, &[0, 1])?;
+        map.mutate_entries(
+            MapUnseqEntryActions::new().ins(vec![2], vec![]).into(),
+            &owner,
+        )?;
+        check_limits(&map, LIMITS)?;
+
+        map.mutate_entries(
+            MapUnseqEntryActions::new().ins(vec![3], vec![]).into(),
+            &owner,
+        )?;
+        match check_limits(&map, LIMITS) {
+            Err(Error::TooManyEntries) => Ok(()),
+            other => Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+    }
+
+    #[test]
+    fn values_are_limited_on_exact_size() -> Result<()> {
+        let owner = random_key();
+        let mut map = map_with(owner, &[0])?;
+        check_limits(&map, LIMITS)?;
+
+        let actions = MapUnseqEntryActions::new().update(vec![0], vec![0; 5]);
+        map.mutate_entries(actions.into(), &owner)?;
+        match check_limits(&map, LIMITS) {
+            Err(Error::NetworkData(DtError::ExceededSize)) => Ok(()),
+            other => Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+    }
+
+    #[test]
+    fn map_is_limited_on_exact_serialised_size() -> Result<()> {
+        let map = map_with(random_key(), &[0, 1, 2])?;
+        let size = bincode::serialized_size(&map)?;
+        check_limits(
+            &map,
+            MapLimits {
+                max_size: size,
+                ..LIMITS
+            },
+        )?;
+        match check_limits(
+            &map,
+            MapLimits {
+                max_size: size - 1,
+                ..LIMITS
+            },
+        ) {
+            Err(Error::NetworkData(DtError::ExceededSize)) => Ok(()),
+            other => Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+    }
+
+    #[test]
+    fn mixed_batch_is_judged_by_its_net_effect() -> Result<()> {
+        let owner = random_key();
+        let full = map_with(owner, &[0, 1, 2])?;
+
+        // Inserting as many entries as deleted keeps the Map at the limit.
+        let mut map = full.clone();
+        let actions = MapUnseqEntryActions::new()
+            .del(vec![0])
+            .del(vec![1])
+            .ins(vec![3], vec![])
+            .ins(vec![4], vec![]);
+        map.mutate_entries(actions.into(), &owner)?;
+        check_limits(&map, LIMITS)?;
+
+        // One insert more than deleted is over it.
+        let mut map = full;
+        let actions = MapUnseqEntryActions::new()
+            .del(vec![0])
+            .ins(vec![3], vec![])
+            .ins(vec![4], vec![]);
+        map.mutate_entries(actions.into(), &owner)?;
+        match check_limits(&map, LIMITS) {
+            Err(Error::TooManyEntries) => Ok(()),
+            other => Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+    }
+
+    #[tokio::test]
+    async fn failing_batch_leaves_stored_map_untouched() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let mut storage = MapStorage::new(root.path(), UsedSpace::new(u64::MAX), 0).await?;
+        let owner = random_key();
+        let origin = EndUser::AllClients(owner);
+
+        let mut map = Map::Seq(SeqMap::new(XorName::random(), 0, owner));
+        let actions = MapSeqEntryActions::new()
+            .ins(vec![0], vec![0], 0)
+            .ins(vec![1], vec![1], 0);
+        map.mutate_entries(actions.into(), &owner)?;
+        let _ = storage.create(&map, MessageId::new(), origin).await?;
+
+        // All but the last action are valid.
+        let actions = MapSeqEntryActions::new()
+            .update(vec![0], vec![10], 1)
+            .ins(vec![2], vec![2], 0)
+            .update(vec![3], vec![3], 1);
+        match storage
+            .edit_entries(*map.address(), actions.into(), MessageId::new(), origin)
+            .await?
+        {
+            NodeDuty::Send(OutgoingMsg {
+                msg:
+                    Message::CmdError {
+                        error: CmdError::Data(ErrorMessage::NoSuchEntry),
+                        ..
+                    },
+                ..
+            }) => (),
+            other => return Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+        assert_eq!(storage.chunks.get(map.address())?, map);
+        Ok(())
+    }
+
+    fn cmd_error(duty: NodeDuty) -> Option<ErrorMessage> {
+        match duty {
+            NodeDuty::Send(OutgoingMsg {
+                msg:
+                    Message::CmdError {
+                        error: CmdError::Data(error),
+                        ..
+                    },
+                ..
+            }) => Some(error),
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn permissions_are_managed_by_owner_and_allowed_keys_only() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let mut storage = MapStorage::new(root.path(), UsedSpace::new(u64::MAX), 0).await?;
+        let owner = random_key();
+        let manager = random_key();
+        let app = random_key();
+        let map = Map::Unseq(UnseqMap::new(XorName::random(), 0, owner));
+        let address = *map.address();
+        let _ = storage
+            .create(&map, MessageId::new(), EndUser::AllClients(owner))
+            .await?;
+
+        // The owner is allowed without being granted any permission.
+        let manage = MapPermissionSet::new().allow(MapAction::ManagePermissions);
+        let duty = storage
+            .set_user_permissions(
+                address,
+                manager,
+                &manage,
+                1,
+                MessageId::new(),
+                EndUser::AllClients(owner),
+            )
+            .await?;
+        assert_eq!(cmd_error(duty), None);
+
+        let read = MapPermissionSet::new().allow(MapAction::Read);
+        let duty = storage
+            .set_user_permissions(
+                address,
+                app,
+                &read,
+                2,
+                MessageId::new(),
+                EndUser::AllClients(manager),
+            )
+            .await?;
+        assert_eq!(cmd_error(duty), None);
+
+        // An app key without ManagePermissions can neither grant nor revoke.
+        let duty = storage
+            .set_user_permissions(
+                address,
+                app,
+                &manage,
+                3,
+                MessageId::new(),
+                EndUser::AllClients(app),
+            )
+            .await?;
+        assert_eq!(cmd_error(duty), Some(ErrorMessage::AccessDenied(app)));
+        let duty = storage
+            .delete_user_permissions(
+                address,
+                manager,
+                3,
+                MessageId::new(),
+                EndUser::AllClients(app),
+            )
+            .await?;
+        assert_eq!(cmd_error(duty), Some(ErrorMessage::AccessDenied(app)));
+
+        let stored = storage.chunks.get(&address)?;
+        assert_eq!(stored.version(), 2);
+        assert_eq!(stored.user_permissions(&app)?, &read);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn ownership_is_transferred_by_owner_only() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let mut storage = MapStorage::new(root.path(), UsedSpace::new(u64::MAX), 0).await?;
+        let owner = random_key();
+        let manager = random_key();
+        let map = Map::Unseq(UnseqMap::new(XorName::random(), 0, owner));
+        let address = *map.address();
+        let _ = storage
+            .create(&map, MessageId::new(), EndUser::AllClients(owner))
+            .await?;
+        let manage = MapPermissionSet::new().allow(MapAction::ManagePermissions);
+        let _ = storage
+            .set_user_permissions(
+                address,
+                manager,
+                &manage,
+                1,
+                MessageId::new(),
+                EndUser::AllClients(owner),
+            )
+            .await?;
+
+        // Managing permissions does not extend to ownership.
+        let duty = storage
+            .change_owner(
+                address,
+                manager,
+                2,
+                MessageId::new(),
+                EndUser::AllClients(manager),
+            )
+            .await?;
+        assert_eq!(cmd_error(duty), Some(ErrorMessage::AccessDenied(manager)));
+        assert_eq!(storage.chunks.get(&address)?.owner(), owner);
+
+        let duty = storage
+            .change_owner(
+                address,
+                manager,
+                2,
+                MessageId::new(),
+                EndUser::AllClients(owner),
+            )
+            .await?;
+        assert_eq!(cmd_error(duty), None);
+        assert_eq!(storage.chunks.get(&address)?.owner(), manager);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn shell_response_is_bounded_whatever_the_entries() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let mut storage = MapStorage::new(root.path(), UsedSpace::new(u64::MAX), 0).await?;
+        let owner = random_key();
+        let origin = EndUser::AllClients(owner);
+
+        let mut map = Map::Seq(SeqMap::new(XorName::random(), 0, owner));
+        let actions = (0..100_u8).fold(MapSeqEntryActions::new(), |actions, key| {
+            actions.ins(vec![key], vec![key; 10 * 1024], 0)
+        });
+        map.mutate_entries(actions.into(), &owner)?;
+        let _ = storage.create(&map, MessageId::new(), origin).await?;
+
+        match storage
+            .get_shell(*map.address(), MessageId::new(), origin)
+            .await?
+        {
+            NodeDuty::Send(OutgoingMsg { msg, .. }) => {
+                assert!(bincode::serialized_size(&msg)? < 1024);
+                match msg {
+                    Message::QueryResponse {
+                        response: QueryResponse::GetMapShell(Ok(Map::Seq(shell))),
+                        ..
+                    } => {
+                        assert!(shell.entries().is_empty());
+                        assert_eq!(shell.owner(), &owner);
+                    }
+                    other => return Err(Error::Logic(format!("Unexpected: {:?}", other))),
+                }
+            }
+            other => return Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+
+        let missing = SeqMap::new(XorName::random(), 0, owner);
+        match storage
+            .get_shell(*missing.address(), MessageId::new(), origin)
+            .await?
+        {
+            NodeDuty::Send(OutgoingMsg {
+                msg:
+                    Message::QueryResponse {
+                        response: QueryResponse::GetMapShell(Err(ErrorMessage::NoSuchData)),
+                        ..
+                    },
+                ..
+            }) => Ok(()),
+            other => Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+    }
+
+    #[tokio::test]
+    async fn maps_per_owner_are_limited_across_restarts() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let owner = random_key();
+        let origin = EndUser::AllClients(owner);
+        let maps: Vec<_> = (0..3)
+            .map(|_| Map::Unseq(UnseqMap::new(XorName::random(), 0, owner)))
+            .collect();
+
+        let mut storage = MapStorage::new(root.path(), UsedSpace::new(u64::MAX), 2).await?;
+        for map in &maps[..2] {
+            let duty = storage.create(map, MessageId::new(), origin).await?;
+            assert_eq!(cmd_error(duty), None);
+        }
+        let duty = storage.create(&maps[2], MessageId::new(), origin).await?;
+        assert_eq!(cmd_error(duty), Some(ErrorMessage::TooManyEntries));
+        // Others are not affected.
+        let other = Map::Unseq(UnseqMap::new(XorName::random(), 0, random_key()));
+        let duty = storage
+            .create(&other, MessageId::new(), EndUser::AllClients(other.owner()))
+            .await?;
+        assert_eq!(cmd_error(duty), None);
+
+        // Deleting a Map frees quota.
+        let duty = storage
+            .delete(*maps[0].address(), MessageId::new(), origin)
+            .await?;
+        assert_eq!(cmd_error(duty), None);
+        let duty = storage.create(&maps[2], MessageId::new(), origin).await?;
+        assert_eq!(cmd_error(duty), None);
+        drop(storage);
+
+        let mut storage = MapStorage::new(root.path(), UsedSpace::new(u64::MAX), 2).await?;
+        assert_eq!(storage.map_count(&owner)?, 2);
+        let duty = storage.create(&maps[0], MessageId::new(), origin).await?;
+        assert_eq!(cmd_error(duty), Some(ErrorMessage::TooManyEntries));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn deleting_map_releases_its_space_to_owner_only() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let used_space = UsedSpace::new(u64::MAX);
+        let mut storage = MapStorage::new(root.path(), used_space.clone(), 0).await?;
+        let owner = random_key();
+        let other = random_key();
+        let before = used_space.total().await;
+
+        let map = map_with(owner, &[0, 1, 2])?;
+        let _ = storage
+            .create(&map, MessageId::new(), EndUser::AllClients(owner))
+            .await?;
+        let stored = used_space.total().await;
+        assert!(stored > before);
+
+        let duty = storage
+            .delete(*map.address(), MessageId::new(), EndUser::AllClients(other))
+            .await?;
+        assert_eq!(cmd_error(duty), Some(ErrorMessage::AccessDenied(other)));
+        assert_eq!(used_space.total().await, stored);
+
+        let duty = storage
+            .delete(*map.address(), MessageId::new(), EndUser::AllClients(owner))
+            .await?;
+        assert_eq!(cmd_error(duty), None);
+        assert_eq!(used_space.total().await, before);
+        Ok(())
+    }
+
+    async fn get_value(
+        storage: &MapStorage,
+        address: MapAddress,
+        key: &[u8],
+        origin: EndUser,
+    ) -> Result<std::result::Result<MapValue, ErrorMessage>> {
+        match storage
+            .get_value(address, key, MessageId::new(), origin)
+            .await?
+        {
+            NodeDuty::Send(OutgoingMsg {
+                msg:
+                    Message::QueryResponse {
+                        response: QueryResponse::GetMapValue(result),
+                        ..
+                    },
+                ..
+            }) => Ok(result),
+            other => Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+    }
+
+    #[tokio::test]
+    async fn missing_value_is_told_apart_from_missing_map() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let mut storage = MapStorage::new(root.path(), UsedSpace::new(u64::MAX), 0).await?;
+        let owner = random_key();
+        let origin = EndUser::AllClients(owner);
+
+        let name = XorName::random();
+        let mut seq = Map::Seq(SeqMap::new(name, 0, owner));
+        let actions = MapSeqEntryActions::new().ins(vec![0], vec![1], 0);
+        seq.mutate_entries(actions.into(), &owner)?;
+        let _ = storage.create(&seq, MessageId::new(), origin).await?;
+        let unseq = map_with(owner, &[0])?;
+        let _ = storage.create(&unseq, MessageId::new(), origin).await?;
+
+        assert_eq!(
+            get_value(&storage, *seq.address(), &[0], origin).await?,
+            Ok(MapValue::Seq(MapSeqValue {
+                data: vec![1],
+                version: 0,
+            }))
+        );
+        assert_eq!(
+            get_value(&storage, *unseq.address(), &[0], origin).await?,
+            Ok(MapValue::Unseq(vec![0; 4]))
+        );
+
+        // Missing key of an existing Map.
+        for address in &[*seq.address(), *unseq.address()] {
+            assert_eq!(
+                get_value(&storage, *address, &[1], origin).await?,
+                Err(ErrorMessage::NoSuchEntry)
+            );
+        }
+
+        // Missing Map, including one of the other kind at the same name and tag.
+        let missing = MapAddress::Seq {
+            name: XorName::random(),
+            tag: 0,
+        };
+        let other_kind = MapAddress::Unseq { name, tag: 0 };
+        for address in &[missing, other_kind] {
+            assert_eq!(
+                get_value(&storage, *address, &[0], origin).await?,
+                Err(ErrorMessage::NoSuchData)
+            );
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn only_one_of_two_updates_from_same_version_succeeds() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let mut storage = MapStorage::new(root.path(), UsedSpace::new(u64::MAX), 0).await?;
+        let owner = random_key();
+        let origin = EndUser::AllClients(owner);
+        let mut map = Map::Seq(SeqMap::new(XorName::random(), 0, owner));
+        let actions = MapSeqEntryActions::new().ins(vec![0], vec![0], 0);
+        map.mutate_entries(actions.into(), &owner)?;
+        let address = *map.address();
+        let _ = storage.create(&map, MessageId::new(), origin).await?;
+
+        // Both expect the entry at version 0, e.g. sent by two clients at once.
+        let mut errors = vec![];
+        for value in 1..3 {
+            let actions = MapSeqEntryActions::new().update(vec![0], vec![value], 1);
+            let duty = storage
+                .edit_entries(address, actions.into(), MessageId::new(), origin)
+                .await?;
+            errors.push(cmd_error(duty));
+        }
+        assert_eq!(errors, vec![None, Some(ErrorMessage::InvalidSuccessor(1))]);
+        assert_eq!(
+            get_value(&storage, address, &[0], origin).await?,
+            Ok(MapValue::Seq(MapSeqValue {
+                data: vec![1],
+                version: 1,
+            }))
+        );
+        Ok(())
+    }
+
+    async fn list_permissions(
+        storage: &MapStorage,
+        address: MapAddress,
+        user: Option<PublicKey>,
+        origin: EndUser,
+    ) -> Result<QueryResponse> {
+        let duty = match user {
+            Some(user) => {
+                storage
+                    .list_user_permissions(address, user, MessageId::new(), origin)
+                    .await?
+            }
+            None => {
+                storage
+                    .list_permissions(address, MessageId::new(), origin)
+                    .await?
+            }
+        };
+        match duty {
+            NodeDuty::Send(OutgoingMsg {
+                msg: Message::QueryResponse { response, .. },
+                ..
+            }) => Ok(response),
+            other => Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+    }
+
+    #[tokio::test]
+    async fn permissions_are_listed_alike_for_both_kinds_of_map() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let mut storage = MapStorage::new(root.path(), UsedSpace::new(u64::MAX), 0).await?;
+        let owner = random_key();
+        let origin = EndUser::AllClients(owner);
+        let stranger = random_key();
+        let read = MapPermissionSet::new().allow(MapAction::Read);
+
+        for &user_count in &[0, 1, 5] {
+            let users: Vec<_> = (0..user_count).map(|_| random_key()).collect();
+            let name = XorName::random();
+            for mut map in [
+                Map::Seq(SeqMap::new(name, 0, owner)),
+                Map::Unseq(UnseqMap::new(name, 0, owner)),
+            ] {
+                for (version, user) in users.iter().enumerate() {
+                    map.set_user_permissions(*user, read.clone(), version as u64 + 1)?;
+                }
+                let address = *map.address();
+                let _ = storage.create(&map, MessageId::new(), origin).await?;
+
+                let expected = users.iter().map(|user| (*user, read.clone())).collect();
+                assert_eq!(
+                    list_permissions(&storage, address, None, origin).await?,
+                    QueryResponse::ListMapPermissions(Ok(expected))
+                );
+                for user in &users {
+                    assert_eq!(
+                        list_permissions(&storage, address, Some(*user), origin).await?,
+                        QueryResponse::ListMapUserPermissions(Ok(read.clone()))
+                    );
+                }
+                assert_eq!(
+                    list_permissions(&storage, address, Some(stranger), origin).await?,
+                    QueryResponse::ListMapUserPermissions(Err(ErrorMessage::NoSuchKey))
+                );
+            }
+        }
+        Ok(())
+    }
+}
@@ -8,31 +8,55 @@
 
 pub mod adult_reader;
 mod blob_register;
+mod client_limits;
 mod elder_stores;
 mod map_storage;
 mod reading;
+mod recent_cmds;
+mod replication_progress;
+mod replication_schedule;
 mod sequence_storage;
+mod sequence_tombstone;
 mod writing;
 
 use self::adult_reader::AdultReader;
 use super::node_ops::NodeDuty;
 use crate::{
-    capacity::ChunkHolderDbs, chunk_store::UsedSpace, node_ops::NodeDuties, Network, Result,
+    capacity::ChunkHolderDbs,
+    chunk_store::UsedSpace,
+    node_ops::{NodeDuties, OutgoingMsg},
+    Network, NodeInfo, Result,
 };
+pub use replication_progress::ReplicationStatus;
+pub use sequence_tombstone::Tombstone;
+
 use blob_register::BlobRegister;
+use client_limits::ClientLimits;
 use elder_stores::ElderStores;
+use log::debug;
 use map_storage::MapStorage;
+use recent_cmds::RecentCmds;
+use replication_schedule::ReplicationScheduleStats;
 use sequence_storage::SequenceStorage;
+use sn_data_types::{BlobAddress, MapAddress, PublicKey, Signature};
 use sn_messaging::{
-    client::{DataCmd, DataQuery},
-    EndUser, MessageId,
+    client::{DataCmd, DataQuery, Error as ErrorMessage, Message},
+    Aggregation, DstLocation, EndUser, MessageId,
 };
 use std::{
+    collections::BTreeSet,
     fmt::{self, Display, Formatter},
-    path::Path,
+    time::{Duration, Instant},
 };
 use xor_name::XorName;
 
+// Time during which a cmd delivered again is answered with its first response.
+const RECENT_CMDS_TTL: Duration = Duration::from_secs(5 * 60);
+// Max number of cmds whose responses are kept for replay.
+const RECENT_CMDS_CAPACITY: usize = 10_000;
+// Window over which the requests of each client are limited.
+const CLIENT_LIMITS_WINDOW: Duration = Duration::from_secs(1);
+
 /// This module is called `Metadata`
 /// as a preparation for the responsibilities
 /// it will have eventually, after `Data Hierarchy Refinement`
@@ -41,33 +65,124 @@ use xor_name::XorName;
 /// all underlying data being chunks stored at `Adults`.
 pub struct Metadata {
     elder_stores: ElderStores,
+    recent_cmds: RecentCmds,
+    client_limits: ClientLimits,
 }
 
 impl Metadata {
     pub async fn new(
-        path: &Path,
+        node_info: &NodeInfo,
         used_space: &UsedSpace,
         dbs: ChunkHolderDbs,
         reader: AdultReader,
     ) -> Result<Self> {
-        let blob_register = BlobRegister::new(dbs, reader);
-        let map_storage = MapStorage::new(path, used_space.clone()).await?;
-        let sequence_storage = SequenceStorage::new(path, used_space.clone()).await?;
+        let path = node_info.metadata_path();
+        let blob_register = BlobRegister::new(
+            dbs,
+            reader,
+            node_info.chunk_replication_rate,
+            node_info.max_chunk_replication_failures,
+        )
+        .await?;
+        let map_storage =
+            MapStorage::new(&path, used_space.clone(), node_info.max_maps_per_owner).await?;
+        let sequence_storage = SequenceStorage::new(
+            &path,
+            used_space.clone(),
+            node_info.max_sequence_range_entries,
+            node_info.max_sequence_range_size,
+        )
+        .await?;
         let elder_stores = ElderStores::new(blob_register, map_storage, sequence_storage);
-        Ok(Self { elder_stores })
+        Ok(Self {
+            elder_stores,
+            recent_cmds: RecentCmds::new(RECENT_CMDS_TTL, RECENT_CMDS_CAPACITY),
+            client_limits: ClientLimits::new(
+                CLIENT_LIMITS_WINDOW,
+                node_info.max_client_requests_per_sec,
+                Instant::now(),
+            ),
+        })
+    }
+
+    /// Sets the rate limits, e.g. as changed in the config of the running node.
+    pub fn set_rate_limits(&mut self, replication_rate: usize, max_client_requests_per_sec: usize) {
+        self.elder_stores
+            .blob_register_mut()
+            .set_replication_rate(replication_rate);
+        self.client_limits
+            .set_max_per_window(max_client_requests_per_sec);
     }
 
-    pub async fn read(&self, query: DataQuery, id: MessageId, origin: EndUser) -> Result<NodeDuty> {
+    /// Processes the query, unless the requester is over its limit of requests.
+    pub async fn read(
+        &mut self,
+        query: DataQuery,
+        id: MessageId,
+        origin: EndUser,
+    ) -> Result<NodeDuty> {
+        if !self.client_limits.admit(*origin.id(), Instant::now()) {
+            crate::trace_msg!("rejected", id, origin.id(), "reason=too_many_requests");
+            return Ok(reject(
+                Message::QueryResponse {
+                    response: query.error(ErrorMessage::TooManyEntries),
+                    id: MessageId::in_response_to(&id),
+                    correlation_id: id,
+                    target_section_pk: None,
+                },
+                origin,
+            ));
+        }
+        crate::trace_msg!("processing", id, origin.id());
         reading::get_result(query, id, origin, &self.elder_stores).await
     }
 
+    /// Processes the cmd, unless delivered already, in which case the first
+    /// response is replayed, or the requester is over its limit of requests.
     pub async fn write(
         &mut self,
         cmd: DataCmd,
         id: MessageId,
         origin: EndUser,
     ) -> Result<NodeDuty> {
-        writing::get_result(cmd, id, origin, &mut self.elder_stores).await
+        let requester = *origin.id();
+        if let Some(duty) = self.recent_cmds.replay(requester, id, Instant::now()) {
+            crate::trace_msg!("replayed", id, requester);
+            return Ok(duty);
+        }
+        if !self.client_limits.admit(requester, Instant::now()) {
+            crate::trace_msg!("rejected", id, requester, "reason=too_many_requests");
+            // Not recorded, for the cmd to be processed if sent again later.
+            return Ok(reject(
+                Message::CmdError {
+                    error: cmd.error(ErrorMessage::TooManyEntries),
+                    id: MessageId::in_response_to(&id),
+                    correlation_id: id,
+                    target_section_pk: None,
+                },
+                origin,
+            ));
+        }
+        crate::trace_msg!("processing", id, requester);
+        let duty = writing::get_result(cmd, id, origin, &mut self.elder_stores).await?;
+        self.recent_cmds
+            .record(requester, id, &duty, Instant::now());
+        Ok(duty)
+    }
+
+    /// Transfers ownership of a Map, if requested by its owner.
+    pub async fn change_map_owner(
+        &mut self,
+        address: MapAddress,
+        new_owner: PublicKey,
+        version: u64,
+        id: MessageId,
+        origin: EndUser,
+    ) -> Result<NodeDuty> {
+        self.elder_stores
+            .map_storage_mut()
+            .change_owner(address, new_owner, version, id, origin)
+            .await
     }
 
     // This should be called whenever a node leaves the section. It fetches the list of data that was
@@ -79,6 +194,74 @@ impl Metadata {
             .replicate_chunks(node)
             .await
     }
+
+    // This should be called whenever an Adult joins the section. It replicates to the new Adult
+    // the chunks it is now closer to than one of their holders, in batches.
+    pub async fn trigger_chunk_redistribution(&mut self, node: XorName) -> Result<NodeDuties> {
+        self.elder_stores
+            .blob_register_mut()
+            .redistribute_chunks(node)
+            .await
+    }
+
+    /// Counts `holder` among the holders of a chunk replicated to it, as reported by it.
+    pub async fn record_chunk_holder(
+        &mut self,
+        address: BlobAddress,
+        holder: XorName,
+        proof: Signature,
+    ) -> Result<()> {
+        self.elder_stores
+            .blob_register_mut()
+            .record_chunk_holder(address, holder, proof)
+            .await
+    }
+
+    /// Counts a failure reported by an Adult to replicate a chunk to it.
+    pub async fn record_replication_failure(
+        &mut self,
+        address: BlobAddress,
+        holder: XorName,
+        error: ErrorMessage,
+    ) -> Result<NodeDuties> {
+        self.elder_stores
+            .blob_register_mut()
+            .record_replication_failure(address, holder, error)
+            .await
+    }
+
+    /// The chunks deemed lost, as they could no longer be replicated.
+    pub async fn lost_chunks(&self) -> Result<BTreeSet<BlobAddress>> {
+        self.elder_stores.blob_register().lost_chunks().await
+    }
+
+    /// Counts of the chunk replications scheduled after nodes left, for diagnostics.
+    pub fn replication_stats(&self) -> ReplicationScheduleStats {
+        self.elder_stores.blob_register().replication_stats()
+    }
+
+    /// How far along the replication of chunks after churn is.
+    pub fn replication_status(&self) -> ReplicationStatus {
+        self.elder_stores.blob_register().replication_status()
+    }
+
+    /// Issues the next batch of the chunk replications scheduled after nodes left.
+    pub async fn continue_chunk_replication(&mut self) -> Result<NodeDuties> {
+        self.elder_stores
+            .blob_register_mut()
+            .replicate_next_batch()
+            .await
+    }
+}
+
+// The response rejecting a request of the client, as it sent too many.
+fn reject(msg: Message, origin: EndUser) -> NodeDuty {
+    NodeDuty::Send(OutgoingMsg {
+        msg,
+        section_source: false, // strictly this is not correct, but we don't expect responses to an error..
+        dst: DstLocation::EndUser(origin),
+        aggregation: Aggregation::None,
+    })
 }
 
 impl Display for Metadata {
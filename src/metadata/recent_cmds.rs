@@ -0,0 +1,160 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::node_ops::{NodeDuty, OutgoingMsg};
+use sn_data_types::PublicKey;
+use sn_messaging::{client::Message, MessageId};
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+/// The responses to the latest cmds of each requester, for the cmds delivered more than
+/// once to be answered as the first time, instead of being processed again. Each response
+/// is kept until `ttl` has passed, and only the latest `capacity` ones. Kept in memory only.
+pub(super) struct RecentCmds {
+    ttl: Duration,
+    capacity: usize,
+    // The response to each cmd, None if it had none for the requester.
+    responses: HashMap<(PublicKey, MessageId), Option<OutgoingMsg>>,
+    // The cmds, oldest first, with the time they were processed.
+    order: VecDeque<(Instant, PublicKey, MessageId)>,
+}
+
+impl RecentCmds {
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            ttl,
+            capacity,
+            responses: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// The response to replay for the cmd, if it was processed already.
+    pub fn replay(
+        &mut self,
+        requester: PublicKey,
+        id: MessageId,
+        now: Instant,
+    ) -> Option<NodeDuty> {
+        self.expire(now);
+        let response = self.responses.get(&(requester, id))?;
+        Some(match response {
+            Some(msg) => NodeDuty::Send(msg.clone()),
+            None => NodeDuty::NoOp,
+        })
+    }
+
+    /// Records the outcome of processing the cmd. Only errors are responses to the requester:
+    /// other duties, such as storing chunks at Adults, are not to be repeated.
+    pub fn record(&mut self, requester: PublicKey, id: MessageId, duty: &NodeDuty, now: Instant) {
+        let response = match duty {
+            NodeDuty::Send(msg) if matches!(msg.msg, Message::CmdError { .. }) => Some(msg.clone()),
+            _ => None,
+        };
+        if self.responses.insert((requester, id), response).is_none() {
+            self.order.push_back((now, requester, id));
+        }
+        while self.order.len() > self.capacity {
+            self.evict_oldest();
+        }
+    }
+
+    fn expire(&mut self, now: Instant) {
+        while let Some((time, _, _)) = self.order.front() {
+            if now.saturating_duration_since(*time) < self.ttl {
+                break;
+            }
+            self.evict_oldest();
+        }
+    }
+
+    fn evict_oldest(&mut self) {
+        if let Some((_, requester, id)) = self.order.pop_front() {
+            let _ = self.responses.remove(&(requester, id));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RecentCmds;
+    use crate::node_ops::{NodeDuty, OutgoingMsg};
+    use sn_data_types::{Keypair, PublicKey};
+    use sn_messaging::{
+        client::{CmdError, Error as ErrorMessage, Message},
+        Aggregation, DstLocation, EndUser, MessageId,
+    };
+    use std::time::{Duration, Instant};
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    fn requester() -> PublicKey {
+        Keypair::new_ed25519(&mut rand::thread_rng()).public_key()
+    }
+
+    fn cmd_error(id: MessageId, requester: PublicKey) -> NodeDuty {
+        NodeDuty::Send(OutgoingMsg {
+            msg: Message::CmdError {
+                error: CmdError::Data(ErrorMessage::InvalidSuccessor(1)),
+                id: MessageId::in_response_to(&id),
+                correlation_id: id,
+                target_section_pk: None,
+            },
+            section_source: false,
+            dst: DstLocation::EndUser(EndUser::AllClients(requester)),
+            aggregation: Aggregation::None,
+        })
+    }
+
+    #[test]
+    fn duplicate_cmd_is_answered_as_the_first() {
+        let now = Instant::now();
+        let mut recent = RecentCmds::new(TTL, 10);
+        let (requester, other) = (requester(), requester());
+        let (succeeded, failed) = (MessageId::new(), MessageId::new());
+        assert!(recent.replay(requester, succeeded, now).is_none());
+        recent.record(requester, succeeded, &NodeDuty::NoOp, now);
+        let error = cmd_error(failed, requester);
+        recent.record(requester, failed, &error, now);
+
+        assert!(matches!(
+            recent.replay(requester, succeeded, now),
+            Some(NodeDuty::NoOp)
+        ));
+        let sent = |duty| match duty {
+            Some(NodeDuty::Send(OutgoingMsg { msg, dst, .. })) => Some((msg, dst)),
+            _ => None,
+        };
+        let first = sent(Some(error));
+        assert!(first.is_some());
+        assert_eq!(sent(recent.replay(requester, failed, now)), first);
+        // The same id from someone else is another cmd.
+        assert!(recent.replay(other, failed, now).is_none());
+    }
+
+    #[test]
+    fn cmds_are_forgotten_when_expired_or_beyond_capacity() {
+        let now = Instant::now();
+        let mut recent = RecentCmds::new(TTL, 2);
+        let requester = requester();
+        let ids: Vec<_> = (0..3).map(|_| MessageId::new()).collect();
+        for (i, id) in ids.iter().enumerate() {
+            recent.record(requester, *id, &NodeDuty::NoOp, now + TTL / 4 * i as u32);
+        }
+        assert!(recent.replay(requester, ids[0], now).is_none());
+        assert!(recent.replay(requester, ids[1], now + TTL / 2).is_some());
+        assert!(recent
+            .replay(requester, ids[1], now + TTL * 5 / 4)
+            .is_none());
+        assert!(recent
+            .replay(requester, ids[2], now + TTL * 5 / 4)
+            .is_some());
+    }
+}
@@ -0,0 +1,110 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use sn_data_types::BlobAddress;
+use std::collections::BTreeSet;
+use xor_name::XorName;
+
+/// How far along the replication of chunks after churn is, as seen by an Elder.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ReplicationStatus {
+    /// Chunks scheduled for replication, whose cmds are yet to be sent.
+    pub pending: usize,
+    /// Replications to Adults not yet reported completed or failed.
+    pub in_flight: usize,
+    /// Replications reported completed since start.
+    pub completed: u64,
+    /// Replications reported failed since start, including those refused by the Adult,
+    /// timed out, or whose Adult left before reporting.
+    pub failed: u64,
+}
+
+/// The replications of chunks issued to Adults, awaiting their report.
+/// Each Adult reports a replication once, however many cmds it was sent for it.
+#[derive(Default)]
+pub(super) struct ReplicationProgress {
+    in_flight: BTreeSet<(BlobAddress, XorName)>,
+    completed: u64,
+    failed: u64,
+}
+
+impl ReplicationProgress {
+    /// Records the replication of the chunk to `holder` as issued.
+    pub fn issued(&mut self, address: BlobAddress, holder: XorName) {
+        let _ = self.in_flight.insert((address, holder));
+    }
+
+    /// Counts the replication of the chunk to `holder` as completed,
+    /// unless not in flight, e.g. as it was reported already.
+    pub fn completed(&mut self, address: BlobAddress, holder: XorName) {
+        if self.in_flight.remove(&(address, holder)) {
+            self.completed += 1;
+        }
+    }
+
+    /// Counts the replication of the chunk to `holder` as failed,
+    /// unless not in flight, e.g. as it was reported already.
+    pub fn failed(&mut self, address: BlobAddress, holder: XorName) {
+        if self.in_flight.remove(&(address, holder)) {
+            self.failed += 1;
+        }
+    }
+
+    /// Counts the replications to `holder` as failed, as it left before reporting them.
+    pub fn holder_left(&mut self, holder: XorName) {
+        let count = self.in_flight.len();
+        self.in_flight.retain(|(_, name)| *name != holder);
+        self.failed += (count - self.in_flight.len()) as u64;
+    }
+
+    pub fn status(&self, pending: usize) -> ReplicationStatus {
+        ReplicationStatus {
+            pending,
+            in_flight: self.in_flight.len(),
+            completed: self.completed,
+            failed: self.failed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ReplicationProgress, ReplicationStatus};
+    use sn_data_types::PublicBlob;
+    use xor_name::XorName;
+
+    #[test]
+    fn each_replication_is_counted_once_whatever_its_outcome() {
+        let mut progress = ReplicationProgress::default();
+        let address = *PublicBlob::new(vec![0]).address();
+        let holders: Vec<_> = (0..3).map(|_| XorName::random()).collect();
+        for holder in &holders {
+            progress.issued(address, *holder);
+        }
+        // Issued again, e.g. after being refused, is still a single replication.
+        progress.issued(address, holders[0]);
+
+        progress.completed(address, holders[0]);
+        // A coalesced replication is reported once per cmd.
+        progress.completed(address, holders[0]);
+        progress.failed(address, holders[1]);
+        progress.holder_left(holders[2]);
+        // Reported after leaving.
+        progress.completed(address, holders[2]);
+
+        assert_eq!(
+            progress.status(5),
+            ReplicationStatus {
+                pending: 5,
+                in_flight: 0,
+                completed: 1,
+                failed: 2,
+            }
+        );
+    }
+}
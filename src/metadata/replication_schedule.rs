@@ -0,0 +1,155 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use sn_data_types::BlobAddress;
+use std::collections::{BTreeMap, BTreeSet};
+use xor_name::XorName;
+
+/// Counts of the chunk replications scheduled after losing holders, for diagnostics.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ReplicationScheduleStats {
+    /// Chunks scheduled for replication, each counted once while pending.
+    pub scheduled: u64,
+    /// Chunks whose replication has been issued.
+    pub sent: u64,
+    /// Chunks deleted before their replication was issued.
+    pub cancelled: u64,
+}
+
+/// Chunks to be replicated after losing holders, handed out one at a time,
+/// the chunks with the fewest remaining holders first.
+#[derive(Default)]
+pub(super) struct ReplicationSchedule {
+    // The remaining holders of each chunk.
+    holders: BTreeMap<BlobAddress, BTreeSet<XorName>>,
+    // The chunks ordered by their number of remaining holders.
+    by_risk: BTreeSet<(usize, BlobAddress)>,
+    stats: ReplicationScheduleStats,
+}
+
+impl ReplicationSchedule {
+    /// Schedules the replication of the chunk from its remaining holders.
+    /// A chunk already scheduled has its holders updated, e.g. as another one left.
+    pub fn schedule(&mut self, address: BlobAddress, holders: BTreeSet<XorName>) {
+        let count = holders.len();
+        match self.holders.insert(address, holders) {
+            Some(previous) => {
+                let _ = self.by_risk.remove(&(previous.len(), address));
+            }
+            None => self.stats.scheduled += 1,
+        }
+        let _ = self.by_risk.insert((count, address));
+    }
+
+    /// The chunk most at risk, with its remaining holders, without taking it.
+    pub fn peek(&self) -> Option<(BlobAddress, BTreeSet<XorName>)> {
+        let (_, address) = self.by_risk.iter().next()?;
+        let holders = self.holders.get(address)?;
+        Some((*address, holders.clone()))
+    }
+
+    /// Takes the chunk most at risk, its replication being issued.
+    pub fn pop(&mut self) -> Option<(BlobAddress, BTreeSet<XorName>)> {
+        let (count, address) = *self.by_risk.iter().next()?;
+        let _ = self.by_risk.remove(&(count, address));
+        let holders = self.holders.remove(&address)?;
+        self.stats.sent += 1;
+        Some((address, holders))
+    }
+
+    /// Drops the chunk from the schedule, e.g. as it was deleted.
+    pub fn cancel(&mut self, address: &BlobAddress) {
+        if let Some(holders) = self.holders.remove(address) {
+            let _ = self.by_risk.remove(&(holders.len(), *address));
+            self.stats.cancelled += 1;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.holders.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.holders.is_empty()
+    }
+
+    pub fn stats(&self) -> ReplicationScheduleStats {
+        self.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ReplicationSchedule, ReplicationScheduleStats};
+    use sn_data_types::{BlobAddress, PublicBlob};
+    use std::collections::BTreeSet;
+    use xor_name::XorName;
+
+    fn address(byte: u8) -> BlobAddress {
+        *PublicBlob::new(vec![byte]).address()
+    }
+
+    fn holders(count: usize) -> BTreeSet<XorName> {
+        (0..count).map(|_| XorName::random()).collect()
+    }
+
+    #[test]
+    fn chunks_with_fewest_holders_come_first() {
+        let mut schedule = ReplicationSchedule::default();
+        let counts = [3, 1, 2, 1, 3, 2];
+        for (byte, count) in counts.iter().enumerate() {
+            schedule.schedule(address(byte as u8), holders(*count));
+        }
+
+        let mut popped = vec![];
+        while let Some((_, holders)) = schedule.pop() {
+            popped.push(holders.len());
+        }
+        assert_eq!(popped, vec![1, 1, 2, 2, 3, 3]);
+        assert!(schedule.is_empty());
+    }
+
+    #[test]
+    fn rescheduled_chunk_is_deduplicated_and_reordered() {
+        let mut schedule = ReplicationSchedule::default();
+        schedule.schedule(address(0), holders(2));
+        schedule.schedule(address(1), holders(3));
+        // Another holder of the second chunk left.
+        schedule.schedule(address(1), holders(1));
+        assert_eq!(schedule.len(), 2);
+
+        assert_eq!(
+            schedule.peek().map(|(address, _)| address),
+            Some(address(1))
+        );
+        assert_eq!(schedule.pop().map(|(address, _)| address), Some(address(1)));
+        assert_eq!(schedule.pop().map(|(address, _)| address), Some(address(0)));
+        assert_eq!(schedule.stats().scheduled, 2);
+    }
+
+    #[test]
+    fn deleted_chunk_is_cancelled() {
+        let mut schedule = ReplicationSchedule::default();
+        schedule.schedule(address(0), holders(1));
+        schedule.schedule(address(1), holders(2));
+        schedule.cancel(&address(0));
+        // Not scheduled, so not counted.
+        schedule.cancel(&address(2));
+
+        assert_eq!(schedule.pop().map(|(address, _)| address), Some(address(1)));
+        assert!(schedule.pop().is_none());
+        assert_eq!(
+            schedule.stats(),
+            ReplicationScheduleStats {
+                scheduled: 2,
+                sent: 1,
+                cancelled: 1,
+            }
+        );
+    }
+}
@@ -6,16 +6,18 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use super::sequence_tombstone::Tombstone;
 use crate::{
     chunk_store::{SequenceChunkStore, UsedSpace},
     error::convert_to_error_message,
     node_ops::{NodeDuty, OutgoingMsg},
     Error, Network, Result,
 };
+use crdts::lseq::Op;
 use log::info;
 use sn_data_types::{
-    Error as DtError, Sequence, SequenceAction, SequenceAddress, SequenceEntry, SequenceIndex,
-    SequenceOp, SequenceUser,
+    Error as DtError, Sequence, SequenceAction, SequenceAddress, SequenceEntries, SequenceEntry,
+    SequenceIndex, SequenceOp, SequenceUser,
 };
 use sn_messaging::{
     client::{CmdError, Message, QueryResponse, SequenceRead, SequenceWrite},
@@ -23,19 +25,42 @@ use sn_messaging::{
 };
 
 use std::{
+    collections::BTreeSet,
     fmt::{self, Display, Formatter},
     path::Path,
 };
 
+/// A range of the entries of a Sequence, truncated if too large for a single response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SequenceRangePage {
+    /// The entries of the range, from its start.
+    pub entries: SequenceEntries,
+    /// The index, from the start of the Sequence, to continue from if the range was truncated.
+    pub next: Option<u64>,
+}
+
 /// Operations over the data type Sequence.
 pub(super) struct SequenceStorage {
     chunks: SequenceChunkStore,
+    // Max number of entries in a range response.
+    max_range_entries: usize,
+    // Max size of the entries in a range response, in bytes.
+    max_range_size: u64,
 }
 
 impl SequenceStorage {
-    pub(super) async fn new(path: &Path, used_space: UsedSpace) -> Result<Self> {
+    pub(super) async fn new(
+        path: &Path,
+        used_space: UsedSpace,
+        max_range_entries: usize,
+        max_range_size: u64,
+    ) -> Result<Self> {
         let chunks = SequenceChunkStore::new(path, used_space).await?;
-        Ok(Self { chunks })
+        Ok(Self {
+            chunks,
+            max_range_entries,
+            max_range_size,
+        })
     }
 
     pub(super) async fn read(
@@ -67,6 +92,8 @@ impl SequenceStorage {
     ) -> Result<NodeDuty> {
         use SequenceWrite::*;
         info!("Matching Sequence Write");
+        // The owner and permissions of a Sequence are set once, at creation: Sequences have
+        // no policy ops to version and apply, unlike Maps, so there are none to handle here.
         match write {
             New(data) => self.store(&data, msg_id, origin).await,
             Edit(operation) => {
@@ -83,7 +110,14 @@ impl SequenceStorage {
         msg_id: MessageId,
         origin: EndUser,
     ) -> Result<NodeDuty> {
-        let result = if self.chunks.has(data.address()) {
+        // The address is deserialised apart from the data, so a crafted Sequence could
+        // claim to be public while its data is private, or the other way around.
+        let result = if data.public_policy().is_ok() != data.is_public() {
+            Err(Error::InvalidOperation(
+                "Sequence address doesn't match its scope".to_string(),
+            ))
+        } else if self.chunks.has(data.address()) {
+            // Whoever owns it, an existing Sequence is never overwritten.
             Err(Error::DataExists)
         } else {
             self.chunks.put(&data).await
@@ -114,6 +148,8 @@ impl SequenceStorage {
         }))
     }
 
+    /// Every query goes through here with `SequenceAction::Read`, so that a private
+    /// Sequence is only read by its owner and the keys it permits to read.
     fn get_chunk(
         &self,
         address: SequenceAddress,
@@ -125,6 +161,8 @@ impl SequenceStorage {
         Ok(data)
     }
 
+    /// Deletes a private Sequence, by its owner only, releasing the space it takes.
+    /// Public Sequences are never deleted.
     async fn delete(
         &mut self,
         address: SequenceAddress,
@@ -132,24 +170,22 @@ impl SequenceStorage {
         origin: EndUser,
     ) -> Result<NodeDuty> {
         let result = match self.chunks.get(&address).and_then(|sequence| {
-            // TODO - Sequence::check_permission() doesn't support Delete yet in safe-nd
-            if sequence.address().is_public() {
-                return Err(Error::InvalidMessage(
-                    msg_id,
-                    "Sequence::check_permission() doesn't support Delete yet in safe-nd"
-                        .to_string(),
+            if sequence.is_public() {
+                return Err(Error::InvalidOperation(
+                    "Public Sequences cannot be deleted".to_string(),
                 ));
             }
-
             let public_key = *origin.id();
-            let policy = sequence.private_policy(Some(public_key))?;
-            if public_key != policy.owner {
+            if public_key != sequence.owner() {
                 Err(Error::InvalidOwners(public_key))
             } else {
                 Ok(())
             }
         }) {
-            Ok(()) => self.chunks.delete(&address).await,
+            Ok(()) => {
+                info!("Deleting Sequence");
+                self.chunks.delete(&address).await
+            }
             Err(error) => Err(error),
         };
 
@@ -163,14 +199,8 @@ impl SequenceStorage {
         msg_id: MessageId,
         origin: EndUser,
     ) -> Result<NodeDuty> {
-        let result = match self
-            .get_chunk(address, SequenceAction::Read, origin)
-            .and_then(|sequence| {
-                sequence
-                    .in_range(range.0, range.1, Some(*origin.id()))?
-                    .ok_or(Error::NetworkData(DtError::NoSuchEntry))
-            }) {
-            Ok(res) => Ok(res),
+        let result = match self.get_range_page(address, range, false, origin) {
+            Ok(page) => Ok(page.entries),
             Err(error) => Err(convert_to_error_message(error)?),
         };
         Ok(NodeDuty::Send(OutgoingMsg {
@@ -186,6 +216,49 @@ impl SequenceStorage {
         }))
     }
 
+    /// Gets the entries of the range, up to the max number and size of entries of a response.
+    /// A truncated range is to be continued from the index after its last entry.
+    /// The current view of the range skips the tombstones and the entries they remove,
+    /// so that its page may hold fewer entries, or none, before the index to continue from.
+    pub(super) fn get_range_page(
+        &self,
+        address: SequenceAddress,
+        range: (SequenceIndex, SequenceIndex),
+        current_view: bool,
+        origin: EndUser,
+    ) -> Result<SequenceRangePage> {
+        let requester = Some(*origin.id());
+        let sequence = self.get_chunk(address, SequenceAction::Read, origin)?;
+        let entries = sequence
+            .in_range(range.0, range.1, requester)?
+            .ok_or(Error::NetworkData(DtError::NoSuchEntry))?;
+        let start = match range.0 {
+            SequenceIndex::FromStart(index) => index,
+            SequenceIndex::FromEnd(index) => sequence.len(requester)? - index,
+        };
+        let mut page = truncate_range(entries, start, self.max_range_entries, self.max_range_size);
+        if current_view {
+            let all = (SequenceIndex::FromStart(0), SequenceIndex::FromEnd(0));
+            let removed: BTreeSet<_> = sequence
+                .in_range(all.0, all.1, requester)?
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|entry| Tombstone::from_entry(entry))
+                .map(|tombstone| tombstone.index)
+                .collect();
+            page.entries = page
+                .entries
+                .into_iter()
+                .zip(start..)
+                .filter(|(entry, index)| {
+                    !removed.contains(index) && Tombstone::from_entry(entry).is_none()
+                })
+                .map(|(entry, _)| entry)
+                .collect();
+        }
+        Ok(page)
+    }
+
     async fn get_last_entry(
         &self,
         address: SequenceAddress,
@@ -357,6 +430,7 @@ impl SequenceStorage {
                 SequenceAction::Append,
                 origin,
                 move |mut sequence| {
+                    check_tombstone(&sequence, &write_op)?;
                     sequence.apply_op(write_op)?;
                     Ok(sequence)
                 },
@@ -370,6 +444,10 @@ impl SequenceStorage {
         self.ok_or_error(result, msg_id, origin).await
     }
 
+    /// Edits are applied and persisted through `&mut self`, hence one at a time, so the
+    /// checks made by `write_fn` hold until the edited Sequence is persisted.
+    /// The edited Sequence replaces the stored one, so the space it's charged for is its
+    /// current size, and an edit growing it beyond the capacity left is rejected.
     async fn edit_chunk<F>(
         &mut self,
         address: SequenceAddress,
@@ -414,8 +492,553 @@ impl SequenceStorage {
     }
 }
 
+/// Checks that a tombstone appended by the op is appended to a private Sequence,
+/// after the entry it removes, which isn't a tombstone itself.
+fn check_tombstone(sequence: &Sequence, op: &SequenceOp<SequenceEntry>) -> Result<()> {
+    let tombstone = match &op.crdt_op {
+        Op::Insert { val, .. } => match Tombstone::from_entry(val) {
+            Some(tombstone) => tombstone,
+            None => return Ok(()),
+        },
+        Op::Delete { .. } => return Ok(()),
+    };
+    if sequence.is_public() {
+        return Err(Error::InvalidOperation(
+            "Entries of public Sequences cannot be removed".to_string(),
+        ));
+    }
+    let removed = sequence.get(
+        SequenceIndex::FromStart(tombstone.index),
+        Some(sequence.owner()),
+    )?;
+    match removed {
+        Some(entry) if Tombstone::from_entry(entry).is_none() => Ok(()),
+        _ => Err(Error::NetworkData(DtError::NoSuchEntry)),
+    }
+}
+
+/// Truncates the entries of a range starting at `start` to at most `max_entries` and
+/// `max_size` bytes, keeping at least one entry so that the range can be continued.
+fn truncate_range(
+    mut entries: SequenceEntries,
+    start: u64,
+    max_entries: usize,
+    max_size: u64,
+) -> SequenceRangePage {
+    let mut size = 0;
+    let count = entries
+        .iter()
+        .take(max_entries.max(1))
+        .take_while(|entry| {
+            size += entry.len() as u64;
+            size <= max_size
+        })
+        .count()
+        .max(1);
+    let next = if count < entries.len() {
+        entries.truncate(count);
+        Some(start + count as u64)
+    } else {
+        None
+    };
+    SequenceRangePage { entries, next }
+}
+
 impl Display for SequenceStorage {
     fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
         write!(formatter, "SequenceStorage")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{truncate_range, SequenceRangePage, SequenceStorage, Tombstone};
+    use crate::node_ops::{NodeDuty, OutgoingMsg};
+    use crate::{chunk_store::UsedSpace, Error, Result};
+    use sn_data_types::{
+        Keypair, PublicKey, Sequence, SequenceAddress, SequenceEntry, SequenceIndex, SequenceOp,
+        SequencePrivatePermissions, SequencePrivatePolicy, SequencePublicPermissions,
+        SequencePublicPolicy, SequenceUser,
+    };
+    use sn_messaging::{
+        client::{CmdError, Error as ErrorMessage, Message, QueryResponse},
+        EndUser, MessageId,
+    };
+    use std::collections::BTreeMap;
+    use tempdir::TempDir;
+    use xor_name::XorName;
+
+    // Ops appending the entries to the client's replica of the Sequence, signed by the client.
+    fn append_ops(
+        replica: &mut Sequence,
+        keypair: &Keypair,
+        entries: impl Iterator<Item = SequenceEntry>,
+    ) -> Result<Vec<SequenceOp<SequenceEntry>>> {
+        entries
+            .map(|entry| {
+                let mut op = replica.create_unsigned_append_op(entry)?;
+                op.signature = Some(keypair.sign(&bincode::serialize(&op.crdt_op)?));
+                replica.apply_op(op.clone())?;
+                Ok(op)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn range_is_truncated_by_count_and_size() {
+        let entries: Vec<_> = (0..10_u8).map(|i| vec![i; 10]).collect();
+        assert_eq!(
+            truncate_range(entries.clone(), 5, 4, u64::MAX),
+            SequenceRangePage {
+                entries: entries[..4].to_vec(),
+                next: Some(9),
+            }
+        );
+        // Exactly at the max size.
+        assert_eq!(
+            truncate_range(entries.clone(), 0, 100, 30),
+            SequenceRangePage {
+                entries: entries[..3].to_vec(),
+                next: Some(3),
+            }
+        );
+        // An entry larger than the max size is still returned, for the range to progress.
+        assert_eq!(
+            truncate_range(entries.clone(), 0, 100, 5),
+            SequenceRangePage {
+                entries: entries[..1].to_vec(),
+                next: Some(1),
+            }
+        );
+        assert_eq!(
+            truncate_range(entries.clone(), 0, 10, 100),
+            SequenceRangePage {
+                entries,
+                next: None,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn truncated_range_is_continued_from_next_index() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let mut storage =
+            SequenceStorage::new(root.path(), UsedSpace::new(u64::MAX), 1000, u64::MAX).await?;
+        let keypair = Keypair::new_ed25519(&mut rand::thread_rng());
+        let owner = keypair.public_key();
+        let origin = EndUser::AllClients(owner);
+        let mut replica =
+            Sequence::new_public(owner, owner.to_string(), XorName::random(), 0, None);
+        let address = *replica.address();
+        let _ = storage.store(&replica, MessageId::new(), origin).await?;
+        let expected: Vec<_> = (0..2500_u32).map(|i| i.to_be_bytes().to_vec()).collect();
+        let _ = append_ops(&mut replica, &keypair, expected.clone().into_iter())?;
+        storage.chunks.put(&replica).await?;
+
+        let mut listed = vec![];
+        let mut start = SequenceIndex::FromStart(0);
+        loop {
+            let page = storage.get_range_page(
+                address,
+                (start, SequenceIndex::FromEnd(0)),
+                false,
+                origin,
+            )?;
+            assert!(page.entries.len() <= 1000);
+            listed.extend(page.entries);
+            match page.next {
+                Some(next) => start = SequenceIndex::FromStart(next),
+                None => break,
+            }
+        }
+        assert_eq!(listed, expected);
+
+        // Relative to the end.
+        let page = storage.get_range_page(
+            address,
+            (SequenceIndex::FromEnd(2200), SequenceIndex::FromEnd(100)),
+            false,
+            origin,
+        )?;
+        assert_eq!(page.entries, expected[300..1300].to_vec());
+        assert_eq!(page.next, Some(1300));
+        let page = storage.get_range_page(
+            address,
+            (SequenceIndex::FromStart(1300), SequenceIndex::FromEnd(100)),
+            false,
+            origin,
+        )?;
+        assert_eq!(page.entries, expected[1300..2300].to_vec());
+        assert_eq!(page.next, Some(2300));
+        let page = storage.get_range_page(
+            address,
+            (SequenceIndex::FromStart(2300), SequenceIndex::FromEnd(100)),
+            false,
+            origin,
+        )?;
+        assert_eq!(page.entries, expected[2300..2400].to_vec());
+        assert_eq!(page.next, None);
+        Ok(())
+    }
+
+    // The outcome of each of the queries reading the Sequence, for the requester,
+    // with the permissions queried being those of `user`.
+    async fn read_all(
+        storage: &SequenceStorage,
+        address: SequenceAddress,
+        requester: PublicKey,
+        user: SequenceUser,
+    ) -> Result<Vec<std::result::Result<(), ErrorMessage>>> {
+        let origin = EndUser::AllClients(requester);
+        let range = (SequenceIndex::FromStart(0), SequenceIndex::FromEnd(0));
+        let duties = vec![
+            storage.get(address, MessageId::new(), origin).await?,
+            storage
+                .get_range(address, range, MessageId::new(), origin)
+                .await?,
+            storage
+                .get_last_entry(address, MessageId::new(), origin)
+                .await?,
+            storage.get_owner(address, MessageId::new(), origin).await?,
+            storage
+                .get_user_permissions(address, user, MessageId::new(), origin)
+                .await?,
+        ];
+        duties
+            .into_iter()
+            .map(|duty| match duty {
+                NodeDuty::Send(OutgoingMsg {
+                    msg: Message::QueryResponse { response, .. },
+                    ..
+                }) => Ok(match response {
+                    QueryResponse::GetSequence(result) => result.map(|_| ()),
+                    QueryResponse::GetSequenceRange(result) => result.map(|_| ()),
+                    QueryResponse::GetSequenceLastEntry(result) => result.map(|_| ()),
+                    QueryResponse::GetSequenceOwner(result) => result.map(|_| ()),
+                    QueryResponse::GetSequenceUserPermissions(result) => result.map(|_| ()),
+                    other => return Err(Error::Logic(format!("Unexpected: {:?}", other))),
+                }),
+                other => Err(Error::Logic(format!("Unexpected: {:?}", other))),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn private_sequences_are_read_by_permitted_keys_only() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let mut storage =
+            SequenceStorage::new(root.path(), UsedSpace::new(u64::MAX), 1000, u64::MAX).await?;
+        let keypair = Keypair::new_ed25519(&mut rand::thread_rng());
+        let owner = keypair.public_key();
+        let app = Keypair::new_ed25519(&mut rand::thread_rng()).public_key();
+        let stranger = Keypair::new_ed25519(&mut rand::thread_rng()).public_key();
+
+        let mut public_permissions = BTreeMap::new();
+        let _ =
+            public_permissions.insert(SequenceUser::Key(app), SequencePublicPermissions::new(true));
+        let public = Sequence::new_public(
+            owner,
+            owner.to_string(),
+            XorName::random(),
+            0,
+            Some(SequencePublicPolicy {
+                owner,
+                permissions: public_permissions,
+            }),
+        );
+        let mut private_permissions = BTreeMap::new();
+        let _ = private_permissions.insert(app, SequencePrivatePermissions::new(true, false));
+        let private = Sequence::new_private(
+            owner,
+            owner.to_string(),
+            XorName::random(),
+            0,
+            Some(SequencePrivatePolicy {
+                owner,
+                permissions: private_permissions,
+            }),
+        );
+
+        for mut sequence in [public, private] {
+            let _ = append_ops(&mut sequence, &keypair, std::iter::once(vec![0]))?;
+            let address = *sequence.address();
+            let _ = storage
+                .store(&sequence, MessageId::new(), EndUser::AllClients(owner))
+                .await?;
+
+            for requester in &[owner, app] {
+                for outcome in
+                    read_all(&storage, address, *requester, SequenceUser::Key(app)).await?
+                {
+                    assert_eq!(outcome, Ok(()));
+                }
+            }
+            for outcome in read_all(&storage, address, stranger, SequenceUser::Key(app)).await? {
+                if sequence.is_public() {
+                    assert_eq!(outcome, Ok(()));
+                } else {
+                    assert_eq!(outcome, Err(ErrorMessage::AccessDenied(stranger)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn cmd_error(duty: NodeDuty) -> Option<ErrorMessage> {
+        match duty {
+            NodeDuty::Send(OutgoingMsg {
+                msg:
+                    Message::CmdError {
+                        error: CmdError::Data(error),
+                        ..
+                    },
+                ..
+            }) => Some(error),
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn private_sequence_is_deleted_by_its_owner_only() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let used_space = UsedSpace::new(u64::MAX);
+        let mut storage =
+            SequenceStorage::new(root.path(), used_space.clone(), 1000, u64::MAX).await?;
+        let keypair = Keypair::new_ed25519(&mut rand::thread_rng());
+        let owner = keypair.public_key();
+        let stranger = Keypair::new_ed25519(&mut rand::thread_rng()).public_key();
+
+        let mut sequence =
+            Sequence::new_private(owner, owner.to_string(), XorName::random(), 0, None);
+        let _ = append_ops(&mut sequence, &keypair, (0..10).map(|i| vec![i; 100]))?;
+        let address = *sequence.address();
+        let _ = storage
+            .store(&sequence, MessageId::new(), EndUser::AllClients(owner))
+            .await?;
+        assert!(used_space.total().await > 0);
+
+        let duty = storage
+            .delete(address, MessageId::new(), EndUser::AllClients(stranger))
+            .await?;
+        assert_eq!(cmd_error(duty), Some(ErrorMessage::InvalidOwners(stranger)));
+
+        let duty = storage
+            .delete(address, MessageId::new(), EndUser::AllClients(owner))
+            .await?;
+        assert_eq!(cmd_error(duty), None);
+        assert_eq!(used_space.total().await, 0);
+
+        let duty = storage
+            .delete(address, MessageId::new(), EndUser::AllClients(owner))
+            .await?;
+        assert_eq!(cmd_error(duty), Some(ErrorMessage::NoSuchData));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn public_sequence_is_not_deleted() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let mut storage =
+            SequenceStorage::new(root.path(), UsedSpace::new(u64::MAX), 1000, u64::MAX).await?;
+        let owner = Keypair::new_ed25519(&mut rand::thread_rng()).public_key();
+        let sequence = Sequence::new_public(owner, owner.to_string(), XorName::random(), 0, None);
+        let address = *sequence.address();
+        let _ = storage
+            .store(&sequence, MessageId::new(), EndUser::AllClients(owner))
+            .await?;
+
+        let duty = storage
+            .delete(address, MessageId::new(), EndUser::AllClients(owner))
+            .await?;
+        assert_eq!(cmd_error(duty), Some(ErrorMessage::InvalidOperation));
+        assert!(storage.chunks.has(&address));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn stored_sequence_is_not_overwritten() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let mut storage =
+            SequenceStorage::new(root.path(), UsedSpace::new(u64::MAX), 1000, u64::MAX).await?;
+        let owner = Keypair::new_ed25519(&mut rand::thread_rng()).public_key();
+        let attacker = Keypair::new_ed25519(&mut rand::thread_rng()).public_key();
+        let name = XorName::random();
+        let sequence = Sequence::new_public(owner, owner.to_string(), name, 0, None);
+        let duty = storage
+            .store(&sequence, MessageId::new(), EndUser::AllClients(owner))
+            .await?;
+        assert_eq!(cmd_error(duty), None);
+
+        let forged = Sequence::new_public(attacker, attacker.to_string(), name, 0, None);
+        let duty = storage
+            .store(&forged, MessageId::new(), EndUser::AllClients(attacker))
+            .await?;
+        assert_eq!(cmd_error(duty), Some(ErrorMessage::DataExists));
+        assert_eq!(storage.chunks.get(sequence.address())?.owner(), owner);
+
+        // The same name under another tag is another Sequence.
+        let other_tag = Sequence::new_public(attacker, attacker.to_string(), name, 1, None);
+        let duty = storage
+            .store(&other_tag, MessageId::new(), EndUser::AllClients(attacker))
+            .await?;
+        assert_eq!(cmd_error(duty), None);
+        assert_eq!(storage.chunks.get(sequence.address())?.owner(), owner);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn sequence_with_address_of_other_scope_is_rejected() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let mut storage =
+            SequenceStorage::new(root.path(), UsedSpace::new(u64::MAX), 1000, u64::MAX).await?;
+        let owner = Keypair::new_ed25519(&mut rand::thread_rng()).public_key();
+        let name = XorName::random();
+        let private = Sequence::new_private(owner, owner.to_string(), name, 0, None);
+
+        // Private data under a public address, by swapping the address variant.
+        let mut bytes = bincode::serialize(&private)?;
+        let mut address = 1_u32.to_le_bytes().to_vec();
+        address.extend_from_slice(&name.0);
+        let position = bytes
+            .windows(address.len())
+            .position(|window| window == address.as_slice())
+            .ok_or_else(|| Error::Logic("Address not serialised".to_string()))?;
+        bytes[position] = 0;
+        let forged: Sequence = bincode::deserialize(&bytes)?;
+        assert!(forged.is_public());
+
+        let duty = storage
+            .store(&forged, MessageId::new(), EndUser::AllClients(owner))
+            .await?;
+        assert_eq!(cmd_error(duty), Some(ErrorMessage::InvalidOperation));
+        assert!(!storage.chunks.has(forged.address()));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn tombstoned_entries_are_skipped_by_the_current_view_only() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let mut storage =
+            SequenceStorage::new(root.path(), UsedSpace::new(u64::MAX), 1000, u64::MAX).await?;
+        let keypair = Keypair::new_ed25519(&mut rand::thread_rng());
+        let owner = keypair.public_key();
+        let reader = Keypair::new_ed25519(&mut rand::thread_rng()).public_key();
+        let writer = Keypair::new_ed25519(&mut rand::thread_rng()).public_key();
+        let mut permissions = BTreeMap::new();
+        let _ = permissions.insert(reader, SequencePrivatePermissions::new(true, false));
+        let _ = permissions.insert(writer, SequencePrivatePermissions::new(true, true));
+        let mut sequence = Sequence::new_private(
+            owner,
+            owner.to_string(),
+            XorName::random(),
+            0,
+            Some(SequencePrivatePolicy { owner, permissions }),
+        );
+        let address = *sequence.address();
+        let _ = append_ops(&mut sequence, &keypair, (0..5).map(|i| vec![i]))?;
+        let _ = storage
+            .store(&sequence, MessageId::new(), EndUser::AllClients(owner))
+            .await?;
+        let tombstone = |index| std::iter::once(Tombstone { index }.to_entry());
+
+        // Only those allowed to append can remove entries.
+        let op = append_ops(&mut sequence.clone(), &keypair, tombstone(1))?.remove(0);
+        let duty = storage
+            .edit(op, MessageId::new(), EndUser::AllClients(reader))
+            .await?;
+        assert_eq!(cmd_error(duty), Some(ErrorMessage::AccessDenied(reader)));
+        let op = append_ops(&mut sequence, &keypair, tombstone(1))?.remove(0);
+        let duty = storage
+            .edit(op, MessageId::new(), EndUser::AllClients(writer))
+            .await?;
+        assert_eq!(cmd_error(duty), None);
+
+        // Neither tombstones nor entries yet to be appended can be removed.
+        for index in [5, 6].iter() {
+            let op = append_ops(&mut sequence.clone(), &keypair, tombstone(*index))?.remove(0);
+            let duty = storage
+                .edit(op, MessageId::new(), EndUser::AllClients(owner))
+                .await?;
+            assert_eq!(cmd_error(duty), Some(ErrorMessage::NoSuchEntry));
+        }
+
+        let all = (SequenceIndex::FromStart(0), SequenceIndex::FromEnd(0));
+        let origin = EndUser::AllClients(reader);
+        let history = storage.get_range_page(address, all, false, origin)?;
+        assert_eq!(history.entries.len(), 6);
+        assert_eq!(history.entries[5], Tombstone { index: 1 }.to_entry());
+        let current = storage.get_range_page(address, all, true, origin)?;
+        assert_eq!(current.entries, vec![vec![0], vec![2], vec![3], vec![4]]);
+        let range = (SequenceIndex::FromStart(1), SequenceIndex::FromStart(3));
+        let current = storage.get_range_page(address, range, true, origin)?;
+        assert_eq!(current.entries, vec![vec![2]]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn entries_of_public_sequences_cannot_be_removed() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let mut storage =
+            SequenceStorage::new(root.path(), UsedSpace::new(u64::MAX), 1000, u64::MAX).await?;
+        let keypair = Keypair::new_ed25519(&mut rand::thread_rng());
+        let owner = keypair.public_key();
+        let origin = EndUser::AllClients(owner);
+        let mut sequence =
+            Sequence::new_public(owner, owner.to_string(), XorName::random(), 0, None);
+        let address = *sequence.address();
+        let _ = append_ops(&mut sequence, &keypair, std::iter::once(vec![0]))?;
+        let _ = storage.store(&sequence, MessageId::new(), origin).await?;
+
+        let entries = std::iter::once(Tombstone { index: 0 }.to_entry());
+        let op = append_ops(&mut sequence, &keypair, entries)?.remove(0);
+        let duty = storage.edit(op, MessageId::new(), origin).await?;
+        assert_eq!(cmd_error(duty), Some(ErrorMessage::InvalidOperation));
+        assert_eq!(storage.chunks.get(&address)?.len(None)?, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn appends_are_charged_up_to_capacity_across_restarts() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let capacity = 10_000;
+        let used_space = UsedSpace::new(capacity);
+        let mut storage =
+            SequenceStorage::new(root.path(), used_space.clone(), 1000, u64::MAX).await?;
+        let keypair = Keypair::new_ed25519(&mut rand::thread_rng());
+        let owner = keypair.public_key();
+        let origin = EndUser::AllClients(owner);
+        let mut sequence =
+            Sequence::new_private(owner, owner.to_string(), XorName::random(), 0, None);
+        let address = *sequence.address();
+        let _ = storage.store(&sequence, MessageId::new(), origin).await?;
+
+        let mut appended = 0;
+        let error = loop {
+            let used = used_space.total().await;
+            let mut replica = sequence.clone();
+            let op = append_ops(&mut replica, &keypair, std::iter::once(vec![0; 500]))?.remove(0);
+            if let Some(error) = cmd_error(storage.edit(op, MessageId::new(), origin).await?) {
+                assert_eq!(used_space.total().await, used);
+                break error;
+            }
+            sequence = replica;
+            appended += 1;
+            assert!(used_space.total().await > used + 500);
+        };
+        assert_eq!(error, ErrorMessage::NotEnoughSpace);
+        assert!(appended > 1);
+        let stored = storage.chunks.get(&address)?;
+        assert_eq!(stored.len(None)?, appended);
+        let size = bincode::serialized_size(&stored)?;
+        assert_eq!(used_space.total().await, size);
+        assert!(size <= capacity);
+
+        // The charge is reloaded on restart.
+        drop(storage);
+        let used_space = UsedSpace::new(capacity);
+        let _storage =
+            SequenceStorage::new(root.path(), used_space.clone(), 1000, u64::MAX).await?;
+        assert_eq!(used_space.total().await, size);
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use sn_data_types::SequenceEntry;
+use std::convert::TryInto;
+
+// Starts the entries which are tombstones, followed by the index they remove.
+const MARKER: &[u8] = b"sn_node::tombstone:";
+
+/// An entry of a private Sequence marking a prior entry as removed. Both are kept
+/// in its history, but skipped by reads of its current view.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tombstone {
+    /// The index, from the start of the Sequence, of the removed entry.
+    pub index: u64,
+}
+
+impl Tombstone {
+    /// The entry to append to the Sequence for the tombstone.
+    pub fn to_entry(self) -> SequenceEntry {
+        let mut entry = MARKER.to_vec();
+        entry.extend_from_slice(&self.index.to_be_bytes());
+        entry
+    }
+
+    /// The tombstone the entry is, if any.
+    pub fn from_entry(entry: &[u8]) -> Option<Self> {
+        if entry.len() != MARKER.len() + 8 || !entry.starts_with(MARKER) {
+            return None;
+        }
+        let index = entry[MARKER.len()..].try_into().ok()?;
+        Some(Self {
+            index: u64::from_be_bytes(index),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Tombstone, MARKER};
+
+    #[test]
+    fn only_tombstone_entries_are_read_as_such() {
+        let tombstone = Tombstone { index: 42 };
+        assert_eq!(
+            Tombstone::from_entry(&tombstone.to_entry()),
+            Some(tombstone)
+        );
+        assert_eq!(Tombstone::from_entry(MARKER), None);
+        assert_eq!(Tombstone::from_entry(&[0; 27]), None);
+        let mut longer = tombstone.to_entry();
+        longer.push(0);
+        assert_eq!(Tombstone::from_entry(&longer), None);
+    }
+}
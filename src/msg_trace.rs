@@ -0,0 +1,153 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+/// Logs a stage of the handling of a client msg, as `msg_id=.. requester=.. stage=..`
+/// followed by the optional formatted details, for a msg to be followed through the logs
+/// of every node it reaches. The time of each stage is that of the log line.
+#[macro_export]
+macro_rules! trace_msg {
+    ($stage:expr, $msg_id:expr, $requester:expr) => {
+        log::debug!(
+            "msg_id={} requester={} stage={}",
+            $msg_id,
+            $requester,
+            $stage
+        )
+    };
+    ($stage:expr, $msg_id:expr, $requester:expr, $($arg:tt)+) => {
+        log::debug!(
+            "msg_id={} requester={} stage={} {}",
+            $msg_id,
+            $requester,
+            $stage,
+            format_args!($($arg)+)
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        chunk_store::{BlobChunkStore, UsedSpace},
+        chunks::chunk_storage::ChunkStorage,
+        event_mapping::map_msg::match_user_sent_msg,
+        transfers::test_utils::payment,
+        Error, Result,
+    };
+    use log::{LevelFilter, Log, Metadata, Record};
+    use sn_data_types::{Blob, Keypair, PublicBlob};
+    use sn_messaging::{
+        client::{BlobWrite, Cmd, DataCmd, Message},
+        DstLocation, EndUser, MessageId,
+    };
+    use std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Mutex, Once,
+        },
+        time::Duration,
+    };
+    use tempdir::TempDir;
+    use xor_name::XorName;
+
+    struct Capture {
+        lines: Mutex<Vec<String>>,
+    }
+
+    impl Log for Capture {
+        fn enabled(&self, _: &Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            if let Ok(mut lines) = self.lines.lock() {
+                lines.push(record.args().to_string());
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    static CAPTURE: Capture = Capture {
+        lines: Mutex::new(Vec::new()),
+    };
+
+    // Installs the capture as the logger of the process, once for all tests, failing
+    // if another logger was installed first, as the trace would then go unseen.
+    fn install_capture() -> Result<()> {
+        static INSTALL: Once = Once::new();
+        static INSTALLED: AtomicBool = AtomicBool::new(false);
+        INSTALL.call_once(|| {
+            if log::set_logger(&CAPTURE).is_ok() {
+                log::set_max_level(LevelFilter::Debug);
+                INSTALLED.store(true, Ordering::SeqCst);
+            }
+        });
+        if INSTALLED.load(Ordering::SeqCst) {
+            Ok(())
+        } else {
+            Err(Error::Logic(
+                "Another logger is installed, the trace can't be captured".to_string(),
+            ))
+        }
+    }
+
+    fn stages_of(msg_id: MessageId) -> Vec<String> {
+        let prefix = format!("msg_id={} ", msg_id);
+        CAPTURE
+            .lines
+            .lock()
+            .map(|lines| {
+                lines
+                    .iter()
+                    .filter(|line| line.starts_with(&prefix))
+                    .filter_map(|line| line.split("stage=").nth(1))
+                    .filter_map(|stage| stage.split(' ').next())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    // Covers the stages at the node receiving the Put and at the Adult storing it. The
+    // `sent_to_holders` stage in between is traced by the Elders' `BlobRegister`, which
+    // needs a running routing node to choose the holders, so isn't driven here.
+    #[tokio::test]
+    async fn put_is_traced_on_receipt_and_storage() -> Result<()> {
+        install_capture()?;
+
+        let keypair = Keypair::new_ed25519(&mut rand::thread_rng());
+        let origin = EndUser::AllClients(keypair.public_key());
+        let data = Blob::Public(PublicBlob::new(vec![1_u8; 64]));
+        let msg = Message::Cmd {
+            cmd: Cmd::Data {
+                cmd: DataCmd::Blob(BlobWrite::New(data.clone())),
+                payment: payment(&keypair, &keypair)?,
+            },
+            id: MessageId::new(),
+            target_section_pk: None,
+        };
+        let msg_id = msg.id();
+        let _ = match_user_sent_msg(msg, DstLocation::Section(XorName::random()), origin);
+
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let chunks = BlobChunkStore::new(root.path(), UsedSpace::new(u64::MAX)).await?;
+        let mut storage = ChunkStorage::new(
+            XorName::random(),
+            Box::new(chunks),
+            0,
+            Duration::from_secs(10),
+            1,
+            1,
+        );
+        let _ = storage.store(&data, msg_id, origin).await?;
+
+        assert_eq!(stages_of(msg_id), vec!["received", "stored"]);
+        Ok(())
+    }
+}
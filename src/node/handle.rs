@@ -12,6 +12,8 @@ use crate::{
     metadata::Metadata,
     node_ops::{NodeDuties, NodeDuty, OutgoingMsg},
     section_funds::{
+        payout_ledger::PayoutLedger,
+        pending_payouts::PendingPayouts,
         reward_process::RewardProcess,
         reward_stage::{CreditAccumulation, RewardStage},
         reward_wallets::RewardWallets,
@@ -21,10 +23,10 @@ use crate::{
     Error, Node, Result,
 };
 use dashmap::DashMap;
-use log::{debug, info};
+use log::{debug, info, trace, warn};
 use sn_data_types::{CreditAgreementProof, CreditId, PublicKey, SectionElders, WalletHistory};
 use sn_messaging::{
-    client::{Message, NodeCmd, NodeQuery, Query},
+    client::{Cmd, CmdError, Error as ErrorMessage, Message, NodeCmd, NodeQuery, Query},
     Aggregation, DstLocation, MessageId,
 };
 use std::collections::{BTreeMap, VecDeque};
@@ -34,11 +36,14 @@ impl Node {
     ///
     pub async fn handle(&mut self, duty: NodeDuty) -> Result<NodeDuties> {
         info!("Handling NodeDuty: {:?}", duty);
-        match duty {
-            NodeDuty::Genesis => {
-                self.level_up().await?;
-                Ok(vec![])
+        if self.meta_data.is_none() {
+            if let Some(rejection) = rejection_by_adult(&duty) {
+                debug!("Rejecting client data msg, as not an Elder: {:?}", duty);
+                return Ok(vec![rejection]);
             }
+        }
+        match duty {
+            NodeDuty::Genesis => self.level_up().await,
             NodeDuty::EldersChanged {
                 our_key,
                 our_prefix,
@@ -46,11 +51,16 @@ impl Node {
             } => {
                 if newbie {
                     info!("Promoted to Elder on Churn");
-                    self.level_up().await?;
-                    Ok(vec![])
+                    self.level_up().await
                 } else {
                     info!("Updating our replicas on Churn");
                     self.update_replicas().await?;
+                    let key_set = self.network_api.our_public_key_set().await?;
+                    if let Some(funds) = &mut self.section_funds {
+                        for churn_process in funds.reward_processes() {
+                            churn_process.update_elders(key_set.clone());
+                        }
+                    }
                     let msg_id =
                         MessageId::combine(vec![our_prefix.name(), XorName::from(our_key)]);
                     Ok(vec![self.push_state(our_prefix, msg_id)])
@@ -64,8 +74,7 @@ impl Node {
             } => {
                 if newbie {
                     info!("Beginning split as Newbie");
-                    self.begin_split_as_newbie(our_key, our_prefix).await?;
-                    Ok(vec![])
+                    self.begin_split_as_newbie(our_key, our_prefix).await
                 } else {
                     info!("Beginning split as Oldie");
                     self.begin_split_as_oldie(our_prefix, our_key, sibling_key)
@@ -77,7 +86,7 @@ impl Node {
                 Ok(vec![self.get_section_elders(msg_id, origin).await?])
             }
             NodeDuty::ReceiveRewardProposal(proposal) => {
-                if let Ok((churn_process, _, _)) = self.get_churning_funds() {
+                if let Ok((churn_process, _, _)) = self.get_churning_funds(proposal.section_key) {
                     info!("Handling Churn proposal as an Elder");
                     Ok(vec![churn_process.receive_churn_proposal(proposal).await?])
                 } else {
@@ -86,7 +95,8 @@ impl Node {
                 }
             }
             NodeDuty::ReceiveRewardAccumulation(accumulation) => {
-                if let Ok((churn_process, reward_wallets, payments)) = self.get_churning_funds() {
+                let section_key = accumulation.section_key;
+                if let Ok((churn_process, payouts, ledger)) = self.get_churning_funds(section_key) {
                     let mut ops = vec![
                         churn_process
                             .receive_wallet_accumulation(accumulation)
@@ -95,12 +105,31 @@ impl Node {
 
                     if let RewardStage::Completed(credit_proofs) = churn_process.stage().clone() {
                         let reward_sum = credit_proofs.sum();
-                        ops.extend(Self::propagate_credits(credit_proofs)?);
-                        // update state
-                        self.section_funds = Some(SectionFunds::KeepingNodeWallets {
-                            wallets: reward_wallets.clone(),
-                            payments: payments.clone(),
-                        });
+                        let epoch = section_key;
+                        let mut unpaid = BTreeMap::new();
+                        for (id, credit_proof) in credit_proofs {
+                            if ledger.record(epoch, &credit_proof)?.is_some() {
+                                debug!(
+                                    "Not paying {} again for the churn at {}",
+                                    credit_proof.recipient(),
+                                    epoch
+                                );
+                            } else {
+                                debug!(
+                                    "Paying {} to {} for the churn at {}",
+                                    credit_proof.amount(),
+                                    credit_proof.recipient(),
+                                    epoch
+                                );
+                                let _ = unpaid.insert(id, credit_proof);
+                            }
+                        }
+                        ops.extend(Self::propagate_credits(unpaid, payouts)?);
+                        // update state, the payouts of other churns going on
+                        self.section_funds = self
+                            .section_funds
+                            .take()
+                            .map(|funds| funds.end_churn(epoch));
                         let section_key = &self.network_api.section_public_key().await?;
                         info!(
                             "COMPLETED SPLIT. New section: ({}). Total rewards paid: {}.",
@@ -125,7 +154,13 @@ impl Node {
                 let members = self.network_api.our_members().await;
                 let rewards = self.get_section_funds()?;
                 if let Some(age) = members.get(&node_id) {
-                    rewards.set_node_wallet(node_id, wallet_id, *age);
+                    // the msg is from the node itself, as its signature was verified by routing
+                    if !rewards.set_node_wallet(node_id, wallet_id, *age, msg_id)? {
+                        warn!(
+                            "Wallet registration {:?} of {} replayed, ignoring it.",
+                            msg_id, node_id
+                        );
+                    }
                     Ok(vec![])
                 } else {
                     debug!(
@@ -145,11 +180,26 @@ impl Node {
             NodeDuty::ProcessLostMember { name, age } => {
                 info!("Member Lost: {:?}", name);
                 let rewards = self.get_section_funds()?;
-                rewards.remove_node_wallet(name);
+                rewards.remove_node_wallet(name)?;
 
                 let metadata = self.get_metadata()?;
                 Ok(metadata.trigger_chunk_replication(name).await?)
             }
+            NodeDuty::ProcessNewMember { name, relocated } => {
+                info!("Member Joined: {:?}", name);
+                let mut duties = vec![];
+                if relocated {
+                    trace!("A relocated node has joined the section.");
+                }
+                if relocated || !self.network_api.our_prefix().await.is_empty() {
+                    // Switch joins_allowed off a new adult joining.
+                    duties.push(NodeDuty::SetNodeJoinsAllowed(false));
+                }
+                if let Some(metadata) = &mut self.meta_data {
+                    duties.extend(metadata.trigger_chunk_redistribution(name).await?);
+                }
+                Ok(duties)
+            }
             //
             // ---------- Levelling --------------
             NodeDuty::SynchState {
@@ -158,17 +208,7 @@ impl Node {
             } => Ok(vec![self.synch_state(node_rewards, user_wallets).await?]),
             NodeDuty::LevelDown => {
                 info!("Getting Demoted");
-                self.meta_data = None;
-                self.transfers = None;
-                self.section_funds = None;
-                self.chunks = Some(
-                    Chunks::new(
-                        self.node_info.node_name,
-                        self.node_info.root_dir.as_path(),
-                        self.used_space.clone(),
-                    )
-                    .await?,
-                );
+                self.level_down().await?;
                 Ok(vec![])
             }
             //
@@ -249,10 +289,7 @@ impl Node {
                     .matches(&&data_section_addr)
                 {
                     let chunks = self.get_chunks()?;
-                    let read = chunks.read(&read, msg_id, origin).await?;
-                    let mut ops = chunks.check_storage().await?;
-                    ops.insert(0, read);
-                    Ok(ops)
+                    chunks.read(&read, msg_id, origin).await
                 } else {
                     Ok(vec![NodeDuty::Send(OutgoingMsg {
                         msg: Message::NodeQuery {
@@ -276,9 +313,77 @@ impl Node {
                 origin,
             } => {
                 let chunks = self.get_chunks()?;
-                Ok(vec![chunks.write(&write, msg_id, origin).await?])
+                let mut ops = vec![chunks.write(&write, msg_id, origin).await?];
+                ops.extend(chunks.check_storage().await?);
+                Ok(ops)
             }
             NodeDuty::ReachingMaxCapacity => Ok(vec![self.notify_section_of_our_storage().await?]),
+            NodeDuty::ScrubChunks => {
+                let chunks = self.get_chunks()?;
+                chunks.scrub().await;
+                Ok(vec![])
+            }
+            NodeDuty::CheckPendingReplications => {
+                let chunks = self.get_chunks()?;
+                Ok(chunks.check_pending_ops())
+            }
+            NodeDuty::ContinueChunkReplication => {
+                let metadata = self.get_metadata()?;
+                metadata.continue_chunk_replication().await
+            }
+            NodeDuty::RetryRewardPayouts => {
+                let mut ops = self.resend_stuck_rewards().await?;
+                ops.extend(self.retry_reward_payouts()?);
+                Ok(ops)
+            }
+            NodeDuty::RecordPayoutFailure {
+                correlation_id,
+                error,
+            } => {
+                self.record_payout_failure(correlation_id, error)?;
+                Ok(vec![])
+            }
+            NodeDuty::ReportReplicationCompleted {
+                address,
+                correlation_id,
+            } => Ok(vec![
+                self.report_replication_completed(address, correlation_id)
+                    .await?,
+            ]),
+            NodeDuty::RecordChunkHolder {
+                address,
+                holder,
+                proof,
+            } => {
+                let metadata = self.get_metadata()?;
+                metadata.record_chunk_holder(address, holder, proof).await?;
+                Ok(vec![])
+            }
+            NodeDuty::ReportReplicationFailure {
+                address,
+                error,
+                correlation_id,
+            } => Ok(vec![self.report_replication_failure(
+                address,
+                error,
+                correlation_id,
+            )]),
+            NodeDuty::RecordReplicationFailure {
+                address,
+                holder,
+                error,
+            } => {
+                let metadata = self.get_metadata()?;
+                metadata
+                    .record_replication_failure(address, holder, error)
+                    .await
+            }
+            NodeDuty::FlushChunkWrites => {
+                let chunks = self.get_chunks()?;
+                let mut ops = chunks.flush_writes().await?;
+                ops.extend(chunks.check_storage().await?);
+                Ok(ops)
+            }
             //
             // ------- Misc ------------
             NodeDuty::IncrementFullNodeCount { node_id } => {
@@ -335,7 +440,13 @@ impl Node {
                 transfers.process_payment(&msg, origin).await
             }
             NodeDuty::AddPayment(credit) => {
-                self.get_section_funds()?.add_payment(credit);
+                let id = *credit.id();
+                let funds = self.get_section_funds()?;
+                if funds.add_payment(credit) {
+                    debug!("Section funds balance: {}", funds.balance());
+                } else {
+                    debug!("Payment {:?} already credited to the section funds", id);
+                }
                 Ok(vec![])
             }
             NodeDuty::ReplicateChunk {
@@ -344,38 +455,39 @@ impl Node {
                 id,
             } => {
                 let chunks = self.get_chunks()?;
-                Ok(vec![
-                    chunks.replicate_chunk(address, current_holders, id).await?,
-                ])
+                chunks.replicate_chunk(address, current_holders, id).await
             }
             NodeDuty::GetChunkForReplication {
                 address,
                 new_holder,
                 id,
+                origin,
             } => {
+                let our_adults = self.network_api.our_adults().await;
                 let chunks = self.get_chunks()?;
-                Ok(vec![
-                    chunks
-                        .get_chunk_for_replication(address, id, new_holder)
-                        .await?,
-                ])
+                chunks
+                    .get_chunk_for_replication(address, id, new_holder, origin, &our_adults)
+                    .await
+            }
+            NodeDuty::ChunkCopyFailed {
+                correlation_id,
+                holder,
+            } => {
+                let chunks = self.get_chunks()?;
+                chunks.handle_copy_failure(correlation_id, holder).await
             }
             NodeDuty::StoreChunkForReplication {
                 data,
                 correlation_id,
+                holder,
             } => {
-                // Recreate original MessageId from Section
-                let msg_id = MessageId::combine(vec![
-                    *data.address().name(),
-                    self.network_api.our_name().await,
-                ]);
-                if msg_id == correlation_id {
-                    let chunks = self.get_chunks()?;
-                    Ok(vec![chunks.store_replicated_chunk(data).await?])
-                } else {
-                    log::warn!("Invalid message ID");
-                    Ok(vec![])
-                }
+                // Copies are only taken for the replications we have pending.
+                let chunks = self.get_chunks()?;
+                let mut ops = chunks
+                    .store_replicated_chunk(data, correlation_id, holder)
+                    .await?;
+                ops.extend(chunks.check_storage().await?);
+                Ok(ops)
             }
             NodeDuty::NoOp => Ok(vec![]),
         }
@@ -413,22 +525,144 @@ impl Node {
         }
     }
 
+    /// The process paying out the rewards of the churn at the section key, if in progress.
     fn get_churning_funds(
         &mut self,
-    ) -> Result<(
-        &mut RewardProcess,
-        &mut RewardWallets,
-        &mut DashMap<CreditId, CreditAgreementProof>,
-    )> {
+        section_key: PublicKey,
+    ) -> Result<(&mut RewardProcess, &mut PendingPayouts, &mut PayoutLedger)> {
         if let Some(SectionFunds::Churning {
-            process,
-            wallets,
-            payments,
+            processes,
+            payouts,
+            ledger,
+            ..
         }) = &mut self.section_funds
         {
-            Ok((process, wallets, payments))
+            match processes.get_mut(&section_key) {
+                Some(process) => Ok((process, payouts, ledger)),
+                None => Err(Error::NotChurningFunds),
+            }
         } else {
             Err(Error::NotChurningFunds)
         }
     }
 }
+
+/// The rejection of a client's data query or cmd received while not an Elder, as only
+/// Elders process them, or None for any other duty.
+fn rejection_by_adult(duty: &NodeDuty) -> Option<NodeDuty> {
+    let (msg, origin) = match duty {
+        NodeDuty::ProcessRead { query, id, origin } => (
+            Message::QueryResponse {
+                response: query.error(ErrorMessage::InvalidOperation),
+                id: MessageId::in_response_to(id),
+                correlation_id: *id,
+                target_section_pk: None,
+            },
+            origin,
+        ),
+        NodeDuty::ProcessWrite { cmd, id, origin } => (
+            Message::CmdError {
+                error: cmd.error(ErrorMessage::InvalidOperation),
+                id: MessageId::in_response_to(id),
+                correlation_id: *id,
+                target_section_pk: None,
+            },
+            origin,
+        ),
+        NodeDuty::ProcessDataPayment { msg, origin } => {
+            let error = match msg {
+                Message::Cmd {
+                    cmd: Cmd::Data { cmd, .. },
+                    ..
+                } => cmd.error(ErrorMessage::InvalidOperation),
+                _ => CmdError::Data(ErrorMessage::InvalidOperation),
+            };
+            (
+                Message::CmdError {
+                    error,
+                    id: MessageId::in_response_to(&msg.id()),
+                    correlation_id: msg.id(),
+                    target_section_pk: None,
+                },
+                origin,
+            )
+        }
+        _ => return None,
+    };
+    Some(NodeDuty::Send(OutgoingMsg {
+        msg,
+        section_source: false,
+        dst: DstLocation::EndUser(*origin),
+        aggregation: Aggregation::None,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::rejection_by_adult;
+    use crate::{
+        node_ops::{NodeDuty, OutgoingMsg},
+        Error, Result,
+    };
+    use sn_data_types::{Keypair, MapAddress};
+    use sn_messaging::{
+        client::{
+            CmdError, DataCmd, DataQuery, Error as ErrorMessage, MapRead, MapWrite, Message,
+            QueryResponse,
+        },
+        DstLocation, EndUser, MessageId,
+    };
+    use xor_name::XorName;
+
+    #[test]
+    fn map_msgs_are_rejected_by_adults() -> Result<()> {
+        let origin =
+            EndUser::AllClients(Keypair::new_ed25519(&mut rand::thread_rng()).public_key());
+        let address = MapAddress::Seq {
+            name: XorName::random(),
+            tag: 0,
+        };
+        let id = MessageId::new();
+
+        let read = NodeDuty::ProcessRead {
+            query: DataQuery::Map(MapRead::Get(address)),
+            id,
+            origin,
+        };
+        match rejection_by_adult(&read) {
+            Some(NodeDuty::Send(OutgoingMsg {
+                msg:
+                    Message::QueryResponse {
+                        response: QueryResponse::GetMap(Err(ErrorMessage::InvalidOperation)),
+                        correlation_id,
+                        ..
+                    },
+                dst: DstLocation::EndUser(dst),
+                ..
+            })) if correlation_id == id && dst == origin => (),
+            other => return Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+
+        let write = NodeDuty::ProcessWrite {
+            cmd: DataCmd::Map(MapWrite::Delete(address)),
+            id,
+            origin,
+        };
+        match rejection_by_adult(&write) {
+            Some(NodeDuty::Send(OutgoingMsg {
+                msg:
+                    Message::CmdError {
+                        error: CmdError::Data(ErrorMessage::InvalidOperation),
+                        correlation_id,
+                        ..
+                    },
+                dst: DstLocation::EndUser(dst),
+                ..
+            })) if correlation_id == id && dst == origin => (),
+            other => return Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+
+        assert!(rejection_by_adult(&NodeDuty::ScrubChunks).is_none());
+        Ok(())
+    }
+}
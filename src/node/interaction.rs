@@ -16,54 +16,147 @@ use crate::{
     Error, Node, Result,
 };
 use dashmap::DashMap;
-use log::{debug, info};
+use log::{debug, error, info, warn};
 use section_funds::{
     elder_signing::ElderSigning,
+    pending_payouts::PendingPayouts,
     reward_process::{OurSection, RewardProcess},
     reward_stage::RewardStage,
     reward_wallets::RewardWallets,
     Credits,
 };
 use sn_data_types::{
-    ActorHistory, CreditAgreementProof, CreditId, NodeAge, PublicKey, SectionElders, Token,
-    WalletHistory,
+    ActorHistory, BlobAddress, CreditAgreementProof, CreditId, NodeAge, PublicKey, SectionElders,
+    Token, WalletHistory,
 };
 use sn_messaging::{
     client::{
-        Message, NodeCmd, NodeEvent, NodeQuery, NodeQueryResponse, NodeSystemCmd, NodeSystemQuery,
-        NodeSystemQueryResponse, NodeTransferCmd,
+        Error as ErrorMessage, Message, NodeCmd, NodeCmdError, NodeDataError, NodeEvent, NodeQuery,
+        NodeQueryResponse, NodeSystemCmd, NodeSystemQuery, NodeSystemQueryResponse,
+        NodeTransferCmd,
     },
     Aggregation, DstLocation, MessageId, SrcLocation,
 };
 use sn_routing::{Prefix, XorName};
 use sn_transfers::TransferActor;
-use std::collections::BTreeMap;
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
 
 impl Node {
+    /// Propagates the credits of a reward payout to the Elders of their recipients,
+    /// recording them as pending, for those failing to be retried.
     pub(crate) fn propagate_credits(
         credit_proofs: BTreeMap<CreditId, CreditAgreementProof>,
+        payouts: &mut PendingPayouts,
     ) -> Result<NodeDuties> {
-        use NodeCmd::*;
-        use NodeTransferCmd::*;
         let mut ops = vec![];
-
+        let now = Instant::now();
         for (_, credit_proof) in credit_proofs {
-            let location = XorName::from(credit_proof.recipient());
             let msg_id = MessageId::from_content(&credit_proof.debiting_replicas_sig)?;
-            ops.push(NodeDuty::Send(OutgoingMsg {
-                msg: Message::NodeCmd {
-                    cmd: Transfers(PropagateTransfer(credit_proof)),
-                    id: msg_id,
-                    target_section_pk: None,
-                },
-                section_source: true, // i.e. errors go to our section
-                dst: DstLocation::Section(location),
-                aggregation: Aggregation::AtDestination, // not necessary, but will be slimmer
-            }))
+            payouts.sent(msg_id, credit_proof.clone(), now)?;
+            ops.push(propagate_credit(credit_proof, msg_id));
+        }
+        Ok(ops)
+    }
+
+    /// Propagates anew the reward payouts whose retry is due.
+    pub(crate) fn retry_reward_payouts(&mut self) -> Result<NodeDuties> {
+        let payouts = match &mut self.section_funds {
+            Some(funds) => funds.pending_payouts(),
+            None => return Err(Error::NoSectionFunds),
+        };
+        Ok(payouts
+            .due(Instant::now())?
+            .into_iter()
+            .map(|(msg_id, credit_proof)| {
+                debug!(
+                    "Retrying payout of {} to {}",
+                    credit_proof.amount(),
+                    credit_proof.recipient()
+                );
+                propagate_credit(credit_proof, msg_id)
+            })
+            .collect())
+    }
+
+    /// Sends anew our signature shares of the reward payouts stuck awaiting the agreement of
+    /// the other Elders, giving up on them after a few times, their payouts then recorded as failed.
+    /// Their rewards are not accrued anew, as the other Elders may have paid them out.
+    pub(crate) async fn resend_stuck_rewards(&mut self) -> Result<NodeDuties> {
+        let timeout = Duration::from_secs(self.node_info.reward_payout_timeout);
+        let mut ops = vec![];
+        let mut given_up = vec![];
+        if let Some(SectionFunds::Churning {
+            processes, payouts, ..
+        }) = &mut self.section_funds
+        {
+            for (section_key, process) in processes.iter_mut() {
+                let credits = match process.resend_if_stuck(Instant::now(), timeout).await? {
+                    None => continue,
+                    Some(Ok(msg)) => {
+                        warn!(
+                            "Reward payout of the churn at {} stuck, sending our shares anew.",
+                            section_key
+                        );
+                        ops.push(msg);
+                        continue;
+                    }
+                    Some(Err(credits)) => credits,
+                };
+                for credit in &credits {
+                    payouts.abandoned(credit)?;
+                    error!(
+                        "Payout of {} to {} failed, stuck awaiting the other Elders.",
+                        credit.amount, credit.recipient
+                    );
+                }
+                error!(
+                    "Giving up on reward payout of {} credits for the churn at {}, stuck awaiting the other Elders.",
+                    credits.len(),
+                    section_key
+                );
+                given_up.push(*section_key);
+            }
+        }
+        for section_key in given_up {
+            self.section_funds = self
+                .section_funds
+                .take()
+                .map(|funds| funds.end_churn(section_key));
         }
         Ok(ops)
     }
 
+    /// Schedules the retry of a reward payout which failed at its recipient's Elders,
+    /// giving up on it once it failed too many times.
+    pub(crate) fn record_payout_failure(
+        &mut self,
+        correlation_id: MessageId,
+        error: ErrorMessage,
+    ) -> Result<()> {
+        let payouts = match &mut self.section_funds {
+            Some(funds) => funds.pending_payouts(),
+            None => return Err(Error::NoSectionFunds),
+        };
+        if let Some(payout) = payouts.failed(&correlation_id, error.clone(), Instant::now())? {
+            error!(
+                "Giving up on payout of {} to {} after {} attempts: {:?}",
+                payout.credit_proof.amount(),
+                payout.credit_proof.recipient(),
+                payout.attempts,
+                error
+            );
+        } else {
+            warn!(
+                "Payout {:?} failed, to be retried: {:?}",
+                correlation_id, error
+            );
+        }
+        Ok(())
+    }
+
     /// https://github.com/rust-lang/rust-clippy/issues?q=is%3Aissue+is%3Aopen+eval_order_dependence
     #[allow(clippy::eval_order_dependence)]
     pub(crate) async fn get_section_elders(
@@ -109,6 +202,56 @@ impl Node {
         }))
     }
 
+    /// Lets the Elders of the chunk's section know that we now hold it, so that they
+    /// count us among its holders. Signed by us, for them to tell it's from us.
+    pub(crate) async fn report_replication_completed(
+        &self,
+        address: BlobAddress,
+        correlation_id: MessageId,
+    ) -> Result<NodeDuty> {
+        let proof = self.network_api.sign_as_node(&address).await?;
+        Ok(NodeDuty::Send(OutgoingMsg {
+            msg: Message::NodeEvent {
+                event: NodeEvent::ReplicationCompleted {
+                    chunk: address,
+                    proof,
+                },
+                id: MessageId::in_response_to(&correlation_id),
+                correlation_id,
+                target_section_pk: None,
+            },
+            section_source: false, // sent as single node
+            dst: DstLocation::Section(*address.name()),
+            aggregation: Aggregation::None,
+        }))
+    }
+
+    /// Lets the Elders of the chunk's section know that we failed to replicate it,
+    /// so that they count the failure, or re-issue the replication if we had no room for it.
+    pub(crate) fn report_replication_failure(
+        &self,
+        address: BlobAddress,
+        error: ErrorMessage,
+        correlation_id: MessageId,
+    ) -> NodeDuty {
+        log::warn!(
+            "Could not replicate chunk {:?}, reporting it: {:?}",
+            address,
+            error
+        );
+        NodeDuty::Send(OutgoingMsg {
+            msg: Message::NodeCmdError {
+                error: NodeCmdError::Data(NodeDataError::ChunkReplication { address, error }),
+                id: MessageId::in_response_to(&correlation_id),
+                correlation_id,
+                target_section_pk: None,
+            },
+            section_source: false, // sent as single node
+            dst: DstLocation::Section(*address.name()),
+            aggregation: Aggregation::None,
+        })
+    }
+
     ///
     pub(crate) async fn register_wallet(&self) -> OutgoingMsg {
         let address = self.network_api.our_prefix().await.name();
@@ -165,3 +308,17 @@ impl Node {
         })
     }
 }
+
+fn propagate_credit(credit_proof: CreditAgreementProof, msg_id: MessageId) -> NodeDuty {
+    let location = XorName::from(credit_proof.recipient());
+    NodeDuty::Send(OutgoingMsg {
+        msg: Message::NodeCmd {
+            cmd: NodeCmd::Transfers(NodeTransferCmd::PropagateTransfer(credit_proof)),
+            id: msg_id,
+            target_section_pk: None,
+        },
+        section_source: true, // i.e. errors go to our section
+        dst: DstLocation::Section(location),
+        aggregation: Aggregation::AtDestination, // not necessary, but will be slimmer
+    })
+}
@@ -8,9 +8,10 @@
 
 use crate::{
     capacity::{Capacity, ChunkHolderDbs, RateLimit},
+    chunks::Chunks,
     metadata::{adult_reader::AdultReader, Metadata},
-    node_ops::NodeDuty,
-    section_funds::{reward_wallets::RewardWallets, SectionFunds},
+    node_ops::{NodeDuties, NodeDuty},
+    section_funds::{payout_ledger::PayoutLedger, reward_wallets::RewardWallets, SectionFunds},
     transfers::get_replicas::{replica_info, transfer_replicas},
     transfers::Transfers,
     Error, Node, Result,
@@ -40,36 +41,52 @@ impl Node {
         Ok(())
     }
 
-    /// Level up a newbie to an oldie on promotion
-    pub async fn level_up(&mut self) -> Result<()> {
+    /// Level up a newbie to an oldie on promotion.
+    /// The chunks held as Adult are kept on disk, and still accounted for in the used space,
+    /// for them to be served again if demoted. The state persisted by a previous stint as
+    /// Elder is loaded by the metadata stores.
+    pub async fn level_up(&mut self) -> Result<NodeDuties> {
         //
         // do not hande immutable chunks anymore
-        self.chunks = None;
-        self.used_space.reset().await;
+        let duties = match self.chunks.take() {
+            Some(mut chunks) => chunks.flush_all_writes().await,
+            None => vec![],
+        };
 
         //
         // start handling metadata
-        let dbs = ChunkHolderDbs::new(self.node_info.path())?;
+        let dbs = ChunkHolderDbs::new(self.node_info.metadata_path())?;
         let reader = AdultReader::new(self.network_api.clone());
-        let meta_data =
-            Metadata::new(&self.node_info.path(), &self.used_space, dbs, reader).await?;
+        let meta_data = Metadata::new(&self.node_info, &self.used_space, dbs, reader).await?;
         self.meta_data = Some(meta_data);
 
         //
         // start handling transfers
-        let dbs = ChunkHolderDbs::new(self.node_info.root_dir.as_path())?;
+        let dbs = ChunkHolderDbs::new(self.node_info.metadata_path())?;
         let rate_limit = RateLimit::new(self.network_api.clone(), Capacity::new(dbs.clone()));
         let user_wallets = BTreeMap::<PublicKey, ActorHistory>::new();
         let replicas = transfer_replicas(&self.node_info, &self.network_api, user_wallets).await?;
-        self.transfers = Some(Transfers::new(replicas, rate_limit));
+        self.transfers = Some(Transfers::new(
+            replicas,
+            rate_limit,
+            self.node_info.strict_payment_checks,
+        ));
 
         //
         // start handling node rewards
-        self.section_funds = Some(SectionFunds::KeepingNodeWallets {
-            wallets: RewardWallets::new(BTreeMap::<XorName, (NodeAge, PublicKey)>::new()),
-            payments: Default::default(),
-        });
+        self.section_funds = Some(SectionFunds::from_history(
+            self.node_info.root_dir.as_path(),
+        )?);
 
+        Ok(duties)
+    }
+
+    /// Level down an oldie to a newbie on demotion, handling the chunks held before promotion.
+    pub async fn level_down(&mut self) -> Result<()> {
+        self.meta_data = None;
+        self.transfers = None;
+        self.section_funds = None;
+        self.chunks = Some(Chunks::new(&self.node_info, self.used_space.clone(), false).await?);
         Ok(())
     }
 
@@ -14,12 +14,12 @@ mod split;
 
 use crate::{
     capacity::{Capacity, ChunkHolderDbs, RateLimit},
-    chunk_store::UsedSpace,
-    chunks::Chunks,
+    chunk_store::{StorageStats, UsedSpace, UsedSpaceCheck},
+    chunks::{ChunkStatus, Chunks, StorageProof},
     event_mapping::{map_routing_event, LazyError, Mapping, MsgContext},
-    metadata::{adult_reader::AdultReader, Metadata},
+    metadata::{adult_reader::AdultReader, Metadata, ReplicationStatus},
     node_ops::{NodeDuties, NodeDuty},
-    section_funds::SectionFunds,
+    section_funds::{PayoutRecord, SectionFunds},
     state_db::store_new_reward_keypair,
     transfers::get_replicas::transfer_replicas,
     transfers::Transfers,
@@ -30,18 +30,41 @@ use ed25519_dalek::PublicKey as Ed25519PublicKey;
 use futures::lock::Mutex;
 use hex_fmt::HexFmt;
 use log::{debug, error, info, trace, warn};
-use sn_data_types::{ActorHistory, PublicKey, TransferPropagated, WalletHistory};
+use sn_data_types::{
+    ActorHistory, BlobAddress, PublicKey, Signature, Token, TransferPropagated, WalletHistory,
+};
 use sn_messaging::{client::Message, DstLocation, SrcLocation};
 use sn_routing::{Event as RoutingEvent, EventStream, NodeElderChange, MIN_AGE};
 use sn_routing::{Prefix, XorName, ELDER_SIZE as GENESIS_ELDER_COUNT};
 use sn_transfers::{TransferActor, Wallet};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::{
     fmt::{self, Display, Formatter},
     net::SocketAddr,
+    time::SystemTime,
 };
+use tokio::time::{interval, Duration};
+
+/// How often the chunks held are scrubbed, the amount scrubbed being throttled by the scrub rate.
+const SCRUB_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often the chunks queued for replication are checked for being due to be written.
+const WRITE_FLUSH_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How often the replications to this node are checked for having timed out.
+const PENDING_OPS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often the next batch of chunk replications scheduled after nodes left is issued,
+/// the size of the batch being throttled by the replication rate.
+const CHUNK_REPLICATION_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often the reward payouts which failed are checked for being due to be retried.
+const PAYOUT_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often the config file is checked for changes to apply to the running node.
+const CONFIG_RELOAD_INTERVAL: Duration = Duration::from_secs(10);
 
 /// Static info about the node.
 #[derive(Clone)]
@@ -50,12 +73,55 @@ pub struct NodeInfo {
     pub genesis: bool,
     ///
     pub root_dir: PathBuf,
+    /// Root directory for the chunks held as an Adult.
+    pub chunk_root_dir: PathBuf,
+    /// Root directory for the Maps, Sequences and chunk metadata held as an Elder.
+    pub metadata_root_dir: PathBuf,
     ///
     pub node_name: XorName,
     ///
     pub node_id: Ed25519PublicKey,
     /// The key used by the node to receive earned rewards.
     pub reward_key: PublicKey,
+    /// Size in bytes of the in-memory cache of chunks held as an Adult.
+    pub chunk_cache_size: u64,
+    /// Number of chunks per minute to verify when scrubbing the chunks held as an Adult.
+    pub chunk_scrub_rate: u64,
+    /// Max space in bytes the quarantined corrupted chunks may consume.
+    pub chunk_quarantine_capacity: u64,
+    /// Seconds to wait for a copy of a chunk being replicated to us.
+    pub chunk_replication_timeout: u64,
+    /// Max number of chunks being replicated to us at once.
+    pub max_chunk_replications: usize,
+    /// Max number of chunk replications waiting for one in flight to complete.
+    pub chunk_replication_queue_size: usize,
+    /// Percentages of the max capacity whose reaching by the used space is notified.
+    pub storage_thresholds: Vec<u8>,
+    /// Seconds for which the chunk reads and writes handled are remembered.
+    pub recent_msg_timeout: u64,
+    /// Max number of chunk reads and writes remembered.
+    pub max_recent_msgs: usize,
+    /// Max number of chunk replication cmds sent per second as an Elder, after nodes left.
+    pub chunk_replication_rate: usize,
+    /// Number of replication failures after which, as an Elder, a chunk is deemed lost.
+    pub max_chunk_replication_failures: usize,
+    /// Max number of Maps a single owner may have stored as an Elder, 0 meaning unlimited.
+    pub max_maps_per_owner: u64,
+    /// Max number of Sequence entries in a range response as an Elder.
+    pub max_sequence_range_entries: usize,
+    /// Max size in bytes of the Sequence entries in a range response as an Elder.
+    pub max_sequence_range_size: u64,
+    /// Max number of data requests per second of a single client as an Elder, 0 meaning unlimited.
+    pub max_client_requests_per_sec: usize,
+    /// Seconds to wait for the other Elders to agree on a reward payout as an Elder,
+    /// before our signature shares of it are sent anew.
+    pub reward_payout_timeout: u64,
+    /// Whether to hold chunks in memory rather than on disk.
+    pub in_memory_chunks: bool,
+    /// Whether to reject the data cmds whose payment wasn't signed by the requester, as an Elder.
+    pub strict_payment_checks: bool,
+    /// The key allowed to query the section funds as an Elder, besides our section's members.
+    pub funds_monitor_key: Option<PublicKey>,
 }
 
 impl NodeInfo {
@@ -63,6 +129,16 @@ impl NodeInfo {
     pub fn path(&self) -> &Path {
         self.root_dir.as_path()
     }
+
+    /// Root directory for the chunks held as an Adult.
+    pub fn chunk_path(&self) -> &Path {
+        self.chunk_root_dir.as_path()
+    }
+
+    /// Root directory for the Maps, Sequences and chunk metadata held as an Elder.
+    pub fn metadata_path(&self) -> &Path {
+        self.metadata_root_dir.as_path()
+    }
 }
 
 /// Main node struct.
@@ -80,6 +156,8 @@ pub struct Node {
     transfers: Option<Transfers>,
     // reward payouts
     section_funds: Option<SectionFunds>,
+    // when the config file was last modified, as last applied
+    config_modified: Option<SystemTime>,
 }
 
 impl Node {
@@ -110,27 +188,56 @@ impl Node {
         .await;
 
         let reward_key = reward_key_task?;
+        let funds_monitor_key = match &config.funds_monitor_key {
+            Some(key) => Some(PublicKey::Bls(crate::state_db::pk_from_hex(key)?)),
+            None => None,
+        };
         let (network_api, network_events) = Network::new(config).await?;
 
         let node_info = NodeInfo {
             genesis: config.is_first(),
             root_dir: root_dir_buf,
+            chunk_root_dir: config.chunk_root_dir()?,
+            metadata_root_dir: config.metadata_root_dir()?,
             node_name: network_api.our_name().await,
             node_id: network_api.public_key().await,
             reward_key,
+            chunk_cache_size: config.chunk_cache_size(),
+            chunk_scrub_rate: config.chunk_scrub_rate(),
+            chunk_quarantine_capacity: config.chunk_quarantine_capacity(),
+            chunk_replication_timeout: config.chunk_replication_timeout(),
+            max_chunk_replications: config.max_chunk_replications(),
+            chunk_replication_queue_size: config.chunk_replication_queue_size(),
+            storage_thresholds: config.storage_thresholds(),
+            recent_msg_timeout: config.recent_msg_timeout(),
+            max_recent_msgs: config.max_recent_msgs(),
+            chunk_replication_rate: config.chunk_replication_rate(),
+            max_chunk_replication_failures: config.max_chunk_replication_failures(),
+            max_maps_per_owner: config.max_maps_per_owner(),
+            max_sequence_range_entries: config.max_sequence_range_entries(),
+            max_sequence_range_size: config.max_sequence_range_size(),
+            max_client_requests_per_sec: config.max_client_requests_per_sec(),
+            reward_payout_timeout: config.reward_payout_timeout(),
+            in_memory_chunks: config.in_memory_chunks,
+            strict_payment_checks: config.strict_payment_checks,
+            funds_monitor_key,
         };
 
-        let used_space = UsedSpace::new(config.max_capacity());
+        let used_space_check = if config.skip_used_space_check {
+            UsedSpaceCheck::Off
+        } else if config.fast_used_space_check {
+            UsedSpaceCheck::Fast
+        } else {
+            UsedSpaceCheck::Full
+        };
+        let used_space = UsedSpace::new(config.max_capacity())
+            .with_check(used_space_check)
+            .with_known_stores(&node_info.root_dir);
 
         let node = Self {
             prefix: network_api.our_prefix().await,
             chunks: Some(
-                Chunks::new(
-                    node_info.node_name,
-                    node_info.root_dir.as_path(),
-                    used_space.clone(),
-                )
-                .await?,
+                Chunks::new(&node_info, used_space.clone(), config.compact_chunk_store).await?,
             ),
             node_info,
             used_space,
@@ -139,6 +246,7 @@ impl Node {
             meta_data: None,
             transfers: None,
             section_funds: None,
+            config_modified: Config::file_modified(),
         };
 
         messaging::send(node.register_wallet().await, &node.network_api).await;
@@ -146,6 +254,70 @@ impl Node {
         Ok(node)
     }
 
+    /// Returns the breakdown of the storage used by each of our chunk stores.
+    pub async fn storage_stats(&self) -> StorageStats {
+        self.used_space.stats().await
+    }
+
+    /// Returns the number of chunks held as an Adult, the space they consume,
+    /// the hits of their cache and the progress of their scrubbing, or None if not an Adult.
+    pub async fn chunk_status(&self) -> Option<ChunkStatus> {
+        match &self.chunks {
+            Some(chunks) => Some(chunks.status().await),
+            None => None,
+        }
+    }
+
+    /// Proves holding the chunk as an Adult, in answer to an audit challenge with the nonce,
+    /// the proof being signed with our node key.
+    pub async fn prove_storage(
+        &self,
+        address: BlobAddress,
+        nonce: Vec<u8>,
+    ) -> Result<(StorageProof, Signature)> {
+        let chunks = self.chunks.as_ref().ok_or(Error::NoChunks)?;
+        let proof = chunks.prove_storage(address, nonce)?;
+        let signature = self.network_api.sign_as_node(&proof).await?;
+        Ok((proof, signature))
+    }
+
+    /// Returns the chunks deemed lost as an Elder, as they could no longer be replicated.
+    pub async fn lost_chunks(&self) -> Result<BTreeSet<BlobAddress>> {
+        match &self.meta_data {
+            Some(meta_data) => meta_data.lost_chunks().await,
+            None => Ok(BTreeSet::new()),
+        }
+    }
+
+    /// Returns the balance of the section funds and their latest payouts, oldest first, as an
+    /// Elder, to a node of our section or the monitoring key configured.
+    pub async fn section_funds_report(
+        &self,
+        requester: PublicKey,
+    ) -> Result<(Token, Vec<PayoutRecord>)> {
+        let is_member = matches!(requester, PublicKey::Ed25519(_))
+            && self
+                .network_api
+                .our_members()
+                .await
+                .contains_key(&XorName::from(requester));
+        if !is_member && self.node_info.funds_monitor_key != Some(requester) {
+            return Err(Error::AccessDenied(requester));
+        }
+        match &self.section_funds {
+            Some(funds) => Ok((funds.balance(), funds.payout_history())),
+            None => Err(Error::NoSectionFunds),
+        }
+    }
+
+    /// Returns how far along the replication of chunks after churn is, as an Elder.
+    pub fn replication_status(&self) -> ReplicationStatus {
+        self.meta_data
+            .as_ref()
+            .map(Metadata::replication_status)
+            .unwrap_or_default()
+    }
+
     /// Returns our connection info.
     pub fn our_connection_info(&mut self) -> SocketAddr {
         self.network_api.our_connection_info()
@@ -165,18 +337,142 @@ impl Node {
     /// Blocks until the node is terminated, which is done
     /// by client sending in a `Command` to free it.
     pub async fn run(&mut self) -> Result<()> {
-        while let Some(event) = self.network_events.next().await {
-            // tokio spawn should only be needed around intensive tasks, ie sign/verify
-            match map_routing_event(event, &self.network_api).await {
-                Mapping::Ok { op, ctx } => self.process_while_any(op, ctx).await,
-                Mapping::Error(error) => handle_error(error),
+        let mut scrub_interval = interval(SCRUB_INTERVAL);
+        let mut flush_interval = interval(WRITE_FLUSH_INTERVAL);
+        let mut pending_ops_interval = interval(PENDING_OPS_INTERVAL);
+        let mut replication_interval = interval(CHUNK_REPLICATION_INTERVAL);
+        let mut payout_retry_interval = interval(PAYOUT_RETRY_INTERVAL);
+        let mut config_reload_interval = interval(CONFIG_RELOAD_INTERVAL);
+        loop {
+            tokio::select! {
+                event = self.network_events.next() => {
+                    let event = match event {
+                        Some(event) => event,
+                        None => break,
+                    };
+                    // tokio spawn should only be needed around intensive tasks, ie sign/verify
+                    match map_routing_event(event, &self.network_api).await {
+                        Mapping::Ok { op, ctx } => self.process_while_any(op, ctx).await,
+                        Mapping::Error(error) => handle_error(error),
+                    }
+                }
+                _ = scrub_interval.tick() => {
+                    if self.chunks.is_some() {
+                        self.process_while_any(NodeDuty::ScrubChunks, None).await
+                    }
+                }
+                _ = flush_interval.tick() => {
+                    if self.chunks.is_some() {
+                        self.process_while_any(NodeDuty::FlushChunkWrites, None).await
+                    }
+                }
+                _ = pending_ops_interval.tick() => {
+                    if self.chunks.is_some() {
+                        self.process_while_any(NodeDuty::CheckPendingReplications, None).await
+                    }
+                }
+                _ = replication_interval.tick() => {
+                    if self.meta_data.is_some() {
+                        self.process_while_any(NodeDuty::ContinueChunkReplication, None).await
+                    }
+                }
+                _ = payout_retry_interval.tick() => {
+                    if self.section_funds.is_some() {
+                        self.process_while_any(NodeDuty::RetryRewardPayouts, None).await
+                    }
+                }
+                _ = config_reload_interval.tick() => self.reload_config().await,
             }
         }
 
+        self.close().await;
+        Ok(())
+    }
+
+    /// Applies the settings of `config` which can be changed while the node runs: the max
+    /// capacity, the chunk cache size, the storage thresholds and the rate limits. The other
+    /// settings are only read at startup, and those locating or holding the data (the root
+    /// directories and in-memory chunks) are refused to change, nothing being applied then.
+    pub async fn update_config(&mut self, config: &Config) -> Result<()> {
+        let fixed = [
+            ("root_dir", self.node_info.root_dir != config.root_dir()?),
+            (
+                "chunk_root_dir",
+                self.node_info.chunk_root_dir != config.chunk_root_dir()?,
+            ),
+            (
+                "metadata_root_dir",
+                self.node_info.metadata_root_dir != config.metadata_root_dir()?,
+            ),
+            (
+                "in_memory_chunks",
+                self.node_info.in_memory_chunks != config.in_memory_chunks,
+            ),
+        ];
+        if let Some((name, _)) = fixed.iter().find(|(_, changed)| *changed) {
+            return Err(Error::Configuration(format!(
+                "{} can't be changed without restarting the node",
+                name
+            )));
+        }
+
+        self.node_info.chunk_cache_size = config.chunk_cache_size();
+        self.node_info.storage_thresholds = config.storage_thresholds();
+        self.node_info.chunk_replication_rate = config.chunk_replication_rate();
+        self.node_info.max_client_requests_per_sec = config.max_client_requests_per_sec();
+
+        self.used_space
+            .set_max_capacity(config.max_capacity())
+            .await;
+        if let Some(chunks) = &mut self.chunks {
+            chunks.set_max_capacity(config.max_capacity()).await;
+            chunks.set_cache_size(self.node_info.chunk_cache_size);
+            chunks.set_storage_thresholds(self.node_info.storage_thresholds.clone());
+        }
+        if let Some(meta_data) = &mut self.meta_data {
+            meta_data.set_rate_limits(
+                self.node_info.chunk_replication_rate,
+                self.node_info.max_client_requests_per_sec,
+            );
+        }
+        info!("Applied the changes to the config");
         Ok(())
     }
 
+    // Applies the config file anew if it was modified since last applied.
+    async fn reload_config(&mut self) {
+        let modified = Config::file_modified();
+        if modified.is_none() || modified == self.config_modified {
+            return;
+        }
+        self.config_modified = modified;
+        let result = match Config::reload() {
+            Ok(config) => self.update_config(&config).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            warn!("Changes to the config file not applied: {}", e);
+        }
+    }
+
+    /// Settles the state pending in memory before the node shuts down: the replicated chunks
+    /// queued for writing are written, and the replications in flight are reported as failed
+    /// to Elders. Called once the event loop ends, and by embedders stopping the node otherwise.
+    /// The metadata and the used space are written on each change, so are already on disk.
+    pub async fn close(&mut self) {
+        let duties = match &mut self.chunks {
+            Some(chunks) => chunks.close().await,
+            None => return,
+        };
+        for duty in duties {
+            self.process_while_any(duty, None).await;
+        }
+    }
+
     /// Keeps processing resulting node operations.
+    /// All the operations resulting from one are processed, in the order they were returned,
+    /// before any of those they result in, so a handler may return several, e.g. a response
+    /// to the client followed by a notification to Elders.
     async fn process_while_any(&mut self, op: NodeDuty, ctx: Option<MsgContext>) {
         let mut next_ops = vec![op];
 
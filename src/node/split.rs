@@ -17,7 +17,7 @@ use crate::{
     Error, Node, Result,
 };
 use dashmap::DashMap;
-use log::{debug, info};
+use log::{debug, info, warn};
 use section_funds::{
     elder_signing::ElderSigning,
     reward_process::{OurSection, RewardProcess},
@@ -46,7 +46,7 @@ impl Node {
         &mut self,
         our_key: PublicKey,
         our_prefix: Prefix,
-    ) -> Result<()> {
+    ) -> Result<NodeDuties> {
         let section_key = self.network_api.section_public_key().await?;
         if our_key != section_key {
             return Err(Error::Logic(format!(
@@ -57,7 +57,7 @@ impl Node {
 
         debug!("begin_split_as_newbie");
 
-        self.level_up().await?;
+        let duties = self.level_up().await?;
 
         let section = OurSection {
             our_prefix,
@@ -67,15 +67,14 @@ impl Node {
         let mut process =
             RewardProcess::new(section, ElderSigning::new(self.network_api.clone()).await?);
 
-        let wallets = RewardWallets::new(BTreeMap::<XorName, (NodeAge, PublicKey)>::new());
-
-        self.section_funds = Some(SectionFunds::Churning {
-            process,
-            wallets,
-            payments: Default::default(),
-        });
+        // set up by the level up
+        let funds = match self.section_funds.take() {
+            Some(funds) => funds,
+            None => SectionFunds::from_history(self.node_info.root_dir.as_path())?,
+        };
+        self.section_funds = Some(funds.into_churning(process));
 
-        Ok(())
+        Ok(duties)
     }
 
     /// Called on split reported from routing layer.
@@ -93,11 +92,26 @@ impl Node {
             return Err(Error::Logic("No transfers on this node".to_string()));
         };
 
-        let (wallets, payments) = match &mut self.section_funds {
-            Some(SectionFunds::KeepingNodeWallets { wallets, payments })
-            | Some(SectionFunds::Churning {
-                wallets, payments, ..
-            }) => (wallets.clone(), payments.sum()),
+        let wallets = match &mut self.section_funds {
+            Some(SectionFunds::KeepingNodeWallets { wallets, .. })
+            | Some(SectionFunds::Churning { wallets, .. }) => wallets.clone(),
+            None => return Err(Error::NoSectionFunds),
+        };
+
+        // the nodes yet to register a wallet are not rewarded
+        for node in self.network_api.our_members().await.keys() {
+            if wallets.get(node).is_none() {
+                warn!(
+                    "Withholding the reward of {}, as it has no wallet registered.",
+                    node
+                );
+            }
+        }
+
+        // our nodes are rewarded out of our share of the balance,
+        // while that of our sibling is paid out to its section
+        let (payments, sibling_share) = match &mut self.section_funds {
+            Some(funds) => funds.split(our_prefix, sibling_key)?,
             None => return Err(Error::NoSectionFunds),
         };
 
@@ -114,13 +128,19 @@ impl Node {
 
         let mut ops = vec![];
 
-        if payments > Token::zero() {
+        if payments > Token::zero() || sibling_share.is_some() {
             let section_managed = self.get_transfers()?.managed_amount().await?;
+            let calc = self.get_transfers()?.reward_calc().await;
 
-            // payments made since last churn
+            // our share of the payments made since last churn
             debug!("Payments: {}", payments);
+            if let Some(share) = &sibling_share {
+                debug!("Share of our sibling: {}", share.amount);
+            }
             // total amount in wallets
             debug!("Managed amount: {}", section_managed);
+            // paid per unit of work
+            debug!("Reward rate: {}", calc.rate());
 
             // generate reward and minting proposal
             let mut process = RewardProcess::new(
@@ -131,17 +151,34 @@ impl Node {
                 ElderSigning::new(self.network_api.clone()).await?,
             );
 
+            let accrual = match &mut self.section_funds {
+                Some(funds) => funds.reward_accrual(),
+                None => return Err(Error::NoSectionFunds),
+            };
             ops.push(
                 process
-                    .reward_and_mint(payments, section_managed, wallets.node_wallets())
+                    .reward_and_mint(
+                        payments,
+                        section_managed,
+                        calc,
+                        &wallets,
+                        accrual,
+                        sibling_share.into_iter().collect(),
+                    )
                     .await?,
             );
 
-            self.section_funds = Some(SectionFunds::Churning {
-                process,
-                wallets: wallets.clone(),
-                payments: Default::default(), // clear old payments
-            });
+            if let Some(funds) = &mut self.section_funds {
+                for credit in process.proposed_credits() {
+                    funds.pending_payouts().proposed(&credit)?;
+                }
+            }
+
+            // clears old payments
+            self.section_funds = self
+                .section_funds
+                .take()
+                .map(|funds| funds.into_churning(process));
         } else {
             debug!("Not paying out rewards, as no payments have been received since last split.");
         }
@@ -11,12 +11,12 @@ use bls::PublicKeySet;
 use sn_data_types::Transfer;
 use sn_data_types::{
     ActorHistory, Blob, BlobAddress, Credit, CreditAgreementProof, NodeAge, PublicKey,
-    ReplicaEvent, RewardAccumulation, RewardProposal, SectionElders, SignatureShare, SignedCredit,
-    SignedTransfer, SignedTransferShare, Token, TransferAgreementProof, TransferValidated,
-    WalletHistory,
+    ReplicaEvent, RewardAccumulation, RewardProposal, SectionElders, Signature, SignatureShare,
+    SignedCredit, SignedTransfer, SignedTransferShare, Token, TransferAgreementProof,
+    TransferValidated, WalletHistory,
 };
 use sn_messaging::{
-    client::{BlobRead, BlobWrite, Message, NodeSystemCmd},
+    client::{BlobRead, BlobWrite, Error as ErrorMessage, Message, NodeSystemCmd},
     Aggregation, DstLocation, EndUser, MessageId, SrcLocation,
 };
 use sn_routing::{NodeElderChange, Prefix};
@@ -160,8 +160,60 @@ pub enum NodeDuty {
         name: XorName,
         age: u8,
     },
+    /// A member joined, so the chunks it is now among
+    /// the closest Adults to are to be moved to it.
+    ProcessNewMember {
+        name: XorName,
+        /// Whether it joined after being relocated from another section.
+        relocated: bool,
+    },
     /// Storage reaching max capacity.
     ReachingMaxCapacity,
+    /// Verify the next stored chunks against their addresses.
+    ScrubChunks,
+    /// Write the chunks queued for replication to the store, if they are due.
+    FlushChunkWrites,
+    /// Drop the replications to us whose copy hasn't arrived in time.
+    CheckPendingReplications,
+    /// Issue the next batch of the chunk replications scheduled after nodes left.
+    ContinueChunkReplication,
+    /// Propagate anew the reward payouts whose retry is due.
+    RetryRewardPayouts,
+    /// A reward payout failed at the Elders of its recipient, so is to be retried.
+    RecordPayoutFailure {
+        /// The id of the propagation cmd being answered.
+        correlation_id: MessageId,
+        error: ErrorMessage,
+    },
+    /// A copy of a chunk being replicated to us could not be obtained,
+    /// or we had no room to replicate it, so Elders need to know.
+    ReportReplicationFailure {
+        address: BlobAddress,
+        error: ErrorMessage,
+        /// The id of the replication cmd being answered.
+        correlation_id: MessageId,
+    },
+    /// An Adult failed to replicate a chunk, so the failure is to be counted against
+    /// the chunk, or its replication re-issued if the Adult had no room for it.
+    RecordReplicationFailure {
+        address: BlobAddress,
+        holder: XorName,
+        error: ErrorMessage,
+    },
+    /// A chunk being replicated to us is held,
+    /// so Elders can consider its replication done.
+    ReportReplicationCompleted {
+        address: BlobAddress,
+        /// The id of the replication cmd being answered.
+        correlation_id: MessageId,
+    },
+    /// An Adult reported holding a chunk replicated to it,
+    /// so it is to be counted among the chunk's holders.
+    RecordChunkHolder {
+        address: BlobAddress,
+        holder: XorName,
+        proof: Signature,
+    },
     /// Increment count of full nodes in the network
     IncrementFullNodeCount {
         /// Node ID of node that reached max capacity.
@@ -208,12 +260,20 @@ pub enum NodeDuty {
         address: BlobAddress,
         new_holder: XorName,
         id: MessageId,
+        origin: SrcLocation,
+    },
+    /// A holder failed to provide a copy of a chunk being replicated to us,
+    /// so the next holder is to be queried.
+    ChunkCopyFailed {
+        correlation_id: MessageId,
+        holder: XorName,
     },
     /// Store a chunk that is a result of data replication
     /// on `MemberLeft`
     StoreChunkForReplication {
         data: Blob,
         correlation_id: MessageId,
+        holder: XorName,
     },
     NoOp,
 }
@@ -256,7 +316,35 @@ impl Debug for NodeDuty {
 
             Self::NoOp => write!(f, "No op."),
             Self::ReachingMaxCapacity => write!(f, "ReachingMaxCapacity"),
+            Self::ScrubChunks => write!(f, "ScrubChunks"),
+            Self::FlushChunkWrites => write!(f, "FlushChunkWrites"),
+            Self::CheckPendingReplications => write!(f, "CheckPendingReplications"),
+            Self::ContinueChunkReplication => write!(f, "ContinueChunkReplication"),
+            Self::RetryRewardPayouts => write!(f, "RetryRewardPayouts"),
+            Self::RecordPayoutFailure {
+                correlation_id,
+                error,
+            } => write!(f, "RecordPayoutFailure({:?}, {:?})", correlation_id, error),
+            Self::ReportReplicationCompleted { address, .. } => {
+                write!(f, "ReportReplicationCompleted({:?})", address)
+            }
+            Self::RecordChunkHolder {
+                address, holder, ..
+            } => write!(f, "RecordChunkHolder({:?}, {})", address, holder),
+            Self::ReportReplicationFailure { address, error, .. } => {
+                write!(f, "ReportReplicationFailure({:?}, {:?})", address, error)
+            }
+            Self::RecordReplicationFailure {
+                address,
+                holder,
+                error,
+            } => write!(
+                f,
+                "RecordReplicationFailure({:?}, {}, {:?})",
+                address, holder, error
+            ),
             Self::ProcessLostMember { .. } => write!(f, "ProcessLostMember"),
+            Self::ProcessNewMember { .. } => write!(f, "ProcessNewMember"),
             //Self::ProcessRelocatedMember { .. } => write!(f, "ProcessRelocatedMember"),
             Self::IncrementFullNodeCount { .. } => write!(f, "IncrementFullNodeCount"),
             Self::SetNodeJoinsAllowed(_) => write!(f, "SetNodeJoinsAllowed"),
@@ -269,6 +357,7 @@ impl Debug for NodeDuty {
             Self::ProcessDataPayment { .. } => write!(f, "ProcessDataPayment"),
             Self::ReplicateChunk { .. } => write!(f, "ReplicateChunk"),
             Self::GetChunkForReplication { .. } => write!(f, "GetChunkForReplication"),
+            Self::ChunkCopyFailed { .. } => write!(f, "ChunkCopyFailed"),
             Self::StoreChunkForReplication { .. } => write!(f, "StoreChunkForReplication"),
         }
     }
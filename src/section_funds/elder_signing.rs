@@ -6,14 +6,18 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::{Error, Network, Result};
+use crate::{utils, Error, Network, Result};
 use bls::PublicKeySet;
 use futures::executor::block_on as block;
+use serde::Serialize;
 use sn_data_types::{OwnerType, Result as DtResult, SignatureShare, Signing};
 
 #[derive(Clone)]
 pub struct ElderSigning {
     id: OwnerType,
+    // The key set of our Elders before the latest churn, for their shares to be told
+    // apart from invalid ones.
+    previous: Option<PublicKeySet>,
     network: Network,
 }
 
@@ -21,10 +25,31 @@ impl ElderSigning {
     pub async fn new(network: Network) -> Result<Self> {
         Ok(Self {
             id: OwnerType::Multi(network.our_public_key_set().await?),
+            previous: None,
             network,
         })
     }
 
+    /// Updates the key set of our Elders on churn,
+    /// shares by the previous Elders being rejected as stale from then on.
+    pub fn set_public_key_set(&mut self, key_set: PublicKeySet) {
+        if let OwnerType::Multi(current) = &self.id {
+            if current == &key_set {
+                return;
+            }
+            self.previous = Some(current.clone());
+        }
+        self.id = OwnerType::Multi(key_set);
+    }
+
+    /// Verifies the share is by one of our current Elders over the data.
+    pub fn verify_share<T: Serialize>(&self, share: &SignatureShare, data: &T) -> Result<()> {
+        match &self.id {
+            OwnerType::Multi(current) => verify_share(current, self.previous.as_ref(), share, data),
+            _ => Err(Error::NoSectionPublicKeySet),
+        }
+    }
+
     pub async fn our_index(&self) -> Result<usize> {
         self.network
             .our_index()
@@ -45,14 +70,14 @@ impl Signing for ElderSigning {
         self.id.clone()
     }
 
-    fn sign<T: serde::Serialize>(&self, data: &T) -> DtResult<sn_data_types::Signature> {
+    fn sign<T: Serialize>(&self, data: &T) -> DtResult<sn_data_types::Signature> {
         use sn_data_types::Error as DtError;
         Ok(sn_data_types::Signature::BlsShare(
             block(self.network.sign_as_elder(data)).map_err(|_| DtError::InvalidOperation)?,
         ))
     }
 
-    fn verify<T: serde::Serialize>(&self, sig: &sn_data_types::Signature, data: &T) -> bool {
+    fn verify<T: Serialize>(&self, sig: &sn_data_types::Signature, data: &T) -> bool {
         let data = match bincode::serialize(data) {
             Ok(data) => data,
             Err(_) => return false,
@@ -84,3 +109,64 @@ impl Signing for ElderSigning {
         }
     }
 }
+
+fn verify_share<T: Serialize>(
+    current: &PublicKeySet,
+    previous: Option<&PublicKeySet>,
+    share: &SignatureShare,
+    data: &T,
+) -> Result<()> {
+    let data = utils::serialise(data)?;
+    let signed_by = |set: &PublicKeySet| {
+        set.public_key_share(share.index)
+            .verify(&share.share, &data)
+    };
+    if signed_by(current) {
+        Ok(())
+    } else if previous.map_or(false, signed_by) {
+        Err(Error::StaleRewardSignatureShare(share.index))
+    } else {
+        Err(Error::InvalidRewardSignatureShare(share.index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::verify_share;
+    use crate::{utils, Error, Result};
+    use sn_data_types::SignatureShare;
+
+    #[test]
+    fn only_shares_by_current_elders_are_valid() -> Result<()> {
+        let mut rng = rand::thread_rng();
+        let (previous, current) = (
+            bls::SecretKeySet::random(1, &mut rng),
+            bls::SecretKeySet::random(1, &mut rng),
+        );
+        let data = "credit";
+        let share = |set: &bls::SecretKeySet, data: &str| -> Result<SignatureShare> {
+            Ok(SignatureShare {
+                share: set.secret_key_share(1).sign(utils::serialise(&data)?),
+                index: 1,
+            })
+        };
+        let verify = |share: &SignatureShare| {
+            verify_share(
+                &current.public_keys(),
+                Some(&previous.public_keys()),
+                share,
+                &data,
+            )
+        };
+
+        verify(&share(&current, data)?)?;
+        match verify(&share(&previous, data)?) {
+            Err(Error::StaleRewardSignatureShare(1)) => (),
+            other => return Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+        match verify(&share(&current, "other credit")?) {
+            Err(Error::InvalidRewardSignatureShare(1)) => Ok(()),
+            other => Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+    }
+}
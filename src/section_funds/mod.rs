@@ -7,22 +7,38 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 pub mod elder_signing;
-mod reward_calc;
+pub(crate) mod payout_history;
+pub(crate) mod payout_ledger;
+pub(crate) mod pending_payouts;
+pub(crate) mod reward_accrual;
+pub mod reward_calc;
 pub mod reward_process;
 pub mod reward_stage;
 pub mod reward_wallets;
+#[cfg(test)]
+mod test_utils;
+pub(crate) mod wallet_registry;
 
-use self::{reward_process::RewardProcess, reward_wallets::RewardWallets};
+pub use self::payout_history::{PayoutRecord, PayoutStatus};
+
+use self::{
+    payout_ledger::PayoutLedger, pending_payouts::PendingPayouts, reward_accrual::RewardAccrual,
+    reward_calc::split_balance, reward_process::RewardProcess, reward_wallets::RewardWallets,
+    wallet_registry::WalletRegistry,
+};
 use super::node_ops::{NodeDuty, OutgoingMsg};
-use crate::Result;
+use crate::{Error, Result};
 use dashmap::DashMap;
-use sn_data_types::{CreditAgreementProof, CreditId, NodeAge, PublicKey, SectionElders, Token};
+use log::{debug, warn};
+use sn_data_types::{
+    Credit, CreditAgreementProof, CreditId, NodeAge, PublicKey, SectionElders, Token,
+};
 use sn_messaging::{
     client::{Message, NodeQuery, NodeSystemQuery},
     Aggregation, DstLocation, MessageId, SrcLocation,
 };
-use sn_routing::XorName;
-use std::collections::BTreeMap;
+use sn_routing::{Prefix, XorName};
+use std::{collections::BTreeMap, path::Path, time::Instant};
 
 /// The management of section funds,
 /// via the usage of a distributed AT2 Actor.
@@ -31,21 +47,66 @@ pub enum SectionFunds {
     KeepingNodeWallets {
         wallets: RewardWallets,
         payments: Payments,
+        payouts: PendingPayouts,
+        ledger: PayoutLedger,
+        accrual: RewardAccrual,
+        registry: WalletRegistry,
     },
+    /// Paying out rewards, by a process for each churn, keyed by the section key at it,
+    /// for a churn to be paid out while that of a previous one is still in progress.
     Churning {
-        process: RewardProcess,
+        processes: BTreeMap<PublicKey, RewardProcess>,
         wallets: RewardWallets,
         payments: Payments,
+        payouts: PendingPayouts,
+        ledger: PayoutLedger,
+        accrual: RewardAccrual,
+        registry: WalletRegistry,
     },
 }
 
 impl SectionFunds {
-    /// Adds payment
-    pub fn add_payment(&self, credit: CreditAgreementProof) {
-        // todo: validate
+    /// The funds of an Elder, with the reward payouts made, those pending, the rewards
+    /// accrued and the wallets registered as kept at the path, for those in progress at a
+    /// restart to be resumed.
+    pub(crate) fn from_history(path: &Path) -> Result<Self> {
+        let registry = WalletRegistry::new(path)?;
+        Ok(Self::KeepingNodeWallets {
+            wallets: RewardWallets::new(registry.wallets()),
+            payments: Default::default(),
+            payouts: PendingPayouts::new(path, Instant::now())?,
+            ledger: PayoutLedger::new(path)?,
+            accrual: RewardAccrual::new(path)?,
+            registry,
+        })
+    }
+
+    /// Credits a payment to the section, i.e. a store cost paid by a client, to the balance
+    /// the payouts at the next churn are drawn from. A payment is credited once only by id.
+    /// Returns whether it was credited, i.e. not seen before.
+    pub fn add_payment(&self, credit: CreditAgreementProof) -> bool {
+        match &self {
+            Self::Churning { payments, .. } | Self::KeepingNodeWallets { payments, .. } => {
+                payments.credit(credit)
+            }
+        }
+    }
+
+    /// The balance of the section, i.e. the payments received since the last churn,
+    /// less the payouts drawn from them.
+    pub fn balance(&self) -> Token {
         match &self {
             Self::Churning { payments, .. } | Self::KeepingNodeWallets { payments, .. } => {
-                let _ = payments.insert(*credit.id(), credit);
+                payments.balance()
+            }
+        }
+    }
+
+    /// Draws the amount of a payout from the balance of the section.
+    pub(crate) fn draw(&mut self, amount: Token) -> Result<()> {
+        match self {
+            Self::Churning { payments, .. } | Self::KeepingNodeWallets { payments, .. } => {
+                payments.draw(amount)
             }
         }
     }
@@ -69,36 +130,274 @@ impl SectionFunds {
         }
     }
 
-    /// Nodes register/updates wallets for future reward payouts.
-    pub fn set_node_wallet(&self, node_id: XorName, wallet: PublicKey, age: u8) {
-        match &self {
-            Self::Churning { wallets, .. } | Self::KeepingNodeWallets { wallets, .. } => {
-                wallets.set_node_wallet(node_id, age, wallet)
+    /// Starts paying out rewards with the process, the payments received until now being
+    /// accounted for by it. The processes of previous churns still in progress are kept.
+    pub(crate) fn into_churning(self, process: RewardProcess) -> Self {
+        let (mut processes, wallets, payouts, ledger, accrual, registry) = match self {
+            Self::Churning {
+                processes,
+                wallets,
+                payouts,
+                ledger,
+                accrual,
+                registry,
+                ..
+            } => (processes, wallets, payouts, ledger, accrual, registry),
+            Self::KeepingNodeWallets {
+                wallets,
+                payouts,
+                ledger,
+                accrual,
+                registry,
+                ..
+            } => (BTreeMap::new(), wallets, payouts, ledger, accrual, registry),
+        };
+        if processes.insert(process.section_key(), process).is_some() {
+            warn!("Reward payout of the same churn started anew.");
+        }
+        Self::Churning {
+            processes,
+            wallets,
+            payments: Default::default(),
+            payouts,
+            ledger,
+            accrual,
+            registry,
+        }
+    }
+
+    /// Ends the paying out of the rewards of the churn at the section key, that of the other
+    /// churns going on. Once none is left, the payments received meanwhile are kept.
+    pub(crate) fn end_churn(self, section_key: PublicKey) -> Self {
+        match self {
+            Self::Churning {
+                mut processes,
+                wallets,
+                payments,
+                payouts,
+                ledger,
+                accrual,
+                registry,
+            } => {
+                let _ = processes.remove(&section_key);
+                if processes.is_empty() {
+                    Self::KeepingNodeWallets {
+                        wallets,
+                        payments,
+                        payouts,
+                        ledger,
+                        accrual,
+                        registry,
+                    }
+                } else {
+                    Self::Churning {
+                        processes,
+                        wallets,
+                        payments,
+                        payouts,
+                        ledger,
+                        accrual,
+                        registry,
+                    }
+                }
+            }
+            funds => funds,
+        }
+    }
+
+    /// The processes paying out the rewards of the churns in progress, by section key.
+    pub(crate) fn reward_processes(&mut self) -> Vec<&mut RewardProcess> {
+        match self {
+            Self::Churning { processes, .. } => processes.values_mut().collect(),
+            Self::KeepingNodeWallets { .. } => vec![],
+        }
+    }
+
+    /// Splits the balance of the section at a split of it, i.e. the payments received since
+    /// the last churn, returning our share, to reward our nodes with, and the credit of the
+    /// share of our sibling to the wallet of its section, to be paid out along with the rewards.
+    /// No credit is returned if the share is empty or was handed over already.
+    /// The pending payouts to the nodes of the sibling section are left to its Elders.
+    pub(crate) fn split(
+        &mut self,
+        our_new_prefix: Prefix,
+        sibling_key: PublicKey,
+    ) -> Result<(Token, Option<Credit>)> {
+        match self {
+            Self::Churning {
+                wallets,
+                payments,
+                payouts,
+                ledger,
+                ..
+            }
+            | Self::KeepingNodeWallets {
+                wallets,
+                payments,
+                payouts,
+                ledger,
+                ..
+            } => {
+                // the nodes the payouts are to, the recipients of unknown nodes being kept
+                let owners: BTreeMap<_, _> = wallets
+                    .node_wallets()
+                    .into_iter()
+                    .flat_map(|(node, (_, wallet))| {
+                        wallets
+                            .destinations(&node, wallet)
+                            .into_iter()
+                            .map(move |(recipient, _)| (recipient, node))
+                    })
+                    .collect();
+                let handed_over = payouts.retain(|credit_proof| {
+                    owners
+                        .get(&credit_proof.recipient())
+                        .map_or(true, |node| our_new_prefix.matches(node))
+                })?;
+                debug!(
+                    "Left {} pending payouts to the Elders of our sibling.",
+                    handed_over
+                );
+
+                let (ours, siblings) = split_balance(payments.balance(), our_new_prefix);
+                if siblings == Token::zero() || !ledger.record_split(sibling_key, siblings)? {
+                    return Ok((ours, None));
+                }
+                payments.draw(siblings)?;
+                let sibling_prefix = our_new_prefix.sibling();
+                let names = vec![sibling_prefix.name(), XorName::from(sibling_key)];
+                let id = MessageId::combine(names).0 .0;
+                Ok((
+                    ours,
+                    Some(Credit {
+                        id,
+                        amount: siblings,
+                        recipient: sibling_key,
+                        msg: format!("Share of {:?} at split", sibling_prefix),
+                    }),
+                ))
+            }
+        }
+    }
+
+    /// The reward payouts which may yet fail, to be retried if so.
+    pub(crate) fn pending_payouts(&mut self) -> &mut PendingPayouts {
+        match self {
+            Self::Churning { payouts, .. } | Self::KeepingNodeWallets { payouts, .. } => payouts,
+        }
+    }
+
+    /// The latest payouts of the section funds, oldest first, with where they are at.
+    pub fn payout_history(&self) -> Vec<PayoutRecord> {
+        match self {
+            Self::Churning { payouts, .. } | Self::KeepingNodeWallets { payouts, .. } => {
+                payouts.history()
+            }
+        }
+    }
+
+    /// The reward payouts made, for none to be made twice.
+    pub(crate) fn payout_ledger(&mut self) -> &mut PayoutLedger {
+        match self {
+            Self::Churning { ledger, .. } | Self::KeepingNodeWallets { ledger, .. } => ledger,
+        }
+    }
+
+    /// The rewards accrued to the wallets of nodes, not yet paid out.
+    pub(crate) fn reward_accrual(&mut self) -> &mut RewardAccrual {
+        match self {
+            Self::Churning { accrual, .. } | Self::KeepingNodeWallets { accrual, .. } => accrual,
+        }
+    }
+
+    /// Nodes register/updates wallets for future reward payouts, by the msg of the id.
+    /// Returns whether it was registered, i.e. the msg not applied before.
+    pub fn set_node_wallet(
+        &mut self,
+        node_id: XorName,
+        wallet: PublicKey,
+        age: u8,
+        msg_id: MessageId,
+    ) -> Result<bool> {
+        match self {
+            Self::Churning {
+                wallets, registry, ..
+            }
+            | Self::KeepingNodeWallets {
+                wallets, registry, ..
+            } => {
+                if !registry.register(node_id, age, wallet, msg_id)? {
+                    return Ok(false);
+                }
+                wallets.set_node_wallet(node_id, age, wallet);
+                Ok(true)
             }
         }
     }
 
     /// When the section becomes aware that a node has left,
-    /// its reward key is removed.
-    pub fn remove_node_wallet(&self, node_name: XorName) {
-        match &self {
-            Self::Churning { wallets, .. } | Self::KeepingNodeWallets { wallets, .. } => {
-                wallets.remove_wallet(node_name)
+    /// its reward key is removed, and the rewards accrued to it are due to be paid out.
+    pub fn remove_node_wallet(&mut self, node_name: XorName) -> Result<()> {
+        match self {
+            Self::Churning {
+                wallets,
+                accrual,
+                registry,
+                ..
+            }
+            | Self::KeepingNodeWallets {
+                wallets,
+                accrual,
+                registry,
+                ..
+            } => {
+                if let Some((_, wallet)) = wallets.get(&node_name) {
+                    for (recipient, _) in wallets.destinations(&node_name, wallet) {
+                        accrual.depart(recipient)?;
+                    }
+                }
+                wallets.remove_wallet(node_name);
+                registry.remove(node_name)
             }
         }
     }
 }
 
-type Payments = DashMap<CreditId, CreditAgreementProof>;
 type Rewards = BTreeMap<CreditId, CreditAgreementProof>;
 
 pub trait Credits {
     fn sum(&self) -> Token;
 }
 
+/// The payments to the section since the last churn, and the payouts drawn from them.
+#[derive(Default)]
+pub struct Payments {
+    credits: DashMap<CreditId, CreditAgreementProof>,
+    drawn: u64,
+}
+
+impl Payments {
+    fn credit(&self, credit: CreditAgreementProof) -> bool {
+        self.credits.insert(*credit.id(), credit).is_none()
+    }
+
+    fn balance(&self) -> Token {
+        Token::from_nano(self.sum().as_nano().saturating_sub(self.drawn))
+    }
+
+    fn draw(&mut self, amount: Token) -> Result<()> {
+        let balance = self.balance();
+        if amount > balance {
+            return Err(Error::InsufficientSectionFunds(amount, balance));
+        }
+        self.drawn += amount.as_nano();
+        Ok(())
+    }
+}
+
 impl Credits for Payments {
     fn sum(&self) -> Token {
-        Token::from_nano(self.iter().map(|c| (*c).amount().as_nano()).sum())
+        Token::from_nano(self.credits.iter().map(|c| (*c).amount().as_nano()).sum())
     }
 }
 
@@ -121,3 +420,96 @@ impl Credits for Rewards {
 // let elders = self.rewards_and_wallets.elder_names();
 // self.rewards.payout_rewards(elders).await
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        reward_calc::RewardCalc,
+        test_utils::{credit_proof, random_key},
+        PayoutStatus, SectionFunds,
+    };
+    use crate::{Error, Result};
+    use sn_data_types::Token;
+    use sn_messaging::MessageId;
+    use sn_routing::{Prefix, XorName};
+    use tempdir::TempDir;
+
+    #[test]
+    fn payouts_are_drawn_from_payments_credited() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let mut funds = SectionFunds::from_history(root.path())?;
+
+        match funds.draw(Token::from_nano(10)) {
+            Err(Error::InsufficientSectionFunds(..)) => (),
+            other => return Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+
+        let credit = credit_proof(random_key(), 15);
+        assert!(funds.add_payment(credit.clone()));
+        // credited once only
+        assert!(!funds.add_payment(credit));
+        assert_eq!(funds.balance(), Token::from_nano(15));
+
+        funds.draw(Token::from_nano(10))?;
+        assert_eq!(funds.balance(), Token::from_nano(5));
+        match funds.draw(Token::from_nano(10)) {
+            Err(Error::InsufficientSectionFunds(..)) => (),
+            other => return Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn balance_decreases_by_the_payouts_of_a_round() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let mut funds = SectionFunds::from_history(root.path())?;
+        let _ = funds.add_payment(credit_proof(random_key(), 60));
+        let _ = funds.add_payment(credit_proof(random_key(), 41));
+        let before = funds.balance();
+
+        let our_prefix = Prefix::default().pushed(true);
+        let sibling_key = random_key();
+        let credit = match funds.split(our_prefix, sibling_key)? {
+            (ours, Some(credit)) if ours == funds.balance() => credit,
+            other => return Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        };
+        funds.pending_payouts().proposed(&credit)?;
+
+        let history = funds.payout_history();
+        let paid: u64 = history.iter().map(|record| record.amount.as_nano()).sum();
+        assert_eq!(funds.balance().as_nano(), before.as_nano() - paid);
+        assert_eq!(
+            history
+                .iter()
+                .map(|record| record.status)
+                .collect::<Vec<_>>(),
+            vec![PayoutStatus::Pending]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn rewards_are_paid_to_the_wallet_registered_last() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let node = XorName::random();
+        let (first, second) = (random_key(), random_key());
+        let registration = MessageId::new();
+        {
+            let mut funds = SectionFunds::from_history(root.path())?;
+            assert!(funds.set_node_wallet(node, first, 8, registration)?);
+            assert!(funds.set_node_wallet(node, second, 8, MessageId::new())?);
+            // the first registration replayed
+            assert!(!funds.set_node_wallet(node, first, 8, registration)?);
+        }
+
+        // the registration survives a restart
+        let funds = SectionFunds::from_history(root.path())?;
+        assert_eq!(funds.get_node_wallet(&node), Some(second));
+        let rewards =
+            RewardCalc::new(0, 1, 1).rewards(Token::from_nano(1_000), funds.node_wallets());
+        match rewards.get(&node) {
+            Some((_, wallet, amount)) if *wallet == second && *amount > Token::zero() => Ok(()),
+            other => Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+    }
+}
@@ -0,0 +1,130 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{utils, Result};
+use pickledb::PickleDb;
+use serde::{Deserialize, Serialize};
+use sn_data_types::{Credit, CreditId, PublicKey, Token};
+use std::{collections::VecDeque, path::Path};
+
+const PAYOUT_HISTORY_DB_NAME: &str = "payout_history.db";
+const PAYOUT_HISTORY_KEY: &str = "payouts";
+
+/// The number of latest payouts kept in the history.
+pub(crate) const MAX_PAYOUT_HISTORY: usize = 100;
+
+/// Where a payout of the section funds is at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PayoutStatus {
+    /// Proposed to the other Elders, not yet agreed.
+    Pending,
+    /// Agreed by the Elders, and propagated to its recipient.
+    Validated,
+    /// Not failed at its recipient within the settle timeout, hence applied.
+    Completed,
+    /// Given up on, after failing at its recipient too many times.
+    Failed,
+}
+
+/// A payout of the section funds, e.g. a reward to a node.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PayoutRecord {
+    /// The id of the credit paid out.
+    pub id: CreditId,
+    /// The wallet paid to.
+    pub recipient: PublicKey,
+    /// The amount paid.
+    pub amount: Token,
+    /// Where the payout is at.
+    pub status: PayoutStatus,
+}
+
+/// The latest payouts of the section funds, up to `MAX_PAYOUT_HISTORY` of them, the oldest
+/// being dropped first. Kept on disk, for the history to survive restarts.
+pub(crate) struct PayoutHistory {
+    records: VecDeque<PayoutRecord>,
+    db: PickleDb,
+}
+
+impl PayoutHistory {
+    pub fn new(path: &Path) -> Result<Self> {
+        let db = utils::new_auto_dump_db(path, PAYOUT_HISTORY_DB_NAME)?;
+        let records = db.get(PAYOUT_HISTORY_KEY).unwrap_or_default();
+        Ok(Self { records, db })
+    }
+
+    /// Records the payout of the credit at the status, updating it if already recorded.
+    pub fn record(&mut self, credit: &Credit, status: PayoutStatus) -> Result<()> {
+        match self
+            .records
+            .iter_mut()
+            .find(|record| record.id == credit.id)
+        {
+            Some(record) => record.status = status,
+            None => {
+                self.records.push_back(PayoutRecord {
+                    id: credit.id,
+                    recipient: credit.recipient,
+                    amount: credit.amount,
+                    status,
+                });
+                while self.records.len() > MAX_PAYOUT_HISTORY {
+                    let _ = self.records.pop_front();
+                }
+            }
+        }
+        self.db.set(PAYOUT_HISTORY_KEY, &self.records)?;
+        Ok(())
+    }
+
+    /// The payouts recorded, oldest first.
+    pub fn records(&self) -> Vec<PayoutRecord> {
+        self.records.iter().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PayoutHistory, PayoutStatus, MAX_PAYOUT_HISTORY};
+    use crate::{Error, Result};
+    use sn_data_types::{Credit, Keypair, Token};
+    use tempdir::TempDir;
+
+    fn credit(id: u8) -> Credit {
+        Credit {
+            id: [id; 32],
+            amount: Token::from_nano(id as u64),
+            recipient: Keypair::new_ed25519(&mut rand::thread_rng()).public_key(),
+            msg: String::new(),
+        }
+    }
+
+    #[test]
+    fn latest_payouts_are_kept_across_reloads() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let first = credit(0);
+        {
+            let mut history = PayoutHistory::new(root.path())?;
+            history.record(&first, PayoutStatus::Pending)?;
+            history.record(&first, PayoutStatus::Validated)?;
+            assert_eq!(history.records().len(), 1);
+            for id in 1..=MAX_PAYOUT_HISTORY as u8 {
+                history.record(&credit(id), PayoutStatus::Completed)?;
+            }
+        }
+
+        let history = PayoutHistory::new(root.path())?;
+        let records = history.records();
+        assert_eq!(records.len(), MAX_PAYOUT_HISTORY);
+        // the oldest was dropped
+        assert!(records.iter().all(|record| record.id != first.id));
+        assert_eq!(records[0].id, credit(1).id);
+        assert_eq!(records[0].status, PayoutStatus::Completed);
+        Ok(())
+    }
+}
@@ -0,0 +1,152 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{to_db_key::ToDbKey, utils, Result};
+use pickledb::PickleDb;
+use serde::Serialize;
+use sn_data_types::{CreditAgreementProof, PublicKey, Token};
+use std::path::Path;
+
+const PAYOUT_LEDGER_DB_NAME: &str = "payout_ledger.db";
+
+/// A reward payout is made once per recipient and reward epoch, which is the key of the
+/// section at the churn the reward is for.
+#[derive(Serialize)]
+struct PayoutKey {
+    recipient: PublicKey,
+    epoch: PublicKey,
+}
+
+impl ToDbKey for PayoutKey {}
+
+/// The section balance is split once with each sibling section, known by its key at the split.
+#[derive(Serialize)]
+struct SplitKey {
+    sibling: PublicKey,
+}
+
+impl ToDbKey for SplitKey {}
+
+/// The reward payouts made by the section, for a churn processed twice, e.g. after a restart,
+/// not to pay the same work twice. Kept on disk.
+pub(crate) struct PayoutLedger {
+    db: PickleDb,
+}
+
+impl PayoutLedger {
+    pub fn new(path: &Path) -> Result<Self> {
+        Ok(Self {
+            db: utils::new_auto_dump_db(path, PAYOUT_LEDGER_DB_NAME)?,
+        })
+    }
+
+    /// The payout made to the recipient for the epoch, if any.
+    pub fn get(&self, recipient: PublicKey, epoch: PublicKey) -> Option<CreditAgreementProof> {
+        let key = PayoutKey { recipient, epoch }.to_db_key().ok()?;
+        self.db.get(&key)
+    }
+
+    /// Whether a payout to the recipient for the epoch was made.
+    pub fn is_paid(&self, recipient: PublicKey, epoch: PublicKey) -> bool {
+        self.get(recipient, epoch).is_some()
+    }
+
+    /// Records the payout for the epoch, unless one was made to its recipient already,
+    /// in which case that prior one is returned instead, the payout not to be made.
+    pub fn record(
+        &mut self,
+        epoch: PublicKey,
+        credit_proof: &CreditAgreementProof,
+    ) -> Result<Option<CreditAgreementProof>> {
+        let recipient = credit_proof.recipient();
+        if let Some(prior) = self.get(recipient, epoch) {
+            return Ok(Some(prior));
+        }
+        let key = PayoutKey { recipient, epoch }.to_db_key()?;
+        self.db.set(&key, credit_proof)?;
+        Ok(None)
+    }
+
+    /// Records the share of the section balance handed over to the sibling section of the key
+    /// at a split, unless the split was made already, in which case false is returned,
+    /// the split not to be made again.
+    pub fn record_split(&mut self, sibling: PublicKey, share: Token) -> Result<bool> {
+        let key = SplitKey { sibling }.to_db_key()?;
+        if self.db.exists(&key) {
+            return Ok(false);
+        }
+        self.db.set(&key, &share)?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PayoutLedger;
+    use crate::{
+        section_funds::test_utils::{credit_proof, random_key},
+        Error, Result,
+    };
+    use sn_data_types::Token;
+    use tempdir::TempDir;
+
+    #[test]
+    fn duplicate_payout_is_refused_with_the_prior_one() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let mut ledger = PayoutLedger::new(root.path())?;
+        let (recipient, epoch, next_epoch) = (random_key(), random_key(), random_key());
+
+        assert!(ledger
+            .record(epoch, &credit_proof(recipient, 10))?
+            .is_none());
+        match ledger.record(epoch, &credit_proof(recipient, 20))? {
+            Some(prior) if prior.amount() == Token::from_nano(10) => (),
+            other => return Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+        // The same recipient is paid for another epoch.
+        assert!(ledger
+            .record(next_epoch, &credit_proof(recipient, 20))?
+            .is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn payouts_are_remembered_across_reloads() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let (recipient, epoch) = (random_key(), random_key());
+        {
+            let mut ledger = PayoutLedger::new(root.path())?;
+            assert!(ledger
+                .record(epoch, &credit_proof(recipient, 10))?
+                .is_none());
+        }
+
+        let mut ledger = PayoutLedger::new(root.path())?;
+        assert!(ledger.is_paid(recipient, epoch));
+        assert!(ledger
+            .record(epoch, &credit_proof(recipient, 10))?
+            .is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn balance_is_split_once_per_sibling() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let (sibling, next_sibling) = (random_key(), random_key());
+        {
+            let mut ledger = PayoutLedger::new(root.path())?;
+            assert!(ledger.record_split(sibling, Token::from_nano(10))?);
+            assert!(!ledger.record_split(sibling, Token::from_nano(10))?);
+        }
+
+        let mut ledger = PayoutLedger::new(root.path())?;
+        assert!(!ledger.record_split(sibling, Token::from_nano(10))?);
+        assert!(ledger.record_split(next_sibling, Token::from_nano(10))?);
+        Ok(())
+    }
+}
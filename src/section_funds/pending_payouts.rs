@@ -0,0 +1,439 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::payout_history::{PayoutHistory, PayoutRecord, PayoutStatus};
+use crate::{to_db_key::ToDbKey, utils, Result};
+use pickledb::PickleDb;
+use serde::{Deserialize, Serialize};
+use sn_data_types::{Credit, CreditAgreementProof};
+use sn_messaging::{client::Error as ErrorMessage, MessageId};
+use std::{
+    collections::{HashMap, VecDeque},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+const PENDING_PAYOUTS_DB_NAME: &str = "pending_payouts.db";
+
+/// The number of times a payout is propagated before it's given up on.
+pub(crate) const MAX_PAYOUT_ATTEMPTS: u32 = 5;
+
+/// The wait before the first retry of a failed payout, doubled on each further failure.
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// How long a propagated payout is waited on for a failure, before it's deemed applied,
+/// as its recipient's Elders only answer failures.
+const SETTLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A reward payout propagated to the Elders of its recipient.
+#[derive(Clone, Debug)]
+pub(crate) struct PendingPayout {
+    pub credit_proof: CreditAgreementProof,
+    /// The number of times it was propagated.
+    pub attempts: u32,
+    /// The error of the latest failed attempt, if any.
+    pub reason: Option<ErrorMessage>,
+    // When to retry it, None while an attempt is in flight.
+    retry_at: Option<Instant>,
+    last_sent: Instant,
+}
+
+// A pending payout as kept on disk, times being meaningless across restarts.
+#[derive(Serialize, Deserialize)]
+struct StoredPayout {
+    msg_id: MessageId,
+    credit_proof: CreditAgreementProof,
+    attempts: u32,
+    reason: Option<ErrorMessage>,
+    // The position of the payout in the order first propagated.
+    seq: u64,
+}
+
+/// The reward payouts of the section which may yet fail, for those failing transiently,
+/// e.g. on a churn at their recipient's section, to be retried with exponential backoff
+/// instead of being lost. Retries are issued in the order the payouts were first propagated,
+/// hence in order per recipient.
+///
+/// Each payout is kept on disk as of its latest attempt, until settled or given up on, so that
+/// the payouts of an Elder restarting are resumed rather than forgotten. Being unknown
+/// whether the attempts in flight at the time succeeded, all are retried on load, the credits
+/// being applied once only by their recipients.
+///
+/// The latest payouts are kept in a history along with where they are at, from proposed
+/// to completed or failed, for the section funds to be observed.
+pub(crate) struct PendingPayouts {
+    payouts: HashMap<MessageId, PendingPayout>,
+    // The ids of the payouts, in the order first propagated.
+    order: VecDeque<(u64, MessageId)>,
+    next_seq: u64,
+    db: PickleDb,
+    history: PayoutHistory,
+}
+
+impl PendingPayouts {
+    /// Loads the payouts kept at the path, all due to be retried.
+    pub fn new(path: &Path, now: Instant) -> Result<Self> {
+        let db = utils::new_auto_dump_db(path, PENDING_PAYOUTS_DB_NAME)?;
+        let mut stored: Vec<_> = db
+            .get_all()
+            .iter()
+            .filter_map(|key| db.get::<StoredPayout>(key))
+            .collect();
+        stored.sort_by_key(|payout| payout.seq);
+        let mut payouts = Self {
+            payouts: HashMap::new(),
+            order: VecDeque::new(),
+            next_seq: stored.last().map_or(0, |payout| payout.seq + 1),
+            db,
+            history: PayoutHistory::new(path)?,
+        };
+        for payout in stored {
+            let _ = payouts.payouts.insert(
+                payout.msg_id,
+                PendingPayout {
+                    credit_proof: payout.credit_proof,
+                    attempts: payout.attempts,
+                    reason: payout.reason,
+                    retry_at: Some(now),
+                    last_sent: now,
+                },
+            );
+            payouts.order.push_back((payout.seq, payout.msg_id));
+        }
+        Ok(payouts)
+    }
+
+    /// Records the proposal of a payout to the other Elders, in the history only,
+    /// as it's yet to be agreed.
+    pub fn proposed(&mut self, credit: &Credit) -> Result<()> {
+        self.history.record(credit, PayoutStatus::Pending)
+    }
+
+    /// Records a payout given up on before agreed by the other Elders, in the history only.
+    pub fn abandoned(&mut self, credit: &Credit) -> Result<()> {
+        self.history.record(credit, PayoutStatus::Failed)
+    }
+
+    /// The latest payouts, oldest first, with where they are at.
+    pub fn history(&self) -> Vec<PayoutRecord> {
+        self.history.records()
+    }
+
+    /// Records the first propagation of a payout, under the id of its msg.
+    pub fn sent(
+        &mut self,
+        msg_id: MessageId,
+        credit_proof: CreditAgreementProof,
+        now: Instant,
+    ) -> Result<()> {
+        if self.payouts.contains_key(&msg_id) {
+            return Ok(());
+        }
+        self.history
+            .record(&credit_proof.signed_credit.credit, PayoutStatus::Validated)?;
+        let _ = self.payouts.insert(
+            msg_id,
+            PendingPayout {
+                credit_proof,
+                attempts: 1,
+                reason: None,
+                retry_at: None,
+                last_sent: now,
+            },
+        );
+        self.order.push_back((self.next_seq, msg_id));
+        self.next_seq += 1;
+        self.store(self.next_seq - 1, &msg_id)
+    }
+
+    /// Records the failure of the latest attempt at the payout, scheduling its retry.
+    /// Returns the payout if it's given up on, having failed `MAX_PAYOUT_ATTEMPTS` times.
+    pub fn failed(
+        &mut self,
+        msg_id: &MessageId,
+        reason: ErrorMessage,
+        now: Instant,
+    ) -> Result<Option<PendingPayout>> {
+        let payout = match self.payouts.get_mut(msg_id) {
+            Some(payout) => payout,
+            None => return Ok(None),
+        };
+        payout.reason = Some(reason);
+        if payout.attempts >= MAX_PAYOUT_ATTEMPTS {
+            self.order.retain(|(_, id)| id != msg_id);
+            let _ = self.db.rem(&msg_id.to_db_key()?)?;
+            let payout = self.payouts.remove(msg_id);
+            if let Some(payout) = &payout {
+                self.history.record(
+                    &payout.credit_proof.signed_credit.credit,
+                    PayoutStatus::Failed,
+                )?;
+            }
+            return Ok(payout);
+        }
+        // Another failure of an attempt already scheduled for retry doesn't delay it further.
+        if payout.retry_at.is_none() {
+            payout.retry_at = Some(now + FIRST_RETRY_DELAY * 2_u32.pow(payout.attempts - 1));
+        }
+        if let Some((seq, _)) = self.order.iter().find(|(_, id)| id == msg_id) {
+            self.store(*seq, msg_id)?;
+        }
+        Ok(None)
+    }
+
+    /// The payouts due to be retried, in the order first propagated, counting the attempts.
+    /// The payouts not failed within the timeout of their latest attempt are forgotten.
+    pub fn due(&mut self, now: Instant) -> Result<Vec<(MessageId, CreditAgreementProof)>> {
+        let payouts = &mut self.payouts;
+        let mut settled = vec![];
+        self.order.retain(|(_, id)| match payouts.get(id) {
+            Some(payout) if payout.retry_at.is_some() => true,
+            Some(payout) if now.saturating_duration_since(payout.last_sent) < SETTLE_TIMEOUT => {
+                true
+            }
+            _ => {
+                if let Some(payout) = payouts.remove(id) {
+                    settled.push((*id, payout.credit_proof));
+                }
+                false
+            }
+        });
+        for (id, credit_proof) in settled {
+            let _ = self.db.rem(&id.to_db_key()?)?;
+            self.history
+                .record(&credit_proof.signed_credit.credit, PayoutStatus::Completed)?;
+        }
+        let mut due = vec![];
+        for (seq, id) in self.order.clone() {
+            if let Some(payout) = self.payouts.get_mut(&id) {
+                if payout.retry_at.map_or(false, |time| time <= now) {
+                    payout.attempts += 1;
+                    payout.retry_at = None;
+                    payout.last_sent = now;
+                    due.push((id, payout.credit_proof.clone()));
+                    self.store(seq, &id)?;
+                }
+            }
+        }
+        Ok(due)
+    }
+
+    /// The payouts which may yet fail, in the order first propagated, for diagnostics.
+    pub fn iter(&self) -> impl Iterator<Item = &PendingPayout> {
+        self.order
+            .iter()
+            .filter_map(move |(_, id)| self.payouts.get(id))
+    }
+
+    /// Keeps only the payouts the predicate holds for, e.g. those to the nodes left in
+    /// our section after a split, forgetting the others also on disk.
+    /// Returns the number of payouts forgotten.
+    pub fn retain(&mut self, keep: impl Fn(&CreditAgreementProof) -> bool) -> Result<usize> {
+        let payouts = &mut self.payouts;
+        let mut dropped = vec![];
+        self.order.retain(|(_, id)| match payouts.get(id) {
+            Some(payout) if keep(&payout.credit_proof) => true,
+            _ => {
+                let _ = payouts.remove(id);
+                dropped.push(*id);
+                false
+            }
+        });
+        for id in &dropped {
+            let _ = self.db.rem(&id.to_db_key()?)?;
+        }
+        Ok(dropped.len())
+    }
+
+    fn store(&mut self, seq: u64, msg_id: &MessageId) -> Result<()> {
+        if let Some(payout) = self.payouts.get(msg_id) {
+            let stored = StoredPayout {
+                msg_id: *msg_id,
+                credit_proof: payout.credit_proof.clone(),
+                attempts: payout.attempts,
+                reason: payout.reason.clone(),
+                seq,
+            };
+            self.db.set(&msg_id.to_db_key()?, &stored)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PendingPayouts, FIRST_RETRY_DELAY, MAX_PAYOUT_ATTEMPTS, SETTLE_TIMEOUT};
+    use crate::{
+        section_funds::{
+            test_utils::{credit_proof, random_key},
+            PayoutStatus,
+        },
+        Error, Result,
+    };
+    use sn_messaging::{client::Error as ErrorMessage, MessageId};
+    use std::time::Instant;
+    use tempdir::TempDir;
+
+    fn temp_dir() -> Result<TempDir> {
+        TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))
+    }
+
+    #[test]
+    fn failed_payout_is_retried_with_backoff_until_it_lands() -> Result<()> {
+        let root = temp_dir()?;
+        let now = Instant::now();
+        let mut payouts = PendingPayouts::new(root.path(), now)?;
+        let (failing, landing) = (MessageId::new(), MessageId::new());
+        payouts.sent(failing, credit_proof(random_key(), 10), now)?;
+        payouts.sent(landing, credit_proof(random_key(), 10), now)?;
+
+        assert!(payouts
+            .failed(&failing, ErrorMessage::NoSuchKey, now)?
+            .is_none());
+        assert!(payouts.due(now)?.is_empty());
+        let retried: Vec<_> = payouts
+            .due(now + FIRST_RETRY_DELAY)?
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        assert_eq!(retried, vec![failing]);
+
+        // Failing again, it waits twice as long.
+        let now = now + FIRST_RETRY_DELAY;
+        let _ = payouts.failed(&failing, ErrorMessage::NoSuchKey, now)?;
+        assert!(payouts.due(now + FIRST_RETRY_DELAY)?.is_empty());
+        assert_eq!(payouts.due(now + FIRST_RETRY_DELAY * 2)?.len(), 1);
+
+        match payouts.iter().next() {
+            Some(payout) if payout.attempts == 3 => (),
+            other => return Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+        // Not failing again, it landed, as did the other one.
+        assert!(payouts
+            .due(now + FIRST_RETRY_DELAY * 2 + SETTLE_TIMEOUT)?
+            .is_empty());
+        assert_eq!(payouts.iter().count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn payout_is_given_up_on_after_max_attempts() -> Result<()> {
+        let root = temp_dir()?;
+        let mut now = Instant::now();
+        let mut payouts = PendingPayouts::new(root.path(), now)?;
+        let id = MessageId::new();
+        payouts.sent(id, credit_proof(random_key(), 10), now)?;
+        for _ in 1..MAX_PAYOUT_ATTEMPTS {
+            assert!(payouts.failed(&id, ErrorMessage::NoSuchKey, now)?.is_none());
+            now += FIRST_RETRY_DELAY * 2_u32.pow(MAX_PAYOUT_ATTEMPTS);
+            assert_eq!(payouts.due(now)?.len(), 1);
+        }
+        match payouts.failed(&id, ErrorMessage::NoSuchKey, now)? {
+            Some(payout) if payout.attempts == MAX_PAYOUT_ATTEMPTS => (),
+            other => return Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+        assert_eq!(payouts.iter().count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn pending_payouts_resume_after_restart() -> Result<()> {
+        let root = temp_dir()?;
+        let now = Instant::now();
+        let ids: Vec<_> = (0..3).map(|_| MessageId::new()).collect();
+        {
+            let mut payouts = PendingPayouts::new(root.path(), now)?;
+            for id in &ids {
+                payouts.sent(*id, credit_proof(random_key(), 10), now)?;
+            }
+            let _ = payouts.failed(&ids[1], ErrorMessage::NoSuchKey, now)?;
+        }
+
+        // Whether they landed or not before the restart, all are resent, in order.
+        let now = now + SETTLE_TIMEOUT * 2;
+        let mut payouts = PendingPayouts::new(root.path(), now)?;
+        let resent: Vec<_> = payouts.due(now)?.into_iter().map(|(id, _)| id).collect();
+        assert_eq!(resent, ids);
+        let attempts: Vec<_> = payouts.iter().map(|payout| payout.attempts).collect();
+        assert_eq!(attempts, vec![2, 2, 2]);
+        assert!(payouts
+            .iter()
+            .nth(1)
+            .and_then(|p| p.reason.clone())
+            .is_some());
+
+        // Once landed, they're forgotten on disk too.
+        assert!(payouts.due(now + SETTLE_TIMEOUT)?.is_empty());
+        let payouts = PendingPayouts::new(root.path(), now)?;
+        assert_eq!(payouts.iter().count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn only_retained_payouts_are_kept() -> Result<()> {
+        let root = temp_dir()?;
+        let now = Instant::now();
+        let kept = random_key();
+        let (first, second, third) = (MessageId::new(), MessageId::new(), MessageId::new());
+        {
+            let mut payouts = PendingPayouts::new(root.path(), now)?;
+            payouts.sent(first, credit_proof(kept, 10), now)?;
+            payouts.sent(second, credit_proof(random_key(), 10), now)?;
+            payouts.sent(third, credit_proof(kept, 10), now)?;
+
+            assert_eq!(payouts.retain(|proof| proof.recipient() == kept)?, 1);
+            assert_eq!(payouts.iter().count(), 2);
+        }
+
+        let mut payouts = PendingPayouts::new(root.path(), now)?;
+        let resent: Vec<_> = payouts.due(now)?.into_iter().map(|(id, _)| id).collect();
+        assert_eq!(resent, vec![first, third]);
+        Ok(())
+    }
+
+    #[test]
+    fn payouts_of_two_churns_complete_independently() -> Result<()> {
+        let root = temp_dir()?;
+        let now = Instant::now();
+        let mut payouts = PendingPayouts::new(root.path(), now)?;
+        // the payout of a churn, and those of the next one agreed while it's still in flight
+        let ids: Vec<_> = (0..3).map(|_| MessageId::new()).collect();
+        for (i, id) in ids.iter().enumerate() {
+            let mut credit_proof = credit_proof(random_key(), 10);
+            credit_proof.signed_credit.credit.id = [i as u8; 32];
+            payouts.sent(*id, credit_proof, now)?;
+        }
+        let statuses = |payouts: &PendingPayouts| -> Vec<_> {
+            payouts
+                .history()
+                .iter()
+                .map(|record| record.status)
+                .collect()
+        };
+
+        // the payout of the first churn fails, while those of the next one land
+        let _ = payouts.failed(&ids[0], ErrorMessage::NoSuchKey, now)?;
+        let now = now + SETTLE_TIMEOUT;
+        let retried: Vec<_> = payouts.due(now)?.into_iter().map(|(id, _)| id).collect();
+        assert_eq!(retried, vec![ids[0]]);
+        assert_eq!(
+            statuses(&payouts),
+            vec![
+                PayoutStatus::Validated,
+                PayoutStatus::Completed,
+                PayoutStatus::Completed
+            ]
+        );
+
+        // then the retried one lands too
+        assert!(payouts.due(now + SETTLE_TIMEOUT)?.is_empty());
+        assert_eq!(statuses(&payouts), vec![PayoutStatus::Completed; 3]);
+        assert_eq!(payouts.iter().count(), 0);
+        Ok(())
+    }
+}
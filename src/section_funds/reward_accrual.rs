@@ -0,0 +1,184 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{to_db_key::ToDbKey, utils, Result};
+use pickledb::PickleDb;
+use serde::{Deserialize, Serialize};
+use sn_data_types::{PublicKey, Token};
+use std::{collections::BTreeMap, path::Path};
+
+const REWARD_ACCRUAL_DB_NAME: &str = "reward_accrual.db";
+
+/// The amount of accrued rewards at which they are paid out.
+pub(crate) const PAYOUT_THRESHOLD: Token = Token::from_nano(1_000_000_000);
+
+#[derive(Serialize, Deserialize)]
+struct Accrued {
+    wallet: PublicKey,
+    amount: u64,
+    // The section key at the latest churn rewarded, not to accrue a churn twice.
+    last_epoch: Option<PublicKey>,
+    // Whether its node left the section, e.g. relocated away.
+    departed: bool,
+}
+
+/// The rewards accrued to the wallets of nodes across churns, paid out once they pass the
+/// payout threshold, or their node leaves the section, instead of tiny amounts at every churn.
+/// Kept on disk, keyed by wallet rather than node name, for the rewards accrued to survive
+/// the change of name of a node relocated.
+pub(crate) struct RewardAccrual {
+    db: PickleDb,
+}
+
+impl RewardAccrual {
+    pub fn new(path: &Path) -> Result<Self> {
+        Ok(Self {
+            db: utils::new_auto_dump_db(path, REWARD_ACCRUAL_DB_NAME)?,
+        })
+    }
+
+    /// The rewards accrued to the wallet and not yet paid out.
+    pub fn accrued(&self, wallet: PublicKey) -> Token {
+        Token::from_nano(self.get(wallet).map_or(0, |accrued| accrued.amount))
+    }
+
+    /// Accrues the reward for the churn at the epoch, i.e. the section key then,
+    /// unless accrued for it already.
+    pub fn accrue(&mut self, wallet: PublicKey, epoch: PublicKey, reward: Token) -> Result<()> {
+        let mut accrued = self.get(wallet).unwrap_or(Accrued {
+            wallet,
+            amount: 0,
+            last_epoch: None,
+            departed: false,
+        });
+        if accrued.last_epoch == Some(epoch) {
+            return Ok(());
+        }
+        accrued.amount = accrued.amount.saturating_add(reward.as_nano());
+        accrued.last_epoch = Some(epoch);
+        self.set(&accrued)
+    }
+
+    /// Marks the rewards accrued to the wallet as due, its node having left the section.
+    pub fn depart(&mut self, wallet: PublicKey) -> Result<()> {
+        match self.get(wallet) {
+            Some(mut accrued) if accrued.amount > 0 => {
+                accrued.departed = true;
+                self.set(&accrued)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Accrues anew the rewards of a payout given up on, to be paid out again later.
+    /// The rewards of wallets never accrued to, e.g. the share of a sibling section,
+    /// are not accrued, returning false.
+    pub fn refund(&mut self, wallet: PublicKey, amount: Token) -> Result<bool> {
+        let mut accrued = match self.get(wallet) {
+            Some(accrued) => accrued,
+            None => return Ok(false),
+        };
+        accrued.amount = accrued.amount.saturating_add(amount.as_nano());
+        self.set(&accrued)?;
+        Ok(true)
+    }
+
+    /// Takes the accrued rewards due to be paid out, i.e. those passing the threshold or of
+    /// nodes which left, zeroing them as they are, not to be paid out again.
+    pub fn take_due(&mut self, threshold: Token) -> Result<BTreeMap<PublicKey, Token>> {
+        let mut due = BTreeMap::new();
+        for key in self.db.get_all() {
+            let mut accrued = match self.db.get::<Accrued>(&key) {
+                Some(accrued) => accrued,
+                None => continue,
+            };
+            if accrued.amount == 0 || !accrued.departed && accrued.amount < threshold.as_nano() {
+                continue;
+            }
+            let _ = due.insert(accrued.wallet, Token::from_nano(accrued.amount));
+            accrued.amount = 0;
+            accrued.departed = false;
+            self.db.set(&key, &accrued)?;
+        }
+        Ok(due)
+    }
+
+    fn get(&self, wallet: PublicKey) -> Option<Accrued> {
+        self.db.get(&wallet.to_db_key().ok()?)
+    }
+
+    fn set(&mut self, accrued: &Accrued) -> Result<()> {
+        self.db.set(&accrued.wallet.to_db_key()?, accrued)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RewardAccrual, PAYOUT_THRESHOLD};
+    use crate::{Error, Result};
+    use sn_data_types::{Keypair, PublicKey, Token};
+    use tempdir::TempDir;
+
+    fn key() -> PublicKey {
+        Keypair::new_ed25519(&mut rand::thread_rng()).public_key()
+    }
+
+    #[test]
+    fn rewards_accrued_are_paid_out_once_on_relocation() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let (wallet, other) = (key(), key());
+        let (first, second) = (key(), key());
+        {
+            let mut accrual = RewardAccrual::new(root.path())?;
+            accrual.accrue(wallet, first, Token::from_nano(10))?;
+            // the same churn processed again
+            accrual.accrue(wallet, first, Token::from_nano(10))?;
+            accrual.accrue(wallet, second, Token::from_nano(15))?;
+            accrual.accrue(other, second, Token::from_nano(5))?;
+            assert!(accrual.take_due(PAYOUT_THRESHOLD)?.is_empty());
+        }
+
+        // the node is relocated, under another name but with the same wallet
+        let mut accrual = RewardAccrual::new(root.path())?;
+        assert_eq!(accrual.accrued(wallet), Token::from_nano(25));
+        accrual.depart(wallet)?;
+        let due = accrual.take_due(PAYOUT_THRESHOLD)?;
+        assert_eq!(due.len(), 1);
+        assert_eq!(due.get(&wallet), Some(&Token::from_nano(25)));
+
+        assert_eq!(accrual.accrued(wallet), Token::zero());
+        assert!(accrual.take_due(PAYOUT_THRESHOLD)?.is_empty());
+        assert_eq!(accrual.accrued(other), Token::from_nano(5));
+        Ok(())
+    }
+
+    #[test]
+    fn rewards_accrued_are_paid_out_past_the_threshold() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let mut accrual = RewardAccrual::new(root.path())?;
+        let wallet = key();
+        let half = Token::from_nano(PAYOUT_THRESHOLD.as_nano() / 2);
+
+        accrual.accrue(wallet, key(), half)?;
+        assert!(accrual.take_due(PAYOUT_THRESHOLD)?.is_empty());
+        accrual.accrue(wallet, key(), half)?;
+        let due = accrual.take_due(PAYOUT_THRESHOLD)?;
+        assert_eq!(
+            due.get(&wallet),
+            Some(&Token::from_nano(half.as_nano() * 2))
+        );
+        assert!(accrual.take_due(PAYOUT_THRESHOLD)?.is_empty());
+
+        // the payout is given up on, its rewards accrued anew
+        assert!(accrual.refund(wallet, due[&wallet])?);
+        assert!(!accrual.refund(key(), half)?);
+        assert_eq!(accrual.accrued(wallet), due[&wallet]);
+        Ok(())
+    }
+}
@@ -49,149 +49,264 @@ pub fn get_reward_and_mint_amount(
     Token::from_nano(reward_nanos)
 }
 
-/// Calculates reward for each public key
-/// proportional to the age of its node,
-/// out of the total amount supplied.
-pub fn distribute_rewards(
-    amount: Token,
-    nodes: BTreeMap<XorName, (NodeAge, PublicKey)>,
-) -> BTreeMap<XorName, (NodeAge, PublicKey, Token)> {
-    let reward_buckets = get_buckets(nodes);
-    distribute(amount, reward_buckets)
+/// Splits the reward of a node among the destinations of its rewards,
+/// proportionally to their weights. The remainder of the division goes to the first
+/// destination, for the parts to always sum up to the reward, and empty parts are left out.
+pub fn split_reward(amount: Token, destinations: &[(PublicKey, u32)]) -> Vec<(PublicKey, Token)> {
+    let total_weight: u128 = destinations.iter().map(|(_, weight)| *weight as u128).sum();
+    if total_weight == 0 {
+        return destinations
+            .first()
+            .map(|(wallet, _)| vec![(*wallet, amount)])
+            .unwrap_or_default();
+    }
+    let amount = amount.as_nano();
+    let mut parts: Vec<_> = destinations
+        .iter()
+        .map(|(wallet, weight)| {
+            let part = amount as u128 * *weight as u128 / total_weight;
+            (*wallet, part as u64)
+        })
+        .collect();
+    let remainder = amount - parts.iter().map(|(_, part)| part).sum::<u64>();
+    if let Some((_, first)) = parts.first_mut() {
+        *first += remainder;
+    }
+    parts
+        .into_iter()
+        .filter(|(_, part)| *part > 0)
+        .map(|(wallet, part)| (wallet, Token::from_nano(part)))
+        .collect()
 }
 
-fn get_buckets(
-    nodes: BTreeMap<XorName, (NodeAge, PublicKey)>,
-) -> BTreeMap<NodeAge, BTreeMap<XorName, PublicKey>> {
-    let mut reward_buckets = BTreeMap::new();
-    for (node_name, (age, wallet)) in nodes {
-        if age >= MIN_REWARD_AGE {
-            let _ = reward_buckets
-                .entry(age)
-                .or_insert_with(BTreeMap::new)
-                .insert(node_name, wallet);
-        }
-    }
-    println!("reward_buckets: {}", reward_buckets.len());
-    reward_buckets
+/// Splits the balance of a section splitting in two halves, one for each child section,
+/// returning ours and our sibling's. The remainder of an odd amount stays with the
+/// lower prefix of the two.
+pub fn split_balance(amount: Token, our_prefix: Prefix) -> (Token, Token) {
+    let amount = amount.as_nano();
+    let half = amount / 2;
+    let (ours, siblings) = if our_prefix < our_prefix.sibling() {
+        (amount - half, half)
+    } else {
+        (half, amount - half)
+    };
+    (Token::from_nano(ours), Token::from_nano(siblings))
 }
 
-fn distribute(
-    amount: Token,
-    reward_buckets: BTreeMap<NodeAge, BTreeMap<XorName, PublicKey>>,
-) -> BTreeMap<XorName, (NodeAge, PublicKey, Token)> {
-    if reward_buckets.is_empty() {
-        return Default::default();
-    }
-    let mut counters = BTreeMap::new();
-    let mut remaining_amount = amount.as_nano();
-
-    // shorten iterations
-    let max_age = (*reward_buckets.keys().max().unwrap_or(&1) as u64);
-    let node_count = reward_buckets.values().map(|b| b.len() as u64).sum::<u64>();
-    let share = remaining_amount / (max_age * node_count);
-    let divisor = max_age * remaining_amount.to_string().len() as u64;
-    let bucket_multiplier = u64::max(1, share / divisor);
-
-    while remaining_amount > 0 {
-        for (age, wallets) in &reward_buckets {
-            // every tick up in age indicates about double amount of work performed
-            let proportional_work = 2_u64.pow(*age as u32);
-            let reward = u64::min(
-                (proportional_work * wallets.len() as u64) * bucket_multiplier,
-                remaining_amount,
-            );
-            let _ = counters
-                .entry(*age)
-                .and_modify(|existing| *existing += reward)
-                .or_insert(reward);
-            remaining_amount -= reward;
-            if remaining_amount == 0 {
-                break;
-            }
+/// The divisor of the proportional supply of a section, giving the reward per unit of work
+/// at a usage of its storage capacity of zero.
+const REWARD_RATE_DIVISOR: u64 = 1 << 32;
+
+/// The calculation of the rewards of nodes, out of their age and the reward rate of their
+/// section, i.e. the amount paid per unit of work.
+///
+/// It's done in integer arithmetic only, for the Elders of a section given the same inputs to
+/// agree on the exact same amounts:
+/// - The rate is the proportional supply of the section, i.e. `MAX_SUPPLY >> prefix_len`,
+///   divided by `REWARD_RATE_DIVISOR`, times `(adults + full_nodes) / adults`, i.e. up to
+///   double when all adults are full, the way the store cost goes up with the capacity used,
+///   for more nodes to be drawn in. Products are taken before divisions, in 128 bits.
+/// - The work of a node is `2^(age - MIN_REWARD_AGE)` units, each step up in age being about
+///   double the work, and none below `MIN_REWARD_AGE`, hence also at age zero.
+/// - The reward of a node is its work times the rate, saturating at the max `Token` amount.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RewardCalc {
+    rate: u64,
+}
+
+impl RewardCalc {
+    /// The calculation for a section of the prefix length, with `adults` adults
+    /// of which `full_nodes` are full.
+    pub fn new(prefix_len: usize, full_nodes: u8, adults: u8) -> Self {
+        let section_supply = MAX_SUPPLY.checked_shr(prefix_len as u32).unwrap_or(0);
+        let base_rate = (section_supply / REWARD_RATE_DIVISOR) as u128;
+        let adults = adults as u128;
+        let full_nodes = u128::min(full_nodes as u128, adults);
+        // no adults known yet, the base rate applies
+        let rate = (base_rate * (adults + full_nodes))
+            .checked_div(adults)
+            .unwrap_or(base_rate);
+        Self {
+            rate: rate.min(u64::MAX as u128) as u64,
         }
     }
 
-    let mut to_return = BTreeMap::new();
-    for (age, reward) in counters {
-        let wallets = reward_buckets.get(&age).unwrap();
-        let wallet_count = wallets.len() as u64;
-        let reward_per_wallet = reward / wallet_count;
-        let remainder = reward % wallet_count;
-
-        let mut first_added: bool = false;
-        for (node_name, wallet) in wallets {
-            if !first_added {
-                let _ = to_return.insert(
-                    *node_name,
-                    (
-                        age,
-                        *wallet,
-                        Token::from_nano(reward_per_wallet + remainder),
-                    ),
-                );
-                first_added = true;
-            } else {
-                let _ = to_return.insert(
-                    *node_name,
-                    (age, *wallet, Token::from_nano(reward_per_wallet)),
-                );
-            }
+    /// The amount paid per unit of work.
+    pub fn rate(&self) -> Token {
+        Token::from_nano(self.rate)
+    }
+
+    /// The units of work performed by a node of the age.
+    pub fn work_units(age: NodeAge) -> u64 {
+        if age < MIN_REWARD_AGE {
+            return 0;
         }
+        1_u64
+            .checked_shl((age - MIN_REWARD_AGE) as u32)
+            .unwrap_or(u64::MAX)
     }
 
-    println!("remaining_amount: {}", remaining_amount);
+    /// The reward of a node of the age.
+    pub fn reward(&self, age: NodeAge) -> Token {
+        let reward = self.rate as u128 * Self::work_units(age) as u128;
+        Token::from_nano(reward.min(u64::MAX as u128) as u64)
+    }
 
-    to_return
+    /// The rewards of the nodes, those with none left out. Should they sum up to more than
+    /// the budget, each is scaled down to its proportional part of it, rounding down,
+    /// for no more than the budget to be paid out.
+    pub fn rewards(
+        &self,
+        budget: Token,
+        nodes: BTreeMap<XorName, (NodeAge, PublicKey)>,
+    ) -> BTreeMap<XorName, (NodeAge, PublicKey, Token)> {
+        let rewards: BTreeMap<_, _> = nodes
+            .into_iter()
+            .map(|(node, (age, wallet))| (node, (age, wallet, self.reward(age).as_nano())))
+            .filter(|(_, (_, _, reward))| *reward > 0)
+            .collect();
+        let total: u128 = rewards.values().map(|(_, _, reward)| *reward as u128).sum();
+        let budget = budget.as_nano() as u128;
+        rewards
+            .into_iter()
+            .map(|(node, (age, wallet, reward))| {
+                let reward = if total > budget {
+                    (reward as u128 * budget / total) as u64
+                } else {
+                    reward
+                };
+                (node, (age, wallet, Token::from_nano(reward)))
+            })
+            .filter(|(_, (_, _, reward))| reward.as_nano() > 0)
+            .collect()
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use itertools::Itertools;
-    use sn_data_types::NodeAge;
-
     use super::*;
+    use itertools::Itertools;
 
     #[test]
-    fn calculates_reward_distribution() {
-        // setup
-        let amount = Token::from_nano(u32::MAX as u64 * 1_000_000_000);
-        println!("Paid to section: {:?}", amount.as_nano());
-        println!();
-
-        let iters = 10;
-        let mut nodes = BTreeMap::<XorName, (NodeAge, PublicKey)>::new();
-        for i in 0..iters {
-            let _ = nodes.insert(XorName::random(), (i + MIN_REWARD_AGE - 1, get_random_pk()));
-            let _ = nodes.insert(XorName::random(), (i + MIN_REWARD_AGE, get_random_pk()));
-            let _ = nodes.insert(XorName::random(), (i + MIN_REWARD_AGE, get_random_pk()));
-        }
-
-        println!("Added {} nodes", nodes.len());
+    fn reward_rate_follows_the_section_usage() {
+        let base = MAX_SUPPLY / REWARD_RATE_DIVISOR;
+        assert_eq!(RewardCalc::new(0, 0, 8).rate(), Token::from_nano(base));
+        assert_eq!(RewardCalc::new(1, 0, 8).rate(), Token::from_nano(base / 2));
+        // 8 adults, 3 of them full: 11/8 of the base rate, rounding down
+        assert_eq!(
+            RewardCalc::new(0, 3, 8).rate(),
+            Token::from_nano(base * 11 / 8)
+        );
+        // all full, or more full nodes than adults reported: double
+        assert_eq!(RewardCalc::new(0, 8, 8).rate(), Token::from_nano(base * 2));
+        assert_eq!(RewardCalc::new(0, 9, 8).rate(), Token::from_nano(base * 2));
+        // no adults known yet
+        assert_eq!(RewardCalc::new(0, 0, 0).rate(), Token::from_nano(base));
+        // beyond the smallest sections
+        assert_eq!(RewardCalc::new(64, 0, 8).rate(), Token::zero());
+        assert_eq!(RewardCalc::new(200, 0, 8).rate(), Token::zero());
+    }
 
-        // start timer
-        let now = std::time::Instant::now();
+    #[test]
+    fn reward_doubles_with_each_step_up_in_age() {
+        let calc = RewardCalc::new(0, 0, 8);
+        let rate = calc.rate().as_nano();
+        assert_eq!(calc.reward(0), Token::zero());
+        assert_eq!(calc.reward(MIN_REWARD_AGE - 1), Token::zero());
+        assert_eq!(calc.reward(MIN_REWARD_AGE), Token::from_nano(rate));
+        assert_eq!(calc.reward(MIN_REWARD_AGE + 3), Token::from_nano(rate * 8));
+        // saturating at the max amount
+        assert_eq!(calc.reward(MIN_REWARD_AGE + 63), Token::from_nano(u64::MAX));
+        assert_eq!(calc.reward(u8::MAX), Token::from_nano(u64::MAX));
+        assert_eq!(RewardCalc::work_units(u8::MAX), u64::MAX);
+    }
 
-        // calc
-        let rewards = distribute_rewards(amount, nodes);
+    #[test]
+    fn rewards_are_scaled_down_to_the_budget() {
+        let calc = RewardCalc::new(0, 0, 8);
+        let rate = calc.rate().as_nano();
+        let (young, old, newbie) = (XorName::random(), XorName::random(), XorName::random());
+        let mut nodes = BTreeMap::new();
+        let _ = nodes.insert(young, (MIN_REWARD_AGE, get_random_pk()));
+        let _ = nodes.insert(old, (MIN_REWARD_AGE + 1, get_random_pk()));
+        let _ = nodes.insert(newbie, (0, get_random_pk()));
 
-        // stop timer
-        let duration = now.elapsed();
+        let amounts = |budget| -> Vec<_> {
+            calc.rewards(Token::from_nano(budget), nodes.clone())
+                .into_iter()
+                .map(|(node, (_, _, reward))| (node, reward.as_nano()))
+                .sorted()
+                .collect()
+        };
+        // in full, when within budget
+        let expected: Vec<_> = vec![(young, rate), (old, rate * 2)]
+            .into_iter()
+            .sorted()
+            .collect();
+        assert_eq!(amounts(u64::MAX), expected);
+        // else proportionally, rounding down
+        let expected: Vec<_> = vec![(young, 3), (old, 6)].into_iter().sorted().collect();
+        assert_eq!(amounts(10), expected);
+        assert!(amounts(0).is_empty());
+    }
 
-        println!();
-        println!("Elapsed: {:?} ms", duration.as_millis());
-        println!();
+    #[test]
+    fn split_rewards_sum_up_to_the_reward() {
+        let (first, second, third) = (get_random_pk(), get_random_pk(), get_random_pk());
+        let three_way = [(first, 1), (second, 1), (third, 1)];
 
-        let mut total = 0;
-        let rewards = rewards.values().sorted();
-        for (_, _, amount) in rewards {
-            println!("{:?}", amount.as_nano());
-            total += amount.as_nano();
-        }
+        assert_eq!(
+            split_reward(Token::from_nano(1), &three_way),
+            vec![(first, Token::from_nano(1))]
+        );
+        assert_eq!(
+            split_reward(Token::from_nano(100), &three_way),
+            vec![
+                (first, Token::from_nano(34)),
+                (second, Token::from_nano(33)),
+                (third, Token::from_nano(33))
+            ]
+        );
+        assert_eq!(
+            split_reward(Token::from_nano(u64::MAX), &[(first, 90), (second, 10)])
+                .iter()
+                .map(|(_, part)| part.as_nano() as u128)
+                .sum::<u128>(),
+            u64::MAX as u128
+        );
+        assert_eq!(
+            split_reward(Token::from_nano(7), &[(first, 0), (second, 0)]),
+            vec![(first, Token::from_nano(7))]
+        );
+    }
 
-        println!();
+    #[test]
+    fn split_balance_leaves_the_remainder_with_the_lower_prefix() {
+        let lower = Prefix::default().pushed(false);
+        let upper = lower.sibling();
 
-        println!("Total rewards: {:?}", total);
+        let odd = Token::from_nano(101);
+        assert_eq!(
+            split_balance(odd, lower),
+            (Token::from_nano(51), Token::from_nano(50))
+        );
+        assert_eq!(
+            split_balance(odd, upper),
+            (Token::from_nano(50), Token::from_nano(51))
+        );
+        assert_eq!(
+            split_balance(Token::from_nano(100), upper),
+            (Token::from_nano(50), Token::from_nano(50))
+        );
+        assert_eq!(
+            split_balance(Token::from_nano(1), upper),
+            (Token::zero(), Token::from_nano(1))
+        );
+        let (ours, siblings) = split_balance(Token::from_nano(u64::MAX), lower.pushed(true));
+        assert_eq!(
+            ours.as_nano() as u128 + siblings.as_nano() as u128,
+            u64::MAX as u128
+        );
     }
 
     fn get_random_pk() -> PublicKey {
@@ -8,35 +8,60 @@
 
 use super::{
     elder_signing::ElderSigning,
-    reward_calc::{distribute_rewards, get_reward_and_mint_amount},
+    reward_accrual::{RewardAccrual, PAYOUT_THRESHOLD},
+    reward_calc::{get_reward_and_mint_amount, split_reward, RewardCalc},
     reward_stage::{
         CreditAccumulation, CreditProposal, RewardAccumulationDetails, RewardProposalDetails,
         RewardStage,
     },
+    reward_wallets::RewardWallets,
 };
 use crate::{
     capacity::MAX_SUPPLY,
     node_ops::{NodeDuties, NodeDuty, OutgoingMsg},
     Error, Result,
 };
-use log::{debug, info, warn};
+use bls::PublicKeySet;
+use log::{debug, error, info, warn};
 use sn_data_types::{
-    Credit, NodeAge, PublicKey, RewardAccumulation, RewardProposal, SectionElders, Signature,
+    Credit, CreditId, PublicKey, RewardAccumulation, RewardProposal, SectionElders, Signature,
     SignatureShare, SignedCredit, SignedCreditShare, Signing, Token, TransferPropagated,
 };
 use sn_messaging::{
     client::{Message, NodeCmd, NodeQuery, NodeSystemCmd, NodeSystemQuery},
     Aggregation, DstLocation, MessageId,
 };
-use std::collections::{BTreeMap, BTreeSet};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    mem,
+    time::{Duration, Instant},
+};
 use xor_name::{Prefix, XorName};
 
+/// The number of times our signature shares of a stuck reward payout are sent anew,
+/// before it's given up on.
+pub const MAX_REWARD_RESENDS: u32 = 3;
+
 ///
 #[derive(Clone)]
 pub struct RewardProcess {
     section: OurSection,
     stage: RewardStage,
     signing: ElderSigning,
+    // The signature shares from other Elders which could not be added, for diagnostics.
+    rejected_shares: usize,
+    // When the current stage was entered, and the times our shares of it were sent anew since.
+    stage_since: Instant,
+    resends: u32,
+}
+
+/// What to do about a reward payout stuck awaiting the agreement of the other Elders.
+#[derive(Debug, PartialEq, Eq)]
+pub enum StuckPayout {
+    /// Our signature shares are to be sent anew, e.g. for new Elders to add theirs.
+    Resend,
+    /// The payout is to be given up on, our shares having been sent anew too many times.
+    GiveUp,
 }
 
 ///
@@ -66,6 +91,9 @@ impl RewardProcess {
             section,
             signing,
             stage: RewardStage::AwaitingThreshold,
+            rejected_shares: 0,
+            stage_since: Instant::now(),
+            resends: 0,
         }
     }
 
@@ -73,15 +101,117 @@ impl RewardProcess {
         &self.stage
     }
 
+    /// Updates the key set of our Elders on churn, for the shares of the Elders
+    /// no longer among them to be rejected.
+    pub fn update_elders(&mut self, key_set: PublicKeySet) {
+        self.signing.set_public_key_set(key_set)
+    }
+
+    /// The number of signature shares from other Elders rejected so far,
+    /// as invalid or over credits not proposed.
+    pub fn rejected_shares(&self) -> usize {
+        self.rejected_shares
+    }
+
+    /// The credits proposed to the other Elders, while awaiting their agreement.
+    pub fn proposed_credits(&self) -> Vec<Credit> {
+        match &self.stage {
+            RewardStage::ProposingCredits(details) => details
+                .rewards
+                .values()
+                .map(|credit| credit.proposal.clone())
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Sends our signature shares of the credits anew if the payout is stuck awaiting the
+    /// agreement of the other Elders for longer than the timeout, e.g. after an Elder churn,
+    /// the same shares over the same credits being sent, not to pay out twice.
+    /// Returns the credits of the payout instead, should it be given up on.
+    pub async fn resend_if_stuck(
+        &mut self,
+        now: Instant,
+        timeout: Duration,
+    ) -> Result<Option<Result<NodeDuty, Vec<Credit>>>> {
+        let stuck = match &self.stage {
+            RewardStage::ProposingCredits(_) | RewardStage::AccumulatingCredits(_) => {
+                check_stuck(self.stage_since, self.resends, now, timeout)
+            }
+            _ => None,
+        };
+        match stuck {
+            None => Ok(None),
+            Some(StuckPayout::GiveUp) => Ok(Some(Err(self.credits()))),
+            Some(StuckPayout::Resend) => {
+                self.resends += 1;
+                let our_index = self.signing.our_index().await?;
+                let msg = match &self.stage {
+                    RewardStage::ProposingCredits(details) => send_prop_msg(
+                        details.get_proposal(self.section.wallet_key(), our_index),
+                        self.section.address(),
+                    ),
+                    RewardStage::AccumulatingCredits(details) => send_acc_msg(
+                        details.get_accumulation(self.section.wallet_key(), our_index),
+                        self.section.address(),
+                    ),
+                    _ => return Ok(None),
+                };
+                Ok(Some(Ok(msg)))
+            }
+        }
+    }
+
+    // The credits paid out, at whichever stage.
+    fn credits(&self) -> Vec<Credit> {
+        match &self.stage {
+            RewardStage::ProposingCredits(details) => details
+                .rewards
+                .values()
+                .map(|credit| credit.proposal.clone())
+                .collect(),
+            RewardStage::AccumulatingCredits(details) => details
+                .rewards
+                .values()
+                .map(|credit| credit.agreed_proposal.credit.clone())
+                .collect(),
+            RewardStage::Completed(credit_proofs) => credit_proofs
+                .values()
+                .map(|proof| proof.signed_credit.credit.clone())
+                .collect(),
+            RewardStage::None | RewardStage::AwaitingThreshold => vec![],
+        }
+    }
+
+    // Moves on to the stage, its timeout starting anew if of another kind.
+    fn set_stage(&mut self, stage: RewardStage) {
+        if mem::discriminant(&stage) != mem::discriminant(&self.stage) {
+            self.stage_since = Instant::now();
+            self.resends = 0;
+        }
+        self.stage = stage;
+    }
+
+    /// The key of our section at the churn the rewards are for.
+    pub fn section_key(&self) -> PublicKey {
+        self.section.our_key
+    }
+
     /// Calculates reward for each node
-    /// proportional to the age of it,
+    /// from the age of it and the reward rate of the section,
     /// out of the total payments received.
     /// Additionally adds newly minted tokens, unless max supply has been reached.
+    /// The rewards are accrued to the wallets of the nodes, those due being paid out,
+    /// along with the credits to other sections, e.g. a sibling's share of our balance
+    /// at a split.
     pub async fn reward_and_mint(
         &mut self,
         payments: Token,
         section_managed: Token,
-        our_nodes: BTreeMap<XorName, (NodeAge, PublicKey)>,
+        calc: RewardCalc,
+        wallets: &RewardWallets,
+        accrual: &mut RewardAccrual,
+        section_credits: Vec<Credit>,
     ) -> Result<NodeDuty> {
         /// Max supply is the proportional supply for a section in a network of a certain size.
         /// The network size is derived from the prefix len.
@@ -89,13 +219,49 @@ impl RewardProcess {
             Token::from_nano(MAX_SUPPLY / 2_u64.pow(self.section.our_prefix.bit_count() as u32));
         // derive an amount to pay out in rewards, i.e. payments + newly minted tokens
         let rewards = get_reward_and_mint_amount(payments, section_managed, max_supply);
+        // nodes accrue rewards once per churn, should it be processed again
+        let our_key = self.section.our_key;
+        for (node, (_, wallet, amount)) in calc.rewards(rewards, wallets.node_wallets()) {
+            // should the node have split its rewards, each destination accrues its part
+            for (recipient, amount) in split_reward(amount, &wallets.destinations(&node, wallet)) {
+                accrual.accrue(recipient, our_key, amount)?;
+            }
+        }
+        // the section pays out at most its payments and what's left of its supply to mint
+        let mut available = Token::from_nano(
+            payments.as_nano().saturating_add(
+                max_supply
+                    .as_nano()
+                    .saturating_sub(section_managed.as_nano()),
+            ),
+        );
+        let mut payouts = accrual.take_due(PAYOUT_THRESHOLD)?;
+        for (recipient, amount) in payouts.clone() {
+            match check_payout(recipient, amount, self.section.wallet_key(), available) {
+                Ok(true) => {
+                    available = Token::from_nano(available.as_nano() - amount.as_nano());
+                    continue;
+                }
+                Ok(false) => (),
+                Err(error) => {
+                    warn!("Payout to {} refused: {}", recipient, error);
+                    let _ = accrual.refund(recipient, amount)?;
+                }
+            }
+            let _ = payouts.remove(&recipient);
+        }
         // generate proposal
-        let reward_credits = self.get_reward_credits(rewards, self.section.our_key, our_nodes);
+        let mut reward_credits = self.get_reward_credits(payouts, our_key);
+        reward_credits.extend(section_credits.into_iter().map(|credit| CreditProposal {
+            proposal: credit,
+            signatures: Default::default(),
+            pending_agreement: None,
+        }));
         let proposal_details = self.sign_proposed_rewards(reward_credits).await?;
         let proposal = proposal_details
             .get_proposal(self.section.wallet_key(), self.signing.our_index().await?);
 
-        self.stage = RewardStage::ProposingCredits(proposal_details.clone());
+        self.set_stage(RewardStage::ProposingCredits(proposal_details.clone()));
         Ok(send_prop_msg(proposal, self.section.address()))
     }
 
@@ -141,26 +307,33 @@ impl RewardProcess {
         Ok(accumulation)
     }
 
+    // A share from another Elder failing to be added is skipped rather than failing the
+    // others with it, for an Elder sending junk not to stall the payout, nor to go unnoticed.
+    fn reject_share(&mut self, id: &CreditId, error: Error) {
+        self.rejected_shares += 1;
+        warn!(
+            "Rejected signature share over reward credit {:?} ({} so far): {}",
+            id, self.rejected_shares, error
+        );
+    }
+
     fn get_reward_credits(
         &self,
-        rewards: Token,
+        payouts: BTreeMap<PublicKey, Token>,
         section_key: PublicKey,
-        nodes: BTreeMap<XorName, (NodeAge, PublicKey)>,
     ) -> Vec<CreditProposal> {
-        // create reward distribution
-        distribute_rewards(rewards, nodes)
+        payouts
             .into_iter()
-            .map(|(node, (age, wallet, amount))| {
-                let id = MessageId::combine(vec![node, XorName::from(section_key)])
-                    .0
-                     .0;
+            .map(|(recipient, amount)| {
+                let names = vec![XorName::from(recipient), XorName::from(section_key)];
+                let id = MessageId::combine(names).0 .0;
 
                 CreditProposal {
                     proposal: Credit {
                         id,
                         amount,
-                        recipient: wallet,
-                        msg: format!("Reward at age {}, from {}", age, section_key),
+                        recipient,
+                        msg: format!("Rewards accrued until {}", section_key),
                     },
                     signatures: Default::default(),
                     pending_agreement: None,
@@ -191,20 +364,32 @@ impl RewardProcess {
 
                 // Add sigs of incoming proposal
                 for p in proposal.rewards {
-                    our_proposal.add_sig(p.id(), &p.actor_signature)?
+                    let added = self
+                        .signing
+                        .verify_share(&p.actor_signature, &p.credit)
+                        .and_then(|_| our_proposal.add_sig(p.id(), &p.actor_signature));
+                    if let Err(error) = added {
+                        self.reject_share(p.id(), error);
+                    }
                 }
 
                 let to_send = our_proposal
                     .get_proposal(self.section.wallet_key(), self.signing.our_index().await?);
 
-                self.stage = RewardStage::ProposingCredits(our_proposal);
+                self.set_stage(RewardStage::ProposingCredits(our_proposal));
 
                 Ok(send_prop_msg(to_send, self.section.address()))
             }
             RewardStage::ProposingCredits(mut proposal_details) => {
                 // Add proposals
                 for p in proposal.rewards {
-                    proposal_details.add_sig(p.id(), &p.actor_signature)?
+                    let added = self
+                        .signing
+                        .verify_share(&p.actor_signature, &p.credit)
+                        .and_then(|_| proposal_details.add_sig(p.id(), &p.actor_signature));
+                    if let Err(error) = added {
+                        self.reject_share(p.id(), error);
+                    }
                 }
 
                 if let Some(rewards) = proposal_details.pending_agreements() {
@@ -230,11 +415,11 @@ impl RewardProcess {
                         self.signing.our_index().await?,
                     );
 
-                    self.stage = RewardStage::AccumulatingCredits(our_acc);
+                    self.set_stage(RewardStage::AccumulatingCredits(our_acc));
 
                     Ok(send_acc_msg(to_send, self.section.address()))
                 } else {
-                    self.stage = RewardStage::ProposingCredits(proposal_details);
+                    self.set_stage(RewardStage::ProposingCredits(proposal_details));
                     Ok(NodeDuty::NoOp)
                 }
             }
@@ -268,13 +453,19 @@ impl RewardProcess {
 
                 // Add sigs of incoming proposal
                 for p in new_acc.rewards {
-                    our_acc.add_sig(p.id(), &p.sig)?
+                    let added = self
+                        .signing
+                        .verify_share(&p.sig, &p.signed_credit)
+                        .and_then(|_| our_acc.add_sig(p.id(), &p.sig));
+                    if let Err(error) = added {
+                        self.reject_share(p.id(), error);
+                    }
                 }
 
                 let to_send = our_acc
                     .get_accumulation(self.section.wallet_key(), self.signing.our_index().await?);
 
-                self.stage = RewardStage::AccumulatingCredits(our_acc);
+                self.set_stage(RewardStage::AccumulatingCredits(our_acc));
 
                 Ok(send_acc_msg(to_send, self.section.address()))
             }
@@ -300,27 +491,39 @@ impl RewardProcess {
 
                 // Add sigs of incoming proposal
                 for p in new_acc.rewards {
-                    our_acc.add_sig(p.id(), &p.sig)?
+                    let added = self
+                        .signing
+                        .verify_share(&p.sig, &p.signed_credit)
+                        .and_then(|_| our_acc.add_sig(p.id(), &p.sig));
+                    if let Err(error) = added {
+                        self.reject_share(p.id(), error);
+                    }
                 }
 
                 let to_send = our_acc
                     .get_accumulation(self.section.wallet_key(), self.signing.our_index().await?);
 
-                self.stage = RewardStage::AccumulatingCredits(our_acc);
+                self.set_stage(RewardStage::AccumulatingCredits(our_acc));
 
                 Ok(send_acc_msg(to_send, self.section.address()))
             }
             RewardStage::AccumulatingCredits(mut our_acc) => {
                 // Add sigs of incoming proposal
                 for p in new_acc.rewards {
-                    our_acc.add_sig(p.id(), &p.sig)?
+                    let added = self
+                        .signing
+                        .verify_share(&p.sig, &p.signed_credit)
+                        .and_then(|_| our_acc.add_sig(p.id(), &p.sig));
+                    if let Err(error) = added {
+                        self.reject_share(p.id(), error);
+                    }
                 }
 
                 if let Some(credit_proofs) = our_acc.pending_agreements() {
                     info!("******* there is an agreement for reward accumulation.");
-                    self.stage = RewardStage::Completed(credit_proofs);
+                    self.set_stage(RewardStage::Completed(credit_proofs));
                 } else {
-                    self.stage = RewardStage::AccumulatingCredits(our_acc);
+                    self.set_stage(RewardStage::AccumulatingCredits(our_acc));
                 }
                 Ok(NodeDuty::NoOp)
             }
@@ -330,6 +533,47 @@ impl RewardProcess {
     }
 }
 
+/// Checks a payout before it's proposed. An empty one, or one to our own section wallet,
+/// is not worth the msgs to the other Elders, and is skipped, returning false.
+/// One exceeding what the section has available to pay out is refused.
+fn check_payout(
+    recipient: PublicKey,
+    amount: Token,
+    section_wallet: PublicKey,
+    available: Token,
+) -> Result<bool> {
+    if amount == Token::zero() {
+        warn!("Skipping empty payout to {}", recipient);
+        return Ok(false);
+    }
+    if recipient == section_wallet {
+        error!("Skipping payout of {} to our own section wallet", amount);
+        return Ok(false);
+    }
+    if amount > available {
+        return Err(Error::InsufficientSectionFunds(amount, available));
+    }
+    Ok(true)
+}
+
+/// Whether the payout in a stage since then is stuck, its timeout being doubled with each
+/// time our shares were sent anew.
+fn check_stuck(
+    since: Instant,
+    resends: u32,
+    now: Instant,
+    timeout: Duration,
+) -> Option<StuckPayout> {
+    let waited = now.saturating_duration_since(since);
+    if waited < timeout * 2_u32.pow(resends.min(MAX_REWARD_RESENDS)) {
+        None
+    } else if resends < MAX_REWARD_RESENDS {
+        Some(StuckPayout::Resend)
+    } else {
+        Some(StuckPayout::GiveUp)
+    }
+}
+
 fn send_prop_msg(proposal: RewardProposal, our_elders: XorName) -> NodeDuty {
     NodeDuty::Send(OutgoingMsg {
         msg: Message::NodeCmd {
@@ -355,3 +599,74 @@ fn send_acc_msg(accumulation: RewardAccumulation, our_elders: XorName) -> NodeDu
         aggregation: Aggregation::None,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{check_payout, check_stuck, StuckPayout, MAX_REWARD_RESENDS};
+    use crate::{Error, Result};
+    use sn_data_types::{Keypair, PublicKey, Token};
+    use std::time::{Duration, Instant};
+
+    fn key() -> PublicKey {
+        Keypair::new_ed25519(&mut rand::thread_rng()).public_key()
+    }
+
+    #[test]
+    fn empty_payout_is_skipped() -> Result<()> {
+        let available = Token::from_nano(10);
+        assert!(!check_payout(key(), Token::zero(), key(), available)?);
+        assert!(check_payout(key(), Token::from_nano(1), key(), available)?);
+        Ok(())
+    }
+
+    #[test]
+    fn payout_to_our_own_section_wallet_is_skipped() -> Result<()> {
+        let section_wallet = key();
+        let amount = Token::from_nano(10);
+        assert!(!check_payout(
+            section_wallet,
+            amount,
+            section_wallet,
+            amount
+        )?);
+        Ok(())
+    }
+
+    #[test]
+    fn payout_exceeding_the_available_funds_is_refused() -> Result<()> {
+        let available = Token::from_nano(10);
+        assert!(check_payout(key(), available, key(), available)?);
+        match check_payout(key(), Token::from_nano(11), key(), available) {
+            Err(Error::InsufficientSectionFunds(amount, left))
+                if amount == Token::from_nano(11) && left == available =>
+            {
+                Ok(())
+            }
+            other => Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+    }
+
+    #[test]
+    fn stuck_payout_is_resent_then_given_up_on() -> Result<()> {
+        let timeout = Duration::from_secs(120);
+        let since = Instant::now();
+        assert_eq!(check_stuck(since, 0, since + timeout / 2, timeout), None);
+
+        // no agreement ever comes, e.g. the other Elders having churned
+        let mut resends = 0;
+        let mut now = since;
+        loop {
+            now += timeout * 2_u32.pow(MAX_REWARD_RESENDS);
+            match check_stuck(since, resends, now, timeout) {
+                Some(StuckPayout::Resend) => resends += 1,
+                Some(StuckPayout::GiveUp) => break,
+                other => return Err(Error::Logic(format!("Unexpected: {:?}", other))),
+            }
+        }
+        assert_eq!(resends, MAX_REWARD_RESENDS);
+
+        // the timeout doubles with each resend
+        assert_eq!(check_stuck(since, 1, since + timeout, timeout), None);
+        Ok(())
+    }
+}
@@ -6,8 +6,9 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::{Error, Result};
+use crate::{utils, Error, Result};
 use log::{debug, info, warn};
+use serde::Serialize;
 use sn_data_types::{
     Credit, CreditAgreementProof, CreditId, PublicKey, ReplicaPublicKeySet, SignatureShare,
     SignedCredit, SignedCreditShare, Token, TransferPropagated,
@@ -120,6 +121,7 @@ impl RewardProposalDetails {
         if let Some(true) = check(&sig, &credit.signatures) {
             return Ok(());
         }
+        verify(&self.pk_set, sig, &credit.proposal)?;
         let _ = credit.signatures.insert(sig.index, sig.share.clone());
         let min_count = 1 + self.pk_set.threshold();
         if credit.signatures.len() >= min_count {
@@ -158,6 +160,21 @@ fn check(sig: &SignatureShare, signatures: &BTreeMap<usize, bls::SignatureShare>
     }
 }
 
+/// Fails unless the share is by the Elder of its index over the credit, for a share
+/// not to spoil the combining of the valid ones.
+fn verify<T: Serialize>(
+    pk_set: &ReplicaPublicKeySet,
+    sig: &SignatureShare,
+    credit: &T,
+) -> Result<()> {
+    let data = utils::serialise(credit)?;
+    if pk_set.public_key_share(sig.index).verify(&sig.share, data) {
+        Ok(())
+    } else {
+        Err(Error::InvalidRewardSignatureShare(sig.index))
+    }
+}
+
 impl RewardAccumulationDetails {
     pub(crate) fn pending_agreements(&self) -> Option<BTreeMap<CreditId, CreditAgreementProof>> {
         let rewards: BTreeMap<CreditId, CreditAgreementProof> = self
@@ -207,6 +224,7 @@ impl RewardAccumulationDetails {
         if let Some(true) = check(sig, &credit.signatures) {
             return Ok(());
         }
+        verify(&self.pk_set, sig, &credit.agreed_proposal)?;
         let _ = credit.signatures.insert(sig.index, sig.share.clone());
         let min_count = 1 + self.pk_set.threshold();
         if credit.signatures.len() >= min_count {
@@ -227,3 +245,57 @@ impl RewardAccumulationDetails {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{CreditProposal, RewardProposalDetails};
+    use crate::{utils, Error, Result};
+    use sn_data_types::{Credit, Keypair, SignatureShare, Token};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn only_valid_shares_are_combined_into_an_agreement() -> Result<()> {
+        let elders = bls::SecretKeySet::random(1, &mut rand::thread_rng());
+        let credit = Credit {
+            id: Default::default(),
+            amount: Token::from_nano(10),
+            recipient: Keypair::new_ed25519(&mut rand::thread_rng()).public_key(),
+            msg: String::new(),
+        };
+        let id = *credit.id();
+        let mut details = RewardProposalDetails {
+            pk_set: elders.public_keys(),
+            rewards: BTreeMap::new(),
+        };
+        let _ = details.rewards.insert(
+            id,
+            CreditProposal {
+                proposal: credit.clone(),
+                signatures: Default::default(),
+                pending_agreement: None,
+            },
+        );
+        let data = utils::serialise(&credit)?;
+        let share = |index: usize, data: &[u8]| SignatureShare {
+            share: elders.secret_key_share(index).sign(data),
+            index,
+        };
+
+        match details.add_sig(&id, &share(0, b"junk")) {
+            Err(Error::InvalidRewardSignatureShare(0)) => (),
+            other => return Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+        details.add_sig(&id, &share(1, &data))?;
+        assert!(details.pending_agreements().is_none());
+
+        details.add_sig(&id, &share(2, &data))?;
+        let agreements = details
+            .pending_agreements()
+            .ok_or_else(|| Error::Logic("No agreement".to_string()))?;
+        assert_eq!(
+            agreements.get(&id).map(|signed| &signed.credit),
+            Some(&credit)
+        );
+        Ok(())
+    }
+}
@@ -27,6 +27,9 @@ use xor_name::XorName;
 #[derive(Clone)]
 pub struct RewardWallets {
     node_rewards: DashMap<XorName, (NodeAge, PublicKey)>,
+    // The weighted destinations the rewards of a node are split among,
+    // for the nodes not paid in full to their registered wallet.
+    splits: DashMap<XorName, Vec<(PublicKey, u32)>>,
 }
 
 // Node age
@@ -36,6 +39,7 @@ impl RewardWallets {
     pub fn new(node_rewards: BTreeMap<XorName, (NodeAge, PublicKey)>) -> Self {
         Self {
             node_rewards: node_rewards.into_iter().collect(),
+            splits: Default::default(),
         }
     }
 
@@ -59,12 +63,31 @@ impl RewardWallets {
             .collect()
     }
 
+    /// The destinations of the rewards of a node with the wallet, with their weights.
+    pub fn destinations(&self, node_name: &XorName, wallet: PublicKey) -> Vec<(PublicKey, u32)> {
+        match self.splits.get(node_name) {
+            Some(split) => split.clone(),
+            None => vec![(wallet, 1)],
+        }
+    }
+
+    /// Splits the future rewards of a registered node among weighted destinations,
+    /// until it registers a wallet anew.
+    /// Not yet reachable by nodes, as the wallet registration msg carries a single wallet.
+    #[allow(dead_code)]
+    pub fn set_wallet_split(&self, node_name: XorName, destinations: Vec<(PublicKey, u32)>) {
+        if self.node_rewards.contains_key(&node_name) {
+            let _ = self.splits.insert(node_name, destinations);
+        }
+    }
+
     /// Removes a subset of the nodes,
     /// more specifically those no longer
     /// part of this section, after a split.
     pub fn remove_wallets(&mut self, split_nodes: BTreeSet<XorName>) {
         for node in split_nodes {
             let _ = self.node_rewards.remove(&node);
+            let _ = self.splits.remove(&node);
         }
     }
 
@@ -72,11 +95,13 @@ impl RewardWallets {
     /// ... or, an active node updates its wallet.
     pub fn set_node_wallet(&self, node_name: XorName, age: Age, wallet: PublicKey) {
         let _ = self.node_rewards.insert(node_name, (age, wallet));
+        let _ = self.splits.remove(&node_name);
     }
 
     /// When the section becomes aware that a node has left,
     /// its reward key is removed.
     pub fn remove_wallet(&self, node_name: XorName) {
         let _ = self.node_rewards.remove(&node_name);
+        let _ = self.splits.remove(&node_name);
     }
 }
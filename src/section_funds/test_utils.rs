@@ -0,0 +1,35 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use bls::{SecretKey, SecretKeySet};
+use sn_data_types::{
+    Credit, CreditAgreementProof, Keypair, PublicKey, Signature, SignedCredit, Token,
+};
+
+/// A new random key, e.g. of a wallet to pay.
+pub fn random_key() -> PublicKey {
+    Keypair::new_ed25519(&mut rand::thread_rng()).public_key()
+}
+
+/// A proof of the credit of `amount` to `recipient`, signed with throwaway keys.
+/// Its signatures aren't checked by the section funds, which only book the credit.
+pub fn credit_proof(recipient: PublicKey, amount: u64) -> CreditAgreementProof {
+    CreditAgreementProof {
+        signed_credit: SignedCredit {
+            credit: Credit {
+                id: Default::default(),
+                amount: Token::from_nano(amount),
+                recipient,
+                msg: String::new(),
+            },
+            actor_signature: Signature::Bls(SecretKey::random().sign(b"credit")),
+        },
+        debiting_replicas_sig: Signature::Bls(SecretKey::random().sign(b"payout")),
+        debiting_replicas_keys: SecretKeySet::random(0, &mut rand::thread_rng()).public_keys(),
+    }
+}
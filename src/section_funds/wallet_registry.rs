@@ -0,0 +1,139 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{to_db_key::ToDbKey, utils, Result};
+use pickledb::PickleDb;
+use serde::{Deserialize, Serialize};
+use sn_data_types::{NodeAge, PublicKey};
+use sn_messaging::MessageId;
+use std::{collections::BTreeMap, path::Path};
+use xor_name::XorName;
+
+const WALLET_REGISTRY_DB_NAME: &str = "wallet_registry.db";
+
+/// The number of latest registration msgs of a node remembered, for those to be refused if
+/// replayed.
+const MAX_APPLIED_REGISTRATIONS: usize = 32;
+
+#[derive(Serialize, Deserialize)]
+struct Registration {
+    node: XorName,
+    age: NodeAge,
+    wallet: PublicKey,
+    // The ids of the latest registration msgs applied, oldest first.
+    applied: Vec<MessageId>,
+}
+
+/// The wallets registered by the nodes of the section to be paid their rewards to,
+/// kept on disk for them to survive restarts. A registration is applied once only,
+/// for an older one replayed not to undo a later update.
+pub(crate) struct WalletRegistry {
+    db: PickleDb,
+}
+
+impl WalletRegistry {
+    pub fn new(path: &Path) -> Result<Self> {
+        Ok(Self {
+            db: utils::new_auto_dump_db(path, WALLET_REGISTRY_DB_NAME)?,
+        })
+    }
+
+    /// The wallets registered, by node.
+    pub fn wallets(&self) -> BTreeMap<XorName, (NodeAge, PublicKey)> {
+        self.db
+            .get_all()
+            .iter()
+            .filter_map(|key| self.db.get::<Registration>(key))
+            .map(|registration| (registration.node, (registration.age, registration.wallet)))
+            .collect()
+    }
+
+    /// Registers the wallet of the node, by the msg of the id, unless applied already,
+    /// in which case false is returned, the registration not to be applied.
+    pub fn register(
+        &mut self,
+        node: XorName,
+        age: NodeAge,
+        wallet: PublicKey,
+        msg_id: MessageId,
+    ) -> Result<bool> {
+        let key = node.to_db_key()?;
+        let mut applied = match self.db.get::<Registration>(&key) {
+            Some(registration) if registration.applied.contains(&msg_id) => return Ok(false),
+            Some(registration) => registration.applied,
+            None => vec![],
+        };
+        applied.push(msg_id);
+        if applied.len() > MAX_APPLIED_REGISTRATIONS {
+            let _ = applied.remove(0);
+        }
+        let registration = Registration {
+            node,
+            age,
+            wallet,
+            applied,
+        };
+        self.db.set(&key, &registration)?;
+        Ok(true)
+    }
+
+    /// Removes the wallet of a node which left the section.
+    pub fn remove(&mut self, node: XorName) -> Result<()> {
+        let _ = self.db.rem(&node.to_db_key()?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WalletRegistry;
+    use crate::{Error, Result};
+    use sn_data_types::{Keypair, PublicKey};
+    use sn_messaging::MessageId;
+    use tempdir::TempDir;
+    use xor_name::XorName;
+
+    fn key() -> PublicKey {
+        Keypair::new_ed25519(&mut rand::thread_rng()).public_key()
+    }
+
+    #[test]
+    fn wallet_is_registered_and_updated() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let node = XorName::random();
+        let (first, second) = (key(), key());
+        {
+            let mut registry = WalletRegistry::new(root.path())?;
+            assert!(registry.register(node, 5, first, MessageId::new())?);
+            assert_eq!(registry.wallets().get(&node), Some(&(5, first)));
+            assert!(registry.register(node, 6, second, MessageId::new())?);
+        }
+
+        let registry = WalletRegistry::new(root.path())?;
+        assert_eq!(registry.wallets().get(&node), Some(&(6, second)));
+        Ok(())
+    }
+
+    #[test]
+    fn older_registration_replayed_is_refused() -> Result<()> {
+        let root = TempDir::new("test").map_err(|e| Error::TempDirCreationFailed(e.to_string()))?;
+        let mut registry = WalletRegistry::new(root.path())?;
+        let node = XorName::random();
+        let (first, second) = (key(), key());
+        let older = MessageId::new();
+
+        assert!(registry.register(node, 5, first, older)?);
+        assert!(registry.register(node, 5, second, MessageId::new())?);
+        assert!(!registry.register(node, 5, first, older)?);
+        assert_eq!(registry.wallets().get(&node), Some(&(5, second)));
+
+        registry.remove(node)?;
+        assert!(registry.wallets().is_empty());
+        Ok(())
+    }
+}
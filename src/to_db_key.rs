@@ -11,6 +11,7 @@ use serde::{de::DeserializeOwned, Serialize};
 use sn_data_types::{
     BlobAddress, CreditId, DebitId, Keypair, MapAddress, PublicKey, SequenceAddress,
 };
+use sn_messaging::MessageId;
 use xor_name::XorName;
 
 pub(crate) trait ToDbKey: Serialize {
@@ -36,6 +37,7 @@ impl ToDbKey for PublicKey {}
 impl ToDbKey for XorName {}
 impl ToDbKey for CreditId {}
 impl ToDbKey for DebitId {}
+impl ToDbKey for MessageId {}
 
 #[cfg(test)]
 mod test {
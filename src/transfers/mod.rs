@@ -10,7 +10,8 @@ pub mod get_replicas;
 pub mod replica_signing;
 pub mod replicas;
 pub mod store;
-mod test_utils;
+#[cfg(test)]
+pub(crate) mod test_utils;
 
 use self::{
     replica_signing::ReplicaSigning,
@@ -20,6 +21,7 @@ use crate::{
     capacity::RateLimit,
     error::{convert_dt_error_to_error_message, convert_to_error_message},
     node_ops::{NodeDuties, NodeDuty, OutgoingMsg},
+    section_funds::reward_calc::RewardCalc,
     utils, Error, Result,
 };
 use log::{debug, error, info, trace, warn};
@@ -80,14 +82,21 @@ pub struct Transfers {
     rate_limit: RateLimit,
     // TODO: limit this? where do we store it
     recently_validated_transfers: Arc<Mutex<HashSet<DebitId>>>,
+    // Whether payments not signed by the requester are rejected.
+    strict_payment_checks: bool,
 }
 
 impl Transfers {
-    pub fn new(replicas: Replicas<ReplicaSigningImpl>, rate_limit: RateLimit) -> Self {
+    pub fn new(
+        replicas: Replicas<ReplicaSigningImpl>,
+        rate_limit: RateLimit,
+        strict_payment_checks: bool,
+    ) -> Self {
         Self {
             replicas,
             rate_limit,
             recently_validated_transfers: Default::default(),
+            strict_payment_checks,
         }
     }
 
@@ -115,6 +124,11 @@ impl Transfers {
         self.replicas.keep_keys_of(prefix).await
     }
 
+    /// The calculation of node rewards, at the current usage of the section's storage capacity.
+    pub async fn reward_calc(&self) -> RewardCalc {
+        self.rate_limit.reward_calc().await
+    }
+
     ///
     pub async fn increase_full_node_count(&mut self, node_id: PublicKey) -> Result<()> {
         self.rate_limit.increase_full_node_count(node_id).await
@@ -182,6 +196,21 @@ impl Transfers {
             _ => return Ok(vec![]),
         };
 
+        if self.strict_payment_checks && !is_signed_by(payment, origin.id()) {
+            warn!("Payment: not signed by the requester {}", origin.id());
+            return Ok(vec![NodeDuty::Send(OutgoingMsg {
+                msg: Message::CmdError {
+                    error: CmdError::Data(ErrorMessage::InvalidSignature),
+                    id: MessageId::in_response_to(&msg.id()),
+                    correlation_id: msg.id(),
+                    target_section_pk: None,
+                },
+                section_source: false, // strictly this is not correct, but we don't expect responses to an error..
+                dst: DstLocation::EndUser(origin),
+                aggregation: Aggregation::None, // TODO: to_be_aggregated: Aggregation::AtDestination,
+            })]);
+        }
+
         // Make sure we are actually at the correct replicas,
         // before executing the debit.
         // (We could also add a method that executes both
@@ -490,11 +519,7 @@ impl Transfers {
                     target_section_pk: None,
                 }
             }
-            Err(e) => {
-                error!("Error receiving propogated: {}", e);
-
-                return Err(e);
-            }
+            Err(e) => propagation_failure(e, credit_proof, msg_id),
         };
         Ok(NodeDuty::Send(OutgoingMsg {
             msg,
@@ -522,3 +547,88 @@ impl Display for Transfers {
         write!(formatter, "Transfers")
     }
 }
+
+/// Whether the payment is from the wallet of the requester, with its debit signed by the
+/// requester's key. The other signatures are checked by the Replicas on registration.
+fn is_signed_by(payment: &TransferAgreementProof, requester: &PublicKey) -> bool {
+    let signed_debit = &payment.signed_debit;
+    if signed_debit.sender() != *requester {
+        return false;
+    }
+    match utils::serialise(&signed_debit.debit) {
+        Ok(bytes) => requester
+            .verify(&signed_debit.actor_signature, &bytes)
+            .is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// The error to answer a failed propagation with, for the sending Elders to learn of
+/// the credit not being applied, such as a reward payout, instead of it going missing.
+fn propagation_failure(
+    error: Error,
+    credit_proof: &CreditAgreementProof,
+    msg_id: MessageId,
+) -> Message {
+    error!(
+        "Error receiving propagated credit of {} to {}: {}",
+        credit_proof.amount(),
+        credit_proof.recipient(),
+        error
+    );
+    let error = match error {
+        Error::InvalidPropagatedTransfer(_) => ErrorMessage::InvalidSignature,
+        error => convert_to_error_message(error).unwrap_or(ErrorMessage::InvalidOperation),
+    };
+    Message::NodeCmdError {
+        error: NodeCmdError::Transfers(NodeTransferError::TransferPropagation(error)),
+        id: MessageId::in_response_to(&msg_id),
+        correlation_id: msg_id,
+        target_section_pk: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_signed_by, propagation_failure, test_utils::payment};
+    use crate::{Error, Result};
+    use sn_data_types::Keypair;
+    use sn_messaging::{
+        client::{Error as ErrorMessage, Message, NodeCmdError, NodeTransferError},
+        MessageId,
+    };
+
+    #[test]
+    fn only_payments_signed_by_the_requester_pass() -> Result<()> {
+        let requester = Keypair::new_ed25519(&mut rand::thread_rng());
+        let other = Keypair::new_ed25519(&mut rand::thread_rng());
+        let key = requester.public_key();
+
+        assert!(is_signed_by(&payment(&requester, &requester)?, &key));
+        // Paid from another wallet, thus without signature by the requester.
+        assert!(!is_signed_by(&payment(&other, &other)?, &key));
+        // From the requester's wallet, but forged by another key.
+        assert!(!is_signed_by(&payment(&requester, &other)?, &key));
+        Ok(())
+    }
+
+    #[test]
+    fn failed_propagation_is_answered_with_an_error() -> Result<()> {
+        let payer = Keypair::new_ed25519(&mut rand::thread_rng());
+        let credit_proof = payment(&payer, &payer)?.credit_proof();
+        let msg_id = MessageId::new();
+        let error = Error::InvalidPropagatedTransfer(credit_proof.clone());
+
+        match propagation_failure(error, &credit_proof, msg_id) {
+            Message::NodeCmdError {
+                error:
+                    NodeCmdError::Transfers(NodeTransferError::TransferPropagation(
+                        ErrorMessage::InvalidSignature,
+                    )),
+                correlation_id,
+                ..
+            } if correlation_id == msg_id => Ok(()),
+            other => Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+    }
+}
@@ -7,12 +7,13 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::replica_signing::ReplicaSigning;
-use crate::{Error, Result};
+use crate::{utils, Error, Result};
 use async_trait::async_trait;
 use bls::{PublicKeySet, PublicKeyShare, SecretKeySet, SecretKeyShare};
+use crdts::Dot;
 use sn_data_types::{
-    Credit, CreditAgreementProof, PublicKey, SignatureShare, SignedCredit, SignedDebit,
-    SignedTransfer, Token,
+    Credit, CreditAgreementProof, Debit, Keypair, PublicKey, SignatureShare, SignedCredit,
+    SignedDebit, SignedTransfer, Token, TransferAgreementProof,
 };
 use std::collections::BTreeMap;
 
@@ -176,3 +177,33 @@ pub fn get_genesis(
         debiting_replicas_keys: peer_replicas,
     })
 }
+
+/// A proof of a payment of 1 nano from the wallet of `payer` to itself, the transfer
+/// being signed by `signer`.
+pub fn payment(payer: &Keypair, signer: &Keypair) -> Result<TransferAgreementProof> {
+    let debit = Debit {
+        id: Dot::new(payer.public_key(), 0),
+        amount: Token::from_nano(1),
+    };
+    let credit = Credit {
+        id: Default::default(),
+        amount: Token::from_nano(1),
+        recipient: payer.public_key(),
+        msg: String::new(),
+    };
+    let debit_sig = signer.sign(&utils::serialise(&debit)?);
+    let credit_sig = signer.sign(&utils::serialise(&credit)?);
+    Ok(TransferAgreementProof {
+        signed_debit: SignedDebit {
+            debit,
+            actor_signature: debit_sig.clone(),
+        },
+        signed_credit: SignedCredit {
+            credit,
+            actor_signature: credit_sig.clone(),
+        },
+        debit_sig,
+        credit_sig,
+        debiting_replicas_keys: SecretKeySet::random(0, &mut rand::thread_rng()).public_keys(),
+    })
+}
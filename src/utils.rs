@@ -16,8 +16,9 @@ use log::{Log, Metadata, Record};
 use pickledb::{PickleDb, PickleDbDumpPolicy};
 use rand::{distributions::Standard, CryptoRng, Rng};
 use serde::{de::DeserializeOwned, Serialize};
-use std::io::Write;
+use std::io::{Read, Write};
 use std::{fs, path::Path};
+use tiny_keccak::{Hasher, Sha3};
 
 const NODE_MODULE_NAME: &str = "sn_node";
 
@@ -51,6 +52,23 @@ pub(crate) fn serialise<T: Serialize>(data: &T) -> Result<Bytes> {
     Ok(Bytes::copy_from_slice(serialised_data.as_slice()))
 }
 
+/// SHA3-256 of the nonce followed by the bytes read, hashed a segment at a time.
+pub(crate) fn digest<R: Read>(nonce: &[u8], mut reader: R) -> Result<[u8; 32]> {
+    let mut hasher = Sha3::v256();
+    hasher.update(nonce);
+    let mut segment = [0; 8192];
+    loop {
+        let read = reader.read(&mut segment)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&segment[..read]);
+    }
+    let mut digest = [0; 32];
+    hasher.finalize(&mut digest);
+    Ok(digest)
+}
+
 #[allow(unused)]
 pub(crate) fn deserialise<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
     bincode::deserialize(bytes).map_err(Error::Bincode)
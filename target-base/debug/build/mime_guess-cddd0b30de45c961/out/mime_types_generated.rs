@@ -0,0 +1,2 @@
+static REV_MAPPINGS: &'static [(UniCase<&'static str>, TopLevelExts)] = &[(UniCase::ascii("application"), TopLevelExts { start: 0, end: 938, subs: &[(UniCase::ascii("acad"), (0, 1)),(UniCase::ascii("andrew-inset"), (1, 2)),(UniCase::ascii("annodex"), (2, 3)),(UniCase::ascii("applixware"), (3, 4)),(UniCase::ascii("atom+xml"), (4, 5)),(UniCase::ascii("atomcat+xml"), (5, 6)),(UniCase::ascii("atomsvc+xml"), (6, 7)),(UniCase::ascii("bdoc"), (7, 8)),(UniCase::ascii("ccxml+xml"), (8, 9)),(UniCase::ascii("cdmi-capability"), (9, 10)),(UniCase::ascii("cdmi-container"), (10, 11)),(UniCase::ascii("cdmi-domain"), (11, 12)),(UniCase::ascii("cdmi-object"), (12, 13)),(UniCase::ascii("cdmi-queue"), (13, 14)),(UniCase::ascii("cu-seeme"), (14, 15)),(UniCase::ascii("dash+xml"), (15, 16)),(UniCase::ascii("davmount+xml"), (16, 17)),(UniCase::ascii("directx"), (17, 18)),(UniCase::ascii("docbook+xml"), (18, 19)),(UniCase::ascii("dssc+der"), (19, 20)),(UniCase::ascii("dssc+xml"), (20, 21)),(UniCase::ascii("ecmascript"), (21, 23)),(UniCase::ascii("emma+xml"), (23, 24)),(UniCase::ascii("envoy"), (24, 25)),(UniCase::ascii("epub+zip"), (25, 26)),(UniCase::ascii("etl"), (26, 27)),(UniCase::ascii("exi"), (27, 28)),(UniCase::ascii("font-sfnt"), (28, 30)),(UniCase::ascii("font-tdpfr"), (30, 31)),(UniCase::ascii("font-woff"), (31, 32)),(UniCase::ascii("fractals"), (32, 33)),(UniCase::ascii("fsharp-script"), (33, 35)),(UniCase::ascii("futuresplash"), (35, 36)),(UniCase::ascii("geo+json"), (36, 37)),(UniCase::ascii("gml+xml"), (37, 38)),(UniCase::ascii("gpx+xml"), (38, 39)),(UniCase::ascii("gxf"), (39, 40)),(UniCase::ascii("hjson"), (40, 41)),(UniCase::ascii("hta"), (41, 42)),(UniCase::ascii("hyperstudio"), (42, 43)),(UniCase::ascii("inkml+xml"), (43, 45)),(UniCase::ascii("internet-property-stream"), (45, 46)),(UniCase::ascii("ipfix"), (46, 47)),(UniCase::ascii("java-archive"), (47, 50)),(UniCase::ascii("java-serialized-object"), (50, 51)),(UniCase::ascii("javascript"), (51, 54)),(UniCase::ascii("json"), (54, 55)),(UniCase::ascii("json5"), (55, 56)),(UniCase::ascii("jsonml+json"), (56, 57)),(UniCase::ascii("ld+json"), (57, 58)),(UniCase::ascii("liquidmotion"), (58, 60)),(UniCase::ascii("lost+xml"), (60, 61)),(UniCase::ascii("mac-binhex40"), (61, 62)),(UniCase::ascii("mac-compactpro"), (62, 63)),(UniCase::ascii("mads+xml"), (63, 64)),(UniCase::ascii("manifest+json"), (64, 65)),(UniCase::ascii("marc"), (65, 66)),(UniCase::ascii("marcxml+xml"), (66, 67)),(UniCase::ascii("mathematica"), (67, 70)),(UniCase::ascii("mathml+xml"), (70, 71)),(UniCase::ascii("mbox"), (71, 72)),(UniCase::ascii("mediaservercontrol+xml"), (72, 73)),(UniCase::ascii("metalink+xml"), (73, 74)),(UniCase::ascii("metalink4+xml"), (74, 75)),(UniCase::ascii("mets+xml"), (75, 76)),(UniCase::ascii("mods+xml"), (76, 77)),(UniCase::ascii("mp21"), (77, 79)),(UniCase::ascii("mp4"), (79, 80)),(UniCase::ascii("mpeg"), (80, 81)),(UniCase::ascii("ms-vsi"), (81, 82)),(UniCase::ascii("msaccess"), (82, 89)),(UniCase::ascii("msaccess.addin"), (89, 90)),(UniCase::ascii("msaccess.cab"), (90, 91)),(UniCase::ascii("msaccess.ftemplate"), (91, 92)),(UniCase::ascii("msaccess.runtime"), (92, 93)),(UniCase::ascii("msaccess.webapplication"), (93, 94)),(UniCase::ascii("msword"), (94, 98)),(UniCase::ascii("mxf"), (98, 99)),(UniCase::ascii("octet-stream"), (99, 172)),(UniCase::ascii("oda"), (172, 173)),(UniCase::ascii("oebps-package+xml"), (173, 174)),(UniCase::ascii("ogg"), (174, 175)),(UniCase::ascii("olescript"), (175, 176)),(UniCase::ascii("omdoc+xml"), (176, 177)),(UniCase::ascii("onenote"), (177, 183)),(UniCase::ascii("opensearchdescription+xml"), (183, 184)),(UniCase::ascii("oxps"), (184, 185)),(UniCase::ascii("patch-ops-error+xml"), (185, 186)),(UniCase::ascii("pdf"), (186, 187)),(UniCase::ascii("pgp-encrypted"), (187, 188)),(UniCase::ascii("pgp-signature"), (188, 190)),(UniCase::ascii("pics-rules"), (190, 191)),(UniCase::ascii("pkcs10"), (191, 192)),(UniCase::ascii("pkcs7-mime"), (192, 194)),(UniCase::ascii("pkcs7-signature"), (194, 195)),(UniCase::ascii("pkcs8"), (195, 196)),(UniCase::ascii("pkix-attr-cert"), (196, 197)),(UniCase::ascii("pkix-crl"), (197, 198)),(UniCase::ascii("pkix-pkipath"), (198, 199)),(UniCase::ascii("pkixcmp"), (199, 200)),(UniCase::ascii("postscript"), (200, 203)),(UniCase::ascii("PowerShell"), (203, 204)),(UniCase::ascii("prs.cww"), (204, 205)),(UniCase::ascii("pskc+xml"), (205, 206)),(UniCase::ascii("raml+yaml"), (206, 207)),(UniCase::ascii("rat-file"), (207, 208)),(UniCase::ascii("rdf+xml"), (208, 209)),(UniCase::ascii("reginfo+xml"), (209, 210)),(UniCase::ascii("relax-ng-compact-syntax"), (210, 211)),(UniCase::ascii("resource-lists+xml"), (211, 212)),(UniCase::ascii("resource-lists-diff+xml"), (212, 213)),(UniCase::ascii("rpki-ghostbusters"), (213, 214)),(UniCase::ascii("rpki-manifest"), (214, 215)),(UniCase::ascii("rpki-roa"), (215, 216)),(UniCase::ascii("rsd+xml"), (216, 217)),(UniCase::ascii("rss+xml"), (217, 218)),(UniCase::ascii("rtf"), (218, 219)),(UniCase::ascii("sbml+xml"), (219, 220)),(UniCase::ascii("scvp-cv-request"), (220, 221)),(UniCase::ascii("scvp-cv-response"), (221, 222)),(UniCase::ascii("scvp-vp-request"), (222, 223)),(UniCase::ascii("scvp-vp-response"), (223, 224)),(UniCase::ascii("sdp"), (224, 225)),(UniCase::ascii("set-payment-initiation"), (225, 226)),(UniCase::ascii("set-registration-initiation"), (226, 227)),(UniCase::ascii("shf+xml"), (227, 228)),(UniCase::ascii("smil+xml"), (228, 229)),(UniCase::ascii("sparql-query"), (229, 230)),(UniCase::ascii("sparql-results+xml"), (230, 231)),(UniCase::ascii("srgs"), (231, 232)),(UniCase::ascii("srgs+xml"), (232, 233)),(UniCase::ascii("sru+xml"), (233, 234)),(UniCase::ascii("ssdl+xml"), (234, 235)),(UniCase::ascii("ssml+xml"), (235, 236)),(UniCase::ascii("step"), (236, 238)),(UniCase::ascii("streamingmedia"), (238, 239)),(UniCase::ascii("tei+xml"), (239, 241)),(UniCase::ascii("thraud+xml"), (241, 242)),(UniCase::ascii("timestamped-data"), (242, 243)),(UniCase::ascii("vnd.3gpp.pic-bw-large"), (243, 244)),(UniCase::ascii("vnd.3gpp.pic-bw-small"), (244, 245)),(UniCase::ascii("vnd.3gpp.pic-bw-var"), (245, 246)),(UniCase::ascii("vnd.3gpp2.tcap"), (246, 247)),(UniCase::ascii("vnd.3m.post-it-notes"), (247, 248)),(UniCase::ascii("vnd.accpac.simply.aso"), (248, 249)),(UniCase::ascii("vnd.accpac.simply.imp"), (249, 250)),(UniCase::ascii("vnd.acucobol"), (250, 251)),(UniCase::ascii("vnd.acucorp"), (251, 253)),(UniCase::ascii("vnd.adobe.air-application-installer-package+zip"), (253, 254)),(UniCase::ascii("vnd.adobe.formscentral.fcdt"), (254, 255)),(UniCase::ascii("vnd.adobe.fxp"), (255, 257)),(UniCase::ascii("vnd.adobe.xdp+xml"), (257, 258)),(UniCase::ascii("vnd.adobe.xfdf"), (258, 259)),(UniCase::ascii("vnd.ahead.space"), (259, 260)),(UniCase::ascii("vnd.airzip.filesecure.azf"), (260, 261)),(UniCase::ascii("vnd.airzip.filesecure.azs"), (261, 262)),(UniCase::ascii("vnd.amazon.ebook"), (262, 263)),(UniCase::ascii("vnd.americandynamics.acc"), (263, 264)),(UniCase::ascii("vnd.amiga.ami"), (264, 265)),(UniCase::ascii("vnd.android.package-archive"), (265, 266)),(UniCase::ascii("vnd.anser-web-certificate-issue-initiation"), (266, 267)),(UniCase::ascii("vnd.anser-web-funds-transfer-initiation"), (267, 268)),(UniCase::ascii("vnd.antix.game-component"), (268, 269)),(UniCase::ascii("vnd.apple.installer+xml"), (269, 270)),(UniCase::ascii("vnd.apple.pkpass"), (270, 271)),(UniCase::ascii("vnd.aristanetworks.swi"), (271, 272)),(UniCase::ascii("vnd.astraea-software.iota"), (272, 273)),(UniCase::ascii("vnd.audiograph"), (273, 274)),(UniCase::ascii("vnd.blueice.multipass"), (274, 275)),(UniCase::ascii("vnd.bmi"), (275, 276)),(UniCase::ascii("vnd.businessobjects"), (276, 277)),(UniCase::ascii("vnd.chemdraw+xml"), (277, 278)),(UniCase::ascii("vnd.chipnuts.karaoke-mmd"), (278, 279)),(UniCase::ascii("vnd.cinderella"), (279, 280)),(UniCase::ascii("vnd.citationstyles.style+xml"), (280, 281)),(UniCase::ascii("vnd.claymore"), (281, 282)),(UniCase::ascii("vnd.cloanto.rp9"), (282, 283)),(UniCase::ascii("vnd.clonk.c4group"), (283, 288)),(UniCase::ascii("vnd.cluetrust.cartomobile-config"), (288, 289)),(UniCase::ascii("vnd.cluetrust.cartomobile-config-pkg"), (289, 290)),(UniCase::ascii("vnd.commonspace"), (290, 291)),(UniCase::ascii("vnd.contact.cmsg"), (291, 292)),(UniCase::ascii("vnd.cosmocaller"), (292, 293)),(UniCase::ascii("vnd.crick.clicker"), (293, 294)),(UniCase::ascii("vnd.crick.clicker.keyboard"), (294, 295)),(UniCase::ascii("vnd.crick.clicker.palette"), (295, 296)),(UniCase::ascii("vnd.crick.clicker.template"), (296, 297)),(UniCase::ascii("vnd.crick.clicker.wordbank"), (297, 298)),(UniCase::ascii("vnd.criticaltools.wbs+xml"), (298, 299)),(UniCase::ascii("vnd.cups-ppd"), (299, 300)),(UniCase::ascii("vnd.curl.car"), (300, 301)),(UniCase::ascii("vnd.curl.pcurl"), (301, 302)),(UniCase::ascii("vnd.dart"), (302, 303)),(UniCase::ascii("vnd.data-vision.rdz"), (303, 304)),(UniCase::ascii("vnd.dece.data"), (304, 308)),(UniCase::ascii("vnd.dece.ttml+xml"), (308, 310)),(UniCase::ascii("vnd.dece.unspecified"), (310, 312)),(UniCase::ascii("vnd.dece.zip"), (312, 314)),(UniCase::ascii("vnd.denovo.fcselayout-link"), (314, 315)),(UniCase::ascii("vnd.dna"), (315, 316)),(UniCase::ascii("vnd.dolby.mlp"), (316, 317)),(UniCase::ascii("vnd.dpgraph"), (317, 318)),(UniCase::ascii("vnd.dreamfactory"), (318, 319)),(UniCase::ascii("vnd.ds-keypoint"), (319, 320)),(UniCase::ascii("vnd.dvb.ait"), (320, 321)),(UniCase::ascii("vnd.dynageo"), (321, 322)),(UniCase::ascii("vnd.ecowin.chart"), (322, 323)),(UniCase::ascii("vnd.enliven"), (323, 324)),(UniCase::ascii("vnd.epson.esf"), (324, 325)),(UniCase::ascii("vnd.epson.msf"), (325, 326)),(UniCase::ascii("vnd.epson.quickanime"), (326, 327)),(UniCase::ascii("vnd.epson.salt"), (327, 328)),(UniCase::ascii("vnd.epson.ssf"), (328, 329)),(UniCase::ascii("vnd.eszigno3+xml"), (329, 331)),(UniCase::ascii("vnd.ezpix-album"), (331, 332)),(UniCase::ascii("vnd.ezpix-package"), (332, 333)),(UniCase::ascii("vnd.fdf"), (333, 334)),(UniCase::ascii("vnd.fdsn.mseed"), (334, 335)),(UniCase::ascii("vnd.fdsn.seed"), (335, 337)),(UniCase::ascii("vnd.flographit"), (337, 338)),(UniCase::ascii("vnd.fluxtime.clip"), (338, 339)),(UniCase::ascii("vnd.framemaker"), (339, 343)),(UniCase::ascii("vnd.frogans.fnc"), (343, 344)),(UniCase::ascii("vnd.frogans.ltf"), (344, 345)),(UniCase::ascii("vnd.fsc.weblaunch"), (345, 346)),(UniCase::ascii("vnd.fujitsu.oasys"), (346, 347)),(UniCase::ascii("vnd.fujitsu.oasys2"), (347, 348)),(UniCase::ascii("vnd.fujitsu.oasys3"), (348, 349)),(UniCase::ascii("vnd.fujitsu.oasysgp"), (349, 350)),(UniCase::ascii("vnd.fujitsu.oasysprs"), (350, 351)),(UniCase::ascii("vnd.fujixerox.ddd"), (351, 352)),(UniCase::ascii("vnd.fujixerox.docuworks"), (352, 353)),(UniCase::ascii("vnd.fujixerox.docuworks.binder"), (353, 354)),(UniCase::ascii("vnd.fuzzysheet"), (354, 355)),(UniCase::ascii("vnd.genomatix.tuxedo"), (355, 356)),(UniCase::ascii("vnd.geogebra.file"), (356, 357)),(UniCase::ascii("vnd.geogebra.tool"), (357, 358)),(UniCase::ascii("vnd.geometry-explorer"), (358, 360)),(UniCase::ascii("vnd.geonext"), (360, 361)),(UniCase::ascii("vnd.geoplan"), (361, 362)),(UniCase::ascii("vnd.geospace"), (362, 363)),(UniCase::ascii("vnd.gmx"), (363, 364)),(UniCase::ascii("vnd.google-apps.document"), (364, 365)),(UniCase::ascii("vnd.google-apps.presentation"), (365, 366)),(UniCase::ascii("vnd.google-apps.spreadsheet"), (366, 367)),(UniCase::ascii("vnd.google-earth.kml+xml"), (367, 368)),(UniCase::ascii("vnd.google-earth.kmz"), (368, 369)),(UniCase::ascii("vnd.grafeq"), (369, 371)),(UniCase::ascii("vnd.groove-account"), (371, 372)),(UniCase::ascii("vnd.groove-help"), (372, 373)),(UniCase::ascii("vnd.groove-identity-message"), (373, 374)),(UniCase::ascii("vnd.groove-injector"), (374, 375)),(UniCase::ascii("vnd.groove-tool-message"), (375, 376)),(UniCase::ascii("vnd.groove-tool-template"), (376, 377)),(UniCase::ascii("vnd.groove-vcard"), (377, 378)),(UniCase::ascii("vnd.hal+xml"), (378, 379)),(UniCase::ascii("vnd.handheld-entertainment+xml"), (379, 380)),(UniCase::ascii("vnd.hbci"), (380, 381)),(UniCase::ascii("vnd.hhe.lesson-player"), (381, 382)),(UniCase::ascii("vnd.hp-hpgl"), (382, 383)),(UniCase::ascii("vnd.hp-hpid"), (383, 384)),(UniCase::ascii("vnd.hp-hps"), (384, 385)),(UniCase::ascii("vnd.hp-jlyt"), (385, 386)),(UniCase::ascii("vnd.hp-pcl"), (386, 387)),(UniCase::ascii("vnd.hp-pclxl"), (387, 388)),(UniCase::ascii("vnd.hydrostatix.sof-data"), (388, 389)),(UniCase::ascii("vnd.ibm.minipay"), (389, 390)),(UniCase::ascii("vnd.ibm.modcap"), (390, 393)),(UniCase::ascii("vnd.ibm.rights-management"), (393, 394)),(UniCase::ascii("vnd.ibm.secure-container"), (394, 395)),(UniCase::ascii("vnd.iccprofile"), (395, 397)),(UniCase::ascii("vnd.igloader"), (397, 398)),(UniCase::ascii("vnd.immervision-ivp"), (398, 399)),(UniCase::ascii("vnd.immervision-ivu"), (399, 400)),(UniCase::ascii("vnd.insors.igm"), (400, 401)),(UniCase::ascii("vnd.intercon.formnet"), (401, 403)),(UniCase::ascii("vnd.intergeo"), (403, 404)),(UniCase::ascii("vnd.intu.qbo"), (404, 405)),(UniCase::ascii("vnd.intu.qfx"), (405, 406)),(UniCase::ascii("vnd.ipunplugged.rcprofile"), (406, 407)),(UniCase::ascii("vnd.irepository.package+xml"), (407, 408)),(UniCase::ascii("vnd.is-xpr"), (408, 409)),(UniCase::ascii("vnd.isac.fcs"), (409, 410)),(UniCase::ascii("vnd.jam"), (410, 411)),(UniCase::ascii("vnd.jcp.javame.midlet-rms"), (411, 412)),(UniCase::ascii("vnd.jisp"), (412, 413)),(UniCase::ascii("vnd.joost.joda-archive"), (413, 414)),(UniCase::ascii("vnd.kahootz"), (414, 416)),(UniCase::ascii("vnd.kde.karbon"), (416, 417)),(UniCase::ascii("vnd.kde.kchart"), (417, 418)),(UniCase::ascii("vnd.kde.kformula"), (418, 419)),(UniCase::ascii("vnd.kde.kivio"), (419, 420)),(UniCase::ascii("vnd.kde.kontour"), (420, 421)),(UniCase::ascii("vnd.kde.kpresenter"), (421, 423)),(UniCase::ascii("vnd.kde.kspread"), (423, 424)),(UniCase::ascii("vnd.kde.kword"), (424, 426)),(UniCase::ascii("vnd.kenameaapp"), (426, 427)),(UniCase::ascii("vnd.kidspiration"), (427, 428)),(UniCase::ascii("vnd.kinar"), (428, 430)),(UniCase::ascii("vnd.koan"), (430, 433)),(UniCase::ascii("vnd.kodak-descriptor"), (433, 434)),(UniCase::ascii("vnd.las.las+xml"), (434, 435)),(UniCase::ascii("vnd.llamagraphics.life-balance.desktop"), (435, 436)),(UniCase::ascii("vnd.llamagraphics.life-balance.exchange+xml"), (436, 437)),(UniCase::ascii("vnd.lotus-1-2-3"), (437, 438)),(UniCase::ascii("vnd.lotus-approach"), (438, 439)),(UniCase::ascii("vnd.lotus-freelance"), (439, 440)),(UniCase::ascii("vnd.lotus-notes"), (440, 441)),(UniCase::ascii("vnd.lotus-organizer"), (441, 442)),(UniCase::ascii("vnd.lotus-screencam"), (442, 443)),(UniCase::ascii("vnd.lotus-wordpro"), (443, 444)),(UniCase::ascii("vnd.macports.portpkg"), (444, 445)),(UniCase::ascii("vnd.mcd"), (445, 446)),(UniCase::ascii("vnd.medcalcdata"), (446, 447)),(UniCase::ascii("vnd.mediastation.cdkey"), (447, 448)),(UniCase::ascii("vnd.mfer"), (448, 449)),(UniCase::ascii("vnd.mfmp"), (449, 450)),(UniCase::ascii("vnd.micrografx.flo"), (450, 451)),(UniCase::ascii("vnd.micrografx.igx"), (451, 452)),(UniCase::ascii("vnd.mif"), (452, 453)),(UniCase::ascii("vnd.mobius.daf"), (453, 454)),(UniCase::ascii("vnd.mobius.dis"), (454, 455)),(UniCase::ascii("vnd.mobius.mbk"), (455, 456)),(UniCase::ascii("vnd.mobius.mqy"), (456, 457)),(UniCase::ascii("vnd.mobius.msl"), (457, 458)),(UniCase::ascii("vnd.mobius.plc"), (458, 459)),(UniCase::ascii("vnd.mobius.txf"), (459, 460)),(UniCase::ascii("vnd.mophun.application"), (460, 461)),(UniCase::ascii("vnd.mophun.certificate"), (461, 462)),(UniCase::ascii("vnd.mozilla.xul+xml"), (462, 463)),(UniCase::ascii("vnd.ms-artgalry"), (463, 464)),(UniCase::ascii("vnd.ms-excel"), (464, 474)),(UniCase::ascii("vnd.ms-excel.addin.macroEnabled.12"), (474, 475)),(UniCase::ascii("vnd.ms-excel.sheet.binary.macroEnabled.12"), (475, 476)),(UniCase::ascii("vnd.ms-excel.sheet.macroEnabled.12"), (476, 477)),(UniCase::ascii("vnd.ms-excel.template.macroEnabled.12"), (477, 478)),(UniCase::ascii("vnd.ms-fontobject"), (478, 479)),(UniCase::ascii("vnd.ms-ims"), (479, 480)),(UniCase::ascii("vnd.ms-lrm"), (480, 481)),(UniCase::ascii("vnd.ms-mediapackage"), (481, 482)),(UniCase::ascii("vnd.ms-office.calx"), (482, 483)),(UniCase::ascii("vnd.ms-officetheme"), (483, 484)),(UniCase::ascii("vnd.ms-outlook"), (484, 486)),(UniCase::ascii("vnd.ms-pki.certstore"), (486, 487)),(UniCase::ascii("vnd.ms-pki.pko"), (487, 488)),(UniCase::ascii("vnd.ms-pki.seccat"), (488, 489)),(UniCase::ascii("vnd.ms-pki.stl"), (489, 490)),(UniCase::ascii("vnd.ms-powerpoint"), (490, 495)),(UniCase::ascii("vnd.ms-powerpoint.addin.macroEnabled.12"), (495, 496)),(UniCase::ascii("vnd.ms-powerpoint.presentation.macroEnabled.12"), (496, 497)),(UniCase::ascii("vnd.ms-powerpoint.slide.macroEnabled.12"), (497, 498)),(UniCase::ascii("vnd.ms-powerpoint.slideshow.macroEnabled.12"), (498, 499)),(UniCase::ascii("vnd.ms-powerpoint.template.macroEnabled.12"), (499, 500)),(UniCase::ascii("vnd.ms-project"), (500, 502)),(UniCase::ascii("vnd.ms-visio.viewer"), (502, 503)),(UniCase::ascii("vnd.ms-word.document.macroEnabled.12"), (503, 504)),(UniCase::ascii("vnd.ms-word.template.macroEnabled.12"), (504, 505)),(UniCase::ascii("vnd.ms-works"), (505, 509)),(UniCase::ascii("vnd.ms-wpl"), (509, 510)),(UniCase::ascii("vnd.ms-xpsdocument"), (510, 511)),(UniCase::ascii("vnd.mseq"), (511, 512)),(UniCase::ascii("vnd.musician"), (512, 513)),(UniCase::ascii("vnd.muvee.style"), (513, 514)),(UniCase::ascii("vnd.mynfc"), (514, 515)),(UniCase::ascii("vnd.neurolanguage.nlu"), (515, 516)),(UniCase::ascii("vnd.nitf"), (516, 518)),(UniCase::ascii("vnd.noblenet-directory"), (518, 519)),(UniCase::ascii("vnd.noblenet-sealer"), (519, 520)),(UniCase::ascii("vnd.noblenet-web"), (520, 521)),(UniCase::ascii("vnd.nokia.n-gage.data"), (521, 522)),(UniCase::ascii("vnd.nokia.n-gage.symbian.install"), (522, 523)),(UniCase::ascii("vnd.nokia.radio-preset"), (523, 524)),(UniCase::ascii("vnd.nokia.radio-presets"), (524, 525)),(UniCase::ascii("vnd.novadigm.edm"), (525, 526)),(UniCase::ascii("vnd.novadigm.edx"), (526, 527)),(UniCase::ascii("vnd.novadigm.ext"), (527, 528)),(UniCase::ascii("vnd.oasis.opendocument.chart"), (528, 529)),(UniCase::ascii("vnd.oasis.opendocument.chart-template"), (529, 530)),(UniCase::ascii("vnd.oasis.opendocument.database"), (530, 531)),(UniCase::ascii("vnd.oasis.opendocument.formula"), (531, 532)),(UniCase::ascii("vnd.oasis.opendocument.formula-template"), (532, 533)),(UniCase::ascii("vnd.oasis.opendocument.graphics"), (533, 534)),(UniCase::ascii("vnd.oasis.opendocument.graphics-template"), (534, 535)),(UniCase::ascii("vnd.oasis.opendocument.image"), (535, 536)),(UniCase::ascii("vnd.oasis.opendocument.image-template"), (536, 537)),(UniCase::ascii("vnd.oasis.opendocument.presentation"), (537, 538)),(UniCase::ascii("vnd.oasis.opendocument.presentation-template"), (538, 539)),(UniCase::ascii("vnd.oasis.opendocument.spreadsheet"), (539, 540)),(UniCase::ascii("vnd.oasis.opendocument.spreadsheet-template"), (540, 541)),(UniCase::ascii("vnd.oasis.opendocument.text"), (541, 542)),(UniCase::ascii("vnd.oasis.opendocument.text-master"), (542, 543)),(UniCase::ascii("vnd.oasis.opendocument.text-template"), (543, 544)),(UniCase::ascii("vnd.oasis.opendocument.text-web"), (544, 545)),(UniCase::ascii("vnd.olpc-sugar"), (545, 546)),(UniCase::ascii("vnd.oma.dd2+xml"), (546, 547)),(UniCase::ascii("vnd.openofficeorg.extension"), (547, 548)),(UniCase::ascii("vnd.openxmlformats-officedocument.presentationml.presentation"), (548, 549)),(UniCase::ascii("vnd.openxmlformats-officedocument.presentationml.slide"), (549, 550)),(UniCase::ascii("vnd.openxmlformats-officedocument.presentationml.slideshow"), (550, 551)),(UniCase::ascii("vnd.openxmlformats-officedocument.presentationml.template"), (551, 552)),(UniCase::ascii("vnd.openxmlformats-officedocument.spreadsheetml.sheet"), (552, 553)),(UniCase::ascii("vnd.openxmlformats-officedocument.spreadsheetml.template"), (553, 554)),(UniCase::ascii("vnd.openxmlformats-officedocument.wordprocessingml.document"), (554, 555)),(UniCase::ascii("vnd.openxmlformats-officedocument.wordprocessingml.template"), (555, 556)),(UniCase::ascii("vnd.osgeo.mapguide.package"), (556, 557)),(UniCase::ascii("vnd.osgi.dp"), (557, 558)),(UniCase::ascii("vnd.osgi.subsystem"), (558, 559)),(UniCase::ascii("vnd.palm"), (559, 562)),(UniCase::ascii("vnd.pawaafile"), (562, 563)),(UniCase::ascii("vnd.pg.format"), (563, 564)),(UniCase::ascii("vnd.pg.osasli"), (564, 565)),(UniCase::ascii("vnd.picsel"), (565, 566)),(UniCase::ascii("vnd.pmi.widget"), (566, 567)),(UniCase::ascii("vnd.pocketlearn"), (567, 568)),(UniCase::ascii("vnd.powerbuilder6"), (568, 569)),(UniCase::ascii("vnd.previewsystems.box"), (569, 570)),(UniCase::ascii("vnd.proteus.magazine"), (570, 571)),(UniCase::ascii("vnd.publishare-delta-tree"), (571, 572)),(UniCase::ascii("vnd.pvi.ptid1"), (572, 573)),(UniCase::ascii("vnd.quark.quarkxpress"), (573, 578)),(UniCase::ascii("vnd.realvnc.bed"), (578, 579)),(UniCase::ascii("vnd.recordare.musicxml"), (579, 580)),(UniCase::ascii("vnd.recordare.musicxml+xml"), (580, 581)),(UniCase::ascii("vnd.rig.cryptonote"), (581, 582)),(UniCase::ascii("vnd.rn-realmedia"), (582, 583)),(UniCase::ascii("vnd.rn-realmedia-vbr"), (583, 584)),(UniCase::ascii("vnd.rn-rn_music_package"), (584, 585)),(UniCase::ascii("vnd.route66.link66+xml"), (585, 586)),(UniCase::ascii("vnd.sailingtracker.track"), (586, 587)),(UniCase::ascii("vnd.seemail"), (587, 588)),(UniCase::ascii("vnd.sema"), (588, 589)),(UniCase::ascii("vnd.semd"), (589, 590)),(UniCase::ascii("vnd.semf"), (590, 591)),(UniCase::ascii("vnd.shana.informed.formdata"), (591, 592)),(UniCase::ascii("vnd.shana.informed.formtemplate"), (592, 593)),(UniCase::ascii("vnd.shana.informed.interchange"), (593, 594)),(UniCase::ascii("vnd.shana.informed.package"), (594, 595)),(UniCase::ascii("vnd.simtech-mindmapper"), (595, 597)),(UniCase::ascii("vnd.smart.teacher"), (597, 598)),(UniCase::ascii("vnd.solent.sdkm+xml"), (598, 600)),(UniCase::ascii("vnd.spotfire.dxp"), (600, 601)),(UniCase::ascii("vnd.spotfire.sfs"), (601, 602)),(UniCase::ascii("vnd.stardivision.calc"), (602, 603)),(UniCase::ascii("vnd.stardivision.draw"), (603, 604)),(UniCase::ascii("vnd.stardivision.impress"), (604, 605)),(UniCase::ascii("vnd.stardivision.math"), (605, 606)),(UniCase::ascii("vnd.stardivision.writer"), (606, 608)),(UniCase::ascii("vnd.stardivision.writer-global"), (608, 609)),(UniCase::ascii("vnd.stepmania.package"), (609, 610)),(UniCase::ascii("vnd.stepmania.stepchart"), (610, 611)),(UniCase::ascii("vnd.sun.wadl+xml"), (611, 612)),(UniCase::ascii("vnd.sun.xml.calc"), (612, 613)),(UniCase::ascii("vnd.sun.xml.calc.template"), (613, 614)),(UniCase::ascii("vnd.sun.xml.draw"), (614, 615)),(UniCase::ascii("vnd.sun.xml.draw.template"), (615, 616)),(UniCase::ascii("vnd.sun.xml.impress"), (616, 617)),(UniCase::ascii("vnd.sun.xml.impress.template"), (617, 618)),(UniCase::ascii("vnd.sun.xml.math"), (618, 619)),(UniCase::ascii("vnd.sun.xml.writer"), (619, 620)),(UniCase::ascii("vnd.sun.xml.writer.global"), (620, 621)),(UniCase::ascii("vnd.sun.xml.writer.template"), (621, 622)),(UniCase::ascii("vnd.sus-calendar"), (622, 624)),(UniCase::ascii("vnd.svd"), (624, 625)),(UniCase::ascii("vnd.symbian.install"), (625, 627)),(UniCase::ascii("vnd.syncml+xml"), (627, 628)),(UniCase::ascii("vnd.syncml.dm+wbxml"), (628, 629)),(UniCase::ascii("vnd.syncml.dm+xml"), (629, 630)),(UniCase::ascii("vnd.tao.intent-module-archive"), (630, 631)),(UniCase::ascii("vnd.tcpdump.pcap"), (631, 634)),(UniCase::ascii("vnd.tmobile-livetv"), (634, 635)),(UniCase::ascii("vnd.trid.tpt"), (635, 636)),(UniCase::ascii("vnd.triscape.mxs"), (636, 637)),(UniCase::ascii("vnd.trueapp"), (637, 638)),(UniCase::ascii("vnd.ufdl"), (638, 640)),(UniCase::ascii("vnd.uiq.theme"), (640, 641)),(UniCase::ascii("vnd.umajin"), (641, 642)),(UniCase::ascii("vnd.unity"), (642, 643)),(UniCase::ascii("vnd.uoml+xml"), (643, 644)),(UniCase::ascii("vnd.vcx"), (644, 645)),(UniCase::ascii("vnd.visio"), (645, 651)),(UniCase::ascii("vnd.visionary"), (651, 652)),(UniCase::ascii("vnd.vsf"), (652, 653)),(UniCase::ascii("vnd.wap.wbxml"), (653, 654)),(UniCase::ascii("vnd.wap.wmlc"), (654, 655)),(UniCase::ascii("vnd.wap.wmlscriptc"), (655, 656)),(UniCase::ascii("vnd.webturbo"), (656, 657)),(UniCase::ascii("vnd.wolfram.player"), (657, 658)),(UniCase::ascii("vnd.wordperfect"), (658, 659)),(UniCase::ascii("vnd.wqd"), (659, 660)),(UniCase::ascii("vnd.wt.stf"), (660, 661)),(UniCase::ascii("vnd.xara"), (661, 662)),(UniCase::ascii("vnd.xfdl"), (662, 663)),(UniCase::ascii("vnd.yamaha.hv-dic"), (663, 664)),(UniCase::ascii("vnd.yamaha.hv-script"), (664, 665)),(UniCase::ascii("vnd.yamaha.hv-voice"), (665, 666)),(UniCase::ascii("vnd.yamaha.openscoreformat"), (666, 667)),(UniCase::ascii("vnd.yamaha.openscoreformat.osfpvg+xml"), (667, 668)),(UniCase::ascii("vnd.yamaha.smaf-audio"), (668, 669)),(UniCase::ascii("vnd.yamaha.smaf-phrase"), (669, 670)),(UniCase::ascii("vnd.yellowriver-custom-menu"), (670, 671)),(UniCase::ascii("vnd.zul"), (671, 673)),(UniCase::ascii("vnd.zzazz.deck+xml"), (673, 674)),(UniCase::ascii("voicexml+xml"), (674, 675)),(UniCase::ascii("vsix"), (675, 676)),(UniCase::ascii("wasm"), (676, 677)),(UniCase::ascii("widget"), (677, 678)),(UniCase::ascii("windows-library+xml"), (678, 679)),(UniCase::ascii("windows-search-connector+xml"), (679, 680)),(UniCase::ascii("winhlp"), (680, 681)),(UniCase::ascii("wlmoviemaker"), (681, 682)),(UniCase::ascii("wspolicy+xml"), (682, 683)),(UniCase::ascii("x-7z-compressed"), (683, 684)),(UniCase::ascii("x-abiword"), (684, 685)),(UniCase::ascii("x-ace-compressed"), (685, 686)),(UniCase::ascii("x-arj"), (686, 687)),(UniCase::ascii("x-authorware-bin"), (687, 690)),(UniCase::ascii("x-authorware-map"), (690, 691)),(UniCase::ascii("x-authorware-seg"), (691, 692)),(UniCase::ascii("x-bcpio"), (692, 693)),(UniCase::ascii("x-bittorrent"), (693, 694)),(UniCase::ascii("x-blorb"), (694, 696)),(UniCase::ascii("x-bridge-url"), (696, 697)),(UniCase::ascii("x-bzip"), (697, 698)),(UniCase::ascii("x-bzip2"), (698, 700)),(UniCase::ascii("x-cbr"), (700, 705)),(UniCase::ascii("x-cdf"), (705, 706)),(UniCase::ascii("x-cdlink"), (706, 707)),(UniCase::ascii("x-cfs-compressed"), (707, 708)),(UniCase::ascii("x-chat"), (708, 709)),(UniCase::ascii("x-chess-pgn"), (709, 710)),(UniCase::ascii("x-chrome-extension"), (710, 711)),(UniCase::ascii("x-cocoa"), (711, 712)),(UniCase::ascii("x-compress"), (712, 713)),(UniCase::ascii("x-compressed"), (713, 714)),(UniCase::ascii("x-cpio"), (714, 715)),(UniCase::ascii("x-csh"), (715, 716)),(UniCase::ascii("x-debian-package"), (716, 717)),(UniCase::ascii("x-dgc-compressed"), (717, 718)),(UniCase::ascii("x-director"), (718, 727)),(UniCase::ascii("x-doom"), (727, 728)),(UniCase::ascii("x-dtbncx+xml"), (728, 729)),(UniCase::ascii("x-dtbook+xml"), (729, 730)),(UniCase::ascii("x-dtbresource+xml"), (730, 731)),(UniCase::ascii("x-dvi"), (731, 732)),(UniCase::ascii("x-dxf"), (732, 733)),(UniCase::ascii("x-eva"), (733, 734)),(UniCase::ascii("x-font-bdf"), (734, 735)),(UniCase::ascii("x-font-ghostscript"), (735, 736)),(UniCase::ascii("x-font-linux-psf"), (736, 737)),(UniCase::ascii("x-font-pcf"), (737, 738)),(UniCase::ascii("x-font-snf"), (738, 739)),(UniCase::ascii("x-font-type1"), (739, 740)),(UniCase::ascii("x-freearc"), (740, 741)),(UniCase::ascii("x-gca-compressed"), (741, 742)),(UniCase::ascii("x-glulx"), (742, 743)),(UniCase::ascii("x-gnumeric"), (743, 744)),(UniCase::ascii("x-gramps-xml"), (744, 745)),(UniCase::ascii("x-gtar"), (745, 746)),(UniCase::ascii("x-gzip"), (746, 747)),(UniCase::ascii("x-hdf"), (747, 748)),(UniCase::ascii("x-httpd-php"), (748, 749)),(UniCase::ascii("x-install-instructions"), (749, 750)),(UniCase::ascii("x-internet-signup"), (750, 752)),(UniCase::ascii("x-iphone"), (752, 753)),(UniCase::ascii("x-itunes-ipa"), (753, 754)),(UniCase::ascii("x-itunes-ipg"), (754, 755)),(UniCase::ascii("x-itunes-ipsw"), (755, 756)),(UniCase::ascii("x-itunes-ite"), (756, 757)),(UniCase::ascii("x-itunes-itlp"), (757, 758)),(UniCase::ascii("x-itunes-itms"), (758, 759)),(UniCase::ascii("x-itunes-itpc"), (759, 760)),(UniCase::ascii("x-java-applet"), (760, 761)),(UniCase::ascii("x-java-archive-diff"), (761, 762)),(UniCase::ascii("x-java-jnlp-file"), (762, 763)),(UniCase::ascii("x-koan"), (763, 764)),(UniCase::ascii("x-latex"), (764, 765)),(UniCase::ascii("x-lua-bytecode"), (765, 766)),(UniCase::ascii("x-lzh-compressed"), (766, 767)),(UniCase::ascii("x-makeself"), (767, 768)),(UniCase::ascii("x-mie"), (768, 769)),(UniCase::ascii("x-miva-compiled"), (769, 770)),(UniCase::ascii("x-mmxp"), (770, 771)),(UniCase::ascii("x-mobipocket-ebook"), (771, 773)),(UniCase::ascii("x-ms-application"), (773, 774)),(UniCase::ascii("x-ms-license"), (774, 775)),(UniCase::ascii("x-ms-manifest"), (775, 776)),(UniCase::ascii("x-ms-reader"), (776, 777)),(UniCase::ascii("x-ms-shortcut"), (777, 778)),(UniCase::ascii("x-ms-vsto"), (778, 779)),(UniCase::ascii("x-ms-wmd"), (779, 780)),(UniCase::ascii("x-ms-wmz"), (780, 781)),(UniCase::ascii("x-ms-xbap"), (781, 782)),(UniCase::ascii("x-msaccess"), (782, 783)),(UniCase::ascii("x-msbinder"), (783, 784)),(UniCase::ascii("x-mscardfile"), (784, 785)),(UniCase::ascii("x-msclip"), (785, 786)),(UniCase::ascii("x-msdownload"), (786, 789)),(UniCase::ascii("x-msmediaview"), (789, 792)),(UniCase::ascii("x-msmetafile"), (792, 794)),(UniCase::ascii("x-msmoney"), (794, 795)),(UniCase::ascii("x-mspublisher"), (795, 796)),(UniCase::ascii("x-msschedule"), (796, 797)),(UniCase::ascii("x-msterminal"), (797, 798)),(UniCase::ascii("x-mswrite"), (798, 799)),(UniCase::ascii("x-netcdf"), (799, 800)),(UniCase::ascii("x-ns-proxy-autoconfig"), (800, 801)),(UniCase::ascii("x-nzb"), (801, 802)),(UniCase::ascii("x-oleobject"), (802, 803)),(UniCase::ascii("x-perfmon"), (803, 808)),(UniCase::ascii("x-perl"), (808, 810)),(UniCase::ascii("x-pkcs12"), (810, 812)),(UniCase::ascii("x-pkcs7-certificates"), (812, 814)),(UniCase::ascii("x-pkcs7-certreqresp"), (814, 815)),(UniCase::ascii("x-podcast"), (815, 816)),(UniCase::ascii("x-quicktimeplayer"), (816, 817)),(UniCase::ascii("x-rar-compressed"), (817, 818)),(UniCase::ascii("x-research-info-systems"), (818, 819)),(UniCase::ascii("x-safari-safariextz"), (819, 820)),(UniCase::ascii("x-safari-webarchive"), (820, 821)),(UniCase::ascii("x-sgimb"), (821, 822)),(UniCase::ascii("x-sh"), (822, 823)),(UniCase::ascii("x-shar"), (823, 824)),(UniCase::ascii("x-shockwave-flash"), (824, 826)),(UniCase::ascii("x-silverlight-app"), (826, 827)),(UniCase::ascii("x-smaf"), (827, 828)),(UniCase::ascii("x-sql"), (828, 829)),(UniCase::ascii("x-stuffit"), (829, 830)),(UniCase::ascii("x-stuffitx"), (830, 831)),(UniCase::ascii("x-subrip"), (831, 832)),(UniCase::ascii("x-sv4cpio"), (832, 833)),(UniCase::ascii("x-sv4crc"), (833, 834)),(UniCase::ascii("x-t3vm-image"), (834, 835)),(UniCase::ascii("x-tads"), (835, 836)),(UniCase::ascii("x-tar"), (836, 837)),(UniCase::ascii("x-tcl"), (837, 839)),(UniCase::ascii("x-tex"), (839, 840)),(UniCase::ascii("x-tex-tfm"), (840, 841)),(UniCase::ascii("x-texinfo"), (841, 843)),(UniCase::ascii("x-tgif"), (843, 844)),(UniCase::ascii("x-troff"), (844, 847)),(UniCase::ascii("x-troff-man"), (847, 848)),(UniCase::ascii("x-troff-me"), (848, 849)),(UniCase::ascii("x-troff-ms"), (849, 850)),(UniCase::ascii("x-ustar"), (850, 851)),(UniCase::ascii("x-virtualbox-hdd"), (851, 852)),(UniCase::ascii("x-virtualbox-ova"), (852, 853)),(UniCase::ascii("x-virtualbox-ovf"), (853, 854)),(UniCase::ascii("x-virtualbox-vbox"), (854, 855)),(UniCase::ascii("x-virtualbox-vbox-extpack"), (855, 856)),(UniCase::ascii("x-virtualbox-vdi"), (856, 857)),(UniCase::ascii("x-virtualbox-vhd"), (857, 858)),(UniCase::ascii("x-virtualbox-vmdk"), (858, 859)),(UniCase::ascii("x-wais-source"), (859, 860)),(UniCase::ascii("x-web-app-manifest+json"), (860, 861)),(UniCase::ascii("x-wlpg-detect"), (861, 862)),(UniCase::ascii("x-wlpg3-detect"), (862, 863)),(UniCase::ascii("x-x509-ca-cert"), (863, 867)),(UniCase::ascii("x-xfig"), (867, 868)),(UniCase::ascii("x-xliff+xml"), (868, 869)),(UniCase::ascii("x-xpinstall"), (869, 870)),(UniCase::ascii("x-xz"), (870, 871)),(UniCase::ascii("x-zmachine"), (871, 879)),(UniCase::ascii("xaml+xml"), (879, 880)),(UniCase::ascii("xcap-diff+xml"), (880, 881)),(UniCase::ascii("xenc+xml"), (881, 882)),(UniCase::ascii("xhtml+xml"), (882, 884)),(UniCase::ascii("xml"), (884, 928)),(UniCase::ascii("xop+xml"), (928, 929)),(UniCase::ascii("xproc+xml"), (929, 930)),(UniCase::ascii("xspf+xml"), (930, 931)),(UniCase::ascii("xv+xml"), (931, 935)),(UniCase::ascii("yang"), (935, 936)),(UniCase::ascii("yin+xml"), (936, 937)),(UniCase::ascii("zip"), (937, 938)),] }),(UniCase::ascii("audio"), TopLevelExts { start: 938, end: 1003, subs: &[(UniCase::ascii("aac"), (938, 940)),(UniCase::ascii("ac3"), (940, 941)),(UniCase::ascii("aiff"), (941, 945)),(UniCase::ascii("annodex"), (945, 946)),(UniCase::ascii("audible"), (946, 947)),(UniCase::ascii("basic"), (947, 949)),(UniCase::ascii("flac"), (949, 950)),(UniCase::ascii("m4a"), (950, 951)),(UniCase::ascii("m4b"), (951, 952)),(UniCase::ascii("m4p"), (952, 953)),(UniCase::ascii("mid"), (953, 956)),(UniCase::ascii("midi"), (956, 957)),(UniCase::ascii("mp4"), (957, 958)),(UniCase::ascii("mpeg"), (958, 963)),(UniCase::ascii("ogg"), (963, 967)),(UniCase::ascii("s3m"), (967, 968)),(UniCase::ascii("scpls"), (968, 969)),(UniCase::ascii("silk"), (969, 970)),(UniCase::ascii("vnd.audible.aax"), (970, 971)),(UniCase::ascii("vnd.dece.audio"), (971, 973)),(UniCase::ascii("vnd.digital-winds"), (973, 974)),(UniCase::ascii("vnd.dlna.adts"), (974, 975)),(UniCase::ascii("vnd.dra"), (975, 976)),(UniCase::ascii("vnd.dts"), (976, 977)),(UniCase::ascii("vnd.dts.hd"), (977, 978)),(UniCase::ascii("vnd.lucent.voice"), (978, 979)),(UniCase::ascii("vnd.ms-playready.media.pya"), (979, 980)),(UniCase::ascii("vnd.nuera.ecelp4800"), (980, 981)),(UniCase::ascii("vnd.nuera.ecelp7470"), (981, 982)),(UniCase::ascii("vnd.nuera.ecelp9600"), (982, 983)),(UniCase::ascii("vnd.rip"), (983, 984)),(UniCase::ascii("wav"), (984, 986)),(UniCase::ascii("webm"), (986, 987)),(UniCase::ascii("x-caf"), (987, 988)),(UniCase::ascii("x-gsm"), (988, 989)),(UniCase::ascii("x-m4r"), (989, 990)),(UniCase::ascii("x-matroska"), (990, 991)),(UniCase::ascii("x-mpegurl"), (991, 993)),(UniCase::ascii("x-ms-wax"), (993, 994)),(UniCase::ascii("x-ms-wma"), (994, 995)),(UniCase::ascii("x-pn-realaudio"), (995, 997)),(UniCase::ascii("x-pn-realaudio-plugin"), (997, 998)),(UniCase::ascii("x-sd2"), (998, 999)),(UniCase::ascii("x-smd"), (999, 1002)),(UniCase::ascii("xm"), (1002, 1003)),] }),(UniCase::ascii("chemical"), TopLevelExts { start: 1003, end: 1009, subs: &[(UniCase::ascii("x-cdx"), (1003, 1004)),(UniCase::ascii("x-cif"), (1004, 1005)),(UniCase::ascii("x-cmdf"), (1005, 1006)),(UniCase::ascii("x-cml"), (1006, 1007)),(UniCase::ascii("x-csml"), (1007, 1008)),(UniCase::ascii("x-xyz"), (1008, 1009)),] }),(UniCase::ascii("drawing"), TopLevelExts { start: 1009, end: 1010, subs: &[(UniCase::ascii("x-dwf"), (1009, 1010)),] }),(UniCase::ascii("font"), TopLevelExts { start: 1010, end: 1012, subs: &[(UniCase::ascii("collection"), (1010, 1011)),(UniCase::ascii("woff2"), (1011, 1012)),] }),(UniCase::ascii("image"), TopLevelExts { start: 1012, end: 1086, subs: &[(UniCase::ascii("apng"), (1012, 1013)),(UniCase::ascii("bmp"), (1013, 1015)),(UniCase::ascii("cgm"), (1015, 1016)),(UniCase::ascii("cis-cod"), (1016, 1017)),(UniCase::ascii("g3fax"), (1017, 1018)),(UniCase::ascii("gif"), (1018, 1019)),(UniCase::ascii("ief"), (1019, 1020)),(UniCase::ascii("jp2"), (1020, 1022)),(UniCase::ascii("jpeg"), (1022, 1025)),(UniCase::ascii("jpm"), (1025, 1026)),(UniCase::ascii("jpx"), (1026, 1028)),(UniCase::ascii("ktx"), (1028, 1029)),(UniCase::ascii("pict"), (1029, 1032)),(UniCase::ascii("pjpeg"), (1032, 1033)),(UniCase::ascii("png"), (1033, 1035)),(UniCase::ascii("prs.btif"), (1035, 1036)),(UniCase::ascii("sgi"), (1036, 1037)),(UniCase::ascii("svg+xml"), (1037, 1039)),(UniCase::ascii("tiff"), (1039, 1041)),(UniCase::ascii("vnd.dece.graphic"), (1041, 1045)),(UniCase::ascii("vnd.djvu"), (1045, 1047)),(UniCase::ascii("vnd.fastbidsheet"), (1047, 1048)),(UniCase::ascii("vnd.fpx"), (1048, 1049)),(UniCase::ascii("vnd.fst"), (1049, 1050)),(UniCase::ascii("vnd.fujixerox.edmics-mmr"), (1050, 1051)),(UniCase::ascii("vnd.fujixerox.edmics-rlc"), (1051, 1052)),(UniCase::ascii("vnd.ms-modi"), (1052, 1053)),(UniCase::ascii("vnd.ms-photo"), (1053, 1054)),(UniCase::ascii("vnd.net-fpx"), (1054, 1055)),(UniCase::ascii("vnd.radiance"), (1055, 1056)),(UniCase::ascii("vnd.rn-realflash"), (1056, 1057)),(UniCase::ascii("vnd.wap.wbmp"), (1057, 1058)),(UniCase::ascii("vnd.xiff"), (1058, 1059)),(UniCase::ascii("webp"), (1059, 1060)),(UniCase::ascii("x-3ds"), (1060, 1061)),(UniCase::ascii("x-cmu-raster"), (1061, 1062)),(UniCase::ascii("x-cmx"), (1062, 1063)),(UniCase::ascii("x-freehand"), (1063, 1068)),(UniCase::ascii("x-icon"), (1068, 1069)),(UniCase::ascii("x-jg"), (1069, 1070)),(UniCase::ascii("x-jng"), (1070, 1071)),(UniCase::ascii("x-macpaint"), (1071, 1074)),(UniCase::ascii("x-mrsid-image"), (1074, 1075)),(UniCase::ascii("x-portable-anymap"), (1075, 1076)),(UniCase::ascii("x-portable-bitmap"), (1076, 1077)),(UniCase::ascii("x-portable-graymap"), (1077, 1078)),(UniCase::ascii("x-portable-pixmap"), (1078, 1079)),(UniCase::ascii("x-quicktime"), (1079, 1081)),(UniCase::ascii("x-rgb"), (1081, 1082)),(UniCase::ascii("x-tga"), (1082, 1083)),(UniCase::ascii("x-xbitmap"), (1083, 1084)),(UniCase::ascii("x-xpixmap"), (1084, 1085)),(UniCase::ascii("x-xwindowdump"), (1085, 1086)),] }),(UniCase::ascii("message"), TopLevelExts { start: 1086, end: 1096, subs: &[(UniCase::ascii("disposition-notification"), (1086, 1087)),(UniCase::ascii("global"), (1087, 1088)),(UniCase::ascii("global-delivery-status"), (1088, 1089)),(UniCase::ascii("global-disposition-notification"), (1089, 1090)),(UniCase::ascii("global-headers"), (1090, 1091)),(UniCase::ascii("rfc822"), (1091, 1096)),] }),(UniCase::ascii("model"), TopLevelExts { start: 1096, end: 1114, subs: &[(UniCase::ascii("gltf+json"), (1096, 1097)),(UniCase::ascii("gltf-binary"), (1097, 1098)),(UniCase::ascii("iges"), (1098, 1100)),(UniCase::ascii("mesh"), (1100, 1103)),(UniCase::ascii("vnd.collada+xml"), (1103, 1104)),(UniCase::ascii("vnd.gdl"), (1104, 1105)),(UniCase::ascii("vnd.gtw"), (1105, 1106)),(UniCase::ascii("vnd.vtu"), (1106, 1107)),(UniCase::ascii("vrml"), (1107, 1108)),(UniCase::ascii("x3d+binary"), (1108, 1110)),(UniCase::ascii("x3d+vrml"), (1110, 1112)),(UniCase::ascii("x3d+xml"), (1112, 1114)),] }),(UniCase::ascii("text"), TopLevelExts { start: 1114, end: 1289, subs: &[(UniCase::ascii("cache-manifest"), (1114, 1115)),(UniCase::ascii("calendar"), (1115, 1116)),(UniCase::ascii("coffeescript"), (1116, 1118)),(UniCase::ascii("css"), (1118, 1119)),(UniCase::ascii("csv"), (1119, 1120)),(UniCase::ascii("dlm"), (1120, 1121)),(UniCase::ascii("h323"), (1121, 1122)),(UniCase::ascii("html"), (1122, 1126)),(UniCase::ascii("iuls"), (1126, 1127)),(UniCase::ascii("jade"), (1127, 1128)),(UniCase::ascii("jscript"), (1128, 1129)),(UniCase::ascii("less"), (1129, 1130)),(UniCase::ascii("markdown"), (1130, 1132)),(UniCase::ascii("mathml"), (1132, 1133)),(UniCase::ascii("n3"), (1133, 1134)),(UniCase::ascii("plain"), (1134, 1202)),(UniCase::ascii("prs.lines.tag"), (1202, 1203)),(UniCase::ascii("richtext"), (1203, 1204)),(UniCase::ascii("scriptlet"), (1204, 1206)),(UniCase::ascii("sgml"), (1206, 1208)),(UniCase::ascii("shex"), (1208, 1209)),(UniCase::ascii("slim"), (1209, 1211)),(UniCase::ascii("stylus"), (1211, 1213)),(UniCase::ascii("tab-separated-values"), (1213, 1214)),(UniCase::ascii("turtle"), (1214, 1215)),(UniCase::ascii("uri-list"), (1215, 1218)),(UniCase::ascii("vbscript"), (1218, 1219)),(UniCase::ascii("vcard"), (1219, 1220)),(UniCase::ascii("vnd.curl"), (1220, 1221)),(UniCase::ascii("vnd.curl.dcurl"), (1221, 1222)),(UniCase::ascii("vnd.curl.mcurl"), (1222, 1223)),(UniCase::ascii("vnd.curl.scurl"), (1223, 1224)),(UniCase::ascii("vnd.dvb.subtitle"), (1224, 1225)),(UniCase::ascii("vnd.fly"), (1225, 1226)),(UniCase::ascii("vnd.fmi.flexstor"), (1226, 1227)),(UniCase::ascii("vnd.graphviz"), (1227, 1228)),(UniCase::ascii("vnd.in3d.3dml"), (1228, 1229)),(UniCase::ascii("vnd.in3d.spot"), (1229, 1230)),(UniCase::ascii("vnd.sun.j2me.app-descriptor"), (1230, 1231)),(UniCase::ascii("vnd.wap.wml"), (1231, 1232)),(UniCase::ascii("vnd.wap.wmlscript"), (1232, 1233)),(UniCase::ascii("vtt"), (1233, 1234)),(UniCase::ascii("webviewhtml"), (1234, 1235)),(UniCase::ascii("x-c"), (1235, 1236)),(UniCase::ascii("x-component"), (1236, 1237)),(UniCase::ascii("x-fortran"), (1237, 1241)),(UniCase::ascii("x-handlebars-template"), (1241, 1242)),(UniCase::ascii("x-hdml"), (1242, 1243)),(UniCase::ascii("x-html-insertion"), (1243, 1245)),(UniCase::ascii("x-lua"), (1245, 1246)),(UniCase::ascii("x-markdown"), (1246, 1248)),(UniCase::ascii("x-ms-contact"), (1248, 1249)),(UniCase::ascii("x-ms-group"), (1249, 1250)),(UniCase::ascii("x-ms-iqy"), (1250, 1251)),(UniCase::ascii("x-ms-rqy"), (1251, 1252)),(UniCase::ascii("x-nfo"), (1252, 1253)),(UniCase::ascii("x-opml"), (1253, 1254)),(UniCase::ascii("x-pascal"), (1254, 1256)),(UniCase::ascii("x-processing"), (1256, 1257)),(UniCase::ascii("x-rust"), (1257, 1258)),(UniCase::ascii("x-sass"), (1258, 1259)),(UniCase::ascii("x-scss"), (1259, 1260)),(UniCase::ascii("x-setext"), (1260, 1261)),(UniCase::ascii("x-sfv"), (1261, 1262)),(UniCase::ascii("x-suse-ymp"), (1262, 1263)),(UniCase::ascii("x-toml"), (1263, 1264)),(UniCase::ascii("x-uuencode"), (1264, 1265)),(UniCase::ascii("x-vcard"), (1265, 1266)),(UniCase::ascii("x-yaml"), (1266, 1268)),(UniCase::ascii("xml"), (1268, 1289)),] }),(UniCase::ascii("video"), TopLevelExts { start: 1289, end: 1370, subs: &[(UniCase::ascii("3gpp"), (1289, 1291)),(UniCase::ascii("3gpp2"), (1291, 1293)),(UniCase::ascii("annodex"), (1293, 1294)),(UniCase::ascii("divx"), (1294, 1295)),(UniCase::ascii("h261"), (1295, 1296)),(UniCase::ascii("h263"), (1296, 1297)),(UniCase::ascii("h264"), (1297, 1298)),(UniCase::ascii("jpeg"), (1298, 1299)),(UniCase::ascii("jpm"), (1299, 1300)),(UniCase::ascii("mj2"), (1300, 1302)),(UniCase::ascii("mp4"), (1302, 1305)),(UniCase::ascii("mpeg"), (1305, 1316)),(UniCase::ascii("ogg"), (1316, 1317)),(UniCase::ascii("quicktime"), (1317, 1320)),(UniCase::ascii("vnd.dece.hd"), (1320, 1322)),(UniCase::ascii("vnd.dece.mobile"), (1322, 1324)),(UniCase::ascii("vnd.dece.pd"), (1324, 1326)),(UniCase::ascii("vnd.dece.sd"), (1326, 1328)),(UniCase::ascii("vnd.dece.video"), (1328, 1330)),(UniCase::ascii("vnd.dlna.mpeg-tts"), (1330, 1335)),(UniCase::ascii("vnd.dvb.file"), (1335, 1336)),(UniCase::ascii("vnd.fvt"), (1336, 1337)),(UniCase::ascii("vnd.mpegurl"), (1337, 1339)),(UniCase::ascii("vnd.ms-playready.media.pyv"), (1339, 1340)),(UniCase::ascii("vnd.uvvu.mp4"), (1340, 1342)),(UniCase::ascii("vnd.vivo"), (1342, 1343)),(UniCase::ascii("webm"), (1343, 1344)),(UniCase::ascii("x-dv"), (1344, 1346)),(UniCase::ascii("x-f4v"), (1346, 1347)),(UniCase::ascii("x-fli"), (1347, 1348)),(UniCase::ascii("x-flv"), (1348, 1349)),(UniCase::ascii("x-ivf"), (1349, 1350)),(UniCase::ascii("x-la-asf"), (1350, 1352)),(UniCase::ascii("x-m4v"), (1352, 1353)),(UniCase::ascii("x-matroska"), (1353, 1356)),(UniCase::ascii("x-mng"), (1356, 1357)),(UniCase::ascii("x-ms-asf"), (1357, 1361)),(UniCase::ascii("x-ms-vob"), (1361, 1362)),(UniCase::ascii("x-ms-wm"), (1362, 1363)),(UniCase::ascii("x-ms-wmp"), (1363, 1364)),(UniCase::ascii("x-ms-wmv"), (1364, 1365)),(UniCase::ascii("x-ms-wmx"), (1365, 1366)),(UniCase::ascii("x-ms-wvx"), (1366, 1367)),(UniCase::ascii("x-msvideo"), (1367, 1368)),(UniCase::ascii("x-sgi-movie"), (1368, 1369)),(UniCase::ascii("x-smv"), (1369, 1370)),] }),(UniCase::ascii("x-conference"), TopLevelExts { start: 1370, end: 1371, subs: &[(UniCase::ascii("x-cooltalk"), (1370, 1371)),] }),(UniCase::ascii("x-world"), TopLevelExts { start: 1371, end: 1376, subs: &[(UniCase::ascii("x-vrml"), (1371, 1376)),] }),];
+const EXTS: &'static [&'static str] = &["dwg", "ez", "anx", "aw", "atom", "atomcat", "atomsvc", "bdoc", "ccxml", "cdmia", "cdmic", "cdmid", "cdmio", "cdmiq", "cu", "mpd", "davmount", "x", "dbk", "dssc", "xdssc", "ecma", "es", "emma", "evy", "epub", "etl", "exi", "otf", "ttf", "pfr", "woff", "fif", "fsscript", "fsx", "spl", "geojson", "gml", "gpx", "gxf", "hjson", "hta", "stk", "ink", "inkml", "acx", "ipfix", "ear", "jar", "war", "ser", "js", "jsm", "mjs", "json", "json5", "jsonml", "jsonld", "jck", "jcz", "lostxml", "hqx", "cpt", "mads", "webmanifest", "mrc", "mrcx", "ma", "mb", "nb", "mathml", "mbox", "mscml", "metalink", "meta4", "mets", "mods", "m21", "mp21", "mp4s", "amc", "vsi", "accdb", "accde", "accdt", "ade", "adp", "mda", "mde", "accda", "accdc", "accft", "accdr", "accdw", "doc", "dot", "wbk", "wiz", "mxf", "aaf", "aca", "afm", "asd", "asi", "bin", "bpk", "buffer", "cab", "chm", "cur", "dat", "deb", "deploy", "dist", "distz", "dmg", "dms", "dsp", "dump", "dwp", "elc", "emz", "exe", "fla", "hhk", "hhp", "hxd", "hxh", "hxi", "hxq", "hxr", "hxs", "hxw", "ics", "img", "inf", "iso", "java", "jpb", "lpk", "lrf", "lzh", "mar", "mdp", "mix", "msi", "msm", "mso", "msp", "ocx", "pcx", "pcz", "pfb", "pfm", "pkg", "prm", "prx", "psd", "psm", "psp", "qxd", "rvt", "sea", "smi", "snp", "so", "thn", "toc", "u32", "xmp", "xsn", "xtp", "oda", "opf", "ogx", "axs", "omdoc", "one", "onea", "onepkg", "onetmp", "onetoc", "onetoc2", "osdx", "oxps", "xer", "pdf", "pgp", "asc", "sig", "prf", "p10", "p7c", "p7m", "p7s", "p8", "ac", "crl", "pkipath", "pki", "ai", "eps", "ps", "psc1", "cww", "pskcxml", "raml", "rat", "rdf", "rif", "rnc", "rl", "rld", "gbr", "mft", "roa", "rsd", "rss", "rtf", "sbml", "scq", "scs", "spq", "spp", "sdp", "setpay", "setreg", "shf", "smil", "rq", "srx", "gram", "grxml", "sru", "ssdl", "ssml", "step", "stp", "ssm", "tei", "teicorpus", "tfi", "tsd", "plb", "psb", "pvb", "tcap", "pwn", "aso", "imp", "acu", "acutc", "atc", "air", "fcdt", "fxp", "fxpl", "xdp", "xfdf", "ahead", "azf", "azs", "azw", "acc", "ami", "apk", "cii", "fti", "atx", "mpkg", "pkpass", "swi", "iota", "aep", "mpm", "bmi", "rep", "cdxml", "mmd", "cdy", "csl", "cla", "rp9", "c4d", "c4f", "c4g", "c4p", "c4u", "c11amc", "c11amz", "csp", "cdbcmsg", "cmc", "clkx", "clkk", "clkp", "clkt", "clkw", "wbs", "ppd", "car", "pcurl", "dart", "rdz", "uvd", "uvf", "uvvd", "uvvf", "uvt", "uvvt", "uvvx", "uvx", "uvvz", "uvz", "fe_launch", "dna", "mlp", "dpg", "dfac", "kpxx", "ait", "geo", "mag", "nml", "esf", "msf", "qam", "slt", "ssf", "es3", "et3", "ez2", "ez3", "fdf", "mseed", "dataless", "seed", "gph", "ftc", "book", "fm", "frame", "maker", "fnc", "ltf", "fsc", "oas", "oa2", "oa3", "fg5", "bh2", "ddd", "xdw", "xbd", "fzs", "txd", "ggb", "ggt", "gex", "gre", "gxt", "g2w", "g3w", "gmx", "gdoc", "gslides", "gsheet", "kml", "kmz", "gqf", "gqs", "gac", "ghf", "gim", "grv", "gtm", "tpl", "vcg", "hal", "zmm", "hbci", "les", "hpgl", "hpid", "hps", "jlt", "pcl", "pclxl", "sfd-hdstx", "mpy", "afp", "list3820", "listafp", "irm", "sc", "icc", "icm", "igl", "ivp", "ivu", "igm", "xpw", "xpx", "i2g", "qbo", "qfx", "rcprofile", "irp", "xpr", "fcs", "jam", "rms", "jisp", "joda", "ktr", "ktz", "karbon", "chrt", "kfo", "flw", "kon", "kpr", "kpt", "ksp", "kwd", "kwt", "htke", "kia", "kne", "knp", "skd", "skm", "skt", "sse", "lasxml", "lbd", "lbe", "123", "apr", "pre", "nsf", "org", "scm", "lwp", "portpkg", "mcd", "mc1", "cdkey", "mwf", "mfm", "flo", "igx", "mif", "daf", "dis", "mbk", "mqy", "msl", "plc", "txf", "mpn", "mpc", "xul", "cil", "slk", "xla", "xlc", "xld", "xlk", "xll", "xlm", "xls", "xlt", "xlw", "xlam", "xlsb", "xlsm", "xltm", "eot", "ims", "lrm", "mpf", "calx", "thmx", "msg", "pst", "sst", "pko", "cat", "stl", "pot", "ppa", "pps", "ppt", "pwz", "ppam", "pptm", "sldm", "ppsm", "potm", "mpp", "mpt", "vdx", "docm", "dotm", "wcm", "wdb", "wks", "wps", "wpl", "xps", "mseq", "mus", "msty", "taglet", "nlu", "nitf", "ntf", "nnd", "nns", "nnw", "ngdat", "n-gage", "rpst", "rpss", "edm", "edx", "ext", "odc", "otc", "odb", "odf", "odft", "odg", "otg", "odi", "oti", "odp", "otp", "ods", "ots", "odt", "odm", "ott", "oth", "xo", "dd2", "oxt", "pptx", "sldx", "ppsx", "potx", "xlsx", "xltx", "docx", "dotx", "mgp", "dp", "esa", "oprc", "pdb", "pqa", "paw", "str", "ei6", "efif", "wg", "plf", "pbd", "box", "mgz", "qps", "ptid", "qwd", "qwt", "qxb", "qxl", "qxt", "bed", "mxl", "musicxml", "cryptonote", "rm", "rmvb", "rmp", "link66", "st", "see", "sema", "semd", "semf", "ifm", "itp", "iif", "ipk", "twd", "twds", "teacher", "sdkd", "sdkm", "dxp", "sfs", "sdc", "sda", "sdd", "smf", "sdw", "vor", "sgl", "smzip", "sm", "wadl", "sxc", "stc", "sxd", "std", "sxi", "sti", "sxm", "sxw", "sxg", "stw", "sus", "susp", "svd", "sis", "sisx", "xsm", "bdm", "xdm", "tao", "cap", "dmp", "pcap", "tmo", "tpt", "mxs", "tra", "ufd", "ufdl", "utz", "umj", "unityweb", "uoml", "vcx", "vsd", "vss", "vst", "vsw", "vsx", "vtx", "vis", "vsf", "wbxml", "wmlc", "wmlsc", "wtb", "nbp", "wpd", "wqd", "stf", "xar", "xfdl", "hvd", "hvs", "hvp", "osf", "osfpvg", "saf", "spf", "cmp", "zir", "zirz", "zaz", "vxml", "vsix", "wasm", "wgt", "library-ms", "searchconnector-ms", "hlp", "wlmp", "wspolicy", "7z", "abw", "ace", "arj", "aab", "vox", "x32", "aam", "aas", "bcpio", "torrent", "blb", "blorb", "adobebridge", "bz", "boz", "bz2", "cb7", "cba", "cbr", "cbt", "cbz", "cdf", "vcd", "cfs", "chat", "pgn", "crx", "cco", "z", "tgz", "cpio", "csh", "udeb", "dgc", "cct", "cst", "cxt", "dcr", "dir", "dxr", "fgd", "swa", "w3d", "wad", "ncx", "dtb", "res", "dvi", "dxf", "eva", "bdf", "gsf", "psf", "pcf", "snf", "pfa", "arc", "gca", "ulx", "gnumeric", "gramps", "gtar", "gz", "hdf", "php", "install", "ins", "isp", "iii", "ipa", "ipg", "ipsw", "ite", "itlp", "itms", "itpc", "class", "jardiff", "jnlp", "skp", "latex", "luac", "lha", "run", "mie", "mvc", "mxp", "mobi", "prc", "application", "slupkg-ms", "manifest", "lit", "lnk", "vsto", "wmd", "wmz", "xbap", "mdb", "obd", "crd", "clp", "bat", "com", "dll", "m13", "m14", "mvb", "emf", "wmf", "mny", "pub", "scd", "trm", "wri", "nc", "pac", "nzb", "hhc", "pma", "pmc", "pml", "pmr", "pmw", "pl", "pm", "p12", "pfx", "p7b", "spc", "p7r", "pcast", "qtl", "rar", "ris", "safariextz", "webarchive", "sgimb", "sh", "shar", "mfp", "swf", "xap", "mmf", "sql", "sit", "sitx", "srt", "sv4cpio", "sv4crc", "t3", "gam", "tar", "tcl", "tk", "tex", "tfm", "texi", "texinfo", "obj", "roff", "t", "tr", "man", "me", "ms", "ustar", "hdd", "ova", "ovf", "vbox", "vbox-extpack", "vdi", "vhd", "vmdk", "src", "webapp", "wlpginstall", "wlpginstall3", "cer", "crt", "der", "pem", "fig", "xlf", "xpi", "xz", "z1", "z2", "z3", "z4", "z5", "z6", "z7", "z8", "xaml", "xdf", "xenc", "xht", "xhtml", "asa", "asax", "ascx", "ashx", "asmx", "aspx", "config", "coverage", "datasource", "dgml", "filters", "generictest", "hxa", "hxc", "hxe", "hxf", "hxk", "hxv", "loadtest", "master", "mtx", "orderedtest", "psess", "rdlc", "resx", "rng", "ruleset", "settings", "sitemap", "skin", "snippet", "svc", "testrunconfig", "testsettings", "trx", "vcproj", "vcxproj", "vscontent", "vsmdi", "webtest", "wiq", "xmta", "xsc", "xss", "xop", "xpl", "xspf", "mxml", "xhvml", "xvm", "xvml", "yang", "yin", "zip", "aac", "adts", "ac3", "aif", "aifc", "aiff", "cdda", "axa", "aa", "au", "snd", "flac", "m4a", "m4b", "m4p", "mid", "midi", "rmi", "kar", "mp4a", "m2a", "m3a", "mp2a", "mp3", "mpga", "oga", "ogg", "opus", "spx", "s3m", "pls", "sil", "aax", "uva", "uvva", "eol", "adt", "dra", "dts", "dtshd", "lvp", "pya", "ecelp4800", "ecelp7470", "ecelp9600", "rip", "wav", "wave", "weba", "caf", "gsm", "m4r", "mka", "m3u", "m3u8", "wax", "wma", "ra", "ram", "rpm", "sd2", "smd", "smx", "smz", "xm", "cdx", "cif", "cmdf", "cml", "csml", "xyz", "dwf", "ttc", "woff2", "apng", "bmp", "dib", "cgm", "cod", "g3", "gif", "ief", "jp2", "jpg2", "jpe", "jpeg", "jpg", "jpm", "jpf", "jpx", "ktx", "pct", "pic", "pict", "jfif", "png", "pnz", "btif", "sgi", "svg", "svgz", "tif", "tiff", "uvg", "uvi", "uvvg", "uvvi", "djv", "djvu", "fbs", "fpx", "fst", "mmr", "rlc", "mdi", "wdp", "npx", "hdr", "rf", "wbmp", "xif", "webp", "3ds", "ras", "cmx", "fh", "fh4", "fh5", "fh7", "fhc", "ico", "art", "jng", "mac", "pnt", "pntg", "sid", "pnm", "pbm", "pgm", "ppm", "qti", "qtif", "rgb", "tga", "xbm", "xpm", "xwd", "disposition-notification", "u8msg", "u8dsn", "u8mdn", "u8hdr", "eml", "mht", "mhtml", "mime", "nws", "gltf", "glb", "iges", "igs", "mesh", "msh", "silo", "dae", "gdl", "gtw", "vtu", "vrml", "x3db", "x3dbz", "x3dv", "x3dvz", "x3d", "x3dz", "appcache", "ifb", "coffee", "litcoffee", "css", "csv", "dlm", "323", "htm", "html", "hxt", "shtml", "uls", "jade", "jsx", "less", "markdown", "md", "mml", "n3", "asm", "bas", "c", "cc", "cd", "cfg", "cmd", "cnf", "conf", "cpp", "cs", "csdproj", "csproj", "cxx", "dbproj", "def", "dsw", "h", "hh", "hpp", "hxx", "i", "idl", "in", "inc", "ini", "inl", "ipproj", "jsxbin", "list", "log", "lst", "mak", "map", "mk", "odh", "odl", "pkgdef", "pkgundef", "py", "rc", "rc2", "rct", "reg", "rgs", "s", "scr", "sln", "sol", "sor", "srf", "text", "tlh", "tli", "txt", "user", "vb", "vbdproj", "vbproj", "vcs", "vddproj", "vdp", "vdproj", "vspscc", "vsscc", "vssscc", "xdr", "xoml", "dsc", "rtx", "sct", "wsc", "sgm", "sgml", "shex", "slim", "slm", "styl", "stylus", "tsv", "ttl", "uri", "uris", "urls", "vbs", "vcard", "curl", "dcurl", "mcurl", "scurl", "sub", "fly", "flx", "gv", "3dml", "spot", "jad", "wml", "wmls", "vtt", "htt", "dic", "htc", "f", "f77", "f90", "for", "hbs", "hdml", "qht", "qhtm", "lua", "md", "mkd", "contact", "group", "iqy", "rqy", "nfo", "opml", "p", "pas", "pde", "rs", "sass", "scss", "etx", "sfv", "ymp", "toml", "uu", "vcf", "yaml", "yml", "addin", "disco", "dll.config", "dtd", "dtsconfig", "exe.config", "mno", "ssisdeploymentmanifest", "vml", "vsct", "vsixlangpack", "vsixmanifest", "vssettings", "vstemplate", "wsdl", "xml", "xrm-ms", "xsd", "xsf", "xsl", "xslt", "3gp", "3gpp", "3g2", "3gp2", "axv", "divx", "h261", "h263", "h264", "jpgv", "jpgm", "mj2", "mjp2", "mp4", "mp4v", "mpg4", "m1v", "m2v", "mod", "mp2", "mp2v", "mpa", "mpe", "mpeg", "mpg", "mpv2", "vbk", "ogv", "mov", "mqv", "qt", "uvh", "uvvh", "uvm", "uvvm", "uvp", "uvvp", "uvs", "uvvs", "uvv", "uvvv", "m2t", "m2ts", "mts", "ts", "tts", "dvb", "fvt", "m4u", "mxu", "pyv", "uvu", "uvvu", "viv", "webm", "dif", "dv", "f4v", "fli", "flv", "ivf", "lsf", "lsx", "m4v", "mk3d", "mks", "mkv", "mng", "asf", "asr", "asx", "nsc", "vob", "wm", "wmp", "wmv", "wmx", "wvx", "avi", "movie", "smv", "ice", "flr", "wrl", "wrz", "xaf", "xof"];